@@ -0,0 +1,222 @@
+//! Compute units the deployed program consumes, backend by backend.
+//!
+//! ```text
+//! cargo build-program --features solana-program,<backends>
+//! cd cu-bench && cargo test --release --target <host-triple>
+//! ```
+//!
+//! `--test litesvm` alone finishes in seconds, for iterating on a backend;
+//! `--test program_test` runs a full bank and is the number to quote.
+//!
+//! Each harness in `tests/` deploys the build at [`program_path`], runs an
+//! `Allowance` instruction for every backend id on every `bench_vectors`
+//! entry, and writes what the runtime charged to
+//! `target/cu-report-<harness>.json` (see [`Report`]). Backends the build
+//! leaves out fail with `UnknownBackend` and are skipped. Harnesses pass
+//! without measuring anything if the program has not been built.
+//!
+//! Builds with `instruction-epoch` take no Clock account and cannot be
+//! measured here.
+//!
+//! With [`MANIFEST_ENV`] naming a `cargo xtask build-all` manifest, each
+//! harness also records its mean compute units per backend there, under
+//! the artifact for that backend:
+//!
+//! ```json
+//! "compute_units": { "litesvm": 1234, "program-test": 1234 }
+//! ```
+//!
+//! The measured program is this harness's `solana-program` build of the
+//! same source, not the artifact itself, so only record into a manifest
+//! built from the same commit.
+
+use std::path::{Path, PathBuf};
+
+use serde_json::{json, Value};
+use stake_ebpf_check::instruction::{Instruction, Operands, ALLOWANCE_INSTRUCTION_LEN};
+use stake_ebpf_check::results::AllowanceResult;
+
+/// Overrides [`program_path`].
+pub const PROGRAM_ENV: &str = "STAKE_EBPF_CHECK_SO";
+
+/// Overrides the directory reports are written to.
+pub const REPORT_DIR_ENV: &str = "CU_REPORT_DIR";
+
+/// A `build-all` manifest to record mean compute units in.
+pub const MANIFEST_ENV: &str = "CU_MANIFEST";
+
+/// Every backend wire id, compiled in or not.
+pub const BACKEND_IDS: std::ops::Range<u8> = 0..7;
+
+/// The program build to deploy: [`PROGRAM_ENV`] if set, otherwise the
+/// workspace's release artifact.
+pub fn program_path() -> PathBuf {
+    match std::env::var_os(PROGRAM_ENV) {
+        Some(path) => PathBuf::from(path),
+        None => PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("../target/bpfel-unknown-none/release/libstake_ebpf_check.so"),
+    }
+}
+
+/// The program ELF, or `None` (with a note on stderr) if it has not been
+/// built.
+pub fn program_elf() -> Option<Vec<u8>> {
+    let path = program_path();
+    match std::fs::read(&path) {
+        Ok(elf) => Some(elf),
+        Err(e) => {
+            eprintln!("skipping: {}: {e}", path.display());
+            None
+        }
+    }
+}
+
+pub fn allowance_data(backend_id: u8, operands: Operands) -> Vec<u8> {
+    let mut data = vec![0u8; ALLOWANCE_INSTRUCTION_LEN];
+    Instruction::Allowance {
+        backend_id,
+        operands,
+    }
+    .pack(&mut data);
+    data
+}
+
+/// One backend on one vector.
+#[derive(Clone, Copy, Debug)]
+pub struct Measurement {
+    pub backend_id: u8,
+    pub vector: &'static str,
+    pub compute_units: u64,
+    /// What the program wrote to the result account.
+    pub result: Option<AllowanceResult>,
+}
+
+impl Measurement {
+    fn to_json(self) -> Value {
+        let result = self.result.map(|result| {
+            json!({
+                "activation": result.activation,
+                "deactivation": result.deactivation,
+                "algo_version": result.algo_version,
+                "activation_condition": result.activation_condition.code(),
+                "deactivation_condition": result.deactivation_condition.code(),
+            })
+        });
+        json!({
+            "backend_id": self.backend_id,
+            "vector": self.vector,
+            "compute_units": self.compute_units,
+            "result": result,
+        })
+    }
+}
+
+/// Measurements from one harness.
+///
+/// ```text
+/// {
+///   "harness": "program-test",
+///   "program": "<path to the .so>",
+///   "measurements": [
+///     { "backend_id": 5, "vector": "mainnet", "compute_units": 1234,
+///       "result": { "activation": ..., "deactivation": ..., ... } },
+///     ...
+///   ]
+/// }
+/// ```
+pub struct Report {
+    harness: &'static str,
+    measurements: Vec<Measurement>,
+}
+
+impl Report {
+    pub fn new(harness: &'static str) -> Self {
+        Self {
+            harness,
+            measurements: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, measurement: Measurement) {
+        self.measurements.push(measurement);
+    }
+
+    pub fn measurements(&self) -> &[Measurement] {
+        &self.measurements
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "harness": self.harness,
+            "program": program_path().display().to_string(),
+            "measurements": self
+                .measurements
+                .iter()
+                .map(|measurement| measurement.to_json())
+                .collect::<Vec<_>>(),
+        })
+    }
+
+    /// Mean compute units of each backend measured, by wire id.
+    pub fn means(&self) -> Vec<(u8, u64)> {
+        BACKEND_IDS
+            .filter_map(|backend_id| {
+                let units: Vec<u64> = self
+                    .measurements
+                    .iter()
+                    .filter(|measurement| measurement.backend_id == backend_id)
+                    .map(|measurement| measurement.compute_units)
+                    .collect();
+                let count = units.len() as u64;
+                (count > 0).then(|| (backend_id, units.iter().sum::<u64>() / count))
+            })
+            .collect()
+    }
+
+    /// Adds [`Report::means`] to the artifacts of the manifest at `path`,
+    /// as `compute_units.<harness>`.
+    pub fn record(&self, path: &Path) -> std::io::Result<()> {
+        let malformed = || std::io::Error::other(format!("{}: not a manifest", path.display()));
+        let mut manifest: Value = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+        let artifacts = manifest["artifacts"].as_array_mut().ok_or_else(malformed)?;
+        for (backend_id, mean) in self.means() {
+            // Artifacts are in wire-id order but may not all be there.
+            let backend = test_vectors::BACKENDS[usize::from(backend_id)];
+            let Some(artifact) = artifacts.iter_mut().find(|a| a["backend"] == backend) else {
+                continue;
+            };
+            if !artifact["compute_units"].is_object() {
+                artifact["compute_units"] = json!({});
+            }
+            artifact["compute_units"][self.harness] = json!(mean);
+        }
+        std::fs::write(path, serde_json::to_string_pretty(&manifest)? + "\n")
+    }
+
+    /// Writes the report, returning where it went, and records it in the
+    /// manifest at [`MANIFEST_ENV`] if set.
+    pub fn write(&self) -> std::io::Result<PathBuf> {
+        let dir = match std::env::var_os(REPORT_DIR_ENV) {
+            Some(dir) => PathBuf::from(dir),
+            None => PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("target"),
+        };
+        std::fs::create_dir_all(&dir)?;
+        let path = dir.join(format!("cu-report-{}.json", self.harness));
+        let json = serde_json::to_string_pretty(&self.to_json())?;
+        std::fs::write(&path, json + "\n")?;
+        if let Some(manifest) = std::env::var_os(MANIFEST_ENV) {
+            self.record(Path::new(&manifest))?;
+        }
+        Ok(path)
+    }
+
+    /// One line per measurement, for test output.
+    pub fn print(&self) {
+        for measurement in &self.measurements {
+            println!(
+                "{:<14} backend {} {:<20} {:>7} CU",
+                self.harness, measurement.backend_id, measurement.vector, measurement.compute_units
+            );
+        }
+    }
+}
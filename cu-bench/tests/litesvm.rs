@@ -0,0 +1,98 @@
+//! Compute units through LiteSVM: the same program runtime as a bank
+//! without the validator around it, so the whole matrix runs in seconds.
+
+use cu_bench::{allowance_data, program_elf, Measurement, Report, BACKEND_IDS};
+use litesvm::LiteSVM;
+use solana_sdk::account::Account;
+use solana_sdk::clock::Clock;
+use solana_sdk::instruction::{AccountMeta, Instruction, InstructionError};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::rent::Rent;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use solana_sdk::sysvar;
+use solana_sdk::transaction::{Transaction, TransactionError};
+use stake_ebpf_check::bench_vectors::VECTORS;
+use stake_ebpf_check::instruction::InstructionError as ProgramInstructionError;
+use stake_ebpf_check::results::{AllowanceResult, ALLOWANCE_RESULT_LEN};
+
+#[test]
+fn compute_units() {
+    let Some(elf) = program_elf() else {
+        return;
+    };
+
+    let program_id = Pubkey::new_unique();
+    let result_key = Pubkey::new_unique();
+    let payer = Keypair::new();
+    let mut svm = LiteSVM::new();
+    svm.add_program(program_id, &elf);
+    svm.set_account(
+        result_key,
+        Account {
+            lamports: Rent::default().minimum_balance(ALLOWANCE_RESULT_LEN),
+            data: vec![0; ALLOWANCE_RESULT_LEN],
+            owner: program_id,
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+    svm.airdrop(&payer.pubkey(), 1_000_000_000).unwrap();
+
+    let mut report = Report::new("litesvm");
+    for vector in VECTORS {
+        // The program takes the epoch from the Clock, not the operands.
+        let mut clock: Clock = svm.get_sysvar();
+        clock.epoch = vector.operands.epoch;
+        svm.set_sysvar(&clock);
+
+        for backend_id in BACKEND_IDS {
+            let instruction = Instruction::new_with_bytes(
+                program_id,
+                &allowance_data(backend_id, vector.operands),
+                vec![
+                    AccountMeta::new_readonly(sysvar::clock::id(), false),
+                    AccountMeta::new(result_key, false),
+                ],
+            );
+            let transaction = Transaction::new_signed_with_payer(
+                &[instruction],
+                Some(&payer.pubkey()),
+                &[&payer],
+                svm.latest_blockhash(),
+            );
+            let metadata = match svm.send_transaction(transaction) {
+                Ok(metadata) => metadata,
+                Err(failed)
+                    if failed.err
+                        == TransactionError::InstructionError(
+                            0,
+                            InstructionError::Custom(
+                                ProgramInstructionError::UnknownBackend.code(),
+                            ),
+                        ) =>
+                {
+                    continue
+                }
+                Err(failed) => panic!("backend {backend_id} on {}: {}", vector.name, failed.err),
+            };
+
+            let result = svm
+                .get_account(&result_key)
+                .and_then(|account| AllowanceResult::read(&account.data));
+            report.push(Measurement {
+                backend_id,
+                vector: vector.name,
+                compute_units: metadata.compute_units_consumed,
+                result,
+            });
+        }
+    }
+
+    report.print();
+    assert!(
+        !report.measurements().is_empty(),
+        "no backend compiled into the program"
+    );
+    println!("wrote {}", report.write().unwrap().display());
+}
@@ -0,0 +1,107 @@
+//! Compute units as a bank charges them, through `solana-program-test`.
+
+use cu_bench::{allowance_data, program_elf, Measurement, Report, BACKEND_IDS};
+use solana_program_test::ProgramTest;
+use solana_sdk::account::Account;
+use solana_sdk::bpf_loader;
+use solana_sdk::clock::Clock;
+use solana_sdk::instruction::{AccountMeta, Instruction, InstructionError};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::rent::Rent;
+use solana_sdk::signer::Signer;
+use solana_sdk::sysvar;
+use solana_sdk::transaction::{Transaction, TransactionError};
+use stake_ebpf_check::bench_vectors::VECTORS;
+use stake_ebpf_check::instruction::InstructionError as ProgramInstructionError;
+use stake_ebpf_check::results::{AllowanceResult, ALLOWANCE_RESULT_LEN};
+
+#[tokio::test]
+async fn compute_units() {
+    let Some(elf) = program_elf() else {
+        return;
+    };
+
+    let program_id = Pubkey::new_unique();
+    let result_key = Pubkey::new_unique();
+    let mut program_test = ProgramTest::default();
+    program_test.add_account(
+        program_id,
+        Account {
+            lamports: Rent::default().minimum_balance(elf.len()),
+            data: elf,
+            owner: bpf_loader::id(),
+            executable: true,
+            rent_epoch: 0,
+        },
+    );
+    program_test.add_account(
+        result_key,
+        Account {
+            lamports: Rent::default().minimum_balance(ALLOWANCE_RESULT_LEN),
+            data: vec![0; ALLOWANCE_RESULT_LEN],
+            owner: program_id,
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+    let mut context = program_test.start_with_context().await;
+
+    let mut report = Report::new("program-test");
+    for vector in VECTORS {
+        // The program takes the epoch from the Clock, not the operands.
+        let mut clock: Clock = context.banks_client.get_sysvar().await.unwrap();
+        clock.epoch = vector.operands.epoch;
+        context.set_sysvar(&clock);
+
+        for backend_id in BACKEND_IDS {
+            let instruction = Instruction::new_with_bytes(
+                program_id,
+                &allowance_data(backend_id, vector.operands),
+                vec![
+                    AccountMeta::new_readonly(sysvar::clock::id(), false),
+                    AccountMeta::new(result_key, false),
+                ],
+            );
+            let transaction = Transaction::new_signed_with_payer(
+                &[instruction],
+                Some(&context.payer.pubkey()),
+                &[&context.payer],
+                context.last_blockhash,
+            );
+            let outcome = context
+                .banks_client
+                .process_transaction_with_metadata(transaction)
+                .await
+                .unwrap();
+            match outcome.result {
+                Ok(()) => {}
+                Err(TransactionError::InstructionError(0, InstructionError::Custom(code)))
+                    if code == ProgramInstructionError::UnknownBackend.code() =>
+                {
+                    continue
+                }
+                Err(e) => panic!("backend {backend_id} on {}: {e}", vector.name),
+            }
+
+            let result = context
+                .banks_client
+                .get_account(result_key)
+                .await
+                .unwrap()
+                .and_then(|account| AllowanceResult::read(&account.data));
+            report.push(Measurement {
+                backend_id,
+                vector: vector.name,
+                compute_units: outcome.metadata.unwrap().compute_units_consumed,
+                result,
+            });
+        }
+    }
+
+    report.print();
+    assert!(
+        !report.measurements().is_empty(),
+        "no backend compiled into the program"
+    );
+    println!("wrote {}", report.write().unwrap().display());
+}
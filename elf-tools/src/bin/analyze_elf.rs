@@ -0,0 +1,261 @@
+//! Flags what in a program build will not survive the SBF loader, or will
+//! but costs more than it looks.
+//!
+//! ```text
+//! cargo build-manual
+//! cargo run --target <host-triple> -p elf-tools --bin analyze-elf -- \
+//!     target/bpfel-unknown-none/release/libstake_ebpf_check.so [more.so ...]
+//! ```
+//!
+//! Errors, which fail the run:
+//!
+//! - instructions the verifier rejects for the build's SBPF version
+//! - calls to external symbols that are not syscalls
+//! - calls into the allocator
+//! - relocation types the loader does not apply, and `.bss`/`.data`
+//!
+//! Warnings: compiler builtins for 128-bit arithmetic linked into the
+//! build, with the number of call sites and their length. They work, but a
+//! `__udivti3` loop is usually where a backend's compute units go.
+
+use std::process::ExitCode;
+
+use elf_tools::{CallTarget, Program};
+use solana_sbpf::ebpf;
+use solana_sbpf::program::{BuiltinProgram, FunctionRegistry};
+use solana_sbpf::verifier::{RequisiteVerifier, Verifier, VerifierError};
+use solana_sbpf::vm::{Config, ContextObject};
+
+/// `compiler_builtins` routines LLVM lowers 128-bit and wide arithmetic to.
+const BUILTINS: &[&str] = &[
+    "__multi3",
+    "__muloti4",
+    "__udivti3",
+    "__umodti3",
+    "__divti3",
+    "__modti3",
+    "__udivmodti4",
+    "__ashlti3",
+    "__ashrti3",
+    "__lshrti3",
+    "__udivdi3",
+    "__umoddi3",
+    "__divdi3",
+    "__moddi3",
+];
+
+const ALLOCATOR: &[&str] = &[
+    "__rust_alloc",
+    "__rust_alloc_zeroed",
+    "__rust_dealloc",
+    "__rust_realloc",
+    "__rg_alloc",
+    "__rg_alloc_zeroed",
+    "__rg_dealloc",
+    "__rg_realloc",
+    "__rust_alloc_error_handler",
+    "sol_alloc_free_",
+];
+
+/// Syscalls the validator registers for programs.
+const SYSCALLS: &[&str] = &[
+    "abort",
+    "sol_panic_",
+    "sol_log_",
+    "sol_log_64_",
+    "sol_log_compute_units_",
+    "sol_log_pubkey",
+    "sol_log_data",
+    "sol_create_program_address",
+    "sol_try_find_program_address",
+    "sol_sha256",
+    "sol_keccak256",
+    "sol_blake3",
+    "sol_secp256k1_recover",
+    "sol_get_clock_sysvar",
+    "sol_get_epoch_schedule_sysvar",
+    "sol_get_fees_sysvar",
+    "sol_get_rent_sysvar",
+    "sol_get_epoch_rewards_sysvar",
+    "sol_get_last_restart_slot",
+    "sol_get_sysvar",
+    "sol_memcpy_",
+    "sol_memmove_",
+    "sol_memcmp_",
+    "sol_memset_",
+    "sol_invoke_signed_c",
+    "sol_invoke_signed_rust",
+    "sol_set_return_data",
+    "sol_get_return_data",
+    "sol_get_stack_height",
+    "sol_get_processed_sibling_instruction",
+    "sol_remaining_compute_units",
+    "sol_curve_validate_point",
+    "sol_curve_group_op",
+    "sol_curve_multiscalar_mul",
+    "sol_alt_bn128_group_op",
+    "sol_alt_bn128_compression",
+    "sol_big_mod_exp",
+    "sol_poseidon",
+    "sol_get_epoch_stake",
+];
+
+const USAGE: &str = "usage: analyze-elf <program.so>...";
+
+/// The verifier is generic over a context it never touches.
+struct NoContext;
+
+impl ContextObject for NoContext {
+    fn trace(&mut self, _state: [u64; 12]) {}
+
+    fn consume(&mut self, _amount: u64) {}
+
+    fn get_remaining(&self) -> u64 {
+        0
+    }
+}
+
+#[derive(Default)]
+struct Findings {
+    errors: Vec<String>,
+    warnings: Vec<String>,
+}
+
+/// Why the verifier rejects instruction `index` on its own, ignoring jump
+/// and call targets, which only make sense against the whole program.
+fn verify_insn(program: &Program, index: usize) -> Option<String> {
+    let width = if program.insn(index).opc == ebpf::LD_DW_IMM {
+        2
+    } else {
+        1
+    };
+    let end = ((index + width) * ebpf::INSN_SIZE).min(program.text().len());
+    let slice = &program.text()[index * ebpf::INSN_SIZE..end];
+    let loader = BuiltinProgram::<NoContext>::new_mock();
+    let result = RequisiteVerifier::verify(
+        slice,
+        &Config::default(),
+        program.sbpf_version(),
+        &FunctionRegistry::default(),
+        loader.get_function_registry(),
+    );
+    match result {
+        Err(VerifierError::JumpOutOfCode(..) | VerifierError::InvalidFunction(_)) | Ok(()) => None,
+        // The verifier numbers instructions within the slice, always 0 here.
+        Err(e) => Some(e.to_string().replace(" (insn #0)", "")),
+    }
+}
+
+fn location(program: &Program, index: usize) -> String {
+    let addr = program.text_addr() + (index * ebpf::INSN_SIZE) as u64;
+    match program.function_at(index) {
+        Some(function) => format!("{addr:#x} in {}", function.name),
+        None => format!("{addr:#x}"),
+    }
+}
+
+fn analyze(program: &Program) -> Findings {
+    let mut findings = Findings::default();
+    let mut builtin_calls = std::collections::BTreeMap::<&str, usize>::new();
+
+    for insn in program.instructions() {
+        if let Some(e) = verify_insn(program, insn.ptr) {
+            findings
+                .errors
+                .push(format!("{}: {e}", location(program, insn.ptr)));
+        }
+
+        let name = match program.call_target(&insn) {
+            Some(CallTarget::Local(_, Some(function))) => function.name.as_str(),
+            Some(CallTarget::External("")) => {
+                findings.errors.push(format!(
+                    "{}: call with no relocation",
+                    location(program, insn.ptr)
+                ));
+                continue;
+            }
+            Some(CallTarget::External(name)) => {
+                if !SYSCALLS.contains(&name) && !ALLOCATOR.contains(&name) {
+                    findings.errors.push(format!(
+                        "{}: call to unresolved symbol {name}",
+                        location(program, insn.ptr)
+                    ));
+                }
+                name
+            }
+            _ => continue,
+        };
+        if ALLOCATOR.contains(&name) {
+            findings.errors.push(format!(
+                "{}: call into the allocator ({name})",
+                location(program, insn.ptr)
+            ));
+        }
+        if let Some(builtin) = BUILTINS.iter().find(|builtin| **builtin == name) {
+            *builtin_calls.entry(builtin).or_default() += 1;
+        }
+    }
+
+    for (builtin, sites) in builtin_calls {
+        let len = program
+            .function_named(builtin)
+            .map_or(String::from("external"), |function| {
+                format!("{} instructions", function.len())
+            });
+        findings
+            .warnings
+            .push(format!("{builtin}: {sites} call sites, {len}"));
+    }
+    for (offset, r_type) in program.unsupported_relocations() {
+        findings
+            .errors
+            .push(format!("{offset:#x}: unsupported relocation type {r_type}"));
+    }
+    for (name, size) in program.writable_sections() {
+        findings
+            .errors
+            .push(format!("{name}: {size} bytes of writable data"));
+    }
+    findings
+}
+
+fn main_inner(args: &[String]) -> Result<bool, String> {
+    if args.is_empty() {
+        return Err(USAGE.to_string());
+    }
+
+    let mut failed = false;
+    for path in args {
+        let bytes = std::fs::read(path).map_err(|e| format!("{path}: {e}"))?;
+        let program = Program::parse(&bytes).map_err(|e| format!("{path}: {e}"))?;
+        let findings = analyze(&program);
+        println!(
+            "{path}: {:?}, {} instructions, {} functions, {} errors, {} warnings",
+            program.sbpf_version(),
+            program.len(),
+            program.functions().len(),
+            findings.errors.len(),
+            findings.warnings.len()
+        );
+        for error in &findings.errors {
+            println!("  error    {error}");
+        }
+        for warning in &findings.warnings {
+            println!("  warning  {warning}");
+        }
+        failed |= !findings.errors.is_empty();
+    }
+    Ok(failed)
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match main_inner(&args) {
+        Ok(false) => ExitCode::SUCCESS,
+        Ok(true) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,311 @@
+//! Disassembles the calculator from two program builds side by side, with
+//! instruction counts by class, to show why one backend costs more than
+//! another.
+//!
+//! ```text
+//! cargo build-manual && cp target/bpfel-unknown-none/release/libstake_ebpf_check.so manual.so
+//! cargo build-uint && cp target/bpfel-unknown-none/release/libstake_ebpf_check.so uint.so
+//! cargo run --target <host-triple> -p elf-tools --bin disasm-diff -- \
+//!     [--symbol <name>] manual.so uint.so
+//! ```
+//!
+//! Without `--symbol`, each build's `rate_limited_stake_change_bps` (or
+//! `rate_limited_stake_change`) is used, or failing that its
+//! `entrypoint_<backend>` export, which is where the calculator ends up
+//! when LLVM inlines it. `--symbol` takes any part of a demangled name; a
+//! build with several matches uses the first and says which.
+//!
+//! The listings are aligned on their longest common subsequence: matching
+//! instructions share a row, `|` marks a row that differs, `<` and `>` an
+//! instruction only on one side. Jump targets are written relative to the
+//! function start so the two sides compare. The counts are static, of the
+//! function alone: calls into 128-bit builtins count once however long the
+//! builtin runs, so read them together with `analyze-elf` and
+//! `rbpf-runner`.
+
+use std::process::ExitCode;
+
+use elf_tools::{CallTarget, Function, Program};
+use solana_sbpf::disassembler::disassemble_instruction;
+use solana_sbpf::ebpf::{self, Insn};
+use solana_sbpf::program::{BuiltinProgram, FunctionRegistry};
+use solana_sbpf::vm::ContextObject;
+
+const USAGE: &str = "usage: disasm-diff [--symbol <name>] <a.so> <b.so>";
+
+/// Tried in order when no `--symbol` is given.
+const DEFAULT_SYMBOLS: &[&str] = &[
+    "rate_limited_stake_change_bps",
+    "rate_limited_stake_change",
+    "entrypoint_",
+];
+
+/// Width of the left column of the listing.
+const COLUMN: usize = 48;
+
+/// The disassembler is generic over a context it never touches.
+struct NoContext;
+
+impl ContextObject for NoContext {
+    fn trace(&mut self, _state: [u64; 12]) {}
+
+    fn consume(&mut self, _amount: u64) {}
+
+    fn get_remaining(&self) -> u64 {
+        0
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Class {
+    Mul,
+    Div,
+    Branch,
+    Call,
+    LoadStore,
+    Other,
+}
+
+impl Class {
+    const ALL: [Class; 6] = [
+        Class::Mul,
+        Class::Div,
+        Class::Branch,
+        Class::Call,
+        Class::LoadStore,
+        Class::Other,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Class::Mul => "mul",
+            Class::Div => "div/mod",
+            Class::Branch => "branch",
+            Class::Call => "call/exit",
+            Class::LoadStore => "load/store",
+            Class::Other => "other",
+        }
+    }
+
+    /// From the mnemonic, which names the operation the same way whichever
+    /// SBPF version encodes it.
+    fn of(mnemonic: &str) -> Self {
+        const MUL: &[&str] = &["mul", "lmul", "uhmul", "shmul"];
+        const DIV: &[&str] = &["div", "mod", "udiv", "urem", "sdiv", "srem"];
+        let op = mnemonic.trim_end_matches(|c: char| c.is_ascii_digit());
+        if MUL.contains(&op) {
+            Class::Mul
+        } else if DIV.contains(&op) {
+            Class::Div
+        } else if mnemonic.starts_with('j') {
+            Class::Branch
+        } else if matches!(op, "call" | "callx" | "syscall" | "exit" | "return") {
+            Class::Call
+        } else if mnemonic.starts_with("ldx") || mnemonic.starts_with("st") {
+            Class::LoadStore
+        } else {
+            Class::Other
+        }
+    }
+}
+
+struct Line {
+    text: String,
+    class: Class,
+}
+
+/// `function`'s instructions as text, with calls named and jump targets
+/// relative to its start.
+fn disassemble(program: &Program, function: &Function) -> Vec<Line> {
+    let loader = BuiltinProgram::<NoContext>::new_mock();
+    let registry = FunctionRegistry::default();
+    let cfg_nodes = Default::default();
+
+    program
+        .instructions()
+        .skip_while(|insn| insn.ptr < function.start)
+        .take_while(|insn| insn.ptr < function.end)
+        .map(|mut insn: Insn| {
+            if insn.opc == ebpf::LD_DW_IMM {
+                ebpf::augment_lddw_unchecked(program.text(), &mut insn);
+            }
+            let text = match program.call_target(&insn) {
+                Some(CallTarget::Local(_, Some(callee))) => format!("call {}", callee.name),
+                Some(CallTarget::Local(target, None)) => format!("call {target:#x}"),
+                Some(CallTarget::External(name)) => format!("call {name}"),
+                Some(CallTarget::Register) | None => {
+                    let text = disassemble_instruction(
+                        &insn,
+                        insn.ptr,
+                        &cfg_nodes,
+                        &registry,
+                        &loader,
+                        program.sbpf_version(),
+                    );
+                    if text.starts_with('j') {
+                        let target = insn.ptr as i64 + insn.off as i64 + 1;
+                        let label = format!("L{}", target - function.start as i64);
+                        text.replace("[invalid]", &label)
+                    } else {
+                        text
+                    }
+                }
+            };
+            let class = Class::of(text.split_whitespace().next().unwrap_or(""));
+            Line { text, class }
+        })
+        .collect()
+}
+
+fn find_function<'p>(program: &'p Program, symbol: Option<&str>) -> Option<&'p Function> {
+    let patterns = match symbol {
+        Some(symbol) => vec![symbol],
+        None => DEFAULT_SYMBOLS.to_vec(),
+    };
+    patterns.into_iter().find_map(|pattern| {
+        program
+            .functions()
+            .iter()
+            .find(|function| function.name.contains(pattern))
+    })
+}
+
+enum Row {
+    Same(usize),
+    Left(usize),
+    Right(usize),
+}
+
+/// The two listings aligned on their longest common subsequence of
+/// instruction text.
+fn align(a: &[Line], b: &[Line]) -> Vec<Row> {
+    // lcs[i][j]: longest common subsequence of a[i..] and b[j..].
+    let mut lcs = vec![vec![0u32; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i].text == b[j].text {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut rows = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i].text == b[j].text {
+            rows.push(Row::Same(i));
+            i += 1;
+            j += 1;
+        } else if j == b.len() || (i < a.len() && lcs[i + 1][j] >= lcs[i][j + 1]) {
+            rows.push(Row::Left(i));
+            i += 1;
+        } else {
+            rows.push(Row::Right(j));
+            j += 1;
+        }
+    }
+    rows
+}
+
+/// Prints the aligned listings, pairing each run of one-sided rows up
+/// across the gutter.
+fn print_listing(a: &[Line], b: &[Line]) {
+    let rows = align(a, b);
+    let mut lefts = Vec::new();
+    let mut rights = Vec::new();
+    let flush = |lefts: &mut Vec<usize>, rights: &mut Vec<usize>| {
+        for k in 0..lefts.len().max(rights.len()) {
+            let left = lefts.get(k).map_or("", |&i| a[i].text.as_str());
+            let right = rights.get(k).map_or("", |&j| b[j].text.as_str());
+            let gutter = match (lefts.get(k), rights.get(k)) {
+                (Some(_), Some(_)) => '|',
+                (Some(_), None) => '<',
+                _ => '>',
+            };
+            println!("{left:<COLUMN$} {gutter} {right}");
+        }
+        lefts.clear();
+        rights.clear();
+    };
+    for row in rows {
+        match row {
+            Row::Left(i) => lefts.push(i),
+            Row::Right(j) => rights.push(j),
+            Row::Same(i) => {
+                flush(&mut lefts, &mut rights);
+                println!("{:<COLUMN$}   {}", a[i].text, a[i].text);
+            }
+        }
+    }
+    flush(&mut lefts, &mut rights);
+}
+
+fn counts(lines: &[Line]) -> Vec<usize> {
+    Class::ALL
+        .iter()
+        .map(|&class| lines.iter().filter(|line| line.class == class).count())
+        .collect()
+}
+
+/// Counts per class, `<` for the first build and `>` for the second as in
+/// the listing.
+fn print_counts(a: &[Line], b: &[Line]) {
+    println!("{:<12} {:>6} {:>6} {:>7}", "class", "<", ">", "delta");
+    let rows = Class::ALL
+        .iter()
+        .map(|class| class.name())
+        .zip(counts(a).into_iter().zip(counts(b)))
+        .chain([("total", (a.len(), b.len()))]);
+    for (name, (a, b)) in rows {
+        println!("{name:<12} {a:>6} {b:>6} {:>+7}", b as i64 - a as i64);
+    }
+}
+
+fn main_inner(args: &[String]) -> Result<(), String> {
+    let mut symbol = None;
+    let mut paths = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--symbol" => symbol = Some(args.next().ok_or(USAGE)?.as_str()),
+            _ => paths.push(arg.as_str()),
+        }
+    }
+    let [a_path, b_path] = paths[..] else {
+        return Err(USAGE.to_string());
+    };
+
+    let a_bytes = std::fs::read(a_path).map_err(|e| format!("{a_path}: {e}"))?;
+    let b_bytes = std::fs::read(b_path).map_err(|e| format!("{b_path}: {e}"))?;
+    let a_program = Program::parse(&a_bytes).map_err(|e| format!("{a_path}: {e}"))?;
+    let b_program = Program::parse(&b_bytes).map_err(|e| format!("{b_path}: {e}"))?;
+    let not_found = |path: &str| match symbol {
+        Some(symbol) => format!("{path}: no function matching `{symbol}`"),
+        None => format!("{path}: no calculator or entrypoint_<backend> symbol; try --symbol"),
+    };
+    let a_function = find_function(&a_program, symbol).ok_or_else(|| not_found(a_path))?;
+    let b_function = find_function(&b_program, symbol).ok_or_else(|| not_found(b_path))?;
+
+    let a = disassemble(&a_program, a_function);
+    let b = disassemble(&b_program, b_function);
+    println!("< {a_path}: {}", a_function.name);
+    println!("> {b_path}: {}", b_function.name);
+    println!();
+    print_listing(&a, &b);
+    println!();
+    print_counts(&a, &b);
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match main_inner(&args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,92 @@
+//! Per-function stack frame sizes of program builds, largest first.
+//!
+//! ```text
+//! cargo build-manual
+//! cargo run --target <host-triple> -p elf-tools --bin frame-sizes -- \
+//!     [--limit <bytes>] [--all] target/bpfel-unknown-none/release/libstake_ebpf_check.so [more.so ...]
+//! ```
+//!
+//! Fails if any frame exceeds the limit: `--limit`, else `STACK_FRAME_LIMIT`
+//! as for the link-time check in `stake-ebpf-check/build.rs`, else the SBF
+//! frame size. The link-time check only names the first function over; this
+//! lists every one, with how close the rest come. Without `--all`, only
+//! functions with a frame at all are listed.
+
+use std::process::ExitCode;
+
+use elf_tools::frame::{frame_sizes, Source};
+use elf_tools::Program;
+
+const SBF_STACK_FRAME_SIZE: u64 = 4096;
+
+const USAGE: &str = "usage: frame-sizes [--limit <bytes>] [--all] <program.so>...";
+
+fn main_inner(args: &[String]) -> Result<bool, String> {
+    let mut limit = match std::env::var("STACK_FRAME_LIMIT") {
+        Ok(value) => value
+            .parse()
+            .map_err(|_| format!("STACK_FRAME_LIMIT: not a byte count: {value}"))?,
+        Err(_) => SBF_STACK_FRAME_SIZE,
+    };
+    let mut all = false;
+    let mut paths = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--limit" => {
+                let value = args.next().ok_or(USAGE)?;
+                limit = value
+                    .parse()
+                    .map_err(|_| format!("--limit: not a byte count: {value}"))?;
+            }
+            "--all" => all = true,
+            _ => paths.push(arg),
+        }
+    }
+    if paths.is_empty() {
+        return Err(USAGE.to_string());
+    }
+
+    let mut failed = false;
+    for path in paths {
+        let bytes = std::fs::read(path).map_err(|e| format!("{path}: {e}"))?;
+        let program = Program::parse(&bytes).map_err(|e| format!("{path}: {e}"))?;
+        let frames = frame_sizes(&program);
+        let over = frames.iter().filter(|frame| frame.size > limit).count();
+        println!(
+            "{path}: {} functions, {over} over {limit} bytes",
+            frames.len()
+        );
+        println!(
+            "  {:>6}  {:>6}  {:<6}  function",
+            "bytes", "insns", "source"
+        );
+        for frame in frames.iter().filter(|frame| all || frame.size > 0) {
+            let source = match frame.source {
+                Source::Recorded => "record",
+                Source::Disassembly => "disasm",
+            };
+            println!(
+                "{} {:>6}  {:>6}  {source:<6}  {}",
+                if frame.size > limit { '!' } else { ' ' },
+                frame.size,
+                frame.function.len(),
+                frame.function.name
+            );
+        }
+        failed |= over > 0;
+    }
+    Ok(failed)
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match main_inner(&args) {
+        Ok(false) => ExitCode::SUCCESS,
+        Ok(true) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
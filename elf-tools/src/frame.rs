@@ -0,0 +1,146 @@
+//! How deep into its stack frame each function reaches.
+//!
+//! Each SBF call gets a fixed frame (4 KiB) with `r10` pointing at its top,
+//! and LLVM addresses locals at negative offsets from it. The deepest such
+//! offset in a function is its frame size. Where the build kept a
+//! `.stack_sizes` section (`-Z emit-stack-sizes`) that is the source;
+//! otherwise the size is read off the disassembly.
+
+use std::collections::BTreeMap;
+
+use solana_sbpf::ebpf::{self, Insn};
+
+use crate::{Function, Program};
+
+/// Where a [`FrameSize`] came from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Source {
+    /// The compiler's `.stack_sizes` record.
+    Recorded,
+    /// The deepest `r10`-relative access in the disassembly.
+    Disassembly,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct FrameSize<'p> {
+    pub function: &'p Function,
+    /// Bytes below `r10` the function touches.
+    pub size: u64,
+    pub source: Source,
+}
+
+/// Every function's frame, largest first.
+pub fn frame_sizes<'p>(program: &'p Program) -> Vec<FrameSize<'p>> {
+    let recorded = recorded(program);
+    let mut frames: Vec<FrameSize> = program
+        .functions()
+        .iter()
+        .map(|function| match recorded.get(&function.start) {
+            Some(&size) => FrameSize {
+                function,
+                size,
+                source: Source::Recorded,
+            },
+            None => FrameSize {
+                function,
+                size: disassembled(program, function),
+                source: Source::Disassembly,
+            },
+        })
+        .collect();
+    frames.sort_by(|a, b| {
+        b.size
+            .cmp(&a.size)
+            .then(a.function.start.cmp(&b.function.start))
+    });
+    frames
+}
+
+/// `.stack_sizes` entries by instruction index: an 8-byte function address
+/// followed by the size as ULEB128.
+fn recorded(program: &Program) -> BTreeMap<usize, u64> {
+    let mut sizes = BTreeMap::new();
+    let Some(mut bytes) = program.section_bytes(".stack_sizes") else {
+        return sizes;
+    };
+    while bytes.len() > 8 {
+        let addr = u64::from_le_bytes(bytes[..8].try_into().unwrap());
+        bytes = &bytes[8..];
+        let (mut size, mut shift) = (0u64, 0);
+        while let Some((&byte, rest)) = bytes.split_first() {
+            bytes = rest;
+            size |= u64::from(byte & 0x7f) << shift;
+            shift += 7;
+            if byte & 0x80 == 0 || shift >= 64 {
+                break;
+            }
+        }
+        if let Some(index) = program.index_of(addr) {
+            sizes.insert(index, size);
+        }
+    }
+    sizes
+}
+
+/// The deepest access below `r10`, following copies of `r10` adjusted by
+/// constants (`mov64 r1, r10; add64 r1, -40`) the way LLVM takes the
+/// address of a local.
+///
+/// Registers are tracked in instruction order without following branches,
+/// which is how LLVM lays these sequences out; a register redefined on one
+/// path only is dropped at its next write.
+fn disassembled(program: &Program, function: &Function) -> u64 {
+    // Register -> its offset from r10.
+    let mut frame_pointers = [None::<i64>; 11];
+    frame_pointers[ebpf::FRAME_PTR_REG] = Some(0);
+    let mut deepest = 0i64;
+    let mut index = function.start;
+    while index < function.end {
+        let insn = program.insn(index);
+        index += if insn.opc == ebpf::LD_DW_IMM { 2 } else { 1 };
+
+        if let Some(offset) = access_offset(&insn, &frame_pointers) {
+            deepest = deepest.max(-offset);
+        }
+        if insn.opc == ebpf::CALL_IMM || insn.opc == ebpf::CALL_REG {
+            frame_pointers[..=5].fill(None);
+            continue;
+        }
+        let dst = insn.dst as usize;
+        if dst >= ebpf::FRAME_PTR_REG || !writes_dst(&insn) {
+            continue;
+        }
+        frame_pointers[dst] = match insn.opc {
+            ebpf::MOV64_REG => frame_pointers[insn.src as usize],
+            ebpf::ADD64_IMM => frame_pointers[dst].map(|offset| offset + insn.imm),
+            ebpf::SUB64_IMM => frame_pointers[dst].map(|offset| offset - insn.imm),
+            _ => None,
+        };
+        if let Some(offset) = frame_pointers[dst] {
+            deepest = deepest.max(-offset);
+        }
+    }
+    deepest.max(0) as u64
+}
+
+/// The offset from `r10` a load or store addresses, if its base register
+/// is a frame pointer.
+fn access_offset(insn: &Insn, frame_pointers: &[Option<i64>; 11]) -> Option<i64> {
+    let base = match insn.opc & ebpf::BPF_CLS_MASK {
+        ebpf::BPF_LDX => insn.src,
+        ebpf::BPF_ST | ebpf::BPF_STX => insn.dst,
+        _ => return None,
+    };
+    let offset = (*frame_pointers.get(base as usize)?)?;
+    Some(offset + i64::from(insn.off))
+}
+
+/// Whether `insn` overwrites its destination register: ALU ops, loads and
+/// `lddw`.
+fn writes_dst(insn: &Insn) -> bool {
+    match insn.opc & ebpf::BPF_CLS_MASK {
+        ebpf::BPF_ALU32_LOAD | ebpf::BPF_ALU64_STORE | ebpf::BPF_PQR | ebpf::BPF_LDX => true,
+        ebpf::BPF_LD => insn.opc == ebpf::LD_DW_IMM,
+        _ => false,
+    }
+}
@@ -0,0 +1,290 @@
+//! Static views of a compiled program `.so`, shared by the binaries in
+//! `src/bin`.
+//!
+//! Nothing here loads or runs the program; see `rbpf-runner` for that. The
+//! parse is lenient where the loader is strict, so a build the loader would
+//! reject can still be inspected for why.
+
+use std::collections::BTreeMap;
+
+use solana_sbpf::ebpf::{self, Insn};
+use solana_sbpf::elf_parser::consts::{EM_BPF, EM_SBPF, R_X86_64_32, SHF_WRITE};
+use solana_sbpf::elf_parser::types::Elf64Shdr;
+use solana_sbpf::elf_parser::Elf64;
+use solana_sbpf::program::SBPFVersion;
+
+pub mod frame;
+
+/// A function symbol, as instruction indices into `.text`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Function {
+    pub name: String,
+    pub start: usize,
+    /// One past the last instruction.
+    pub end: usize,
+}
+
+impl Function {
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+}
+
+/// Where a `call` instruction goes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CallTarget<'p> {
+    /// A function in `.text`, with its symbol if it has one.
+    Local(usize, Option<&'p Function>),
+    /// A symbol the loader resolves: a syscall, or nothing at all.
+    External(&'p str),
+    /// `callx`, through a register.
+    Register,
+}
+
+pub struct Program<'a> {
+    bytes: &'a [u8],
+    elf: Elf64<'a>,
+    text: &'a [u8],
+    text_addr: u64,
+    sbpf_version: SBPFVersion,
+    /// Sorted by `start`.
+    functions: Vec<Function>,
+    /// Relocated call instructions, by index, and the symbol each names.
+    external_calls: BTreeMap<usize, String>,
+    /// Relocations the loader does not support, as `(offset, type)`.
+    unsupported_relocations: Vec<(u64, u32)>,
+}
+
+impl<'a> Program<'a> {
+    pub fn parse(bytes: &'a [u8]) -> Result<Self, String> {
+        let elf = Elf64::parse(bytes).map_err(|e| format!("parsing: {e}"))?;
+        let header = elf.file_header();
+        if header.e_machine != EM_BPF && header.e_machine != EM_SBPF {
+            return Err(format!("not a BPF ELF (machine {})", header.e_machine));
+        }
+        let sbpf_version = match header.e_flags {
+            0 => SBPFVersion::V0,
+            1 => SBPFVersion::V1,
+            2 => SBPFVersion::V2,
+            3 => SBPFVersion::V3,
+            _ => SBPFVersion::Reserved,
+        };
+
+        let text_header = section(&elf, b".text").ok_or("no .text section")?;
+        let text = text_header
+            .file_range()
+            .and_then(|range| bytes.get(range))
+            .ok_or(".text out of bounds")?;
+        let text_addr = text_header.sh_addr;
+        let text_len = text.len() / ebpf::INSN_SIZE;
+        let index_of = |addr: u64| -> Option<usize> {
+            let offset = addr.checked_sub(text_addr)?;
+            let index = (offset / ebpf::INSN_SIZE as u64) as usize;
+            (index < text_len).then_some(index)
+        };
+
+        let mut starts = BTreeMap::new();
+        let symbols = elf.symbol_table().ok().flatten().unwrap_or_default();
+        for symbol in symbols {
+            if let (true, Some(start), Ok(name)) = (
+                symbol.is_function(),
+                index_of(symbol.st_value),
+                elf.symbol_name(symbol.st_name),
+            ) {
+                starts.insert(start, (name, symbol.st_size));
+            }
+        }
+        for symbol in elf.dynamic_symbol_table().unwrap_or_default() {
+            if let (true, Some(start), Ok(name)) = (
+                symbol.is_function(),
+                index_of(symbol.st_value),
+                elf.dynamic_symbol_name(symbol.st_name),
+            ) {
+                starts.entry(start).or_insert((name, symbol.st_size));
+            }
+        }
+        let mut functions = Vec::with_capacity(starts.len());
+        let mut iter = starts.iter().peekable();
+        while let Some((&start, &(name, size))) = iter.next() {
+            let next = iter.peek().map_or(text_len, |(&next, _)| next);
+            let end = match (size as usize) / ebpf::INSN_SIZE {
+                0 => next,
+                len => (start + len).min(text_len),
+            };
+            functions.push(Function {
+                name: demangle(name),
+                start,
+                end,
+            });
+        }
+
+        let mut external_calls = BTreeMap::new();
+        let mut unsupported_relocations = Vec::new();
+        let dynamic_symbols = elf.dynamic_symbol_table().unwrap_or_default();
+        for relocation in elf.dynamic_relocations_table().unwrap_or_default() {
+            match relocation.r_type() {
+                // Absolute and relative data relocations; nothing to report.
+                0 | 1 | 8 => {}
+                R_X86_64_32 => {
+                    let name = dynamic_symbols
+                        .get(relocation.r_sym() as usize)
+                        .and_then(|symbol| elf.dynamic_symbol_name(symbol.st_name).ok())
+                        .map(|name| String::from_utf8_lossy(name).into_owned())
+                        .unwrap_or_default();
+                    if let Some(index) = index_of(relocation.r_offset) {
+                        external_calls.insert(index, name);
+                    }
+                }
+                r_type => unsupported_relocations.push((relocation.r_offset, r_type)),
+            }
+        }
+
+        Ok(Self {
+            bytes,
+            elf,
+            text,
+            text_addr,
+            sbpf_version,
+            functions,
+            external_calls,
+            unsupported_relocations,
+        })
+    }
+
+    pub fn sbpf_version(&self) -> SBPFVersion {
+        self.sbpf_version
+    }
+
+    pub fn text(&self) -> &'a [u8] {
+        self.text
+    }
+
+    /// Address of `.text` in the ELF, for reporting offsets.
+    pub fn text_addr(&self) -> u64 {
+        self.text_addr
+    }
+
+    /// Length of `.text` in instruction slots.
+    pub fn len(&self) -> usize {
+        self.text.len() / ebpf::INSN_SIZE
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The instruction index of `addr`, if it is in `.text`.
+    pub fn index_of(&self, addr: u64) -> Option<usize> {
+        let offset = addr.checked_sub(self.text_addr)?;
+        let index = (offset / ebpf::INSN_SIZE as u64) as usize;
+        (index < self.len()).then_some(index)
+    }
+
+    pub fn insn(&self, index: usize) -> Insn {
+        ebpf::get_insn(self.text, index)
+    }
+
+    /// Every instruction in order, the second slot of each `lddw` skipped.
+    pub fn instructions(&self) -> impl Iterator<Item = Insn> + '_ {
+        let mut index = 0;
+        std::iter::from_fn(move || {
+            if index >= self.len() {
+                return None;
+            }
+            let insn = self.insn(index);
+            index += if insn.opc == ebpf::LD_DW_IMM { 2 } else { 1 };
+            Some(insn)
+        })
+    }
+
+    pub fn functions(&self) -> &[Function] {
+        &self.functions
+    }
+
+    /// The function containing instruction `index`.
+    pub fn function_at(&self, index: usize) -> Option<&Function> {
+        let position = self
+            .functions
+            .partition_point(|function| function.start <= index);
+        self.functions[..position]
+            .last()
+            .filter(|function| index < function.end)
+    }
+
+    pub fn function_named(&self, name: &str) -> Option<&Function> {
+        self.functions.iter().find(|function| function.name == name)
+    }
+
+    /// Where `insn` calls, if it is a call.
+    ///
+    /// In SBPFv0 a `call` whose immediate is `-1` is patched by the loader
+    /// through a relocation; any other immediate is relative to the next
+    /// instruction.
+    pub fn call_target(&self, insn: &Insn) -> Option<CallTarget<'_>> {
+        match insn.opc {
+            ebpf::CALL_REG => Some(CallTarget::Register),
+            ebpf::CALL_IMM => match self.external_calls.get(&insn.ptr) {
+                Some(name) => Some(CallTarget::External(name)),
+                None if insn.imm == -1 => Some(CallTarget::External("")),
+                None => {
+                    let target = (insn.ptr as i64 + 1 + insn.imm) as usize;
+                    let function = self
+                        .functions
+                        .iter()
+                        .find(|function| function.start == target);
+                    Some(CallTarget::Local(target, function))
+                }
+            },
+            _ => None,
+        }
+    }
+
+    /// Relocations the loader rejects, as `(offset, type)`.
+    pub fn unsupported_relocations(&self) -> &[(u64, u32)] {
+        &self.unsupported_relocations
+    }
+
+    /// Sections the loader refuses as writable data: any `.bss`, and
+    /// writable `.data` other than `.data.rel*`.
+    pub fn writable_sections(&self) -> Vec<(String, u64)> {
+        self.elf
+            .section_header_table()
+            .iter()
+            .filter_map(|header| {
+                let name = self.elf.section_name(header.sh_name).ok()?;
+                let writable_data = header.sh_flags & SHF_WRITE != 0
+                    && name.starts_with(b".data")
+                    && !name.starts_with(b".data.rel");
+                (name.starts_with(b".bss") || writable_data)
+                    .then(|| (String::from_utf8_lossy(name).into_owned(), header.sh_size))
+            })
+            .collect()
+    }
+
+    /// Size of the section called `name`, if present.
+    pub fn section_size(&self, name: &str) -> Option<u64> {
+        section(&self.elf, name.as_bytes()).map(|header| header.sh_size)
+    }
+
+    /// Contents of the section called `name`, if present and in the file.
+    pub fn section_bytes(&self, name: &str) -> Option<&'a [u8]> {
+        let range = section(&self.elf, name.as_bytes())?.file_range()?;
+        self.bytes.get(range)
+    }
+}
+
+fn section<'e>(elf: &'e Elf64, name: &[u8]) -> Option<&'e Elf64Shdr> {
+    elf.section_header_table()
+        .iter()
+        .find(|header| elf.section_name(header.sh_name).ok() == Some(name))
+}
+
+/// Rust symbols without the hash suffix; others unchanged.
+fn demangle(name: &[u8]) -> String {
+    let name = String::from_utf8_lossy(name);
+    format!("{:#}", rustc_demangle::demangle(&name))
+}
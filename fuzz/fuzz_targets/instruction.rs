@@ -0,0 +1,86 @@
+//! Arbitrary instruction data through the native loader stand-in
+//! ([`host_sim::invoke`]), with a Clock, a result and a diag account.
+//!
+//! ```text
+//! cargo +nightly fuzz run instruction --target <host-triple> [--features <backends>]
+//! ```
+//!
+//! Any input may fail, but none may panic. An `Allowance` that succeeds
+//! must have written what `streaming` computes for the same operands, since
+//! every backend has to agree with it.
+//!
+//! `cargo xtask fuzz-seeds` writes a starting corpus from the shared
+//! known-answer vectors.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use stake_ebpf_check::host_sim::{self, SimAccount};
+use stake_ebpf_check::instruction::{Instruction, Operands, MAX_BATCH_LEN};
+use stake_ebpf_check::program::SUCCESS;
+use stake_ebpf_check::results::{batch_result_len, AllowanceResult};
+use stake_ebpf_check::{Backend, Epoch};
+
+const PROGRAM_ID: [u8; 32] = [0xaa; 32];
+const RESULT_KEY: [u8; 32] = [0xbb; 32];
+const DIAG_KEY: [u8; 32] = [0xcc; 32];
+
+/// Room for the largest result, a full batch.
+const RESULT_LEN: usize = batch_result_len(MAX_BATCH_LEN);
+const DIAG_LEN: usize = 4096;
+
+/// Caps `Stress` iterations and `MeasureComputeUnits` repetitions, which
+/// would otherwise time the fuzzer out rather than find anything.
+const MAX_LOOP: u64 = 1_000;
+
+fn too_slow(instruction: &Instruction) -> bool {
+    match *instruction {
+        Instruction::Stress { iterations, .. } => iterations > MAX_LOOP,
+        Instruction::MeasureComputeUnits { repetitions, .. } => repetitions > MAX_LOOP,
+        _ => false,
+    }
+}
+
+fn expected(operands: &Operands, epoch: Epoch) -> (u64, u64) {
+    let operands = Operands { epoch, ..*operands };
+    let streaming = Backend::Streaming;
+    (
+        streaming.calculate_activation_allowance(
+            operands.epoch,
+            operands.account_portion,
+            &operands.cluster_state,
+            operands.new_rate_activation_epoch,
+        ),
+        streaming.calculate_deactivation_allowance(
+            operands.epoch,
+            (operands.account_portion / 2) + 1,
+            &operands.cluster_state,
+            operands.new_rate_activation_epoch,
+        ),
+    )
+}
+
+fuzz_target!(|input: (Epoch, &[u8])| {
+    let (epoch, data) = input;
+    let parsed = Instruction::unpack(data);
+    if parsed.as_ref().is_ok_and(too_slow) {
+        return;
+    }
+
+    let mut accounts = [
+        SimAccount::clock(epoch),
+        SimAccount::result(RESULT_KEY, PROGRAM_ID, RESULT_LEN),
+        SimAccount::result(DIAG_KEY, PROGRAM_ID, DIAG_LEN),
+    ];
+    let code = host_sim::invoke(&mut accounts, data, &PROGRAM_ID);
+
+    if let (SUCCESS, Ok(Instruction::Allowance { operands, .. })) = (code, parsed) {
+        let result = AllowanceResult::read(&accounts[1].data).expect("written on success");
+        assert_eq!(
+            (result.activation, result.deactivation),
+            expected(&operands, epoch),
+            "backend {} on {operands:?} at epoch {epoch}",
+            result.backend_id
+        );
+    }
+});
@@ -0,0 +1,135 @@
+//! Arbitrary delegations run epoch by epoch the way the runtime records
+//! them, checking the cluster-wide rate limit at every step.
+//!
+//! ```text
+//! cargo +nightly fuzz run multi_epoch --target <host-triple> [--features <backends>]
+//! ```
+//!
+//! Each epoch every delegation's status comes from
+//! [`Delegation::stake_activating_and_deactivating`] on the history so far,
+//! and their sum, on top of a fixed rest of the cluster, becomes the epoch's
+//! history entry. Between consecutive epochs, what the delegations gained
+//! and lost in effective stake must each pass
+//! [`invariant::check_epoch_invariant`] against the previous entry. Gains
+//! are exempt while the previous entry has no effective stake, since an
+//! empty cluster activates everything at once. Every backend must also
+//! produce the same history as `streaming`.
+//!
+//! The inputs come from the [`stake_ebpf_check::fuzzing`] impls, so
+//! delegations overlap in time; raw `u64` fuzzing almost never lines up
+//! their activation and deactivation epochs.
+
+#![no_main]
+
+use libfuzzer_sys::arbitrary::{self, Arbitrary};
+use libfuzzer_sys::fuzz_target;
+use stake_ebpf_check::delegation::StakeActivationStatus;
+use stake_ebpf_check::fuzzing::EPOCH_SPAN;
+use stake_ebpf_check::invariant;
+use stake_ebpf_check::stake_history::StakeHistoryEntry;
+use stake_ebpf_check::state::Delegation;
+use stake_ebpf_check::{Backend, BackendVisitor, Epoch, RateSchedule, StakeCalculator};
+
+/// Keeps each run, quadratic in epochs per delegation, well inside the
+/// fuzzer's timeout.
+const MAX_DELEGATIONS: usize = 32;
+
+#[derive(Arbitrary, Debug)]
+struct Scenario<'a> {
+    /// Stake outside the tracked delegations, held as it is.
+    rest: StakeHistoryEntry,
+    delegations: Vec<Delegation>,
+    schedule: RateSchedule<'a>,
+    /// Past the last generated deactivation, so cooldowns can finish.
+    extra_epochs: u8,
+}
+
+struct Simulate<'s> {
+    scenario: &'s Scenario<'s>,
+    new_rate_activation_epoch: Option<Epoch>,
+}
+
+impl BackendVisitor for Simulate<'_> {
+    /// Newest first.
+    type Output = Vec<(Epoch, StakeHistoryEntry)>;
+
+    fn visit<T: StakeCalculator>(self) -> Self::Output {
+        let delegations = &self.scenario.delegations[..];
+        let last_epoch = 2 * EPOCH_SPAN + Epoch::from(self.scenario.extra_epochs);
+        let mut history: Vec<(Epoch, StakeHistoryEntry)> = Vec::new();
+        let mut previous: Vec<StakeActivationStatus> = Vec::new();
+
+        for epoch in 0..=last_epoch {
+            let statuses: Vec<_> = delegations
+                .iter()
+                .map(|delegation| {
+                    delegation.stake_activating_and_deactivating::<T>(
+                        epoch,
+                        &history[..],
+                        self.new_rate_activation_epoch,
+                    )
+                })
+                .collect();
+
+            if let Some(&(_, prev_entry)) = history.first() {
+                let rate_bps = self.scenario.schedule.rate_bps(epoch);
+                let gained: Vec<u64> = statuses
+                    .iter()
+                    .zip(&previous)
+                    .map(|(now, before)| now.effective.saturating_sub(before.effective))
+                    .collect();
+                let lost: Vec<u64> = statuses
+                    .iter()
+                    .zip(&previous)
+                    .map(|(now, before)| before.effective.saturating_sub(now.effective))
+                    .collect();
+                if prev_entry.effective != 0 {
+                    if let Err(violation) =
+                        invariant::check_epoch_invariant(&gained, &prev_entry, rate_bps)
+                    {
+                        panic!("activation at epoch {epoch}: {violation:?} after {prev_entry:?}");
+                    }
+                }
+                if let Err(violation) =
+                    invariant::check_epoch_invariant(&lost, &prev_entry, rate_bps)
+                {
+                    panic!("deactivation at epoch {epoch}: {violation:?} after {prev_entry:?}");
+                }
+            }
+
+            let entry = statuses.iter().fold(self.scenario.rest, |sum, status| {
+                sum + StakeHistoryEntry {
+                    activating: status.activating,
+                    deactivating: status.deactivating,
+                    effective: status.effective,
+                }
+            });
+            history.insert(0, (epoch, entry));
+            previous = statuses;
+        }
+        history
+    }
+}
+
+fuzz_target!(|scenario: Scenario| {
+    if scenario.delegations.len() > MAX_DELEGATIONS {
+        return;
+    }
+    // The only schedule `Arbitrary` makes, and the only one the delegation
+    // path takes.
+    let RateSchedule::TwoRate(new_rate_activation_epoch) = scenario.schedule else {
+        return;
+    };
+    let simulate = || Simulate {
+        scenario: &scenario,
+        new_rate_activation_epoch,
+    };
+
+    let reference = Backend::Streaming.visit(simulate());
+    for &backend in Backend::ALL {
+        assert!(
+            backend.visit(simulate()) == reference,
+            "{backend:?} history differs from streaming"
+        );
+    }
+});
@@ -0,0 +1,63 @@
+//! Arbitrary `entrypoint(arg)` values: every backend must agree with
+//! `streaming`, whose result must pass the independent consensus check.
+//! A disagreement is shrunk and written out as a JSON report (see
+//! [`stake_ebpf_check::divergence`]) before the target fails.
+//!
+//! ```text
+//! cargo +nightly fuzz run packed_arg --target <host-triple> [--features <backends>]
+//! ```
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use stake_ebpf_check::consensus;
+use stake_ebpf_check::divergence;
+use stake_ebpf_check::packed_args::PackedOperands;
+use stake_ebpf_check::{warmup_cooldown_rate_bps, Backend, BackendVisitor, StakeCalculator};
+
+struct Run(PackedOperands);
+
+impl BackendVisitor for Run {
+    type Output = (u64, u64);
+
+    fn visit<T: StakeCalculator>(self) -> (u64, u64) {
+        self.0.allowances::<T>()
+    }
+}
+
+fuzz_target!(|arg: u64| {
+    let operands = PackedOperands::unpack(arg);
+    let (activation, deactivation) = Backend::Streaming.visit(Run(operands));
+
+    let cluster = &operands.cluster_state;
+    assert!(consensus::verify(
+        warmup_cooldown_rate_bps(operands.epoch, operands.activation_rate_epoch),
+        operands.account_stake,
+        cluster.activating,
+        cluster.effective,
+        activation,
+    ));
+    assert!(consensus::verify(
+        warmup_cooldown_rate_bps(operands.epoch, operands.deactivation_rate_epoch),
+        operands.deactivating_stake,
+        cluster.deactivating,
+        cluster.effective,
+        deactivation,
+    ));
+
+    for &backend in Backend::ALL {
+        let computed = backend.visit(Run(operands));
+        if computed != (activation, deactivation) {
+            for report in divergence::packed_reports(arg, "fuzz packed_arg") {
+                if let Ok(path) = divergence::write_report(&divergence::report_dir(), &report) {
+                    eprintln!("divergence report: {}", path.display());
+                }
+            }
+        }
+        assert_eq!(
+            computed,
+            (activation, deactivation),
+            "{backend:?} on arg {arg:#x}"
+        );
+    }
+});
@@ -0,0 +1,149 @@
+//! Interpreted instructions per call of each backend build, through
+//! criterion.
+//!
+//! ```text
+//! cargo xtask build-all
+//! cargo bench --target <host-triple> -p rbpf-runner --bench instructions
+//! ```
+//!
+//! Runs `entrypoint_full` of every `stake_ebpf_check-<backend>.so` in
+//! `target/artifacts/` (or `$STAKE_EBPF_CHECK_ARTIFACTS`) on the
+//! [`bench_vectors::VECTORS`] in turn, as `Allowance` for the build's
+//! backend. [`Instructions`] stands in for wall time, so criterion's
+//! estimates, outlier analysis and comparison with the last run are all in
+//! instructions, which is what the validator charges as compute units.
+//! Syscall costs come on top; `rbpf-runner` lists them. Builds that are
+//! missing are skipped.
+
+use std::cell::Cell;
+use std::path::PathBuf;
+
+use criterion::measurement::{Measurement, ValueFormatter};
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use rbpf_runner::vm::{Build, INPUT_START};
+use rbpf_runner::BACKENDS;
+use stake_ebpf_check::bench_vectors;
+use stake_ebpf_check::instruction::{Instruction, ALLOWANCE_INSTRUCTION_LEN};
+
+/// Overrides the directory builds are read from.
+const ARTIFACTS_ENV: &str = "STAKE_EBPF_CHECK_ARTIFACTS";
+
+thread_local! {
+    /// Instructions executed by every call so far on this thread.
+    static EXECUTED: Cell<u64> = const { Cell::new(0) };
+}
+
+/// Criterion measurement reading [`EXECUTED`] around each sample, so a
+/// routine only has to add what its calls ran.
+struct Instructions;
+
+impl Measurement for Instructions {
+    type Intermediate = u64;
+    type Value = u64;
+
+    fn start(&self) -> u64 {
+        EXECUTED.get()
+    }
+
+    fn end(&self, start: u64) -> u64 {
+        EXECUTED.get() - start
+    }
+
+    fn add(&self, v1: &u64, v2: &u64) -> u64 {
+        v1 + v2
+    }
+
+    fn zero(&self) -> u64 {
+        0
+    }
+
+    fn to_f64(&self, value: &u64) -> f64 {
+        *value as f64
+    }
+
+    fn formatter(&self) -> &dyn ValueFormatter {
+        &InstructionFormatter
+    }
+}
+
+/// Counts as they are; no unit prefixes.
+struct InstructionFormatter;
+
+impl ValueFormatter for InstructionFormatter {
+    fn scale_values(&self, _typical_value: f64, _values: &mut [f64]) -> &'static str {
+        "insns"
+    }
+
+    fn scale_throughputs(
+        &self,
+        _typical_value: f64,
+        throughput: &Throughput,
+        values: &mut [f64],
+    ) -> &'static str {
+        let (per, unit) = match *throughput {
+            Throughput::Elements(elements) => (elements, "insns/elem"),
+            Throughput::Bytes(bytes) | Throughput::BytesDecimal(bytes) => (bytes, "insns/byte"),
+        };
+        for value in values {
+            *value /= per as f64;
+        }
+        unit
+    }
+
+    fn scale_for_machines(&self, _values: &mut [f64]) -> &'static str {
+        "insns"
+    }
+}
+
+fn artifact_dir() -> PathBuf {
+    match std::env::var_os(ARTIFACTS_ENV) {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../target/artifacts"),
+    }
+}
+
+fn instructions(c: &mut Criterion<Instructions>) {
+    let dir = artifact_dir();
+    let mut group = c.benchmark_group("allowance");
+    for (backend, backend_id) in BACKENDS {
+        let path = dir.join(format!("stake_ebpf_check-{backend}.so"));
+        let Ok(bytes) = std::fs::read(&path) else {
+            eprintln!("skipping {backend}: no {}", path.display());
+            continue;
+        };
+        let build = Build::load(&bytes).unwrap_or_else(|e| panic!("{}: {e}", path.display()));
+        let Some(full) = build.symbol("entrypoint_full") else {
+            eprintln!(
+                "skipping {backend}: {} has no entrypoint_full",
+                path.display()
+            );
+            continue;
+        };
+
+        group.bench_function(backend, |b| {
+            let mut vectors = bench_vectors::VECTORS.iter().cycle();
+            b.iter(|| {
+                let vector = vectors.next().unwrap();
+                let mut input = [0u8; ALLOWANCE_INSTRUCTION_LEN];
+                Instruction::Allowance {
+                    backend_id,
+                    operands: vector.operands,
+                }
+                .pack(&mut input);
+                let run = build.run(full, INPUT_START, &mut input);
+                if let Err(e) = run.result {
+                    panic!("{backend} on {}: {e}", vector.name);
+                }
+                EXECUTED.set(EXECUTED.get() + run.instructions);
+            });
+        });
+    }
+    group.finish();
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().with_measurement(Instructions);
+    targets = instructions
+}
+criterion_main!(benches);
@@ -0,0 +1,15 @@
+//! The `solana-sbpf` harness behind the `rbpf-runner` binary and the
+//! `instructions` benchmark.
+
+pub mod vm;
+
+/// Backend names as they appear in `entrypoint_<name>`, by wire id.
+pub const BACKENDS: [(&str, u8); 7] = [
+    ("bnum", 0),
+    ("crypto", 1),
+    ("fixed", 2),
+    ("uint", 3),
+    ("plain", 4),
+    ("manual", 5),
+    ("streaming", 6),
+];
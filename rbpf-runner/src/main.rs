@@ -0,0 +1,214 @@
+//! Counts the instructions each backend entry point executes in the
+//! `solana-sbpf` interpreter, the VM the validator runs programs in.
+//!
+//! ```text
+//! cargo build-manual
+//! cp target/bpfel-unknown-none/release/libstake_ebpf_check.so manual.so
+//! cargo run --target <host-triple> -p rbpf-runner -- manual.so [streaming.so ...]
+//! cargo run --target <host-triple> -p rbpf-runner -- --manifest target/artifacts/manifest.json
+//! ```
+//!
+//! Builds must come from the packed entry points, not `solana-program`.
+//! Every `entrypoint_<backend>` symbol a build exports runs on each of
+//! [`PACKED_ARGS`], and `entrypoint_full` runs each of those backends on
+//! every [`bench_vectors::VECTORS`] entry. Executed instructions are what
+//! the validator charges as compute units; syscalls are counted separately
+//! since each carries its own fixed cost on top. `cargo bench -p
+//! rbpf-runner` measures the same `entrypoint_full` calls with criterion.
+//!
+//! `--manifest` runs every artifact a `cargo xtask build-all` manifest
+//! lists and writes each one's summary back into it as `instructions`:
+//!
+//! ```json
+//! "instructions": { "runs": 36, "mean": 1234, "min": 96, "max": 2345,
+//!                   "syscalls": { "sol_log_": 0 } }
+//! ```
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::process::ExitCode;
+
+use serde_json::{json, Value};
+
+use stake_ebpf_check::bench_vectors;
+use stake_ebpf_check::instruction::{Instruction, ALLOWANCE_INSTRUCTION_LEN};
+use stake_ebpf_check::packed_return::PackedReturn;
+
+use rbpf_runner::vm::{self, Build, Run};
+use rbpf_runner::BACKENDS;
+
+/// Arguments for the packed symbols: the smallest operands, both 16-bit
+/// fields at their maximum, and a mixed value with every epoch bit set.
+const PACKED_ARGS: [u64; 4] = [0, 0x0001_0001, 0xffff_ffff, u64::MAX];
+
+const USAGE: &str = "usage: rbpf-runner <program.so>... | --manifest <manifest.json>";
+
+fn describe(run: &Run) -> String {
+    match run.result {
+        Ok(value) => match PackedReturn::decode(value) {
+            Some(packed) => format!("{:?} checksum {:#014x}", packed.status, packed.checksum),
+            None => format!("returned {value:#x}"),
+        },
+        Err(ref error) => format!("failed: {error}"),
+    }
+}
+
+fn print_run(backend: &str, input: &str, run: &Run) {
+    println!(
+        "  {backend:<10} {input:<20} {:>7} insns  {}",
+        run.instructions,
+        describe(run)
+    );
+}
+
+/// Instruction totals and syscall counts of one backend over every run.
+#[derive(Default)]
+struct Summary {
+    runs: u64,
+    instructions: u64,
+    min: Option<u64>,
+    max: u64,
+    syscalls: BTreeMap<&'static str, u64>,
+}
+
+impl Summary {
+    fn add(&mut self, run: &Run) {
+        self.runs += 1;
+        self.instructions += run.instructions;
+        self.min = Some(
+            self.min
+                .map_or(run.instructions, |min| min.min(run.instructions)),
+        );
+        self.max = self.max.max(run.instructions);
+        for (name, count) in &run.syscalls {
+            *self.syscalls.entry(name).or_default() += count;
+        }
+    }
+
+    fn mean(&self) -> u64 {
+        self.instructions / self.runs.max(1)
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "runs": self.runs,
+            "mean": self.mean(),
+            "min": self.min.unwrap_or(0),
+            "max": self.max,
+            "syscalls": self.syscalls,
+        })
+    }
+}
+
+fn run_build(path: &str, summaries: &mut BTreeMap<String, Summary>) -> Result<bool, String> {
+    let bytes = std::fs::read(path).map_err(|e| format!("{path}: {e}"))?;
+    let build = Build::load(&bytes).map_err(|e| format!("{path}: {e}"))?;
+    println!("{path}");
+
+    let mut failed = false;
+    for (name, id) in BACKENDS {
+        let Some(packed) = build.symbol(&format!("entrypoint_{name}")) else {
+            continue;
+        };
+        let summary = summaries.entry(format!("{path} {name}")).or_default();
+        for arg in PACKED_ARGS {
+            let run = build.run(packed, arg, &mut []);
+            print_run(name, &format!("packed {arg:#x}"), &run);
+            failed |= run.result.is_err();
+            summary.add(&run);
+        }
+
+        let Some(full) = build.symbol("entrypoint_full") else {
+            continue;
+        };
+        for vector in bench_vectors::VECTORS {
+            let mut input = [0u8; ALLOWANCE_INSTRUCTION_LEN];
+            Instruction::Allowance {
+                backend_id: id,
+                operands: vector.operands,
+            }
+            .pack(&mut input);
+            let run = build.run(full, vm::INPUT_START, &mut input);
+            print_run(name, vector.name, &run);
+            failed |= run.result.is_err();
+            summary.add(&run);
+        }
+    }
+    Ok(failed)
+}
+
+fn print_summaries(summaries: &BTreeMap<String, Summary>) {
+    println!();
+    for (backend, summary) in summaries {
+        println!(
+            "{backend}: {} runs, {} insns mean, {} min, {} max",
+            summary.runs,
+            summary.mean(),
+            summary.min.unwrap_or(0),
+            summary.max
+        );
+        for (name, count) in &summary.syscalls {
+            println!("    {name}: {count}");
+        }
+    }
+}
+
+/// Runs every artifact in the manifest at `path` and records its summary
+/// there.
+fn run_manifest(path: &str) -> Result<bool, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("{path}: {e}"))?;
+    let mut manifest: Value = serde_json::from_str(&text).map_err(|e| format!("{path}: {e}"))?;
+    let dir = Path::new(path).parent().unwrap_or(Path::new(""));
+    let artifacts = manifest["artifacts"]
+        .as_array_mut()
+        .ok_or_else(|| format!("{path}: no artifacts"))?;
+
+    let mut summaries = BTreeMap::new();
+    let mut failed = false;
+    for artifact in artifacts {
+        let (Some(file), Some(backend)) = (artifact["file"].as_str(), artifact["backend"].as_str())
+        else {
+            return Err(format!("{path}: malformed artifact {artifact}"));
+        };
+        let build = dir.join(file).display().to_string();
+        failed |= run_build(&build, &mut summaries)?;
+        let summary = summaries
+            .get(&format!("{build} {backend}"))
+            .ok_or_else(|| format!("{build}: no entrypoint_{backend}"))?;
+        artifact["instructions"] = summary.to_json();
+    }
+    print_summaries(&summaries);
+
+    let text = serde_json::to_string_pretty(&manifest).unwrap() + "\n";
+    std::fs::write(path, text).map_err(|e| format!("{path}: {e}"))?;
+    println!("\ninstruction counts written to {path}");
+    Ok(failed)
+}
+
+fn main_inner(args: &[String]) -> Result<bool, String> {
+    match args {
+        [] => return Err(USAGE.to_string()),
+        [flag, path] if flag == "--manifest" => return run_manifest(path),
+        _ => {}
+    }
+
+    let mut summaries = BTreeMap::new();
+    let mut failed = false;
+    for path in args {
+        failed |= run_build(path, &mut summaries)?;
+    }
+    print_summaries(&summaries);
+    Ok(failed)
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match main_inner(&args) {
+        Ok(false) => ExitCode::SUCCESS,
+        Ok(true) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,230 @@
+//! Loading a program build and calling one of its symbols.
+//!
+//! `EbpfVm::execute_program` always starts at the ELF entry point with `r1`
+//! pointing at the input region, so calls go through the interpreter
+//! directly instead: `r11` (the program counter) at the symbol, `r1` set to
+//! the argument.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use solana_sbpf::aligned_memory::AlignedMemory;
+use solana_sbpf::declare_builtin_function;
+use solana_sbpf::ebpf::{self, HOST_ALIGN};
+use solana_sbpf::elf::Executable;
+use solana_sbpf::elf_parser::Elf64;
+use solana_sbpf::error::ProgramResult;
+use solana_sbpf::interpreter::Interpreter;
+use solana_sbpf::memory_region::{MemoryMapping, MemoryRegion};
+use solana_sbpf::program::BuiltinProgram;
+use solana_sbpf::vm::{Config, ContextObject, EbpfVm};
+
+pub use solana_sbpf::ebpf::MM_INPUT_START as INPUT_START;
+
+/// The validator's per-instruction compute budget, as an instruction limit.
+const INSTRUCTION_LIMIT: u64 = 1_400_000;
+
+const HEAP_LEN: usize = 32 * 1024;
+
+/// Instruction meter and syscall tally for one call.
+pub struct Meter {
+    remaining: u64,
+    syscalls: BTreeMap<&'static str, u64>,
+}
+
+impl Meter {
+    fn count(&mut self, name: &'static str) {
+        *self.syscalls.entry(name).or_default() += 1;
+    }
+}
+
+impl ContextObject for Meter {
+    fn trace(&mut self, _state: [u64; 12]) {}
+
+    fn consume(&mut self, amount: u64) {
+        self.remaining = self.remaining.saturating_sub(amount);
+    }
+
+    fn get_remaining(&self) -> u64 {
+        self.remaining
+    }
+}
+
+#[derive(Debug)]
+struct Abort(&'static str);
+
+impl std::fmt::Display for Abort {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "program called {}", self.0)
+    }
+}
+
+impl std::error::Error for Abort {}
+
+/// A syscall the runner only counts, returning `$ret(meter)`.
+macro_rules! counted_syscall {
+    ($name:ident, $symbol:literal, |$meter:ident| $ret:expr) => {
+        declare_builtin_function!(
+            $name,
+            fn rust(
+                $meter: &mut Meter,
+                _arg1: u64,
+                _arg2: u64,
+                _arg3: u64,
+                _arg4: u64,
+                _arg5: u64,
+                _memory_mapping: &mut MemoryMapping,
+            ) -> Result<u64, Box<dyn std::error::Error>> {
+                $meter.count($symbol);
+                $ret
+            }
+        );
+    };
+}
+
+counted_syscall!(SyscallLog, "sol_log_", |meter| Ok(0));
+counted_syscall!(SyscallLog64, "sol_log_64_", |meter| Ok(0));
+counted_syscall!(
+    SyscallRemainingComputeUnits,
+    "sol_remaining_compute_units",
+    |meter| Ok(meter.remaining)
+);
+counted_syscall!(SyscallPanic, "sol_panic_", |meter| Err(Box::new(Abort(
+    "sol_panic_"
+))));
+counted_syscall!(SyscallAbort, "abort", |meter| Err(Box::new(Abort("abort"))));
+
+fn loader() -> Result<BuiltinProgram<Meter>, String> {
+    let mut loader = BuiltinProgram::new_loader(Config {
+        enable_symbol_and_section_labels: true,
+        ..Config::default()
+    });
+    let syscalls: [(&str, _); 5] = [
+        ("sol_log_", SyscallLog::vm as _),
+        ("sol_log_64_", SyscallLog64::vm as _),
+        (
+            "sol_remaining_compute_units",
+            SyscallRemainingComputeUnits::vm as _,
+        ),
+        ("sol_panic_", SyscallPanic::vm as _),
+        ("abort", SyscallAbort::vm as _),
+    ];
+    for (name, function) in syscalls {
+        loader
+            .register_function(name, function)
+            .map_err(|e| format!("registering {name}: {e}"))?;
+    }
+    Ok(loader)
+}
+
+/// Outcome of one call.
+pub struct Run {
+    pub instructions: u64,
+    /// `r0` on return, or why the VM stopped.
+    pub result: Result<u64, String>,
+    pub syscalls: BTreeMap<&'static str, u64>,
+}
+
+/// A verified executable and the program counter of each exported function.
+pub struct Build {
+    executable: Executable<Meter>,
+    symbols: BTreeMap<String, u64>,
+}
+
+impl Build {
+    pub fn load(bytes: &[u8]) -> Result<Self, String> {
+        let executable = Executable::from_elf(bytes, Arc::new(loader()?))
+            .map_err(|e| format!("loading: {e}"))?;
+
+        let elf = Elf64::parse(bytes).map_err(|e| format!("parsing: {e}"))?;
+        let text = elf
+            .section_header_table()
+            .iter()
+            .find(|header| elf.section_name(header.sh_name).ok() == Some(b".text"))
+            .ok_or("no .text section")?;
+        let mut symbols = BTreeMap::new();
+        for symbol in elf.dynamic_symbol_table().unwrap_or_default() {
+            if !symbol.is_function() || !text.vm_range().contains(&symbol.st_value) {
+                continue;
+            }
+            let Ok(name) = elf.dynamic_symbol_name(symbol.st_name) else {
+                continue;
+            };
+            let pc = (symbol.st_value - text.sh_addr) / ebpf::INSN_SIZE as u64;
+            symbols.insert(String::from_utf8_lossy(name).into_owned(), pc);
+        }
+
+        Ok(Self {
+            executable,
+            symbols,
+        })
+    }
+
+    /// Program counter of the exported function `name`.
+    pub fn symbol(&self, name: &str) -> Option<u64> {
+        self.symbols.get(name).copied()
+    }
+
+    /// Calls the function at `pc` with `arg` in `r1` and `input` mapped at
+    /// [`INPUT_START`].
+    pub fn run(&self, pc: u64, arg: u64, input: &mut [u8]) -> Run {
+        let config = self.executable.get_config();
+        let sbpf_version = self.executable.get_sbpf_version();
+        let mut stack = AlignedMemory::<HOST_ALIGN>::zero_filled(config.stack_size());
+        let mut heap = AlignedMemory::<HOST_ALIGN>::zero_filled(HEAP_LEN);
+        let stack_len = stack.len();
+        let stack_gap = if !sbpf_version.dynamic_stack_frames() && config.enable_stack_frame_gaps {
+            config.stack_frame_size as u64
+        } else {
+            0
+        };
+        let regions = vec![
+            self.executable.get_ro_region(),
+            MemoryRegion::new_writable_gapped(
+                stack.as_slice_mut(),
+                ebpf::MM_STACK_START,
+                stack_gap,
+            ),
+            MemoryRegion::new_writable(heap.as_slice_mut(), ebpf::MM_HEAP_START),
+            MemoryRegion::new_writable(input, INPUT_START),
+        ];
+        let mapping = match MemoryMapping::new(regions, config, sbpf_version) {
+            Ok(mapping) => mapping,
+            Err(e) => {
+                return Run {
+                    instructions: 0,
+                    result: Err(format!("mapping memory: {e}")),
+                    syscalls: BTreeMap::new(),
+                }
+            }
+        };
+
+        let mut meter = Meter {
+            remaining: INSTRUCTION_LIMIT,
+            syscalls: BTreeMap::new(),
+        };
+        let mut vm = EbpfVm::new(
+            self.executable.get_loader().clone(),
+            sbpf_version,
+            &mut meter,
+            mapping,
+            stack_len,
+        );
+        let mut registers = vm.registers;
+        registers[1] = arg;
+        registers[11] = pc;
+        vm.previous_instruction_meter = INSTRUCTION_LIMIT;
+        vm.due_insn_count = 0;
+        let mut interpreter = Interpreter::new(&mut vm, &self.executable, registers);
+        while interpreter.step() {}
+        let due = vm.due_insn_count;
+        vm.context_object_pointer.consume(due);
+        let result = std::mem::replace(&mut vm.program_result, ProgramResult::Ok(0));
+
+        Run {
+            instructions: INSTRUCTION_LIMIT - meter.remaining,
+            result: Result::from(result).map_err(|e| e.to_string()),
+            syscalls: meter.syscalls,
+        }
+    }
+}
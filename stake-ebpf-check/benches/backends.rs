@@ -0,0 +1,76 @@
+//! Host throughput of every compiled-in backend on one operand corpus.
+//!
+//! ```text
+//! cargo bench --target <host-triple> --features host-sim,<backends> --bench backends
+//! ```
+//!
+//! Each iteration computes one allowance, stepping through the corpus, so
+//! criterion's time is per operation and its throughput in operations per
+//! second. The corpus is [`StressOperands`] drawn from a fixed seed, every
+//! magnitude about equally often, so backends with fast narrow paths do not
+//! get only narrow operands. Sets with a zero stake operand are left out,
+//! since `plain` divides by each of them. This is the cost that bounds the
+//! host tools (`monte-carlo`, `snapshot-replay`); on-chain cost is compute
+//! units and ranks the backends differently, see `cu-bench`.
+
+use criterion::measurement::WallTime;
+use criterion::{
+    black_box, criterion_group, criterion_main, BenchmarkGroup, Criterion, Throughput,
+};
+use stake_ebpf_check::stress::{StressOperands, Xorshift64Star};
+use stake_ebpf_check::{Backend, BackendVisitor, StakeCalculator};
+
+const SEED: u64 = 1;
+const CORPUS_SIZE: usize = 4_096;
+
+fn corpus() -> Vec<StressOperands> {
+    let mut rng = Xorshift64Star::new(SEED);
+    std::iter::repeat_with(|| StressOperands::generate(&mut rng))
+        .filter(|o| o.account_portion != 0 && o.cluster_portion != 0 && o.cluster_effective != 0)
+        .take(CORPUS_SIZE)
+        .collect()
+}
+
+struct Bench<'g, 'c> {
+    group: &'g mut BenchmarkGroup<'c, WallTime>,
+    backend: Backend,
+    corpus: &'g [StressOperands],
+}
+
+impl BackendVisitor for Bench<'_, '_> {
+    type Output = ();
+
+    fn visit<T: StakeCalculator>(self) {
+        let corpus = self.corpus;
+        self.group
+            .bench_function(format!("{:?}", self.backend), |b| {
+                let mut operands = corpus.iter().cycle();
+                b.iter(|| {
+                    let o = operands.next().unwrap();
+                    T::rate_limited_stake_change_bps(
+                        black_box(o.rate_bps),
+                        black_box(o.account_portion),
+                        black_box(o.cluster_portion),
+                        black_box(o.cluster_effective),
+                    )
+                });
+            });
+    }
+}
+
+fn backends(c: &mut Criterion) {
+    let corpus = corpus();
+    let mut group = c.benchmark_group("rate_limited_stake_change_bps");
+    group.throughput(Throughput::Elements(1));
+    for &backend in Backend::ALL {
+        backend.visit(Bench {
+            group: &mut group,
+            backend,
+            corpus: &corpus,
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, backends);
+criterion_main!(benches);
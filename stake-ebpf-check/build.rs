@@ -0,0 +1,67 @@
+//! Enforces the SBF 4 KiB stack frame limit at link time.
+//!
+//! `bpf-linker` runs LLVM code generation for the whole program, so handing
+//! it the frame limit turns any function whose frame would overflow on the VM
+//! into a link error naming that function. `STACK_FRAME_LIMIT` overrides the
+//! limit, e.g. to see how much headroom the backends leave.
+//!
+//! The `stack-budget-violation` feature exports a function with an 8 KiB
+//! frame; `cargo check-stack-budget` builds it and checks the link fails
+//! naming it.
+//!
+//! It also generates `self_test::VECTORS` from the shared vector files, so
+//! the program checks itself against the same answers as the host tests.
+
+use std::env;
+use std::fmt::Write;
+use std::path::Path;
+
+const SBF_STACK_FRAME_SIZE: u32 = 4096;
+
+/// Writes `$OUT_DIR/self_test_vectors.rs`, the `VECTORS` table
+/// `src/self_test.rs` includes.
+fn generate_self_test_vectors() {
+    let dir = test_vectors::vector_dir();
+    println!("cargo:rerun-if-changed={}", dir.display());
+    let files = test_vectors::files(&dir).unwrap_or_else(|e| panic!("{e}"));
+    for path in &files {
+        println!("cargo:rerun-if-changed={}", path.display());
+    }
+    let vectors = test_vectors::load_dir(&dir).unwrap_or_else(|e| panic!("{e}"));
+
+    let mut table = String::from("pub const VECTORS: &[Vector] = &[\n");
+    for v in &vectors {
+        writeln!(
+            table,
+            "    // {}\n    vector({}, {}, {}, {}, {}),",
+            v.name,
+            v.rate_bps,
+            v.account_portion,
+            v.cluster_portion,
+            v.cluster_effective,
+            v.expected
+        )
+        .unwrap();
+    }
+    table.push_str("];\n");
+
+    let out = Path::new(&env::var("OUT_DIR").unwrap()).join("self_test_vectors.rs");
+    std::fs::write(out, table).unwrap();
+}
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-env-changed=STACK_FRAME_LIMIT");
+    generate_self_test_vectors();
+
+    if env::var("CARGO_CFG_TARGET_ARCH").as_deref() != Ok("bpf") {
+        return;
+    }
+    let limit = match env::var("STACK_FRAME_LIMIT") {
+        Ok(limit) => limit
+            .parse::<u32>()
+            .expect("STACK_FRAME_LIMIT must be a byte count"),
+        Err(_) => SBF_STACK_FRAME_SIZE,
+    };
+    println!("cargo:rustc-link-arg=--llvm-args=-bpf-stack-size={limit}");
+}
@@ -0,0 +1,110 @@
+#ifndef STAKE_EBPF_CHECK_H
+#define STAKE_EBPF_CHECK_H
+
+/* Generated by cbindgen from src/ffi.rs; run `cargo xtask ffi-header` instead of editing. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+#define STAKE_OK 0
+
+/**
+ * The backend id names a backend not compiled into the library.
+ */
+#define STAKE_ERR_BACKEND 1
+
+/**
+ * A required pointer was null.
+ */
+#define STAKE_ERR_NULL 2
+
+/**
+ * Stands in for an absent epoch.
+ */
+#define STAKE_EPOCH_NONE UINT64_MAX
+
+/**
+ * The cluster's stake in one epoch, as the StakeHistory sysvar records it.
+ */
+typedef struct StakeClusterState {
+  uint64_t effective;
+  uint64_t activating;
+  uint64_t deactivating;
+} StakeClusterState;
+
+typedef struct StakeHistoryItem {
+  uint64_t epoch;
+  struct StakeClusterState state;
+} StakeHistoryItem;
+
+typedef struct StakeStatus {
+  uint64_t effective;
+  uint64_t activating;
+  uint64_t deactivating;
+} StakeStatus;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Stake of `account_stake` that becomes effective at the end of `epoch`,
+ * given the cluster's state in the previous epoch.
+ *
+ * # Safety
+ *
+ * `cluster` must point to a readable [`StakeClusterState`] and `out` to a
+ * writable `u64`.
+ */
+int32_t stake_activation_allowance(uint8_t backend_id,
+                                   uint64_t epoch,
+                                   uint64_t account_stake,
+                                   const struct StakeClusterState *cluster,
+                                   uint64_t new_rate_activation_epoch,
+                                   uint64_t *out);
+
+/**
+ * Stake of `account_stake` that cools down at the end of `epoch`, given
+ * the cluster's state in the previous epoch.
+ *
+ * # Safety
+ *
+ * As for [`stake_activation_allowance`].
+ */
+int32_t stake_deactivation_allowance(uint8_t backend_id,
+                                     uint64_t epoch,
+                                     uint64_t account_stake,
+                                     const struct StakeClusterState *cluster,
+                                     uint64_t new_rate_activation_epoch,
+                                     uint64_t *out);
+
+/**
+ * Effective, activating and deactivating stake of a delegation at `epoch`.
+ *
+ * `history` holds `history_len` entries, newest first with no repeated
+ * epoch, as in the StakeHistory sysvar; it may be null if `history_len`
+ * is zero.
+ *
+ * # Safety
+ *
+ * `history` must point to `history_len` readable [`StakeHistoryItem`]s and
+ * `out` to a writable [`StakeStatus`].
+ */
+int32_t stake_delegation_status(uint8_t backend_id,
+                                uint64_t stake,
+                                uint64_t activation_epoch,
+                                uint64_t deactivation_epoch,
+                                uint64_t epoch,
+                                const struct StakeHistoryItem *history,
+                                size_t history_len,
+                                uint64_t new_rate_activation_epoch,
+                                struct StakeStatus *out);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* STAKE_EBPF_CHECK_H */
@@ -0,0 +1,53 @@
+//! Per-vote-account stake totals from a snapshot of delegations.
+
+use crate::delegation::StakeActivationStatus;
+use crate::stake_history::StakeHistoryGetEntry;
+use crate::state::{Delegation, Pubkey};
+use crate::{Epoch, StakeCalculator};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct VoterTotals {
+    pub voter_pubkey: Pubkey,
+    pub status: StakeActivationStatus,
+}
+
+/// `out` has no room for another vote account.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OutputFull;
+
+/// Sums each delegation's status at `epoch` into one entry per vote account,
+/// in order of first appearance. Returns how many entries of `out` are used.
+pub fn aggregate_by_voter<T: StakeCalculator>(
+    delegations: &[Delegation],
+    epoch: Epoch,
+    history: &(impl StakeHistoryGetEntry + ?Sized),
+    new_rate_activation_epoch: Option<Epoch>,
+    out: &mut [VoterTotals],
+) -> Result<usize, OutputFull> {
+    let mut used = 0;
+    for delegation in delegations {
+        let status = delegation.stake_activating_and_deactivating::<T>(
+            epoch,
+            history,
+            new_rate_activation_epoch,
+        );
+
+        let index = match out[..used]
+            .iter()
+            .position(|totals| totals.voter_pubkey == delegation.voter_pubkey)
+        {
+            Some(index) => index,
+            None => {
+                let slot = out.get_mut(used).ok_or(OutputFull)?;
+                *slot = VoterTotals {
+                    voter_pubkey: delegation.voter_pubkey,
+                    status: StakeActivationStatus::default(),
+                };
+                used += 1;
+                used - 1
+            }
+        };
+        out[index].status = out[index].status + status;
+    }
+    Ok(used)
+}
@@ -0,0 +1,14 @@
+//! Integer-only yield estimates from a per-epoch rate.
+
+use crate::streaming::compound_bps;
+use crate::BASIS_POINTS_PER_UNIT;
+
+/// Compounded annual yield in basis points.
+pub fn estimate_apy_bps(rate_per_epoch_bps: u64, epochs_per_year: u64) -> Option<u64> {
+    compound_bps(rate_per_epoch_bps, epochs_per_year).map(|f| f - BASIS_POINTS_PER_UNIT)
+}
+
+/// Simple (non-compounded) annual yield in basis points.
+pub fn estimate_simple_apy_bps(rate_per_epoch_bps: u64, epochs_per_year: u64) -> Option<u64> {
+    rate_per_epoch_bps.checked_mul(epochs_per_year)
+}
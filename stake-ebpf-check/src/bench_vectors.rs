@@ -0,0 +1,93 @@
+//! Named `Allowance` operands for compute-unit measurements.
+//!
+//! Each set steers the calculators down a different path: early-outs on
+//! zero operands, products that fit in 64 bits, mainnet-sized stakes whose
+//! product needs the full 128, and the top of the range, where the quotient
+//! clamps or overflows. Measuring tools run every backend on every set so
+//! counts line up across backends and builds.
+
+use crate::instruction::Operands;
+use crate::stake_history::StakeHistoryEntry;
+use crate::Epoch;
+
+const LAMPORTS_PER_SOL: u64 = 1_000_000_000;
+
+/// An epoch after [`NEW_RATE_EPOCH`], so the tower rate applies.
+const TOWER_EPOCH: Epoch = 800;
+
+const NEW_RATE_EPOCH: Option<Epoch> = Some(600);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BenchVector {
+    pub name: &'static str,
+    pub operands: Operands,
+}
+
+const fn vector(
+    name: &'static str,
+    new_rate_activation_epoch: Option<Epoch>,
+    account_portion: u64,
+    effective: u64,
+    activating: u64,
+    deactivating: u64,
+) -> BenchVector {
+    BenchVector {
+        name,
+        operands: Operands {
+            epoch: TOWER_EPOCH,
+            account_portion,
+            cluster_state: StakeHistoryEntry {
+                effective,
+                activating,
+                deactivating,
+            },
+            new_rate_activation_epoch,
+        },
+    }
+}
+
+pub const VECTORS: &[BenchVector] = &[
+    vector("zero-account", NEW_RATE_EPOCH, 0, 1_000, 100, 100),
+    vector("zero-effective", NEW_RATE_EPOCH, 100, 0, 100, 100),
+    vector("small", NEW_RATE_EPOCH, 1_000, 1_000, 1_000, 1_000),
+    vector("small-original-rate", None, 1_000, 1_000, 1_000, 1_000),
+    vector(
+        "mainnet",
+        NEW_RATE_EPOCH,
+        10 * LAMPORTS_PER_SOL,
+        380_000_000 * LAMPORTS_PER_SOL,
+        200_000_000 * LAMPORTS_PER_SOL,
+        150_000_000 * LAMPORTS_PER_SOL,
+    ),
+    vector(
+        "mainnet-whale",
+        NEW_RATE_EPOCH,
+        5_000_000 * LAMPORTS_PER_SOL,
+        380_000_000 * LAMPORTS_PER_SOL,
+        2_000_000 * LAMPORTS_PER_SOL,
+        1_500_000 * LAMPORTS_PER_SOL,
+    ),
+    vector(
+        "wide-product",
+        NEW_RATE_EPOCH,
+        1 << 40,
+        1 << 50,
+        (1 << 45) + 7,
+        (1 << 44) + 3,
+    ),
+    vector("clamped", NEW_RATE_EPOCH, 1_000, 1_000_000, 1, 1),
+    vector("quotient-overflow", None, u64::MAX, u64::MAX, 1, 1),
+    vector(
+        "max-operands",
+        NEW_RATE_EPOCH,
+        u64::MAX - 1,
+        u64::MAX - 1,
+        u64::MAX,
+        u64::MAX,
+    ),
+];
+
+/// The vector called `name`, if any.
+pub fn find(name: &str) -> Option<&'static BenchVector> {
+    VECTORS.iter().find(|vector| vector.name == name)
+}
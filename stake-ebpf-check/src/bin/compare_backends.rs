@@ -0,0 +1,99 @@
+//! Runs every compiled-in backend on the same seeded random operands and
+//! reports where they disagree.
+//!
+//! ```text
+//! cargo run --release --target <host-triple> --features host-sim,<backends> \
+//!     --bin compare-backends -- [seed] [iterations] [max-reports]
+//! ```
+//!
+//! Operands come from [`StressOperands::generate`], with zero cluster
+//! portions skipped since the instruction rejects them and `plain` divides
+//! by them. Each divergence is shrunk operand by operand while the backends
+//! still disagree, so reports show the smallest inputs found rather than
+//! the random ones, and the first `max-reports` are also written out as
+//! JSON (see [`stake_ebpf_check::divergence`]). Exits non-zero if any case
+//! diverged.
+
+use std::process::ExitCode;
+
+use stake_ebpf_check::divergence::{self, DivergenceReport};
+use stake_ebpf_check::stress::{StressOperands, Xorshift64Star};
+use stake_ebpf_check::Backend;
+
+const DEFAULT_SEED: u64 = 1;
+const DEFAULT_ITERATIONS: u64 = 1_000_000;
+const DEFAULT_MAX_REPORTS: u64 = 10;
+
+const USAGE: &str = "usage: compare-backends [seed] [iterations] [max-reports]";
+
+fn report(report: &DivergenceReport) -> Result<(), String> {
+    let operands = &report.minimized;
+    println!(
+        "{}: rate_bps={} account={} cluster={} effective={}",
+        report.source,
+        operands.rate_bps,
+        operands.account_portion,
+        operands.cluster_portion,
+        operands.cluster_effective
+    );
+    for (backend, result) in &report.outputs {
+        println!("    {backend:?}: {result}");
+    }
+    let dir = divergence::report_dir();
+    let path = divergence::write_report(&dir, report)
+        .map_err(|e| format!("writing report to {}: {e}", dir.display()))?;
+    println!("    report: {}", path.display());
+    Ok(())
+}
+
+fn parse_arg(args: &[String], index: usize, default: u64) -> Result<u64, String> {
+    match args.get(index) {
+        Some(arg) => arg
+            .parse()
+            .map_err(|_| format!("invalid argument `{arg}`\n{USAGE}")),
+        None => Ok(default),
+    }
+}
+
+fn main_inner(args: &[String]) -> Result<u64, String> {
+    let seed = parse_arg(args, 0, DEFAULT_SEED)?;
+    let iterations = parse_arg(args, 1, DEFAULT_ITERATIONS)?;
+    let max_reports = parse_arg(args, 2, DEFAULT_MAX_REPORTS)?;
+    if Backend::ALL.len() < 2 {
+        return Err(format!(
+            "need at least two backends to compare; have {:?}",
+            Backend::ALL
+        ));
+    }
+
+    let mut rng = Xorshift64Star::new(seed);
+    let mut divergences = 0;
+    for index in 0..iterations {
+        let operands = StressOperands::generate(&mut rng);
+        if operands.cluster_portion == 0 || !divergence::diverges(&operands) {
+            continue;
+        }
+        divergences += 1;
+        if divergences <= max_reports {
+            let source = format!("compare-backends seed {seed} case {index}");
+            report(&DivergenceReport::new(source, operands))?;
+        }
+    }
+    println!(
+        "{iterations} cases, {divergences} divergent, backends {:?}, seed {seed}",
+        Backend::ALL
+    );
+    Ok(divergences)
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match main_inner(&args) {
+        Ok(0) => ExitCode::SUCCESS,
+        Ok(_) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,84 @@
+//! Serves the reference calculation over HTTP for implementations in other
+//! languages to validate against.
+//!
+//! ```text
+//! cargo run --release --target <host-triple> --features oracle,<backends> --bin conformance-oracle -- \
+//!     [--backend <id>] [--listen <addr>]
+//! curl -s --data-binary @my_answers.json http://127.0.0.1:8383/check
+//! ```
+//!
+//! The routes and bodies are described in [`stake_ebpf_check::oracle`]. The
+//! reference is the `--backend` (default the highest id compiled in, the
+//! streaming calculator in a full build); `--listen` defaults to
+//! `127.0.0.1:8383`.
+
+use std::net::TcpListener;
+use std::process::ExitCode;
+use std::sync::Arc;
+
+use stake_ebpf_check::oracle::{self, Oracle};
+use stake_ebpf_check::Backend;
+
+const DEFAULT_LISTEN: &str = "127.0.0.1:8383";
+
+const USAGE: &str = "usage: conformance-oracle [--backend <id>] [--listen <addr>]";
+
+struct Options {
+    backend: Backend,
+    listen: String,
+}
+
+fn parse<T: std::str::FromStr>(flag: &str, value: Option<&String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("{flag} needs a value\n{USAGE}"))?;
+    value
+        .parse()
+        .map_err(|_| format!("invalid {flag} `{value}`\n{USAGE}"))
+}
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let mut backend = Backend::ALL.last().copied();
+    let mut listen = DEFAULT_LISTEN.to_owned();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--backend" => {
+                let id = parse("--backend", args.next())?;
+                backend = Some(Backend::from_id(id).ok_or_else(|| {
+                    format!("backend {id} is not compiled in; have {:?}", Backend::ALL)
+                })?);
+            }
+            "--listen" => listen = parse("--listen", args.next())?,
+            _ => return Err(format!("unknown argument `{arg}`\n{USAGE}")),
+        }
+    }
+
+    Ok(Options {
+        backend: backend.ok_or("no backend compiled in")?,
+        listen,
+    })
+}
+
+fn main_inner(args: &[String]) -> Result<(), String> {
+    let options = parse_options(args)?;
+    let known_answers = test_vectors::load()?;
+    let listener =
+        TcpListener::bind(&options.listen).map_err(|e| format!("{}: {e}", options.listen))?;
+    eprintln!(
+        "serving {:?} answers on http://{}",
+        options.backend, options.listen
+    );
+    let oracle = Arc::new(Oracle::new(options.backend, known_answers));
+    oracle::serve(listener, oracle).map_err(|e| e.to_string())
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match main_inner(&args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
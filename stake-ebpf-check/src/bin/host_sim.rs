@@ -0,0 +1,216 @@
+//! Runs the program entrypoint natively, the way the loader would.
+//!
+//! ```text
+//! cargo run --target <host-triple> --features host-sim,<backends> --bin host-sim -- \
+//!     allowance <backend> <epoch> <account> <effective> <activating> <deactivating> [new-rate-epoch]
+//!     self-test <backend>
+//!     stress <backend> <seed> <iterations>
+//!     batch <backend> <epoch> <effective> <activating> <deactivating> <account>...
+//!     differential <backend> <other-backend> <epoch> <account> <effective> <activating> <deactivating>
+//! ```
+//!
+//! The workspace defaults to the `bpfel` target, hence the explicit host
+//! triple. Built with the `trace` feature, every calculator call is
+//! appended to the file `$STAKE_TRACE` names, if set (see
+//! [`stake_ebpf_check::trace`]).
+
+use std::process::ExitCode;
+
+use stake_ebpf_check::host_sim::{self, SimAccount};
+use stake_ebpf_check::instruction::{
+    BatchOperands, Instruction, InstructionError, Operands, MAX_INSTRUCTION_LEN,
+};
+use stake_ebpf_check::results::{
+    batch_result_len, read_batch_entry, AllowanceResult, BatchResultHeader, DifferentialResult,
+    SelfTestResult, StressResult, ALLOWANCE_RESULT_LEN, DIFFERENTIAL_RESULT_LEN,
+    SELF_TEST_RESULT_LEN, STRESS_RESULT_LEN,
+};
+use stake_ebpf_check::stake_history::StakeHistoryEntry;
+use stake_ebpf_check::{Backend, Epoch};
+
+const PROGRAM_ID: [u8; 32] = [0xaa; 32];
+const RESULT_KEY: [u8; 32] = [0xbb; 32];
+
+const USAGE: &str = "usage: host-sim allowance <backend> <epoch> <account> <effective> <activating> <deactivating> [new-rate-epoch]
+       host-sim self-test <backend>
+       host-sim stress <backend> <seed> <iterations>
+       host-sim batch <backend> <epoch> <effective> <activating> <deactivating> <account>...
+       host-sim differential <backend> <other-backend> <epoch> <account> <effective> <activating> <deactivating>";
+
+fn parse<T: std::str::FromStr>(args: &[String], index: usize) -> Result<T, String> {
+    let arg = args.get(index).ok_or_else(|| USAGE.to_string())?;
+    arg.parse().map_err(|_| format!("invalid argument `{arg}`"))
+}
+
+fn backend(args: &[String]) -> Result<u8, String> {
+    backend_at(args, 1)
+}
+
+fn backend_at(args: &[String], index: usize) -> Result<u8, String> {
+    let id = parse(args, index)?;
+    match Backend::from_id(id) {
+        Some(_) => Ok(id),
+        None => Err(format!(
+            "backend {id} is not compiled in; have {:?}",
+            Backend::ALL
+        )),
+    }
+}
+
+/// Runs `instruction` against a fresh result account of `len` bytes,
+/// preceded by a Clock sysvar at `clock_epoch` if given and the program
+/// reads it.
+fn run(
+    instruction: Instruction<'_>,
+    clock_epoch: Option<Epoch>,
+    len: usize,
+) -> Result<Vec<u8>, String> {
+    match invoke(instruction, clock_epoch, len) {
+        (0, data) => Ok(data),
+        (code, _) => Err(format!("entrypoint returned {code:#x}")),
+    }
+}
+
+/// [`run`], returning the result account's data whatever the entrypoint
+/// returned, along with its return code.
+fn invoke(instruction: Instruction<'_>, clock_epoch: Option<Epoch>, len: usize) -> (u64, Vec<u8>) {
+    let mut data = [0u8; MAX_INSTRUCTION_LEN];
+    let data_len = instruction.pack(&mut data);
+    let mut accounts = Vec::new();
+    if let Some(epoch) = clock_epoch.filter(|_| !cfg!(feature = "instruction-epoch")) {
+        accounts.push(SimAccount::clock(epoch));
+    }
+    accounts.push(SimAccount::result(RESULT_KEY, PROGRAM_ID, len));
+    let code = host_sim::invoke(&mut accounts, &data[..data_len], &PROGRAM_ID);
+    let data = accounts
+        .pop()
+        .map(|account| account.data)
+        .unwrap_or_default();
+    (code, data)
+}
+
+fn main_inner(args: &[String]) -> Result<(), String> {
+    match args.first().map(String::as_str) {
+        Some("allowance") => {
+            let operands = Operands {
+                epoch: parse(args, 2)?,
+                account_portion: parse(args, 3)?,
+                cluster_state: StakeHistoryEntry {
+                    effective: parse(args, 4)?,
+                    activating: parse(args, 5)?,
+                    deactivating: parse(args, 6)?,
+                },
+                new_rate_activation_epoch: args.get(7).map(|_| parse(args, 7)).transpose()?,
+            };
+            let instruction = Instruction::Allowance {
+                backend_id: backend(args)?,
+                operands,
+            };
+            let data = run(instruction, Some(operands.epoch), ALLOWANCE_RESULT_LEN)?;
+            println!("{:?}", AllowanceResult::read(&data));
+        }
+        Some("self-test") => {
+            let instruction = Instruction::SelfTest {
+                backend_id: backend(args)?,
+            };
+            let data = run(instruction, None, SELF_TEST_RESULT_LEN)?;
+            println!("{:?}", SelfTestResult::read(&data));
+        }
+        Some("stress") => {
+            let instruction = Instruction::Stress {
+                backend_id: backend(args)?,
+                seed: parse(args, 2)?,
+                iterations: parse(args, 3)?,
+            };
+            let data = run(instruction, None, STRESS_RESULT_LEN)?;
+            println!("{:?}", StressResult::read(&data));
+        }
+        Some("batch") => {
+            let cluster_state = StakeHistoryEntry {
+                effective: parse(args, 3)?,
+                activating: parse(args, 4)?,
+                deactivating: parse(args, 5)?,
+            };
+            let portions = (6..args.len())
+                .map(|index| parse(args, index))
+                .collect::<Result<Vec<u64>, _>>()?;
+            let portion_bytes: Vec<u8> = portions.iter().flat_map(|p| p.to_le_bytes()).collect();
+            let operands = BatchOperands::new(parse(args, 2)?, cluster_state, None, &portion_bytes)
+                .ok_or("too many accounts")?;
+            let instruction = Instruction::Batch {
+                backend_id: backend(args)?,
+                operands,
+            };
+            let data = run(
+                instruction,
+                Some(operands.epoch),
+                batch_result_len(portions.len()),
+            )?;
+            println!("{:?}", BatchResultHeader::read(&data));
+            for index in 0..portions.len() {
+                println!("{:?}", read_batch_entry(&data, index));
+            }
+        }
+        Some("differential") => {
+            let operands = Operands {
+                epoch: parse(args, 3)?,
+                account_portion: parse(args, 4)?,
+                cluster_state: StakeHistoryEntry {
+                    effective: parse(args, 5)?,
+                    activating: parse(args, 6)?,
+                    deactivating: parse(args, 7)?,
+                },
+                new_rate_activation_epoch: None,
+            };
+            let instruction = Instruction::Differential {
+                backend_id: backend(args)?,
+                other_backend_id: backend_at(args, 2)?,
+                operands,
+            };
+            // A divergence fails the instruction; unlike a cluster, the
+            // simulator keeps the result it wrote first.
+            let divergence = u64::from(InstructionError::Divergence.code());
+            let (code, data) = invoke(instruction, Some(operands.epoch), DIFFERENTIAL_RESULT_LEN);
+            if code != 0 && code != divergence {
+                return Err(format!("entrypoint returned {code:#x}"));
+            }
+            println!("{:?}", DifferentialResult::read(&data));
+            if code == divergence {
+                return Err("backends diverge".to_string());
+            }
+        }
+        _ => return Err(USAGE.to_string()),
+    }
+    Ok(())
+}
+
+/// `main_inner`, recording calculator calls to `$STAKE_TRACE` if set.
+#[cfg(feature = "trace")]
+fn traced_main(args: &[String]) -> Result<(), String> {
+    use stake_ebpf_check::trace;
+
+    let Some(path) = std::env::var_os("STAKE_TRACE") else {
+        return main_inner(args);
+    };
+    let path = std::path::PathBuf::from(path);
+    trace::start(&path).map_err(|e| format!("{}: {e}", path.display()))?;
+    let result = main_inner(args);
+    trace::stop().map_err(|e| format!("{}: {e}", path.display()))?;
+    result
+}
+
+#[cfg(not(feature = "trace"))]
+fn traced_main(args: &[String]) -> Result<(), String> {
+    main_inner(args)
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match traced_main(&args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
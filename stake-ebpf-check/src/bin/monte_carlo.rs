@@ -0,0 +1,416 @@
+//! Monte Carlo simulation of cluster warmup and cooldown under random
+//! deposit and withdrawal churn.
+//!
+//! ```text
+//! cargo run --release --target <host-triple> --features host-sim,<backends> --bin monte-carlo -- \
+//!     [--backend <id>] [--seed <n>] [--runs <n>] [--delegations <n>] [--epochs <n>] \
+//!     [--deposit-bps <bps>] [--withdraw-bps <bps>] [--new-rate-epoch <epoch|none>] \
+//!     [--stride <n>] [--csv]
+//! ```
+//!
+//! Each run starts from `--delegations` genesis delegations, fully
+//! effective, and then every epoch:
+//!
+//! 1. new delegations totalling `--deposit-bps` of the previous epoch's
+//!    effective stake activate;
+//! 2. each fully effective delegation starts deactivating with probability
+//!    `--withdraw-bps` / 10 000;
+//! 3. every delegation's status comes from
+//!    [`Delegation::stake_activating_and_deactivating`] on the history so
+//!    far, and their sum becomes the epoch's history entry, as the runtime
+//!    records it. Delegations that have fully cooled down are withdrawn.
+//!
+//! Stakes are drawn about log-uniformly from 1 to 1 000 000 SOL. Runs are
+//! seeded `seed`, `seed + 1`, ... and spread across cores; the output
+//! depends only on the arguments.
+//!
+//! Prints the cluster's trajectory across runs (mean and 5th/95th
+//! percentile effective stake, mean activating and deactivating, in SOL)
+//! every `--stride` epochs, then tail statistics of how many epochs
+//! deposits took to become fully effective and withdrawals to fully cool
+//! down, pooled over every run; those still waiting when the run ends are
+//! counted as pending rather than left out. `--csv` prints the trajectory
+//! for every epoch, in lamports, and sends the tail statistics to stderr.
+
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use stake_ebpf_check::delegation::StakeActivationStatus;
+use stake_ebpf_check::sol::LAMPORTS_PER_SOL;
+use stake_ebpf_check::stake_history::StakeHistoryEntry;
+use stake_ebpf_check::state::Delegation;
+use stake_ebpf_check::stress::Xorshift64Star;
+use stake_ebpf_check::{Backend, BackendVisitor, Epoch, StakeCalculator, BASIS_POINTS_PER_UNIT};
+
+const DEFAULT_SEED: u64 = 1;
+const DEFAULT_RUNS: u64 = 20;
+const DEFAULT_DELEGATIONS: usize = 2_000;
+const DEFAULT_EPOCHS: u64 = 300;
+const DEFAULT_DEPOSIT_BPS: u64 = 50;
+const DEFAULT_WITHDRAW_BPS: u64 = 50;
+const DEFAULT_STRIDE: u64 = 10;
+
+const MIN_STAKE: u64 = LAMPORTS_PER_SOL;
+const MAX_STAKE: u64 = 1_000_000 * LAMPORTS_PER_SOL;
+
+const USAGE: &str = "usage: monte-carlo [--backend <id>] [--seed <n>] [--runs <n>] [--delegations <n>] [--epochs <n>] [--deposit-bps <bps>] [--withdraw-bps <bps>] [--new-rate-epoch <epoch|none>] [--stride <n>] [--csv]";
+
+#[derive(Clone, Copy)]
+struct Params {
+    seed: u64,
+    runs: u64,
+    delegations: usize,
+    epochs: u64,
+    deposit_bps: u64,
+    withdraw_bps: u64,
+    new_rate_activation_epoch: Option<Epoch>,
+}
+
+struct Options {
+    backend: Backend,
+    params: Params,
+    stride: u64,
+    csv: bool,
+}
+
+fn parse<T: std::str::FromStr>(flag: &str, value: Option<&String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("{flag} needs a value\n{USAGE}"))?;
+    value
+        .parse()
+        .map_err(|_| format!("invalid {flag} `{value}`\n{USAGE}"))
+}
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let mut backend = Backend::ALL.last().copied();
+    let mut params = Params {
+        seed: DEFAULT_SEED,
+        runs: DEFAULT_RUNS,
+        delegations: DEFAULT_DELEGATIONS,
+        epochs: DEFAULT_EPOCHS,
+        deposit_bps: DEFAULT_DEPOSIT_BPS,
+        withdraw_bps: DEFAULT_WITHDRAW_BPS,
+        new_rate_activation_epoch: Some(0),
+    };
+    let mut stride = DEFAULT_STRIDE;
+    let mut csv = false;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--backend" => {
+                let id = parse("--backend", args.next())?;
+                backend = Some(Backend::from_id(id).ok_or_else(|| {
+                    format!("backend {id} is not compiled in; have {:?}", Backend::ALL)
+                })?);
+            }
+            "--seed" => params.seed = parse("--seed", args.next())?,
+            "--runs" => params.runs = parse("--runs", args.next())?,
+            "--delegations" => params.delegations = parse("--delegations", args.next())?,
+            "--epochs" => params.epochs = parse("--epochs", args.next())?,
+            "--deposit-bps" => params.deposit_bps = parse("--deposit-bps", args.next())?,
+            "--withdraw-bps" => params.withdraw_bps = parse("--withdraw-bps", args.next())?,
+            "--new-rate-epoch" => {
+                let value = args.next();
+                params.new_rate_activation_epoch = match value.map(String::as_str) {
+                    Some("none") => None,
+                    _ => Some(parse("--new-rate-epoch", value)?),
+                };
+            }
+            "--stride" => stride = parse::<u64>("--stride", args.next())?.max(1),
+            "--csv" => csv = true,
+            _ => return Err(format!("unknown argument `{arg}`\n{USAGE}")),
+        }
+    }
+    if params.runs == 0 || params.epochs == 0 {
+        return Err("--runs and --epochs must be positive".to_owned());
+    }
+
+    Ok(Options {
+        backend: backend.ok_or("no backend compiled in")?,
+        params,
+        stride,
+        csv,
+    })
+}
+
+/// About log-uniform in `MIN_STAKE..=MAX_STAKE`: a uniform bit length,
+/// then a uniform value of that length.
+fn draw_stake(rng: &mut Xorshift64Star) -> u64 {
+    let (lo, hi) = (MIN_STAKE.ilog2(), MAX_STAKE.ilog2());
+    let bits = lo + (rng.next_u64() % u64::from(hi - lo + 1)) as u32;
+    ((1 << bits) + rng.next_u64() % (1 << bits)).clamp(MIN_STAKE, MAX_STAKE)
+}
+
+fn chance(rng: &mut Xorshift64Star, bps: u64) -> bool {
+    rng.next_u64() % BASIS_POINTS_PER_UNIT < bps
+}
+
+struct Tracked {
+    delegation: Delegation,
+    fully_effective: bool,
+}
+
+struct RunResult {
+    /// The cluster's status at each epoch.
+    trajectory: Vec<StakeActivationStatus>,
+    /// Epochs from activation to fully effective, per deposit.
+    warmup: Vec<u64>,
+    /// Epochs from deactivation to fully cooled down, per withdrawal.
+    cooldown: Vec<u64>,
+    /// Deposits still warming up at the end, not in `warmup`.
+    warmup_pending: usize,
+    /// Withdrawals still cooling down at the end, not in `cooldown`.
+    cooldown_pending: usize,
+}
+
+fn run<T: StakeCalculator>(params: &Params, seed: u64) -> RunResult {
+    let mut rng = Xorshift64Star::new(seed);
+    let mut live: Vec<Tracked> = (0..params.delegations)
+        .map(|_| Tracked {
+            delegation: Delegation {
+                stake: draw_stake(&mut rng),
+                activation_epoch: u64::MAX,
+                ..Delegation::default()
+            },
+            fully_effective: true,
+        })
+        .collect();
+    let mut effective: u64 = live.iter().map(|t| t.delegation.stake).sum();
+
+    // Newest first, as the sysvar.
+    let mut history: Vec<(Epoch, StakeHistoryEntry)> = Vec::new();
+    let mut result = RunResult {
+        trajectory: Vec::with_capacity(params.epochs as usize),
+        warmup: Vec::new(),
+        cooldown: Vec::new(),
+        warmup_pending: 0,
+        cooldown_pending: 0,
+    };
+
+    for epoch in 0..params.epochs {
+        let target = effective / BASIS_POINTS_PER_UNIT * params.deposit_bps;
+        let mut deposited = 0;
+        while deposited < target {
+            let stake = draw_stake(&mut rng);
+            deposited += stake;
+            live.push(Tracked {
+                delegation: Delegation {
+                    stake,
+                    activation_epoch: epoch,
+                    ..Delegation::default()
+                },
+                fully_effective: false,
+            });
+        }
+        for tracked in &mut live {
+            if tracked.fully_effective
+                && !tracked.delegation.is_deactivated()
+                && chance(&mut rng, params.withdraw_bps)
+            {
+                tracked.delegation.deactivation_epoch = epoch;
+            }
+        }
+
+        let mut total = StakeActivationStatus::default();
+        live.retain_mut(|tracked| {
+            let delegation = &tracked.delegation;
+            let status = delegation.stake_activating_and_deactivating::<T>(
+                epoch,
+                &history[..],
+                params.new_rate_activation_epoch,
+            );
+            if delegation.is_deactivated() {
+                if status.effective == 0 && epoch > delegation.deactivation_epoch {
+                    result.cooldown.push(epoch - delegation.deactivation_epoch);
+                    return false;
+                }
+            } else if !tracked.fully_effective && status.effective == delegation.stake {
+                result.warmup.push(epoch - delegation.activation_epoch);
+                tracked.fully_effective = true;
+            }
+            total = total + status;
+            true
+        });
+
+        history.insert(
+            0,
+            (
+                epoch,
+                StakeHistoryEntry {
+                    effective: total.effective,
+                    activating: total.activating,
+                    deactivating: total.deactivating,
+                },
+            ),
+        );
+        result.trajectory.push(total);
+        effective = total.effective;
+    }
+
+    for tracked in &live {
+        if tracked.delegation.is_deactivated() {
+            result.cooldown_pending += 1;
+        } else if !tracked.fully_effective {
+            result.warmup_pending += 1;
+        }
+    }
+    result
+}
+
+struct Simulation {
+    params: Params,
+}
+
+impl BackendVisitor for Simulation {
+    type Output = Vec<RunResult>;
+
+    /// Every run, in seed order, spread across cores.
+    fn visit<T: StakeCalculator>(self) -> Vec<RunResult> {
+        let params = &self.params;
+        let next_run = AtomicU64::new(0);
+        let results = Mutex::new(Vec::new());
+        let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+
+        std::thread::scope(|scope| {
+            for _ in 0..threads {
+                scope.spawn(|| loop {
+                    let index = next_run.fetch_add(1, Ordering::Relaxed);
+                    if index >= params.runs {
+                        break;
+                    }
+                    let result = run::<T>(params, params.seed.wrapping_add(index));
+                    results.lock().unwrap().push((index, result));
+                });
+            }
+        });
+
+        let mut results = results.into_inner().unwrap();
+        results.sort_by_key(|(index, _)| *index);
+        results.into_iter().map(|(_, result)| result).collect()
+    }
+}
+
+/// Nearest-rank percentile of sorted `values`.
+fn percentile(sorted: &[u64], p: u64) -> u64 {
+    match sorted.len() {
+        0 => 0,
+        len => sorted[((len - 1) * p as usize + 50) / 100],
+    }
+}
+
+fn mean(values: &[u64]) -> u64 {
+    let sum: u128 = values.iter().map(|&v| u128::from(v)).sum();
+    (sum / values.len().max(1) as u128) as u64
+}
+
+/// `(p5, mean, p95)` effective, mean activating, mean deactivating.
+fn epoch_stats(results: &[RunResult], epoch: usize) -> [u64; 5] {
+    let at = |field: fn(&StakeActivationStatus) -> u64| -> Vec<u64> {
+        results
+            .iter()
+            .map(|r| field(&r.trajectory[epoch]))
+            .collect()
+    };
+    let mut effective = at(|s| s.effective);
+    effective.sort_unstable();
+    [
+        percentile(&effective, 5),
+        mean(&effective),
+        percentile(&effective, 95),
+        mean(&at(|s| s.activating)),
+        mean(&at(|s| s.deactivating)),
+    ]
+}
+
+fn tail_stats(name: &str, mut waits: Vec<u64>, pending: usize) -> String {
+    waits.sort_unstable();
+    let max = waits.last().copied().unwrap_or(0);
+    format!(
+        "{name:<10} {:>9} {:>9} {:>6} {:>6} {:>6} {:>6} {:>6}",
+        waits.len(),
+        pending,
+        mean(&waits),
+        percentile(&waits, 50),
+        percentile(&waits, 90),
+        percentile(&waits, 99),
+        max
+    )
+}
+
+fn main_inner(args: &[String]) -> Result<(), String> {
+    let options = parse_options(args)?;
+    let params = options.params;
+    let results = options.backend.visit(Simulation { params });
+
+    let epochs = params.epochs as usize;
+    if options.csv {
+        println!(
+            "epoch,effective_p5,effective_mean,effective_p95,activating_mean,deactivating_mean"
+        );
+        for epoch in 0..epochs {
+            let stats = epoch_stats(&results, epoch).map(|v| v.to_string());
+            println!("{epoch},{}", stats.join(","));
+        }
+    } else {
+        println!(
+            "{:?}, {} runs of {} delegations over {} epochs, deposits {} bps, withdrawals {} bps, seed {}",
+            options.backend,
+            params.runs,
+            params.delegations,
+            params.epochs,
+            params.deposit_bps,
+            params.withdraw_bps,
+            params.seed
+        );
+        println!();
+        println!(
+            "{:>6} {:>14} {:>14} {:>14} {:>14} {:>14}  (SOL)",
+            "epoch", "effective p5", "mean", "p95", "activating", "deactivating"
+        );
+        let last = epochs - 1;
+        for epoch in
+            (0..epochs).filter(|e| (*e as u64).is_multiple_of(options.stride) || *e == last)
+        {
+            let [p5, mean, p95, activating, deactivating] =
+                epoch_stats(&results, epoch).map(|v| v / LAMPORTS_PER_SOL);
+            println!(
+                "{epoch:>6} {p5:>14} {mean:>14} {p95:>14} {activating:>14} {deactivating:>14}"
+            );
+        }
+        println!();
+    }
+
+    let warmup_pending = results.iter().map(|r| r.warmup_pending).sum();
+    let cooldown_pending = results.iter().map(|r| r.cooldown_pending).sum();
+    let (warmup, cooldown): (Vec<_>, Vec<_>) = results
+        .into_iter()
+        .map(|result| (result.warmup, result.cooldown))
+        .unzip();
+    let tails = [
+        format!(
+            "{:<10} {:>9} {:>9} {:>6} {:>6} {:>6} {:>6} {:>6}  (epochs)",
+            "", "done", "pending", "mean", "p50", "p90", "p99", "max"
+        ),
+        tail_stats("warmup", warmup.concat(), warmup_pending),
+        tail_stats("cooldown", cooldown.concat(), cooldown_pending),
+    ];
+    for line in tails {
+        match options.csv {
+            true => eprintln!("{line}"),
+            false => println!("{line}"),
+        }
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match main_inner(&args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,531 @@
+//! Merges what the comparison tools measured into one table, a row per
+//! backend, as Markdown or CSV.
+//!
+//! ```text
+//! cargo xtask sizes > sizes.txt
+//! cargo run --target <host-triple> -p rbpf-runner -- bnum.so manual.so ... > insns.txt
+//! (cd cu-bench && cargo test --release --target <host-triple>)
+//! cargo run --target <host-triple> --features report,<backends> --bin report -- \
+//!     [--sizes sizes.txt] [--insns insns.txt] [--cu target/cu-report-program-test.json]... \
+//!     [--divergences divergences] [--csv]
+//! cargo run --target <host-triple> --features report,<backends> --bin report -- \
+//!     --manifest old/manifest.json --manifest target/artifacts/manifest.json [--csv]
+//! ```
+//!
+//! Every input is optional; columns without one stay blank.
+//!
+//! - `--sizes`: `cargo xtask sizes` output. Rows building several backends
+//!   together are left out.
+//! - `--insns`: `rbpf-runner` output, from its per-backend summary lines.
+//!   If several builds export the same backend, the first one listed wins.
+//! - `--cu`: a `cu-bench` report, repeatable, one column of mean compute
+//!   units per harness.
+//! - `--divergences`: a directory of divergence reports (see
+//!   [`stake_ebpf_check::divergence`]); counts the reports in which the
+//!   backend's answer differs from `streaming`'s.
+//!
+//! The `vectors` column is computed here rather than read: the shared
+//! known-answer vectors, run on the backends compiled into this binary, as
+//! `passed/checked`.
+//!
+//! `--manifest` instead tracks builds across commits. It takes a `cargo
+//! xtask build-all` manifest, repeatable and oldest first, after
+//! `rbpf-runner --manifest` and `cu-bench` have added their measurements
+//! to it, and prints a row per backend per manifest: commit, sizes, mean
+//! instructions and mean compute units per harness. In Markdown each value
+//! also shows its change from the same backend's previous row. It can't be
+//! combined with the inputs above, which describe a single run.
+
+use std::collections::BTreeMap;
+use std::process::ExitCode;
+
+use serde_json::Value;
+use stake_ebpf_check::Backend;
+use test_vectors::BACKENDS;
+
+const USAGE: &str =
+    "usage: report [--sizes <file>] [--insns <file>] [--cu <file>]... [--divergences <dir>] [--csv]
+       report --manifest <file>... [--csv]";
+
+#[derive(Clone, Copy)]
+struct Sizes {
+    text: u64,
+    rodata: u64,
+    file: u64,
+}
+
+#[derive(Clone, Copy)]
+struct Instructions {
+    mean: u64,
+    min: u64,
+    max: u64,
+}
+
+#[derive(Default)]
+struct Row {
+    sizes: Option<Sizes>,
+    instructions: Option<Instructions>,
+    /// Mean compute units, indexed like [`Table::harnesses`].
+    compute_units: Vec<Option<u64>>,
+    /// `(passed, checked)`.
+    vectors: Option<(usize, usize)>,
+    divergent: Option<u64>,
+}
+
+impl Row {
+    fn is_empty(&self) -> bool {
+        self.sizes.is_none()
+            && self.instructions.is_none()
+            && self.compute_units.iter().all(Option::is_none)
+            && self.vectors.is_none()
+            && self.divergent.is_none()
+    }
+}
+
+/// Rows indexed by backend id.
+#[derive(Default)]
+struct Table {
+    harnesses: Vec<String>,
+    rows: [Row; BACKENDS.len()],
+}
+
+fn backend_id(name: &str) -> Option<usize> {
+    BACKENDS
+        .iter()
+        .position(|backend| backend.eq_ignore_ascii_case(name))
+}
+
+fn read(path: &str) -> Result<String, String> {
+    std::fs::read_to_string(path).map_err(|e| format!("{path}: {e}"))
+}
+
+fn number(field: &str, path: &str) -> Result<u64, String> {
+    field
+        .parse()
+        .map_err(|_| format!("{path}: expected a number, found `{field}`"))
+}
+
+/// The table `cargo xtask sizes` prints: `features .text .rodata .so`.
+fn add_sizes(table: &mut Table, path: &str) -> Result<(), String> {
+    let text = read(path)?;
+    let rows = text
+        .lines()
+        .skip_while(|line| !line.starts_with("features"))
+        .skip(1);
+    for line in rows {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let [features, text, rodata, file] = fields[..] else {
+            continue;
+        };
+        let Some(id) = backend_id(features) else {
+            continue;
+        };
+        table.rows[id].sizes = Some(Sizes {
+            text: number(text, path)?,
+            rodata: number(rodata, path)?,
+            file: number(file, path)?,
+        });
+    }
+    Ok(())
+}
+
+/// `rbpf-runner`'s summary lines: `<path> <backend>: <runs> runs, <mean>
+/// insns mean, <min> min, <max> max`.
+fn add_instructions(table: &mut Table, path: &str) -> Result<(), String> {
+    let text = read(path)?;
+    for line in text.lines().filter(|line| !line.starts_with(' ')) {
+        let Some((build, counts)) = line.split_once(": ") else {
+            continue;
+        };
+        let Some(id) = build
+            .rsplit_once(' ')
+            .and_then(|(_, name)| backend_id(name))
+        else {
+            continue;
+        };
+        let fields: Vec<&str> = counts
+            .split(", ")
+            .filter_map(|count| count.split_whitespace().next())
+            .collect();
+        let [_, mean, min, max] = fields[..] else {
+            continue;
+        };
+        table.rows[id].instructions.get_or_insert(Instructions {
+            mean: number(mean, path)?,
+            min: number(min, path)?,
+            max: number(max, path)?,
+        });
+    }
+    Ok(())
+}
+
+/// A `cu-bench` report; see `cu_bench::Report`.
+fn add_compute_units(table: &mut Table, path: &str) -> Result<(), String> {
+    let report: Value = serde_json::from_str(&read(path)?).map_err(|e| format!("{path}: {e}"))?;
+    let harness = report["harness"]
+        .as_str()
+        .ok_or_else(|| format!("{path}: no harness"))?;
+    let measurements = report["measurements"]
+        .as_array()
+        .ok_or_else(|| format!("{path}: no measurements"))?;
+
+    let mut totals = [(0u64, 0u64); BACKENDS.len()];
+    for measurement in measurements {
+        let (Some(id), Some(compute_units)) = (
+            measurement["backend_id"].as_u64(),
+            measurement["compute_units"].as_u64(),
+        ) else {
+            return Err(format!("{path}: malformed measurement {measurement}"));
+        };
+        let total = totals
+            .get_mut(id as usize)
+            .ok_or_else(|| format!("{path}: unknown backend id {id}"))?;
+        total.0 += compute_units;
+        total.1 += 1;
+    }
+
+    table.harnesses.push(harness.to_owned());
+    for (row, (sum, count)) in table.rows.iter_mut().zip(totals) {
+        row.compute_units.push((count > 0).then(|| sum / count));
+    }
+    Ok(())
+}
+
+/// Every `*.json` divergence report in `dir`.
+fn add_divergences(table: &mut Table, dir: &str) -> Result<(), String> {
+    let entries = std::fs::read_dir(dir).map_err(|e| format!("{dir}: {e}"))?;
+    for entry in entries {
+        let path = entry.map_err(|e| format!("{dir}: {e}"))?.path();
+        if path.extension().is_none_or(|extension| extension != "json") {
+            continue;
+        }
+        let display = path.display().to_string();
+        let report: Value =
+            serde_json::from_str(&read(&display)?).map_err(|e| format!("{display}: {e}"))?;
+        let (Some(outputs), Some(reference)) = (
+            report["outputs"].as_object(),
+            report["streaming"]["result"].as_u64(),
+        ) else {
+            return Err(format!("{display}: not a divergence report"));
+        };
+        for (name, output) in outputs {
+            let Some(id) = backend_id(name) else {
+                continue;
+            };
+            let divergent = table.rows[id].divergent.get_or_insert(0);
+            *divergent += u64::from(output.as_u64() != Some(reference));
+        }
+    }
+    Ok(())
+}
+
+fn add_vectors(table: &mut Table) -> Result<(), String> {
+    let vectors = test_vectors::load()?;
+    for &backend in Backend::ALL {
+        let mut passed = 0;
+        let mut checked = 0;
+        for vector in vectors.iter().filter(|v| !v.excludes(backend.id())) {
+            let computed = backend.rate_limited_stake_change_bps(
+                vector.rate_bps,
+                vector.account_portion,
+                vector.cluster_portion,
+                vector.cluster_effective,
+            );
+            checked += 1;
+            passed += usize::from(computed == vector.expected);
+        }
+        table.rows[usize::from(backend.id())].vectors = Some((passed, checked));
+    }
+    Ok(())
+}
+
+impl Table {
+    fn header(&self) -> Vec<String> {
+        let mut header: Vec<String> = [
+            "backend",
+            ".text",
+            ".rodata",
+            ".so",
+            "insns mean",
+            "insns min",
+            "insns max",
+        ]
+        .map(String::from)
+        .to_vec();
+        header.extend(self.harnesses.iter().map(|harness| format!("CU {harness}")));
+        header.extend(["vectors", "divergent"].map(String::from));
+        header
+    }
+
+    /// Non-empty rows as cells, blank where a tool reported nothing.
+    fn cells(&self) -> Vec<Vec<String>> {
+        fn cell(value: Option<u64>) -> String {
+            value.map_or(String::new(), |value| value.to_string())
+        }
+
+        self.rows
+            .iter()
+            .enumerate()
+            .filter(|(_, row)| !row.is_empty())
+            .map(|(id, row)| {
+                let mut cells = vec![BACKENDS[id].to_owned()];
+                cells.extend([
+                    cell(row.sizes.map(|s| s.text)),
+                    cell(row.sizes.map(|s| s.rodata)),
+                    cell(row.sizes.map(|s| s.file)),
+                    cell(row.instructions.map(|i| i.mean)),
+                    cell(row.instructions.map(|i| i.min)),
+                    cell(row.instructions.map(|i| i.max)),
+                ]);
+                cells.extend(row.compute_units.iter().map(|&cu| cell(cu)));
+                cells.push(row.vectors.map_or(String::new(), |(passed, checked)| {
+                    format!("{passed}/{checked}")
+                }));
+                cells.push(cell(row.divergent));
+                cells
+            })
+            .collect()
+    }
+
+    fn markdown(&self) -> String {
+        markdown_table(&self.header(), &self.cells())
+    }
+
+    fn csv(&self) -> String {
+        csv_table(&self.header(), &self.cells())
+    }
+}
+
+fn markdown_table(header: &[String], rows: &[Vec<String>]) -> String {
+    let mut out = format!("| {} |\n", header.join(" | "));
+    let alignments: Vec<&str> = header
+        .iter()
+        .enumerate()
+        .map(|(i, _)| if i == 0 { "---" } else { "---:" })
+        .collect();
+    out += &format!("| {} |\n", alignments.join(" | "));
+    for row in rows {
+        out += &format!("| {} |\n", row.join(" | "));
+    }
+    out
+}
+
+fn csv_table(header: &[String], rows: &[Vec<String>]) -> String {
+    let mut out = header.join(",") + "\n";
+    for row in rows {
+        out += &(row.join(",") + "\n");
+    }
+    out
+}
+
+/// One artifact's figures from a manifest.
+#[derive(Default)]
+struct Artifact {
+    sizes: Option<Sizes>,
+    instructions_mean: Option<u64>,
+    /// Mean compute units by harness.
+    compute_units: BTreeMap<String, u64>,
+}
+
+/// A `build-all` manifest with whatever measurements were added to it.
+struct Manifest {
+    /// Abbreviated, with `-dirty` for uncommitted changes.
+    commit: String,
+    /// `YYYY-MM-DD` of the commit.
+    date: String,
+    artifacts: [Option<Artifact>; BACKENDS.len()],
+}
+
+fn read_manifest(path: &str) -> Result<Manifest, String> {
+    let manifest: Value = serde_json::from_str(&read(path)?).map_err(|e| format!("{path}: {e}"))?;
+    let (Some(commit), Some(artifacts)) = (
+        manifest["git_commit"].as_str(),
+        manifest["artifacts"].as_array(),
+    ) else {
+        return Err(format!("{path}: not a build-all manifest"));
+    };
+    let mut commit = commit[..commit.len().min(10)].to_owned();
+    if manifest["git_dirty"].as_bool() == Some(true) {
+        commit += "-dirty";
+    }
+    let date = manifest["git_commit_time"].as_str().unwrap_or("");
+
+    let mut parsed = Manifest {
+        commit,
+        date: date[..date.len().min(10)].to_owned(),
+        artifacts: Default::default(),
+    };
+    for artifact in artifacts {
+        let Some(id) = artifact["backend"].as_str().and_then(backend_id) else {
+            return Err(format!("{path}: malformed artifact {artifact}"));
+        };
+        let sizes = match (
+            artifact["text"].as_u64(),
+            artifact["rodata"].as_u64(),
+            artifact["size"].as_u64(),
+        ) {
+            (Some(text), Some(rodata), Some(file)) => Some(Sizes { text, rodata, file }),
+            _ => None,
+        };
+        let compute_units = artifact["compute_units"]
+            .as_object()
+            .into_iter()
+            .flatten()
+            .filter_map(|(harness, mean)| Some((harness.clone(), mean.as_u64()?)))
+            .collect();
+        parsed.artifacts[id] = Some(Artifact {
+            sizes,
+            instructions_mean: artifact["instructions"]["mean"].as_u64(),
+            compute_units,
+        });
+    }
+    Ok(parsed)
+}
+
+/// Manifests from several runs, oldest first.
+#[derive(Default)]
+struct Trend {
+    manifests: Vec<Manifest>,
+}
+
+impl Trend {
+    /// Every harness any manifest has compute units from.
+    fn harnesses(&self) -> Vec<&str> {
+        let mut harnesses: Vec<&str> = self
+            .manifests
+            .iter()
+            .flat_map(|manifest| manifest.artifacts.iter().flatten())
+            .flat_map(|artifact| artifact.compute_units.keys().map(String::as_str))
+            .collect();
+        harnesses.sort_unstable();
+        harnesses.dedup();
+        harnesses
+    }
+
+    fn header(&self) -> Vec<String> {
+        let mut header: Vec<String> = [
+            "backend",
+            "commit",
+            "date",
+            ".text",
+            ".rodata",
+            ".so",
+            "insns mean",
+        ]
+        .map(String::from)
+        .to_vec();
+        header.extend(
+            self.harnesses()
+                .iter()
+                .map(|harness| format!("CU {harness}")),
+        );
+        header
+    }
+
+    /// A row per backend per manifest, blank where a manifest lacks a
+    /// figure; with `deltas`, values show their change from the backend's
+    /// previous row.
+    fn cells(&self, deltas: bool) -> Vec<Vec<String>> {
+        let cell = |value: Option<u64>, previous: Option<u64>| match (value, previous) {
+            (Some(value), Some(previous)) if deltas && value != previous => {
+                format!("{value} ({:+})", i128::from(value) - i128::from(previous))
+            }
+            (value, _) => value.map_or(String::new(), |value| value.to_string()),
+        };
+        let harnesses = self.harnesses();
+
+        let mut rows = Vec::new();
+        for (id, backend) in BACKENDS.iter().enumerate() {
+            let mut previous: Option<&Artifact> = None;
+            for manifest in &self.manifests {
+                let Some(artifact) = &manifest.artifacts[id] else {
+                    continue;
+                };
+                let figures = |artifact: Option<&Artifact>| -> Vec<Option<u64>> {
+                    let sizes = artifact.and_then(|a| a.sizes);
+                    let mut figures = vec![
+                        sizes.map(|s| s.text),
+                        sizes.map(|s| s.rodata),
+                        sizes.map(|s| s.file),
+                        artifact.and_then(|a| a.instructions_mean),
+                    ];
+                    figures.extend(harnesses.iter().map(|&harness| {
+                        artifact.and_then(|a| a.compute_units.get(harness).copied())
+                    }));
+                    figures
+                };
+                let mut cells = vec![
+                    (*backend).to_owned(),
+                    manifest.commit.clone(),
+                    manifest.date.clone(),
+                ];
+                cells.extend(
+                    figures(Some(artifact))
+                        .into_iter()
+                        .zip(figures(previous))
+                        .map(|(value, previous)| cell(value, previous)),
+                );
+                rows.push(cells);
+                previous = Some(artifact);
+            }
+        }
+        rows
+    }
+}
+
+fn main_inner(args: &[String]) -> Result<String, String> {
+    let mut table = Table::default();
+    let mut trend = Trend::default();
+    let mut single_run_inputs = false;
+    let mut csv = false;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .map(String::as_str)
+                .ok_or_else(|| format!("{arg} needs a value\n{USAGE}"))
+        };
+        single_run_inputs |= !matches!(arg.as_str(), "--manifest" | "--csv");
+        match arg.as_str() {
+            "--manifest" => trend.manifests.push(read_manifest(value()?)?),
+            "--sizes" => add_sizes(&mut table, value()?)?,
+            "--insns" => add_instructions(&mut table, value()?)?,
+            "--cu" => add_compute_units(&mut table, value()?)?,
+            "--divergences" => add_divergences(&mut table, value()?)?,
+            "--csv" => csv = true,
+            _ => return Err(format!("unknown argument `{arg}`\n{USAGE}")),
+        }
+    }
+
+    if !trend.manifests.is_empty() {
+        if single_run_inputs {
+            return Err(format!(
+                "--manifest can't be combined with other inputs\n{USAGE}"
+            ));
+        }
+        let header = trend.header();
+        return Ok(if csv {
+            csv_table(&header, &trend.cells(false))
+        } else {
+            markdown_table(&header, &trend.cells(true))
+        });
+    }
+
+    add_vectors(&mut table)?;
+    Ok(if csv { table.csv() } else { table.markdown() })
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match main_inner(&args) {
+        Ok(report) => {
+            print!("{report}");
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
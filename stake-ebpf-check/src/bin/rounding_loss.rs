@@ -0,0 +1,308 @@
+//! Prices per-account flooring on a real epoch: lamports of warmup and
+//! cooldown withheld across the cluster and per stake pool, per backend and
+//! rounding mode.
+//!
+//! ```text
+//! cargo run --release --target <host-triple> --features snapshots,<backends> --bin rounding-loss -- \
+//!     [--backend <id>] [--pools <n>] [--new-rate-epoch <epoch|none>] <snapshot.json>
+//! ```
+//!
+//! The snapshot is in the [`stake_ebpf_check::snapshot`] format; observed
+//! statuses are not needed. The step priced is the one into the epoch after
+//! the newest history entry: each delegation's activating and deactivating
+//! stake at that entry's epoch are the account portions, and the entry is
+//! the cluster state, as [`stake_ebpf_check::rounding`] takes them.
+//!
+//! Pools are delegations sharing a withdraw authority, which is how stake
+//! pool programs hold their accounts; the `--pools` (default 10) with the
+//! most floor loss are listed, and a pool's loss is measured against its
+//! own summed entitlement. `lost` is entitlement minus granted, so rounding
+//! up shows as negative. Every compiled-in backend is run unless
+//! `--backend` picks one.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::ExitCode;
+
+use stake_ebpf_check::rounding::{
+    activation_rounding_loss_with_mode, deactivation_rounding_loss_with_mode, RoundingLoss,
+    RoundingMode,
+};
+use stake_ebpf_check::snapshot::Snapshot;
+use stake_ebpf_check::stake_history::StakeHistoryEntry;
+use stake_ebpf_check::state::Pubkey;
+use stake_ebpf_check::{Backend, BackendVisitor, Epoch, StakeCalculator};
+
+const DEFAULT_POOLS: usize = 10;
+
+const USAGE: &str = "usage: rounding-loss [--backend <id>] [--pools <n>] [--new-rate-epoch <epoch|none>] <snapshot.json>";
+
+struct Options {
+    backends: Vec<Backend>,
+    pools: usize,
+    /// `None` takes it from the snapshot.
+    new_rate_activation_epoch: Option<Option<Epoch>>,
+    path: String,
+}
+
+fn parse<T: std::str::FromStr>(flag: &str, value: Option<&String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("{flag} needs a value\n{USAGE}"))?;
+    value
+        .parse()
+        .map_err(|_| format!("invalid {flag} `{value}`\n{USAGE}"))
+}
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let mut backends = Backend::ALL.to_vec();
+    let mut pools = DEFAULT_POOLS;
+    let mut new_rate_activation_epoch = None;
+    let mut positional = Vec::new();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--backend" => {
+                let id = parse("--backend", args.next())?;
+                backends = vec![Backend::from_id(id).ok_or_else(|| {
+                    format!("backend {id} is not compiled in; have {:?}", Backend::ALL)
+                })?];
+            }
+            "--pools" => pools = parse("--pools", args.next())?,
+            "--new-rate-epoch" => {
+                let value = args.next();
+                new_rate_activation_epoch = Some(match value.map(String::as_str) {
+                    Some("none") => None,
+                    _ => Some(parse("--new-rate-epoch", value)?),
+                });
+            }
+            flag if flag.starts_with("--") => {
+                return Err(format!("unknown flag `{flag}`\n{USAGE}"))
+            }
+            _ => positional.push(arg.clone()),
+        }
+    }
+
+    let [path] = <[String; 1]>::try_from(positional).map_err(|_| USAGE.to_owned())?;
+    if backends.is_empty() {
+        return Err("no backend compiled in".to_owned());
+    }
+    Ok(Options {
+        backends,
+        pools,
+        new_rate_activation_epoch,
+        path,
+    })
+}
+
+/// Indexed `[activation, deactivation][mode]`, modes as
+/// [`RoundingMode::ALL`].
+type Losses = [[RoundingLoss; 3]; 2];
+
+struct PoolLoss {
+    withdrawer: Pubkey,
+    accounts: usize,
+    losses: Losses,
+}
+
+struct Audit {
+    total: Losses,
+    /// Every pool, most floor loss first.
+    pools: Vec<PoolLoss>,
+}
+
+struct Rounding<'a> {
+    snapshot: &'a Snapshot,
+    prev_epoch: Epoch,
+    cluster: StakeHistoryEntry,
+    new_rate_activation_epoch: Option<Epoch>,
+}
+
+impl Rounding<'_> {
+    /// Loss over these portions, each zero portion left out since it has
+    /// nothing to round and `plain` divides by it; no portions, no loss.
+    fn losses<T: StakeCalculator>(&self, activating: &[u64], deactivating: &[u64]) -> Losses {
+        let nonzero = |portions: &[u64]| -> Vec<u64> {
+            portions.iter().copied().filter(|&p| p != 0).collect()
+        };
+        let (activating, deactivating) = (nonzero(activating), nonzero(deactivating));
+        let epoch = self.prev_epoch + 1;
+        [
+            RoundingMode::ALL.map(|mode| match activating.is_empty() {
+                true => RoundingLoss::default(),
+                false => activation_rounding_loss_with_mode::<T>(
+                    epoch,
+                    &activating,
+                    &self.cluster,
+                    self.new_rate_activation_epoch,
+                    mode,
+                ),
+            }),
+            RoundingMode::ALL.map(|mode| match deactivating.is_empty() {
+                true => RoundingLoss::default(),
+                false => deactivation_rounding_loss_with_mode::<T>(
+                    epoch,
+                    &deactivating,
+                    &self.cluster,
+                    self.new_rate_activation_epoch,
+                    mode,
+                ),
+            }),
+        ]
+    }
+}
+
+impl BackendVisitor for Rounding<'_> {
+    type Output = Audit;
+
+    fn visit<T: StakeCalculator>(self) -> Audit {
+        let mut activating = Vec::new();
+        let mut deactivating = Vec::new();
+        // By withdraw authority, in order of first appearance.
+        let mut pool_index: HashMap<Pubkey, usize> = HashMap::new();
+        let mut pool_portions: Vec<(Pubkey, Vec<u64>, Vec<u64>)> = Vec::new();
+
+        for account in &self.snapshot.accounts {
+            let (Some(meta), Some(delegation)) = (account.state.meta(), account.delegation())
+            else {
+                continue;
+            };
+            let status = delegation.stake_activating_and_deactivating::<T>(
+                self.prev_epoch,
+                &self.snapshot.history[..],
+                self.new_rate_activation_epoch,
+            );
+            activating.push(status.activating);
+            deactivating.push(status.deactivating);
+
+            let withdrawer = meta.authorized.withdrawer;
+            let index = *pool_index.entry(withdrawer).or_insert_with(|| {
+                pool_portions.push((withdrawer, Vec::new(), Vec::new()));
+                pool_portions.len() - 1
+            });
+            pool_portions[index].1.push(status.activating);
+            pool_portions[index].2.push(status.deactivating);
+        }
+
+        let floor_lost = |losses: &Losses| losses[0][0].lost + losses[1][0].lost;
+        let mut pools: Vec<PoolLoss> = pool_portions
+            .iter()
+            .map(|(withdrawer, activating, deactivating)| PoolLoss {
+                withdrawer: *withdrawer,
+                accounts: activating.len(),
+                losses: self.losses::<T>(activating, deactivating),
+            })
+            .collect();
+        pools.sort_by_key(|pool| std::cmp::Reverse(floor_lost(&pool.losses)));
+
+        Audit {
+            total: self.losses::<T>(&activating, &deactivating),
+            pools,
+        }
+    }
+}
+
+fn signed_lost(loss: &RoundingLoss) -> i128 {
+    i128::from(loss.entitlement) - i128::from(loss.granted)
+}
+
+fn mode_name(mode: RoundingMode) -> &'static str {
+    match mode {
+        RoundingMode::Floor => "floor",
+        RoundingMode::Ceil => "ceil",
+        RoundingMode::Nearest => "nearest",
+    }
+}
+
+fn print_audit(backend: Backend, audit: &Audit, pools: usize) {
+    println!("{backend:?} backend");
+    println!(
+        "  {:<13} {:<8} {:>20} {:>20} {:>8}",
+        "", "mode", "entitlement", "granted", "lost"
+    );
+    for (direction, losses) in ["activation", "deactivation"].iter().zip(&audit.total) {
+        for (mode, loss) in RoundingMode::ALL.iter().zip(losses) {
+            println!(
+                "  {direction:<13} {:<8} {:>20} {:>20} {:>8}",
+                mode_name(*mode),
+                loss.entitlement,
+                loss.granted,
+                signed_lost(loss)
+            );
+        }
+    }
+
+    let listed = pools.min(audit.pools.len());
+    if listed == 0 {
+        println!();
+        return;
+    }
+    println!(
+        "  top {listed} of {} pools by floor loss, activation + deactivation:",
+        audit.pools.len()
+    );
+    println!(
+        "  {:<44} {:>8} {:>8} {:>8} {:>8}",
+        "withdraw authority", "accounts", "floor", "ceil", "nearest"
+    );
+    for pool in &audit.pools[..listed] {
+        let lost =
+            |mode: usize| signed_lost(&pool.losses[0][mode]) + signed_lost(&pool.losses[1][mode]);
+        println!(
+            "  {:<44} {:>8} {:>8} {:>8} {:>8}",
+            bs58::encode(pool.withdrawer).into_string(),
+            pool.accounts,
+            lost(0),
+            lost(1),
+            lost(2)
+        );
+    }
+    println!();
+}
+
+fn main_inner(args: &[String]) -> Result<(), String> {
+    let options = parse_options(args)?;
+    let snapshot = Snapshot::load(Path::new(&options.path))?;
+    let &(prev_epoch, cluster) = snapshot
+        .history
+        .first()
+        .ok_or_else(|| format!("{}: StakeHistory is empty", options.path))?;
+    let new_rate_activation_epoch = options
+        .new_rate_activation_epoch
+        .unwrap_or(snapshot.new_rate_activation_epoch);
+
+    let delegations = snapshot
+        .accounts
+        .iter()
+        .filter(|account| account.delegation().is_some())
+        .count();
+    println!(
+        "step into epoch {}: {delegations} delegations, cluster at epoch {prev_epoch} \
+         effective {} activating {} deactivating {}",
+        prev_epoch + 1,
+        cluster.effective,
+        cluster.activating,
+        cluster.deactivating
+    );
+    println!();
+    for &backend in &options.backends {
+        let audit = backend.visit(Rounding {
+            snapshot: &snapshot,
+            prev_epoch,
+            cluster,
+            new_rate_activation_epoch,
+        });
+        print_audit(backend, &audit, options.pools);
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match main_inner(&args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,224 @@
+//! Replays downloaded stake-account snapshots of consecutive epochs through
+//! one backend and reports every account whose recomputed status differs
+//! from the one observed.
+//!
+//! ```text
+//! cargo run --release --target <host-triple> --features snapshots,<backends> \
+//!     --bin snapshot-replay -- [--backend <id>] [--new-rate-epoch <epoch|none>] <snapshot.json>...
+//! ```
+//!
+//! Snapshots are in the [`stake_ebpf_check::snapshot`] format and must
+//! carry each account's observed status. The rate switch comes from each
+//! snapshot unless `--new-rate-epoch` overrides it.
+//!
+//! Snapshots may be given in any order. For each pair of consecutive epochs
+//! `e` and `e + 1`, every delegated account in the later snapshot is moved
+//! to `e + 1` with `Delegation::stake_activating_and_deactivating` on the
+//! later snapshot's history, which is the first to hold epoch `e`'s entry,
+//! and compared against what was observed. Accounts whose delegation
+//! changed since the earlier snapshot (redelegated, deactivated, split or
+//! merged) are checked all the same and marked `changed`, since a
+//! mismatch there may be the snapshot's timing rather than the math. Gaps
+//! between snapshots are reported and skipped. Exits non-zero if any
+//! account mismatched.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::ExitCode;
+
+use stake_ebpf_check::delegation::StakeActivationStatus;
+use stake_ebpf_check::snapshot::{Snapshot, SnapshotAccount};
+use stake_ebpf_check::{Backend, BackendVisitor, Epoch, StakeCalculator};
+
+const USAGE: &str =
+    "usage: snapshot-replay [--backend <id>] [--new-rate-epoch <epoch|none>] <snapshot.json>...";
+
+struct Options {
+    backend: Backend,
+    /// `None` takes it from each snapshot.
+    new_rate_activation_epoch: Option<Option<Epoch>>,
+    paths: Vec<String>,
+}
+
+fn parse<T: std::str::FromStr>(flag: &str, value: Option<&String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("{flag} needs a value\n{USAGE}"))?;
+    value
+        .parse()
+        .map_err(|_| format!("invalid {flag} `{value}`\n{USAGE}"))
+}
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let mut backend = Backend::ALL.last().copied();
+    let mut new_rate_activation_epoch = None;
+    let mut paths = Vec::new();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--backend" => {
+                let id = parse("--backend", args.next())?;
+                backend = Some(Backend::from_id(id).ok_or_else(|| {
+                    format!("backend {id} is not compiled in; have {:?}", Backend::ALL)
+                })?);
+            }
+            "--new-rate-epoch" => {
+                let value = args.next();
+                new_rate_activation_epoch = Some(match value.map(String::as_str) {
+                    Some("none") => None,
+                    _ => Some(parse("--new-rate-epoch", value)?),
+                });
+            }
+            flag if flag.starts_with("--") => {
+                return Err(format!("unknown flag `{flag}`\n{USAGE}"))
+            }
+            _ => paths.push(arg.clone()),
+        }
+    }
+    if paths.len() < 2 {
+        return Err(format!("need at least two snapshots\n{USAGE}"));
+    }
+
+    Ok(Options {
+        backend: backend.ok_or("no backend compiled in")?,
+        new_rate_activation_epoch,
+        paths,
+    })
+}
+
+struct Transition<'a> {
+    previous: &'a Snapshot,
+    next: &'a Snapshot,
+    new_rate_activation_epoch: Option<Epoch>,
+}
+
+#[derive(Default)]
+struct PairResult {
+    checked: usize,
+    changed: usize,
+    /// `(pubkey, changed, recomputed, observed)`.
+    mismatches: Vec<(String, bool, StakeActivationStatus, StakeActivationStatus)>,
+}
+
+impl BackendVisitor for Transition<'_> {
+    type Output = PairResult;
+
+    fn visit<T: StakeCalculator>(self) -> PairResult {
+        let previous: HashMap<&str, &SnapshotAccount> = self
+            .previous
+            .accounts
+            .iter()
+            .map(|account| (account.pubkey.as_str(), account))
+            .collect();
+
+        let mut result = PairResult::default();
+        for account in &self.next.accounts {
+            let (Some(delegation), Some(observed)) = (account.delegation(), account.observed)
+            else {
+                continue;
+            };
+            let changed = previous
+                .get(account.pubkey.as_str())
+                .is_none_or(|previous| previous.delegation() != Some(delegation));
+            let recomputed = delegation.stake_activating_and_deactivating::<T>(
+                self.next.epoch,
+                &self.next.history[..],
+                self.new_rate_activation_epoch,
+            );
+            result.checked += 1;
+            result.changed += usize::from(changed);
+            if recomputed != observed {
+                result
+                    .mismatches
+                    .push((account.pubkey.clone(), changed, recomputed, observed));
+            }
+        }
+        result
+    }
+}
+
+fn format_status(status: &StakeActivationStatus) -> String {
+    format!(
+        "{}/{}/{}",
+        status.effective, status.activating, status.deactivating
+    )
+}
+
+fn main_inner(args: &[String]) -> Result<bool, String> {
+    let options = parse_options(args)?;
+    let mut snapshots = Vec::with_capacity(options.paths.len());
+    for path in &options.paths {
+        let snapshot = Snapshot::load(Path::new(path))?;
+        if let Some(account) = snapshot
+            .accounts
+            .iter()
+            .find(|account| account.delegation().is_some() && account.observed.is_none())
+        {
+            return Err(format!("{path}: {} has no observed status", account.pubkey));
+        }
+        snapshots.push((path, snapshot));
+    }
+    snapshots.sort_by_key(|(_, snapshot)| snapshot.epoch);
+
+    println!(
+        "{:?} backend, {} snapshots, epochs {}..={}",
+        options.backend,
+        snapshots.len(),
+        snapshots[0].1.epoch,
+        snapshots[snapshots.len() - 1].1.epoch
+    );
+    let mut mismatches = 0;
+    for pair in snapshots.windows(2) {
+        let ((_, previous), (next_path, next)) = (&pair[0], &pair[1]);
+        if next.epoch != previous.epoch + 1 {
+            println!(
+                "epochs {} -> {}: not consecutive, skipped",
+                previous.epoch, next.epoch
+            );
+            continue;
+        }
+        if next.history.first().map(|(epoch, _)| *epoch) != Some(previous.epoch) {
+            return Err(format!(
+                "{next_path}: StakeHistory should end at epoch {}",
+                previous.epoch
+            ));
+        }
+
+        let result = options.backend.visit(Transition {
+            previous,
+            next,
+            new_rate_activation_epoch: options
+                .new_rate_activation_epoch
+                .unwrap_or(next.new_rate_activation_epoch),
+        });
+        println!(
+            "epochs {} -> {}: {} accounts checked, {} changed, {} mismatched",
+            previous.epoch,
+            next.epoch,
+            result.checked,
+            result.changed,
+            result.mismatches.len()
+        );
+        for (pubkey, changed, recomputed, observed) in &result.mismatches {
+            println!(
+                "  {pubkey}{}: recomputed {} observed {} (effective/activating/deactivating)",
+                if *changed { " changed" } else { "" },
+                format_status(recomputed),
+                format_status(observed)
+            );
+        }
+        mismatches += result.mismatches.len();
+    }
+    Ok(mismatches == 0)
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match main_inner(&args) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
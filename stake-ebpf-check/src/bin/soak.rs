@@ -0,0 +1,339 @@
+//! Runs the randomized backend comparison for hours, checkpointing where
+//! it is so any stretch can be run again.
+//!
+//! ```text
+//! cargo run --release --target <host-triple> --features host-sim,<backends> --bin soak -- \
+//!     [--seed <n>] [--threads <n>] [--hours <h>] [--checkpoint <path>] \
+//!     [--checkpoint-secs <n>] [--resume]
+//! ```
+//!
+//! Each thread draws its own stream of [`StressOperands`], seeded `seed`,
+//! `seed + 1`, ..., and compares every compiled-in backend on each case as
+//! `compare-backends` does; a divergence is shrunk and written out as a
+//! report (see [`stake_ebpf_check::divergence`]) whose source names the
+//! stream and case. Runs until `--hours` (default forever) have passed.
+//!
+//! Streams report in after every [`CHUNK`] cases, and every
+//! `--checkpoint-secs` (default 60) the last report of each is written to
+//! `--checkpoint` (default `soak.checkpoint`), replacing the file whole:
+//!
+//! ```text
+//! soak-checkpoint 1
+//! seed 1
+//! elapsed-secs 3600
+//! stream 0 <prng state> <cases> <divergences>
+//! ```
+//!
+//! `--resume` carries on from the checkpoint, with its seed and streams,
+//! so a stopped soak loses at most one interval. A case is fully determined
+//! by the PRNG state before it, so resuming a copy of an older checkpoint
+//! reproduces everything its streams ran after it. Exits non-zero if
+//! anything diverged.
+
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use stake_ebpf_check::divergence::{self, DivergenceReport};
+use stake_ebpf_check::stress::{StressOperands, Xorshift64Star};
+use stake_ebpf_check::Backend;
+
+/// Cases a stream runs between reports.
+const CHUNK: u64 = 1 << 16;
+
+const CHECKPOINT_VERSION: u64 = 1;
+
+const DEFAULT_SEED: u64 = 1;
+const DEFAULT_CHECKPOINT: &str = "soak.checkpoint";
+const DEFAULT_CHECKPOINT_SECS: u64 = 60;
+
+const USAGE: &str = "usage: soak [--seed <n>] [--threads <n>] [--hours <h>] [--checkpoint <path>] [--checkpoint-secs <n>] [--resume]";
+
+struct Options {
+    seed: u64,
+    threads: usize,
+    duration: Option<Duration>,
+    checkpoint: PathBuf,
+    checkpoint_interval: Duration,
+    resume: bool,
+}
+
+fn parse<T: std::str::FromStr>(flag: &str, value: Option<&String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("{flag} needs a value\n{USAGE}"))?;
+    value
+        .parse()
+        .map_err(|_| format!("invalid {flag} `{value}`\n{USAGE}"))
+}
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let mut options = Options {
+        seed: DEFAULT_SEED,
+        threads: std::thread::available_parallelism().map_or(1, |n| n.get()),
+        duration: None,
+        checkpoint: PathBuf::from(DEFAULT_CHECKPOINT),
+        checkpoint_interval: Duration::from_secs(DEFAULT_CHECKPOINT_SECS),
+        resume: false,
+    };
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--seed" => options.seed = parse("--seed", args.next())?,
+            "--threads" => options.threads = parse("--threads", args.next())?,
+            "--hours" => {
+                let hours: f64 = parse("--hours", args.next())?;
+                options.duration = Some(
+                    Duration::try_from_secs_f64(hours * 3600.0)
+                        .map_err(|_| format!("invalid --hours `{hours}`\n{USAGE}"))?,
+                );
+            }
+            "--checkpoint" => options.checkpoint = parse("--checkpoint", args.next())?,
+            "--checkpoint-secs" => {
+                options.checkpoint_interval =
+                    Duration::from_secs(parse("--checkpoint-secs", args.next())?);
+            }
+            "--resume" => options.resume = true,
+            _ => return Err(format!("unknown argument `{arg}`\n{USAGE}")),
+        }
+    }
+
+    if options.threads == 0 {
+        return Err("--threads must be at least 1".to_owned());
+    }
+    if Backend::ALL.len() < 2 {
+        return Err(format!(
+            "need at least two backends to compare; have {:?}",
+            Backend::ALL
+        ));
+    }
+    Ok(options)
+}
+
+/// Where one stream had got to at its last report.
+#[derive(Clone, Copy)]
+struct StreamProgress {
+    state: u64,
+    cases: u64,
+    divergences: u64,
+}
+
+struct Checkpoint {
+    seed: u64,
+    /// Run time before this session.
+    elapsed: Duration,
+    streams: Vec<StreamProgress>,
+}
+
+impl Checkpoint {
+    fn cases(&self) -> u64 {
+        self.streams.iter().map(|stream| stream.cases).sum()
+    }
+
+    fn divergences(&self) -> u64 {
+        self.streams.iter().map(|stream| stream.divergences).sum()
+    }
+
+    fn to_text(&self) -> String {
+        let mut text = format!(
+            "soak-checkpoint {CHECKPOINT_VERSION}\nseed {}\nelapsed-secs {}\n",
+            self.seed,
+            self.elapsed.as_secs()
+        );
+        for (index, stream) in self.streams.iter().enumerate() {
+            text += &format!(
+                "stream {index} {} {} {}\n",
+                stream.state, stream.cases, stream.divergences
+            );
+        }
+        text
+    }
+
+    fn from_text(text: &str) -> Result<Self, String> {
+        let mut lines = text
+            .lines()
+            .map(|line| line.split_whitespace().collect::<Vec<_>>());
+        let mut field = |name: &str| -> Result<u64, String> {
+            match lines.next().as_deref() {
+                Some([key, value]) if *key == name => {
+                    value.parse().map_err(|_| format!("invalid `{name}`"))
+                }
+                _ => Err(format!("expected `{name}`")),
+            }
+        };
+        let version = field("soak-checkpoint")?;
+        if version != CHECKPOINT_VERSION {
+            return Err(format!("unsupported version {version}"));
+        }
+        let seed = field("seed")?;
+        let elapsed = Duration::from_secs(field("elapsed-secs")?);
+
+        let mut streams = Vec::new();
+        for line in lines {
+            let malformed = || format!("malformed stream {}", streams.len());
+            let ["stream", index, state, cases, divergences] = line[..] else {
+                return Err(malformed());
+            };
+            if index != streams.len().to_string() {
+                return Err(malformed());
+            }
+            let number = |text: &str| text.parse::<u64>().map_err(|_| malformed());
+            streams.push(StreamProgress {
+                state: number(state)?,
+                cases: number(cases)?,
+                divergences: number(divergences)?,
+            });
+        }
+        if streams.is_empty() {
+            return Err("no streams".to_owned());
+        }
+        Ok(Self {
+            seed,
+            elapsed,
+            streams,
+        })
+    }
+
+    fn load(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+        Self::from_text(&text).map_err(|e| format!("{}: {e}", path.display()))
+    }
+
+    /// Writes beside `path` and renames over it, so a crash mid-write
+    /// leaves the previous checkpoint.
+    fn save(&self, path: &Path) -> Result<(), String> {
+        let mut partial = path.as_os_str().to_owned();
+        partial.push(".partial");
+        std::fs::write(&partial, self.to_text())
+            .and_then(|()| std::fs::rename(&partial, path))
+            .map_err(|e| format!("{}: {e}", path.display()))
+    }
+}
+
+/// State the streams share with the checkpointing thread.
+struct Shared {
+    progress: Mutex<Vec<StreamProgress>>,
+    stop: AtomicBool,
+}
+
+fn run_stream(shared: &Shared, seed: u64, index: usize) {
+    let start = shared.progress.lock().unwrap()[index];
+    let mut rng = Xorshift64Star::new(start.state);
+    let mut cases = start.cases;
+    // Counted with the cases, so a resumed stream doesn't count again the
+    // divergences it reruns.
+    let mut divergences = start.divergences;
+    while !shared.stop.load(Ordering::Relaxed) {
+        for case in cases..cases + CHUNK {
+            let operands = StressOperands::generate(&mut rng);
+            if operands.cluster_portion == 0 || !divergence::diverges(&operands) {
+                continue;
+            }
+            let source = format!("soak seed {seed} stream {index} case {case}");
+            let report = DivergenceReport::new(source, operands);
+            divergences += 1;
+            match divergence::write_report(&divergence::report_dir(), &report) {
+                Ok(path) => eprintln!("{}: report {}", report.source, path.display()),
+                Err(e) => eprintln!("{}: writing report: {e}", report.source),
+            }
+        }
+        cases += CHUNK;
+        shared.progress.lock().unwrap()[index] = StreamProgress {
+            state: rng.state(),
+            cases,
+            divergences,
+        };
+    }
+}
+
+fn main_inner(args: &[String]) -> Result<u64, String> {
+    let options = parse_options(args)?;
+    let checkpoint = match options.resume {
+        true => Checkpoint::load(&options.checkpoint)?,
+        false => Checkpoint {
+            seed: options.seed,
+            elapsed: Duration::ZERO,
+            streams: (0..options.threads as u64)
+                .map(|index| StreamProgress {
+                    state: Xorshift64Star::new(options.seed.wrapping_add(index)).state(),
+                    cases: 0,
+                    divergences: 0,
+                })
+                .collect(),
+        },
+    };
+    let seed = checkpoint.seed;
+    let resumed_cases = checkpoint.cases();
+    eprintln!(
+        "soaking {:?} on {} streams from seed {seed}, {resumed_cases} cases already run",
+        Backend::ALL,
+        checkpoint.streams.len()
+    );
+
+    let shared = Shared {
+        progress: Mutex::new(checkpoint.streams.clone()),
+        stop: AtomicBool::new(false),
+    };
+    let started = Instant::now();
+    let snapshot = |shared: &Shared| Checkpoint {
+        seed,
+        elapsed: checkpoint.elapsed + started.elapsed(),
+        streams: shared.progress.lock().unwrap().clone(),
+    };
+
+    std::thread::scope(|scope| {
+        for index in 0..checkpoint.streams.len() {
+            let shared = &shared;
+            scope.spawn(move || run_stream(shared, seed, index));
+        }
+
+        let mut result = Ok(());
+        while result.is_ok() && options.duration.is_none_or(|d| started.elapsed() < d) {
+            let wait = match options.duration {
+                Some(duration) => options
+                    .checkpoint_interval
+                    .min(duration.saturating_sub(started.elapsed())),
+                None => options.checkpoint_interval,
+            };
+            std::thread::sleep(wait);
+
+            let current = snapshot(&shared);
+            eprintln!(
+                "{:>8}s {} cases, {:.0}/s this session, {} divergent",
+                current.elapsed.as_secs(),
+                current.cases(),
+                (current.cases() - resumed_cases) as f64 / started.elapsed().as_secs_f64(),
+                current.divergences()
+            );
+            result = current.save(&options.checkpoint);
+        }
+        shared.stop.store(true, Ordering::Relaxed);
+        result
+    })?;
+
+    // The streams each finished a last chunk after the final report above.
+    let last = snapshot(&shared);
+    last.save(&options.checkpoint)?;
+    println!(
+        "{} cases over {}s, {} divergent, backends {:?}, seed {seed}; checkpoint {}",
+        last.cases(),
+        last.elapsed.as_secs(),
+        last.divergences(),
+        Backend::ALL,
+        options.checkpoint.display()
+    );
+    Ok(last.divergences())
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match main_inner(&args) {
+        Ok(0) => ExitCode::SUCCESS,
+        Ok(_) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
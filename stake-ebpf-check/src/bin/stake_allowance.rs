@@ -0,0 +1,219 @@
+//! Fetches a stake account and the StakeHistory sysvar from a cluster and
+//! prints the delegation's activation/deactivation schedule under one
+//! backend.
+//!
+//! ```text
+//! cargo run --target <host-triple> --features rpc,<backends> --bin stake-allowance -- \
+//!     [--backend <id>] [--epochs <n>] [--new-rate-epoch <epoch|none>] <rpc-url> <stake-account>
+//! ```
+//!
+//! The schedule starts at the current epoch and runs until the stake has
+//! fully activated or deactivated, or for `--epochs` epochs (default 64).
+//! Epochs after the newest history entry depend on cluster entries nobody
+//! has recorded yet; those are projected from the newest one assuming no
+//! new activations or deactivations (see [`synthetic_history`]), and their
+//! rows are marked `*`. The backend defaults to the highest id compiled in,
+//! `streaming` when present, and the rate switch to the cluster's
+//! `reduce_stake_warmup_cooldown` activation.
+
+use std::process::ExitCode;
+
+use stake_ebpf_check::delegation::StakeActivationStatus;
+use stake_ebpf_check::rpc::{self, RpcClient};
+use stake_ebpf_check::stake_account::parse_stake_state;
+use stake_ebpf_check::stake_history::StakeHistoryEntry;
+use stake_ebpf_check::stake_history_sysvar::{self, StakeHistorySysvar};
+use stake_ebpf_check::state::{Delegation, StakeStateV2};
+use stake_ebpf_check::synthetic::{synthetic_history, ChurnParams};
+use stake_ebpf_check::{Backend, BackendVisitor, Epoch, StakeCalculator};
+
+const DEFAULT_EPOCHS: u64 = 64;
+
+const USAGE: &str = "usage: stake-allowance [--backend <id>] [--epochs <n>] [--new-rate-epoch <epoch|none>] <rpc-url> <stake-account>";
+
+struct Options {
+    backend: Backend,
+    epochs: u64,
+    /// `None` reads it from the cluster.
+    new_rate_activation_epoch: Option<Option<Epoch>>,
+    url: String,
+    stake_account: String,
+}
+
+fn parse<T: std::str::FromStr>(flag: &str, value: Option<&String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("{flag} needs a value\n{USAGE}"))?;
+    value
+        .parse()
+        .map_err(|_| format!("invalid {flag} `{value}`\n{USAGE}"))
+}
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let mut backend = Backend::ALL.last().copied();
+    let mut epochs = DEFAULT_EPOCHS;
+    let mut new_rate_activation_epoch = None;
+    let mut positional = Vec::new();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--backend" => {
+                let id = parse("--backend", args.next())?;
+                backend = Some(Backend::from_id(id).ok_or_else(|| {
+                    format!("backend {id} is not compiled in; have {:?}", Backend::ALL)
+                })?);
+            }
+            "--epochs" => epochs = parse("--epochs", args.next())?,
+            "--new-rate-epoch" => {
+                let value = args.next();
+                new_rate_activation_epoch = Some(match value.map(String::as_str) {
+                    Some("none") => None,
+                    _ => Some(parse("--new-rate-epoch", value)?),
+                });
+            }
+            flag if flag.starts_with("--") => {
+                return Err(format!("unknown flag `{flag}`\n{USAGE}"))
+            }
+            _ => positional.push(arg.clone()),
+        }
+    }
+
+    let [url, stake_account] = <[String; 2]>::try_from(positional).map_err(|_| USAGE.to_owned())?;
+    Ok(Options {
+        backend: backend.ok_or("no backend compiled in")?,
+        epochs,
+        new_rate_activation_epoch,
+        url,
+        stake_account,
+    })
+}
+
+struct Status<'a> {
+    delegation: &'a Delegation,
+    epoch: Epoch,
+    history: &'a [(Epoch, StakeHistoryEntry)],
+    new_rate_activation_epoch: Option<Epoch>,
+}
+
+impl BackendVisitor for Status<'_> {
+    type Output = StakeActivationStatus;
+
+    fn visit<T: StakeCalculator>(self) -> StakeActivationStatus {
+        self.delegation.stake_activating_and_deactivating::<T>(
+            self.epoch,
+            self.history,
+            self.new_rate_activation_epoch,
+        )
+    }
+}
+
+fn format_epoch(epoch: Epoch) -> String {
+    match epoch {
+        u64::MAX => "none".to_owned(),
+        epoch => epoch.to_string(),
+    }
+}
+
+fn main_inner(args: &[String]) -> Result<(), String> {
+    let options = parse_options(args)?;
+    let pubkey = rpc::parse_pubkey(&options.stake_account)
+        .ok_or_else(|| format!("invalid pubkey `{}`", options.stake_account))?;
+    let client = RpcClient::new(options.url);
+    let rpc_error = |e: rpc::RpcError| e.to_string();
+
+    let data = client
+        .account_data(&pubkey)
+        .map_err(rpc_error)?
+        .ok_or_else(|| format!("account {} not found", options.stake_account))?;
+    let delegation = match parse_stake_state(&data) {
+        Ok(StakeStateV2::Stake(_, stake, _)) => stake.delegation,
+        Ok(state) => {
+            return Err(format!(
+                "{} is not delegated: {state:?}",
+                options.stake_account
+            ))
+        }
+        Err(e) => {
+            return Err(format!(
+                "{} is not a stake account: {e:?}",
+                options.stake_account
+            ))
+        }
+    };
+
+    let sysvar_data = client
+        .account_data(&stake_history_sysvar::ID)
+        .map_err(rpc_error)?
+        .ok_or("StakeHistory sysvar not found")?;
+    let sysvar = StakeHistorySysvar::from_bytes(&sysvar_data)
+        .map_err(|e| format!("StakeHistory sysvar: {e:?}"))?;
+    let (newest_epoch, newest_entry) = sysvar.get(0).ok_or("StakeHistory sysvar is empty")?;
+
+    let current_epoch = client.epoch().map_err(rpc_error)?;
+    let new_rate_activation_epoch = match options.new_rate_activation_epoch {
+        Some(epoch) => epoch,
+        None => client.new_rate_activation_epoch().map_err(rpc_error)?,
+    };
+
+    // Projected entries, newest first, ahead of the recorded ones.
+    let last_epoch = current_epoch.saturating_add(options.epochs);
+    let projected = last_epoch.saturating_sub(newest_epoch) as usize;
+    let mut history: Vec<_> = synthetic_history(
+        newest_epoch,
+        newest_entry,
+        ChurnParams::default(),
+        projected + 1,
+        new_rate_activation_epoch,
+    )
+    .skip(1)
+    .collect();
+    history.reverse();
+    history.extend(sysvar.iter());
+
+    println!(
+        "{}: {} lamports to {}, activation epoch {}, deactivation epoch {}",
+        options.stake_account,
+        delegation.stake,
+        rpc::pubkey_to_string(&delegation.voter_pubkey),
+        format_epoch(delegation.activation_epoch),
+        format_epoch(delegation.deactivation_epoch),
+    );
+    println!(
+        "{:?} backend, current epoch {current_epoch}, history through epoch {newest_epoch}, \
+         new rate from epoch {}",
+        options.backend,
+        new_rate_activation_epoch.map_or("none".to_owned(), format_epoch),
+    );
+    println!(
+        "{:>8}  {:>20}  {:>20}  {:>20}",
+        "epoch", "effective", "activating", "deactivating"
+    );
+    for epoch in current_epoch..last_epoch {
+        let status = options.backend.visit(Status {
+            delegation: &delegation,
+            epoch,
+            history: &history,
+            new_rate_activation_epoch,
+        });
+        let marker = if epoch > newest_epoch + 1 { "*" } else { " " };
+        println!(
+            "{epoch:>7}{marker}  {:>20}  {:>20}  {:>20}",
+            status.effective, status.activating, status.deactivating
+        );
+        if status.activating == 0 && status.deactivating == 0 {
+            return Ok(());
+        }
+    }
+    println!("still changing after {} epochs", options.epochs);
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match main_inner(&args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
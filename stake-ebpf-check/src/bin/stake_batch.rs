@@ -0,0 +1,329 @@
+//! Computes many delegations' status at once from a CSV, for operators who
+//! want bulk answers without writing code.
+//!
+//! ```text
+//! solana account SysvarStakeHistory1111111111111111111111111 --output-file stake-history.bin
+//! cargo run --release --target <host-triple> --features host-sim,<backends> --bin stake-batch -- \
+//!     [--backend <id>] [--out <file>] --new-rate-epoch <epoch|none> \
+//!     --history stake-history.bin <delegations.csv>
+//! ```
+//!
+//! The input has a header row naming its columns, in any order; others are
+//! ignored:
+//!
+//! ```text
+//! stake_account,delegated_stake,activation_epoch,target_epoch[,deactivation_epoch]
+//! ```
+//!
+//! `deactivation_epoch` may be left out or blank for a delegation that was
+//! never deactivated. Each row is moved to `target_epoch` with
+//! `Delegation::stake_activating_and_deactivating` on the history and
+//! written out, to `--out` or stdout, as:
+//!
+//! ```text
+//! stake_account,delegated_stake,activation_epoch,deactivation_epoch,target_epoch,
+//! effective,activating,deactivating,activated,deactivated,note
+//! ```
+//!
+//! `activated` and `deactivated` are how much stake became effective or
+//! stopped being effective entering `target_epoch`, the allowance the rate
+//! limit granted it. A target past the epoch after the newest history
+//! entry depends on entries nobody has recorded yet, which the calculation
+//! treats as absent, and is noted as such.
+//!
+//! The history is the StakeHistory sysvar's raw data as `solana account`
+//! saves it, or, for a file ending in `.csv`, rows of
+//! `epoch,effective,activating,deactivating` under that header. The rate
+//! switch has no safe default, so `--new-rate-epoch` is required: the
+//! cluster's `reduce_stake_warmup_cooldown` activation epoch, or `none`.
+//! The backend defaults to the highest id compiled in. A malformed row
+//! stops the run with its line number.
+
+use std::collections::HashMap;
+use std::process::ExitCode;
+
+use stake_ebpf_check::stake_history::StakeHistoryEntry;
+use stake_ebpf_check::stake_history_sysvar::StakeHistorySysvar;
+use stake_ebpf_check::state::Delegation;
+use stake_ebpf_check::{Backend, BackendVisitor, Epoch, StakeCalculator};
+
+const USAGE: &str = "usage: stake-batch [--backend <id>] [--out <file>] --new-rate-epoch <epoch|none> --history <file> <delegations.csv>";
+
+const OUTPUT_HEADER: &str = "stake_account,delegated_stake,activation_epoch,deactivation_epoch,target_epoch,effective,activating,deactivating,activated,deactivated,note";
+
+struct Options {
+    backend: Backend,
+    new_rate_activation_epoch: Option<Epoch>,
+    history: String,
+    out: Option<String>,
+    input: String,
+}
+
+fn parse<T: std::str::FromStr>(flag: &str, value: Option<&String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("{flag} needs a value\n{USAGE}"))?;
+    value
+        .parse()
+        .map_err(|_| format!("invalid {flag} `{value}`\n{USAGE}"))
+}
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let mut backend = Backend::ALL.last().copied();
+    let mut new_rate_activation_epoch = None;
+    let mut history = None;
+    let mut out = None;
+    let mut positional = Vec::new();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--backend" => {
+                let id = parse("--backend", args.next())?;
+                backend = Some(Backend::from_id(id).ok_or_else(|| {
+                    format!("backend {id} is not compiled in; have {:?}", Backend::ALL)
+                })?);
+            }
+            "--new-rate-epoch" => {
+                let value = args.next();
+                new_rate_activation_epoch = Some(match value.map(String::as_str) {
+                    Some("none") => None,
+                    _ => Some(parse("--new-rate-epoch", value)?),
+                });
+            }
+            "--history" => history = Some(parse("--history", args.next())?),
+            "--out" => out = Some(parse("--out", args.next())?),
+            flag if flag.starts_with("--") => {
+                return Err(format!("unknown flag `{flag}`\n{USAGE}"))
+            }
+            _ => positional.push(arg.clone()),
+        }
+    }
+
+    let [input] = <[String; 1]>::try_from(positional).map_err(|_| USAGE.to_owned())?;
+    Ok(Options {
+        backend: backend.ok_or("no backend compiled in")?,
+        new_rate_activation_epoch: new_rate_activation_epoch
+            .ok_or_else(|| format!("--new-rate-epoch is required\n{USAGE}"))?,
+        history: history.ok_or_else(|| format!("--history is required\n{USAGE}"))?,
+        out,
+        input,
+    })
+}
+
+/// Comma-separated fields, trimmed and unquoted. Fields here never contain
+/// commas, so quoting is not otherwise interpreted.
+fn fields(line: &str) -> Vec<&str> {
+    line.split(',')
+        .map(|field| field.trim().trim_matches('"'))
+        .collect()
+}
+
+/// Data lines with their 1-based line numbers, skipping blank ones.
+fn data_lines(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    text.lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line))
+        .filter(|(_, line)| !line.trim().is_empty())
+}
+
+/// Column positions by header name.
+struct Columns(HashMap<String, usize>);
+
+impl Columns {
+    fn new(header: &str, required: &[&str], path: &str) -> Result<Self, String> {
+        let columns: HashMap<String, usize> = fields(header)
+            .into_iter()
+            .enumerate()
+            .map(|(index, name)| (name.to_ascii_lowercase(), index))
+            .collect();
+        if let Some(missing) = required.iter().find(|name| !columns.contains_key(**name)) {
+            return Err(format!("{path}: no `{missing}` column in the header"));
+        }
+        Ok(Self(columns))
+    }
+
+    /// The field under `name`, if the column exists and the row reaches it.
+    fn get<'a>(&self, row: &[&'a str], name: &str) -> Option<&'a str> {
+        self.0.get(name).and_then(|&index| row.get(index).copied())
+    }
+
+    fn number(&self, row: &[&str], name: &str, at: &str) -> Result<u64, String> {
+        let field = self.get(row, name).unwrap_or("");
+        field
+            .replace('_', "")
+            .parse()
+            .map_err(|_| format!("{at}: invalid {name} `{field}`"))
+    }
+}
+
+/// Entries newest first, from raw sysvar data or a `.csv` file.
+fn read_history(path: &str) -> Result<Vec<(Epoch, StakeHistoryEntry)>, String> {
+    let bytes = std::fs::read(path).map_err(|e| format!("{path}: {e}"))?;
+    let mut history = if !path.ends_with(".csv") {
+        StakeHistorySysvar::from_bytes(&bytes)
+            .map_err(|e| format!("{path}: not StakeHistory sysvar data: {e:?}"))?
+            .iter()
+            .collect()
+    } else {
+        let text = String::from_utf8(bytes).map_err(|_| format!("{path}: not UTF-8"))?;
+        let mut lines = data_lines(&text);
+        let (_, header) = lines.next().ok_or_else(|| format!("{path}: empty"))?;
+        const COLUMNS: [&str; 4] = ["epoch", "effective", "activating", "deactivating"];
+        let columns = Columns::new(header, &COLUMNS, path)?;
+        let mut history = Vec::new();
+        for (number, line) in lines {
+            let row = fields(line);
+            let at = format!("{path}:{number}");
+            let [epoch, effective, activating, deactivating] =
+                COLUMNS.map(|name| columns.number(&row, name, &at));
+            history.push((
+                epoch?,
+                StakeHistoryEntry {
+                    effective: effective?,
+                    activating: activating?,
+                    deactivating: deactivating?,
+                },
+            ));
+        }
+        history
+    };
+    history.sort_by(|(a, _), (b, _)| b.cmp(a));
+    if let Some(pair) = history.windows(2).find(|pair| pair[0].0 == pair[1].0) {
+        return Err(format!("{path}: epoch {} appears twice", pair[0].0));
+    }
+    Ok(history)
+}
+
+struct Row {
+    stake_account: String,
+    delegation: Delegation,
+    target_epoch: Epoch,
+}
+
+fn read_rows(path: &str) -> Result<Vec<Row>, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("{path}: {e}"))?;
+    let mut lines = data_lines(&text);
+    let (_, header) = lines.next().ok_or_else(|| format!("{path}: empty"))?;
+    let columns = Columns::new(
+        header,
+        &[
+            "stake_account",
+            "delegated_stake",
+            "activation_epoch",
+            "target_epoch",
+        ],
+        path,
+    )?;
+
+    lines
+        .map(|(number, line)| {
+            let row = fields(line);
+            let at = format!("{path}:{number}");
+            let deactivation_epoch = match columns.get(&row, "deactivation_epoch") {
+                None | Some("") => u64::MAX,
+                Some(_) => columns.number(&row, "deactivation_epoch", &at)?,
+            };
+            Ok(Row {
+                stake_account: columns.get(&row, "stake_account").unwrap_or("").to_owned(),
+                delegation: Delegation {
+                    stake: columns.number(&row, "delegated_stake", &at)?,
+                    activation_epoch: columns.number(&row, "activation_epoch", &at)?,
+                    deactivation_epoch,
+                    ..Delegation::default()
+                },
+                target_epoch: columns.number(&row, "target_epoch", &at)?,
+            })
+        })
+        .collect()
+}
+
+/// Every row as an output line.
+struct Compute<'a> {
+    rows: &'a [Row],
+    history: &'a [(Epoch, StakeHistoryEntry)],
+    new_rate_activation_epoch: Option<Epoch>,
+}
+
+impl BackendVisitor for Compute<'_> {
+    type Output = Vec<String>;
+
+    fn visit<T: StakeCalculator>(self) -> Vec<String> {
+        let newest = self.history.first().map(|&(epoch, _)| epoch);
+        self.rows
+            .iter()
+            .map(|row| {
+                let delegation = &row.delegation;
+                let status = |epoch| {
+                    delegation.stake_activating_and_deactivating::<T>(
+                        epoch,
+                        self.history,
+                        self.new_rate_activation_epoch,
+                    )
+                };
+                let now = status(row.target_epoch);
+                let before = match row.target_epoch.checked_sub(1) {
+                    Some(epoch) => status(epoch).effective,
+                    None => 0,
+                };
+                let note = match newest {
+                    None => "history is empty".to_owned(),
+                    Some(newest) if row.target_epoch > newest.saturating_add(1) => {
+                        format!("past newest history epoch {newest}")
+                    }
+                    Some(_) => String::new(),
+                };
+                let deactivation_epoch = match delegation.deactivation_epoch {
+                    u64::MAX => String::new(),
+                    epoch => epoch.to_string(),
+                };
+                format!(
+                    "{},{},{},{deactivation_epoch},{},{},{},{},{},{},{note}",
+                    row.stake_account,
+                    delegation.stake,
+                    delegation.activation_epoch,
+                    row.target_epoch,
+                    now.effective,
+                    now.activating,
+                    now.deactivating,
+                    now.effective.saturating_sub(before),
+                    before.saturating_sub(now.effective),
+                )
+            })
+            .collect()
+    }
+}
+
+fn main_inner(args: &[String]) -> Result<(), String> {
+    let options = parse_options(args)?;
+    let history = read_history(&options.history)?;
+    let rows = read_rows(&options.input)?;
+
+    let lines = options.backend.visit(Compute {
+        rows: &rows,
+        history: &history,
+        new_rate_activation_epoch: options.new_rate_activation_epoch,
+    });
+    let mut output = format!("{OUTPUT_HEADER}\n");
+    for line in &lines {
+        output += line;
+        output.push('\n');
+    }
+    match &options.out {
+        Some(path) => {
+            std::fs::write(path, output).map_err(|e| format!("{path}: {e}"))?;
+            eprintln!("{} rows with {:?} to {path}", lines.len(), options.backend);
+        }
+        None => print!("{output}"),
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match main_inner(&args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,318 @@
+//! Samples stake accounts from a cluster and checks the node's
+//! `getStakeActivation` answer against one backend's recomputation.
+//!
+//! ```text
+//! cargo run --target <host-triple> --features rpc,<backends> --bin stake-cross-check -- \
+//!     [--backend <id>] [--new-rate-epoch <epoch|none>] [--voter <vote-account>]... \
+//!     [--sample <n>] [--seed <n>] [--interval <secs>] <rpc-url> [<stake-account>...]
+//! ```
+//!
+//! Accounts are those named, plus every stake account delegated to each
+//! `--voter`; `--sample` checks a random `n` of them per round instead of
+//! all. Each is recomputed at the current epoch with
+//! `Delegation::stake_activating_and_deactivating` on the fetched
+//! StakeHistory sysvar and turned into what `getStakeActivation` reports,
+//! as Agave's RPC did it:
+//!
+//! - `state`: `deactivating` if any stake is deactivating, else
+//!   `activating` if any is activating, else `active` if any is effective,
+//!   else `inactive`;
+//! - `active`: the effective stake;
+//! - `inactive`: the activating stake when activating, zero when active,
+//!   and otherwise the account's lamports less its rent-exempt reserve, and
+//!   less the effective stake when deactivating.
+//!
+//! Any field that differs is a mismatch; either this crate's math or the
+//! node's changed. A round that straddles an epoch boundary is discarded
+//! and rerun. With `--interval` rounds repeat every `secs` seconds until
+//! interrupted, each resampled, which makes a standing monitor; otherwise
+//! one round runs and the exit status says whether it matched.
+//! `getStakeActivation` was removed in Agave 2.0, so point this at a node
+//! that still serves it.
+
+use std::process::ExitCode;
+use std::time::Duration;
+
+use stake_ebpf_check::delegation::StakeActivationStatus;
+use stake_ebpf_check::rpc::{
+    self, RpcAccount, RpcClient, RpcError, RpcStakeActivation, StakeActivationState,
+};
+use stake_ebpf_check::stake_account::parse_stake_state;
+use stake_ebpf_check::stake_history::StakeHistoryEntry;
+use stake_ebpf_check::stake_history_sysvar::{self, StakeHistorySysvar};
+use stake_ebpf_check::state::{Delegation, Pubkey, StakeStateV2};
+use stake_ebpf_check::stress::Xorshift64Star;
+use stake_ebpf_check::{Backend, BackendVisitor, Epoch, StakeCalculator};
+
+const DEFAULT_SEED: u64 = 1;
+
+/// Rounds rerun after straddling an epoch boundary before giving up.
+const MAX_RETRIES: u32 = 3;
+
+const USAGE: &str = "usage: stake-cross-check [--backend <id>] [--new-rate-epoch <epoch|none>] [--voter <vote-account>]... [--sample <n>] [--seed <n>] [--interval <secs>] <rpc-url> [<stake-account>...]";
+
+struct Options {
+    backend: Backend,
+    /// `None` reads it from the cluster.
+    new_rate_activation_epoch: Option<Option<Epoch>>,
+    voters: Vec<Pubkey>,
+    sample: Option<usize>,
+    seed: u64,
+    interval: Option<u64>,
+    url: String,
+    stake_accounts: Vec<Pubkey>,
+}
+
+fn parse<T: std::str::FromStr>(flag: &str, value: Option<&String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("{flag} needs a value\n{USAGE}"))?;
+    value
+        .parse()
+        .map_err(|_| format!("invalid {flag} `{value}`\n{USAGE}"))
+}
+
+fn parse_pubkey(text: &str) -> Result<Pubkey, String> {
+    rpc::parse_pubkey(text).ok_or_else(|| format!("invalid pubkey `{text}`"))
+}
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let mut backend = Backend::ALL.last().copied();
+    let mut new_rate_activation_epoch = None;
+    let mut voters = Vec::new();
+    let mut sample = None;
+    let mut seed = DEFAULT_SEED;
+    let mut interval = None;
+    let mut positional = Vec::new();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--backend" => {
+                let id = parse("--backend", args.next())?;
+                backend = Some(Backend::from_id(id).ok_or_else(|| {
+                    format!("backend {id} is not compiled in; have {:?}", Backend::ALL)
+                })?);
+            }
+            "--new-rate-epoch" => {
+                let value = args.next();
+                new_rate_activation_epoch = Some(match value.map(String::as_str) {
+                    Some("none") => None,
+                    _ => Some(parse("--new-rate-epoch", value)?),
+                });
+            }
+            "--voter" => {
+                let voter: String = parse("--voter", args.next())?;
+                voters.push(parse_pubkey(&voter)?);
+            }
+            "--sample" => sample = Some(parse("--sample", args.next())?),
+            "--seed" => seed = parse("--seed", args.next())?,
+            "--interval" => interval = Some(parse("--interval", args.next())?),
+            flag if flag.starts_with("--") => {
+                return Err(format!("unknown flag `{flag}`\n{USAGE}"))
+            }
+            _ => positional.push(arg.clone()),
+        }
+    }
+
+    let mut positional = positional.into_iter();
+    let url = positional.next().ok_or_else(|| USAGE.to_owned())?;
+    let stake_accounts = positional
+        .map(|text| parse_pubkey(&text))
+        .collect::<Result<Vec<_>, _>>()?;
+    if stake_accounts.is_empty() && voters.is_empty() {
+        return Err(format!("name stake accounts or a --voter\n{USAGE}"));
+    }
+    Ok(Options {
+        backend: backend.ok_or("no backend compiled in")?,
+        new_rate_activation_epoch,
+        voters,
+        sample,
+        seed,
+        interval,
+        url,
+        stake_accounts,
+    })
+}
+
+struct Status<'a> {
+    delegation: &'a Delegation,
+    epoch: Epoch,
+    history: &'a [(Epoch, StakeHistoryEntry)],
+    new_rate_activation_epoch: Option<Epoch>,
+}
+
+impl BackendVisitor for Status<'_> {
+    type Output = StakeActivationStatus;
+
+    fn visit<T: StakeCalculator>(self) -> StakeActivationStatus {
+        self.delegation.stake_activating_and_deactivating::<T>(
+            self.epoch,
+            self.history,
+            self.new_rate_activation_epoch,
+        )
+    }
+}
+
+/// What `getStakeActivation` answers for an account with this status.
+fn expected_activation(
+    status: &StakeActivationStatus,
+    lamports: u64,
+    rent_exempt_reserve: u64,
+) -> RpcStakeActivation {
+    let state = if status.deactivating > 0 {
+        StakeActivationState::Deactivating
+    } else if status.activating > 0 {
+        StakeActivationState::Activating
+    } else if status.effective > 0 {
+        StakeActivationState::Active
+    } else {
+        StakeActivationState::Inactive
+    };
+    let inactive = match state {
+        StakeActivationState::Activating => status.activating,
+        StakeActivationState::Active => 0,
+        StakeActivationState::Deactivating => {
+            lamports.saturating_sub(status.effective.saturating_add(rent_exempt_reserve))
+        }
+        StakeActivationState::Inactive => lamports.saturating_sub(rent_exempt_reserve),
+    };
+    RpcStakeActivation {
+        state,
+        active: status.effective,
+        inactive,
+    }
+}
+
+#[derive(Default)]
+struct Round {
+    checked: usize,
+    /// Not delegated, so `getStakeActivation` has nothing to say.
+    skipped: usize,
+    mismatches: Vec<String>,
+}
+
+/// The accounts to check this round: every named one and every one
+/// delegated to a `--voter`, or a random `--sample` of them.
+fn candidates(
+    client: &RpcClient,
+    options: &Options,
+    rng: &mut Xorshift64Star,
+) -> Result<Vec<(Pubkey, RpcAccount)>, RpcError> {
+    let mut accounts = Vec::new();
+    for pubkey in &options.stake_accounts {
+        match client.account(pubkey)? {
+            Some(account) => accounts.push((*pubkey, account)),
+            None => eprintln!("{} not found", rpc::pubkey_to_string(pubkey)),
+        }
+    }
+    for voter in &options.voters {
+        accounts.extend(client.stake_accounts_by_voter(voter)?);
+    }
+    if let Some(sample) = options.sample {
+        // Partial Fisher-Yates: the first `sample` end up a uniform pick.
+        for i in 0..sample.min(accounts.len()) {
+            let j = i + (rng.next_u64() % (accounts.len() - i) as u64) as usize;
+            accounts.swap(i, j);
+        }
+        accounts.truncate(sample);
+    }
+    Ok(accounts)
+}
+
+/// One pass over the candidates, or `None` if the epoch changed under it.
+fn round(
+    client: &RpcClient,
+    options: &Options,
+    rng: &mut Xorshift64Star,
+) -> Result<Option<Round>, RpcError> {
+    let epoch = client.epoch()?;
+    let sysvar_data = client
+        .account_data(&stake_history_sysvar::ID)?
+        .ok_or(RpcError::Malformed("StakeHistory sysvar"))?;
+    let history: Vec<_> = StakeHistorySysvar::from_bytes(&sysvar_data)
+        .map_err(|_| RpcError::Malformed("StakeHistory sysvar"))?
+        .iter()
+        .collect();
+    let new_rate_activation_epoch = match options.new_rate_activation_epoch {
+        Some(epoch) => epoch,
+        None => client.new_rate_activation_epoch()?,
+    };
+
+    let mut result = Round::default();
+    for (pubkey, account) in candidates(client, options, rng)? {
+        let Ok(StakeStateV2::Stake(meta, stake, _)) = parse_stake_state(&account.data) else {
+            result.skipped += 1;
+            continue;
+        };
+        let status = options.backend.visit(Status {
+            delegation: &stake.delegation,
+            epoch,
+            history: &history,
+            new_rate_activation_epoch,
+        });
+        let expected = expected_activation(&status, account.lamports, meta.rent_exempt_reserve);
+        let reported = client.stake_activation(&pubkey)?;
+        result.checked += 1;
+        if reported != expected {
+            result.mismatches.push(format!(
+                "{}: node {:?} active {} inactive {}, {:?} {:?} active {} inactive {}",
+                rpc::pubkey_to_string(&pubkey),
+                reported.state,
+                reported.active,
+                reported.inactive,
+                options.backend,
+                expected.state,
+                expected.active,
+                expected.inactive,
+            ));
+        }
+    }
+
+    Ok((client.epoch()? == epoch).then_some(result))
+}
+
+fn main_inner(args: &[String]) -> Result<bool, String> {
+    let options = parse_options(args)?;
+    let client = RpcClient::new(options.url.clone());
+    let mut rng = Xorshift64Star::new(options.seed);
+
+    loop {
+        let mut retries = 0;
+        let result = loop {
+            match round(&client, &options, &mut rng).map_err(|e| e.to_string())? {
+                Some(result) => break result,
+                None if retries < MAX_RETRIES => {
+                    eprintln!("epoch changed mid-round, rerunning");
+                    retries += 1;
+                }
+                None => return Err("epoch kept changing mid-round".to_owned()),
+            }
+        };
+
+        for mismatch in &result.mismatches {
+            println!("{mismatch}");
+        }
+        println!(
+            "{:?} backend: {} checked, {} not delegated, {} mismatched",
+            options.backend,
+            result.checked,
+            result.skipped,
+            result.mismatches.len()
+        );
+        match options.interval {
+            Some(secs) => std::thread::sleep(Duration::from_secs(secs)),
+            None => return Ok(result.mismatches.is_empty()),
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match main_inner(&args) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
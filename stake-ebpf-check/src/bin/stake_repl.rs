@@ -0,0 +1,287 @@
+//! Interactive calculator for exploring the rate-limited stake change at
+//! its edges.
+//!
+//! ```text
+//! cargo run --release --target <host-triple> --features host-sim,<backends> --bin stake-repl -- \
+//!     [<rate-bps> <account> <cluster> <effective>]
+//! ```
+//!
+//! Starts from the given operands, or from the `mainnet` known answer, and
+//! after every line prints them again with the exact answer and its
+//! remainder, the streaming calculator's intermediate values, and what each
+//! compiled-in backend returns, marking those that differ from the exact
+//! answer. One field changes at a time:
+//!
+//! ```text
+//! > account 1_000_000      set it
+//! > e max                  fields go by initial too; max is u64::MAX
+//! > c +1                   step up or down, saturating
+//! > set 900 1 3 1          all four, in argument order
+//! ```
+//!
+//! `help` lists the commands, and `quit` or end of input leaves. A backend
+//! that panics shows as such; `plain` divides by every operand. Panics are
+//! only caught with unwinding, so run this in release: the workspace's dev
+//! profile aborts.
+
+use std::io::{self, BufRead, Write};
+use std::panic::{self, AssertUnwindSafe};
+use std::process::ExitCode;
+
+use stake_ebpf_check::streaming::{div_rem_wide, mul_div_wide, mul_wide};
+use stake_ebpf_check::{Backend, BASIS_POINTS_PER_UNIT};
+
+const USAGE: &str = "usage: stake-repl [<rate-bps> <account> <cluster> <effective>]";
+
+const HELP: &str = "  rate|r <value>        rate in basis points
+  account|a <value>     account portion
+  cluster|c <value>     cluster portion
+  effective|e <value>   cluster effective
+  set <r> <a> <c> <e>   all four
+  help                  this
+  quit                  leave
+values: <n>, max, +<n>, -<n>; underscores are ignored";
+
+/// The `mainnet` known answer.
+const START: Operands = Operands {
+    rate_bps: 900,
+    account_portion: 10_000_000_000,
+    cluster_portion: 200_000_000_000_000_000,
+    cluster_effective: 380_000_000_000_000_000,
+};
+
+#[derive(Clone, Copy)]
+struct Operands {
+    rate_bps: u64,
+    account_portion: u64,
+    cluster_portion: u64,
+    cluster_effective: u64,
+}
+
+impl Operands {
+    fn field_mut(&mut self, name: &str) -> Option<&mut u64> {
+        match name {
+            "rate" | "r" => Some(&mut self.rate_bps),
+            "account" | "a" => Some(&mut self.account_portion),
+            "cluster" | "c" => Some(&mut self.cluster_portion),
+            "effective" | "e" => Some(&mut self.cluster_effective),
+            _ => None,
+        }
+    }
+}
+
+fn parse_u64(text: &str) -> Result<u64, String> {
+    text.replace('_', "")
+        .parse()
+        .map_err(|_| format!("invalid value `{text}`"))
+}
+
+/// `text` as a new value for a field now at `current`.
+fn parse_value(text: &str, current: u64) -> Result<u64, String> {
+    if text == "max" {
+        Ok(u64::MAX)
+    } else if let Some(step) = text.strip_prefix('+') {
+        Ok(current.saturating_add(parse_u64(step)?))
+    } else if let Some(step) = text.strip_prefix('-') {
+        Ok(current.saturating_sub(parse_u64(step)?))
+    } else {
+        parse_u64(text)
+    }
+}
+
+/// `(hi, lo)` as one number.
+fn wide((hi, lo): (u64, u64)) -> u128 {
+    (u128::from(hi) << 64) | u128::from(lo)
+}
+
+/// `min(account * effective * rate / (cluster * 10_000), account)`
+/// computed exactly, as the known answers are; `None` if it can't be.
+fn print_exact(operands: &Operands) -> Option<u64> {
+    let Operands {
+        rate_bps,
+        account_portion,
+        cluster_portion,
+        cluster_effective,
+    } = *operands;
+    if account_portion == 0 || cluster_portion == 0 || cluster_effective == 0 {
+        println!("  exact      0 (a stake operand is zero)");
+        return Some(0);
+    }
+    let denominator = u128::from(cluster_portion) * u128::from(BASIS_POINTS_PER_UNIT);
+    let product = u128::from(account_portion) * u128::from(cluster_effective);
+    let (q, rem) = (product / denominator, product % denominator);
+    // `rem * rate` is what the exact answer floors away; it is below
+    // `denominator * rate`, which only overflows for absurd rates.
+    let Some(scaled) = rem.checked_mul(u128::from(rate_bps)) else {
+        println!("  exact      remainder times rate overflows u128");
+        return None;
+    };
+    let uncapped = q
+        .checked_mul(u128::from(rate_bps))
+        .and_then(|whole| whole.checked_add(scaled / denominator));
+    let answer = uncapped.map_or(account_portion, |uncapped| {
+        uncapped.min(u128::from(account_portion)) as u64
+    });
+    let uncapped = uncapped.map_or("past u128".to_owned(), |uncapped| uncapped.to_string());
+    println!(
+        "  exact      {answer} (uncapped {uncapped}, remainder {}/{denominator})",
+        scaled % denominator
+    );
+    Some(answer)
+}
+
+/// The steps of `EbpfStreamingCalculator`, redone here with the same
+/// [`stake_ebpf_check::streaming`] primitives to show what it computes on
+/// the way.
+fn print_streaming_steps(operands: &Operands) {
+    let Operands {
+        rate_bps,
+        account_portion,
+        cluster_portion,
+        cluster_effective,
+    } = *operands;
+    if account_portion == 0 || cluster_portion == 0 || cluster_effective == 0 {
+        println!("  streaming  returns 0 early: a stake operand is zero");
+        return;
+    }
+    let numerator = mul_wide(account_portion, cluster_effective);
+    let denominator = mul_wide(cluster_portion, BASIS_POINTS_PER_UNIT);
+    print!(
+        "  streaming  numerator {} denominator {}",
+        wide(numerator),
+        wide(denominator)
+    );
+    let Some((q1, rem)) = div_rem_wide(numerator, denominator) else {
+        println!(", quotient past 64 bits so returns the account portion");
+        return;
+    };
+    let t2 = mul_div_wide(rem, rate_bps, denominator);
+    let delta = q1.saturating_mul(rate_bps).saturating_add(t2.unwrap_or(0));
+    println!(
+        "\n             q1 {q1} rem {} t2 {} delta {delta}{}",
+        wide(rem),
+        t2.map_or("0 (overflowed)".to_owned(), |t2| t2.to_string()),
+        if delta > account_portion {
+            " capped"
+        } else {
+            ""
+        }
+    );
+}
+
+/// Each backend's answer, marked where it isn't `exact`.
+fn print_backends(operands: &Operands, exact: Option<u64>) {
+    for backend in Backend::ALL {
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            backend.rate_limited_stake_change_bps(
+                operands.rate_bps,
+                operands.account_portion,
+                operands.cluster_portion,
+                operands.cluster_effective,
+            )
+        }));
+        let backend = format!("{backend:?}");
+        match result {
+            Ok(result) if exact.is_some_and(|exact| exact != result) => {
+                println!("  {backend:<10} {result}  differs from exact")
+            }
+            Ok(result) => println!("  {backend:<10} {result}"),
+            Err(_) => println!("  {backend:<10} panicked"),
+        }
+    }
+}
+
+fn print_state(operands: &Operands) {
+    println!(
+        "  rate_bps {}  account {}  cluster {}  effective {}",
+        operands.rate_bps,
+        operands.account_portion,
+        operands.cluster_portion,
+        operands.cluster_effective
+    );
+    let exact = print_exact(operands);
+    print_streaming_steps(operands);
+    print_backends(operands, exact);
+}
+
+enum Outcome {
+    Changed,
+    Unchanged,
+    Quit,
+}
+
+fn command(operands: &mut Operands, line: &str) -> Result<Outcome, String> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    match words[..] {
+        [] => return Ok(Outcome::Unchanged),
+        ["quit" | "q" | "exit"] => return Ok(Outcome::Quit),
+        ["help" | "?"] => {
+            println!("{HELP}");
+            return Ok(Outcome::Unchanged);
+        }
+        ["set", rate, account, cluster, effective] => {
+            *operands = Operands {
+                rate_bps: parse_value(rate, operands.rate_bps)?,
+                account_portion: parse_value(account, operands.account_portion)?,
+                cluster_portion: parse_value(cluster, operands.cluster_portion)?,
+                cluster_effective: parse_value(effective, operands.cluster_effective)?,
+            };
+        }
+        [name, value] => {
+            let field = operands
+                .field_mut(name)
+                .ok_or_else(|| format!("unknown field `{name}`; try help"))?;
+            *field = parse_value(value, *field)?;
+        }
+        _ => return Err(format!("unknown command `{line}`; try help")),
+    }
+    Ok(Outcome::Changed)
+}
+
+fn main_inner(args: &[String]) -> Result<(), String> {
+    let mut operands = match args {
+        [] => START,
+        [rate, account, cluster, effective] => Operands {
+            rate_bps: parse_u64(rate)?,
+            account_portion: parse_u64(account)?,
+            cluster_portion: parse_u64(cluster)?,
+            cluster_effective: parse_u64(effective)?,
+        },
+        _ => return Err(USAGE.to_owned()),
+    };
+    if Backend::ALL.is_empty() {
+        return Err("no backend compiled in".to_owned());
+    }
+    // Backend panics are shown in the table instead.
+    panic::set_hook(Box::new(|_| {}));
+
+    print_state(&operands);
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    loop {
+        print!("> ");
+        io::stdout().flush().map_err(|e| e.to_string())?;
+        let Some(line) = lines.next() else {
+            println!();
+            return Ok(());
+        };
+        let line = line.map_err(|e| e.to_string())?;
+        match command(&mut operands, &line) {
+            Ok(Outcome::Changed) => print_state(&operands),
+            Ok(Outcome::Unchanged) => {}
+            Ok(Outcome::Quit) => return Ok(()),
+            Err(e) => println!("  {e}"),
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match main_inner(&args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,160 @@
+//! Reruns a recorded call trace against other backends and reports every
+//! call whose answer differs from the recorded one.
+//!
+//! ```text
+//! STAKE_TRACE=calls.trace cargo run --target <host-triple> --features trace,<backends> --bin host-sim -- ...
+//! cargo run --release --target <host-triple> --features trace,<backends> --bin trace-replay -- \
+//!     [--backend <id>]... [--max-reports <n>] calls.trace
+//! ```
+//!
+//! Each record is replayed on every compiled-in backend, or on those named
+//! by `--backend`, and compared against the result recorded for it; the
+//! first `--max-reports` (default 20) mismatches are printed. This is how a
+//! new backend is qualified: on the traffic the existing ones saw, it must
+//! answer as they did. A backend that panics counts as mismatched on that
+//! record. Records from a backend that was itself wrong are
+//! not told apart, so replay a trace recorded on a trusted backend. Exits
+//! non-zero on any mismatch, or if the trace is cut short.
+
+use std::fs::File;
+use std::io::BufReader;
+use std::panic::{self, AssertUnwindSafe};
+use std::process::ExitCode;
+
+use stake_ebpf_check::trace::TraceReader;
+use stake_ebpf_check::Backend;
+
+const DEFAULT_MAX_REPORTS: usize = 20;
+
+const USAGE: &str = "usage: trace-replay [--backend <id>]... [--max-reports <n>] <trace>";
+
+struct Options {
+    backends: Vec<Backend>,
+    max_reports: usize,
+    path: String,
+}
+
+fn parse<T: std::str::FromStr>(flag: &str, value: Option<&String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("{flag} needs a value\n{USAGE}"))?;
+    value
+        .parse()
+        .map_err(|_| format!("invalid {flag} `{value}`\n{USAGE}"))
+}
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let mut backends = Vec::new();
+    let mut max_reports = DEFAULT_MAX_REPORTS;
+    let mut positional = Vec::new();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--backend" => {
+                let id = parse("--backend", args.next())?;
+                backends.push(Backend::from_id(id).ok_or_else(|| {
+                    format!("backend {id} is not compiled in; have {:?}", Backend::ALL)
+                })?);
+            }
+            "--max-reports" => max_reports = parse("--max-reports", args.next())?,
+            flag if flag.starts_with("--") => {
+                return Err(format!("unknown flag `{flag}`\n{USAGE}"))
+            }
+            _ => positional.push(arg.clone()),
+        }
+    }
+
+    let [path] = <[String; 1]>::try_from(positional).map_err(|_| USAGE.to_owned())?;
+    if backends.is_empty() {
+        backends = Backend::ALL.to_vec();
+    }
+    if backends.is_empty() {
+        return Err("no backend compiled in".to_owned());
+    }
+    Ok(Options {
+        backends,
+        max_reports,
+        path,
+    })
+}
+
+fn main_inner(args: &[String]) -> Result<bool, String> {
+    let options = parse_options(args)?;
+    let path = &options.path;
+    let file = File::open(path).map_err(|e| format!("{path}: {e}"))?;
+    let reader = TraceReader::new(BufReader::new(file)).map_err(|e| format!("{path}: {e}"))?;
+
+    let mut records = 0u64;
+    let mut recorded_by = [0u64; 256];
+    let mut mismatches = vec![0u64; options.backends.len()];
+    let mut reported = 0;
+    let mut truncated = None;
+    // Panics are reported per record below.
+    panic::set_hook(Box::new(|_| {}));
+    for record in reader {
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                truncated = Some(e);
+                break;
+            }
+        };
+        records += 1;
+        recorded_by[usize::from(record.backend_id)] += 1;
+
+        for (backend, mismatches) in options.backends.iter().zip(&mut mismatches) {
+            let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                backend.rate_limited_stake_change_bps(
+                    record.rate_bps,
+                    record.account_portion,
+                    record.cluster_portion,
+                    record.cluster_effective,
+                )
+            }));
+            if result.as_ref().ok() == Some(&record.result) {
+                continue;
+            }
+            *mismatches += 1;
+            if reported < options.max_reports {
+                reported += 1;
+                let result = result.map_or("a panic".to_owned(), |result| result.to_string());
+                println!(
+                    "record {records}: {backend:?} returned {result}, backend {} recorded {} \
+                     (rate_bps {}, account {}, cluster {}, effective {})",
+                    record.backend_id,
+                    record.result,
+                    record.rate_bps,
+                    record.account_portion,
+                    record.cluster_portion,
+                    record.cluster_effective
+                );
+            }
+        }
+    }
+
+    let sources: Vec<String> = recorded_by
+        .iter()
+        .enumerate()
+        .filter(|(_, count)| **count > 0)
+        .map(|(id, count)| format!("{count} from backend {id}"))
+        .collect();
+    println!("{path}: {records} records ({})", sources.join(", "));
+    for (backend, mismatches) in options.backends.iter().zip(&mismatches) {
+        println!("{backend:?}: {mismatches} mismatched");
+    }
+    if let Some(e) = &truncated {
+        eprintln!("{path}: stopped after record {records}: {e}");
+    }
+    Ok(truncated.is_none() && mismatches.iter().all(|&m| m == 0))
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match main_inner(&args) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
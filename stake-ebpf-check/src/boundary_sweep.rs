@@ -0,0 +1,83 @@
+//! Every combination of boundary operands, for overflow-trap audits.
+//!
+//! Built with the `overflow-audit` profile (`overflow-checks = true`), any
+//! arithmetic a backend leaves to wrap or saturate implicitly traps on one of
+//! these cases instead of silently producing a value. Results are folded
+//! into the same rolling hash [`crate::stress`] uses, so an unchecked build
+//! of the same backend can confirm nothing changed.
+
+use crate::stress::{RollingHash, StressOperands};
+use crate::{
+    BackendVisitor, StakeCalculator, BASIS_POINTS_PER_UNIT, ORIGINAL_WARMUP_COOLDOWN_RATE_BPS,
+    TOWER_WARMUP_COOLDOWN_RATE_BPS,
+};
+
+/// Stake operands on either side of every width the backends narrow or
+/// widen through.
+pub const VALUES: [u64; 11] = [
+    0,
+    1,
+    2,
+    BASIS_POINTS_PER_UNIT,
+    u32::MAX as u64,
+    u32::MAX as u64 + 1,
+    1 << 63,
+    u64::MAX / BASIS_POINTS_PER_UNIT,
+    u64::MAX / BASIS_POINTS_PER_UNIT + 1,
+    u64::MAX - 1,
+    u64::MAX,
+];
+
+pub const RATES_BPS: [u64; 3] = [
+    TOWER_WARMUP_COOLDOWN_RATE_BPS,
+    ORIGINAL_WARMUP_COOLDOWN_RATE_BPS,
+    BASIS_POINTS_PER_UNIT,
+];
+
+/// Number of distinct cases, indexed `0..CASES`.
+pub const CASES: u64 = (RATES_BPS.len() * VALUES.len() * VALUES.len() * VALUES.len()) as u64;
+
+/// Case `index`, `None` past [`CASES`].
+pub fn case(index: u64) -> Option<StressOperands> {
+    if index >= CASES {
+        return None;
+    }
+    let n = VALUES.len() as u64;
+    Some(StressOperands {
+        cluster_effective: VALUES[(index % n) as usize],
+        cluster_portion: VALUES[(index / n % n) as usize],
+        account_portion: VALUES[(index / (n * n) % n) as usize],
+        rate_bps: RATES_BPS[(index / (n * n * n)) as usize],
+    })
+}
+
+/// Hash of `T`'s results over cases `start..start + count`, clipped to
+/// [`CASES`], and how many cases ran.
+pub fn sweep<T: StakeCalculator>(start: u64, count: u64) -> (u64, u64) {
+    let end = start.saturating_add(count).min(CASES);
+    let mut hash = RollingHash::new();
+    for index in start..end {
+        let Some(operands) = case(index) else { break };
+        hash.update(T::rate_limited_stake_change_bps(
+            operands.rate_bps,
+            operands.account_portion,
+            operands.cluster_portion,
+            operands.cluster_effective,
+        ));
+    }
+    (hash.finish(), end.saturating_sub(start))
+}
+
+/// Runs [`sweep`] on a [`crate::Backend`].
+pub struct BoundarySweep {
+    pub start: u64,
+    pub count: u64,
+}
+
+impl BackendVisitor for BoundarySweep {
+    type Output = (u64, u64);
+
+    fn visit<T: StakeCalculator>(self) -> (u64, u64) {
+        sweep::<T>(self.start, self.count)
+    }
+}
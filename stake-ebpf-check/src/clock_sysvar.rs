@@ -0,0 +1,62 @@
+//! The Clock sysvar account.
+
+use crate::state::{Clock, Pubkey, UnixTimestamp};
+use crate::Epoch;
+
+/// `SysvarC1ock11111111111111111111111111111111`.
+pub const ID: Pubkey = [
+    6, 167, 213, 23, 24, 199, 116, 201, 40, 86, 99, 152, 105, 29, 94, 182, 139, 94, 184, 163, 155,
+    75, 109, 92, 115, 85, 91, 33, 0, 0, 0, 0,
+];
+
+/// Serialized size of [`ClockSysvar`].
+pub const CLOCK_SYSVAR_LEN: usize = 40;
+
+/// The Clock sysvar as the runtime lays it out.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[repr(C)]
+pub struct ClockSysvar {
+    pub slot: u64,
+    pub epoch_start_timestamp: UnixTimestamp,
+    pub epoch: Epoch,
+    pub leader_schedule_epoch: Epoch,
+    pub unix_timestamp: UnixTimestamp,
+}
+
+impl From<ClockSysvar> for Clock {
+    fn from(clock: ClockSysvar) -> Self {
+        Clock {
+            epoch: clock.epoch,
+            unix_timestamp: clock.unix_timestamp,
+        }
+    }
+}
+
+impl ClockSysvar {
+    /// `None` if `data` is shorter than [`CLOCK_SYSVAR_LEN`].
+    pub fn from_bytes(data: &[u8]) -> Option<Self> {
+        let data = data.get(..CLOCK_SYSVAR_LEN)?;
+        let field = |index: usize| {
+            let mut bytes = [0u8; 8];
+            bytes.copy_from_slice(&data[index * 8..index * 8 + 8]);
+            bytes
+        };
+        Some(Self {
+            slot: u64::from_le_bytes(field(0)),
+            epoch_start_timestamp: i64::from_le_bytes(field(1)),
+            epoch: u64::from_le_bytes(field(2)),
+            leader_schedule_epoch: u64::from_le_bytes(field(3)),
+            unix_timestamp: i64::from_le_bytes(field(4)),
+        })
+    }
+
+    pub fn to_bytes(&self) -> [u8; CLOCK_SYSVAR_LEN] {
+        let mut data = [0u8; CLOCK_SYSVAR_LEN];
+        data[0..8].copy_from_slice(&self.slot.to_le_bytes());
+        data[8..16].copy_from_slice(&self.epoch_start_timestamp.to_le_bytes());
+        data[16..24].copy_from_slice(&self.epoch.to_le_bytes());
+        data[24..32].copy_from_slice(&self.leader_schedule_epoch.to_le_bytes());
+        data[32..40].copy_from_slice(&self.unix_timestamp.to_le_bytes());
+        data
+    }
+}
@@ -0,0 +1,70 @@
+//! Stake concentration with exact integer shares.
+
+use crate::streaming::{add_wide, mul_div_wide, mul_wide};
+use crate::BASIS_POINTS_PER_UNIT;
+
+/// Share of the total held by more than a third of stake, in bps.
+pub const SUPERMINORITY_THRESHOLD_BPS: u64 = 3_340;
+
+fn total_stake(stakes: &[u64]) -> (u64, u64) {
+    stakes.iter().fold((0, 0), |acc, &stake| {
+        add_wide(acc, (0, stake)).unwrap_or(acc)
+    })
+}
+
+fn share_bps(stake: (u64, u64), total: (u64, u64)) -> u64 {
+    mul_div_wide(stake, BASIS_POINTS_PER_UNIT, total).unwrap_or(0)
+}
+
+/// Each stake's share of the total in bps, floored, written to `out`.
+/// Writes `min(stakes.len(), out.len())` entries.
+pub fn share_bps_each(stakes: &[u64], out: &mut [u64]) {
+    let total = total_stake(stakes);
+    for (slot, &stake) in out.iter_mut().zip(stakes) {
+        *slot = share_bps((0, stake), total);
+    }
+}
+
+/// Running share in bps of the first `i + 1` stakes, floored, written to
+/// `out[i]`. Sort `stakes` descending first to get the concentration curve.
+pub fn cumulative_share_bps(stakes: &[u64], out: &mut [u64]) {
+    let total = total_stake(stakes);
+    let mut running = (0, 0);
+    for (slot, &stake) in out.iter_mut().zip(stakes) {
+        running = add_wide(running, (0, stake)).unwrap_or(running);
+        *slot = share_bps(running, total);
+    }
+}
+
+/// Size of the smallest prefix of `stakes_desc` (sorted descending) holding
+/// strictly more than `threshold_bps` of the total.
+///
+/// Compared as `prefix * 10_000 > total * threshold_bps` so boundary
+/// validators are never misclassified by flooring.
+pub fn minimal_controlling_set(stakes_desc: &[u64], threshold_bps: u64) -> Option<usize> {
+    let total = total_stake(stakes_desc);
+    let (t_hi, t_lo) = total;
+    let (a_hi, a_lo) = mul_wide(t_lo, threshold_bps);
+    let (_, b_lo) = mul_wide(t_hi, threshold_bps);
+    let bound = (a_hi.wrapping_add(b_lo), a_lo);
+
+    let mut running = (0, 0);
+    for (i, &stake) in stakes_desc.iter().enumerate() {
+        running = add_wide(running, (0, stake))?;
+        let (r_hi, r_lo) = running;
+        let (p_hi, p_lo) = mul_wide(r_lo, BASIS_POINTS_PER_UNIT);
+        let scaled = (
+            p_hi.wrapping_add(r_hi.wrapping_mul(BASIS_POINTS_PER_UNIT)),
+            p_lo,
+        );
+        if scaled.0 > bound.0 || (scaled.0 == bound.0 && scaled.1 > bound.1) {
+            return Some(i + 1);
+        }
+    }
+    None
+}
+
+/// Superminority size: fewest validators jointly holding more than 33.4%.
+pub fn superminority_size(stakes_desc: &[u64]) -> Option<usize> {
+    minimal_controlling_set(stakes_desc, SUPERMINORITY_THRESHOLD_BPS)
+}
@@ -0,0 +1,107 @@
+//! Why an allowance came out the way it did.
+//!
+//! Calculators return a bare `u64`, so a clamped result, one whose exact
+//! quotient would not even fit in 64 bits, and the zero that degenerate
+//! inputs produce all look like ordinary values. [`classify`] recovers the
+//! distinction from the operands alone:
+//!
+//! ```text
+//! 0  rate-limited: below the account portion
+//! 1  clamped to the account portion
+//! 2  clamped, and the unclamped quotient exceeds u64::MAX
+//! 3  zero cluster portion
+//! 4  zero account portion, cluster effective stake or rate
+//! ```
+
+use crate::instruction::Operands;
+use crate::streaming::{mul_128_by_64, mul_wide};
+use crate::{warmup_cooldown_rate_bps, BASIS_POINTS_PER_UNIT};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+#[cfg_attr(feature = "borsh", borsh(use_discriminant = true))]
+#[repr(u8)]
+pub enum Condition {
+    RateLimited = 0,
+    Clamped = 1,
+    QuotientOverflow = 2,
+    ZeroClusterPortion = 3,
+    ZeroOperand = 4,
+}
+
+impl Condition {
+    pub fn code(self) -> u8 {
+        self as u8
+    }
+
+    pub fn from_code(code: u8) -> Option<Self> {
+        match code {
+            0 => Some(Self::RateLimited),
+            1 => Some(Self::Clamped),
+            2 => Some(Self::QuotientOverflow),
+            3 => Some(Self::ZeroClusterPortion),
+            4 => Some(Self::ZeroOperand),
+            _ => None,
+        }
+    }
+
+    /// Whether the allowance equals the account portion.
+    pub fn is_clamped(self) -> bool {
+        matches!(self, Self::Clamped | Self::QuotientOverflow)
+    }
+
+    /// Whether the inputs, not the rate limit, decided the allowance.
+    pub fn is_degenerate(self) -> bool {
+        matches!(self, Self::ZeroClusterPortion | Self::ZeroOperand)
+    }
+}
+
+/// The condition an exact calculator meets on these operands.
+pub fn classify(
+    rate_bps: u64,
+    account_portion: u64,
+    cluster_portion: u64,
+    cluster_effective: u64,
+) -> Condition {
+    if cluster_portion == 0 {
+        return Condition::ZeroClusterPortion;
+    }
+    if account_portion == 0 || cluster_effective == 0 || rate_bps == 0 {
+        return Condition::ZeroOperand;
+    }
+
+    let numerator = mul_128_by_64(mul_wide(account_portion, cluster_effective), rate_bps);
+    let (denominator_hi, denominator_lo) = mul_wide(cluster_portion, BASIS_POINTS_PER_UNIT);
+    if numerator >= [denominator_hi, denominator_lo, 0] {
+        Condition::QuotientOverflow
+    } else if numerator >= mul_128_by_64((denominator_hi, denominator_lo), account_portion) {
+        Condition::Clamped
+    } else {
+        Condition::RateLimited
+    }
+}
+
+/// Conditions of the `(activation, deactivation)` pair an `Allowance`
+/// instruction computes for `operands`.
+pub fn classify_allowances(operands: &Operands) -> (Condition, Condition) {
+    let rate_bps = warmup_cooldown_rate_bps(operands.epoch, operands.new_rate_activation_epoch);
+    let cluster = &operands.cluster_state;
+    (
+        classify(
+            rate_bps,
+            operands.account_portion,
+            cluster.activating,
+            cluster.effective,
+        ),
+        classify(
+            rate_bps,
+            (operands.account_portion / 2) + 1,
+            cluster.deactivating,
+            cluster.effective,
+        ),
+    )
+}
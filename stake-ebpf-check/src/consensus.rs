@@ -0,0 +1,41 @@
+//! Independent check of a computed allowance.
+//!
+//! An allowance `r` for `min(account * effective * rate / (cluster *
+//! 10_000), account)` is correct exactly when multiplying back brackets the
+//! numerator: `r * D <= N < (r + 1) * D`, or `N >= account * D` when clamped.
+//! That takes a handful of 64-bit multiplies and no division, so it costs
+//! far less than computing `r` and shares none of the division code that
+//! produced it. The `consensus` feature runs it on every streaming result.
+
+use crate::streaming::{add_192_128, mul_128_by_64, mul_wide};
+use crate::BASIS_POINTS_PER_UNIT;
+
+/// Whether `allowance` is what an exact calculator returns for these
+/// operands, zero when any of them is zero.
+pub fn verify(
+    rate_bps: u64,
+    account_portion: u64,
+    cluster_portion: u64,
+    cluster_effective: u64,
+    allowance: u64,
+) -> bool {
+    if rate_bps == 0 || account_portion == 0 || cluster_portion == 0 || cluster_effective == 0 {
+        return allowance == 0;
+    }
+    if allowance > account_portion {
+        return false;
+    }
+
+    let numerator = mul_128_by_64(mul_wide(account_portion, cluster_effective), rate_bps);
+    let denominator = mul_wide(cluster_portion, BASIS_POINTS_PER_UNIT);
+
+    let floor = mul_128_by_64(denominator, allowance);
+    if allowance == account_portion {
+        return floor <= numerator;
+    }
+    // `(allowance + 1) * D <= account * D`, so the ceiling never overflows.
+    match add_192_128(floor, denominator) {
+        Some(ceiling) => floor <= numerator && numerator < ceiling,
+        None => floor <= numerator,
+    }
+}
@@ -0,0 +1,115 @@
+//! Many accounts cooling down together.
+//!
+//! Each epoch the queue's own remaining stake (plus any other deactivating
+//! stake on the cluster) forms the cluster deactivating total, and every
+//! account is released its share exactly as the per-account formula grants
+//! it, including the runtime's one-lamport minimum step.
+
+use crate::stake_history::StakeHistoryEntry;
+use crate::{calculate_deactivation_allowance, Epoch, StakeCalculator};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct QueuedDeactivation {
+    pub remaining: u64,
+    /// First epoch at which the account is fully deactivated.
+    pub completed_epoch: Option<Epoch>,
+}
+
+impl QueuedDeactivation {
+    pub fn new(stake: u64) -> Self {
+        Self {
+            remaining: stake,
+            completed_epoch: None,
+        }
+    }
+}
+
+pub struct CooldownQueue<'a> {
+    accounts: &'a mut [QueuedDeactivation],
+    epoch: Epoch,
+}
+
+impl<'a> CooldownQueue<'a> {
+    /// All `accounts` deactivated in `deactivation_epoch`.
+    pub fn new(accounts: &'a mut [QueuedDeactivation], deactivation_epoch: Epoch) -> Self {
+        Self {
+            accounts,
+            epoch: deactivation_epoch,
+        }
+    }
+
+    pub fn epoch(&self) -> Epoch {
+        self.epoch
+    }
+
+    pub fn accounts(&self) -> &[QueuedDeactivation] {
+        self.accounts
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.accounts.iter().all(|account| account.remaining == 0)
+    }
+
+    fn remaining(&self) -> u64 {
+        self.accounts
+            .iter()
+            .fold(0u64, |acc, account| acc.saturating_add(account.remaining))
+    }
+
+    /// Advances one epoch and returns the lamports the queue released.
+    pub fn step<T: StakeCalculator>(
+        &mut self,
+        cluster_effective: u64,
+        other_deactivating: u64,
+        new_rate_activation_epoch: Option<Epoch>,
+    ) -> u64 {
+        let prev_cluster_state = StakeHistoryEntry {
+            effective: cluster_effective,
+            deactivating: self.remaining().saturating_add(other_deactivating),
+            activating: 0,
+        };
+        let current_epoch = self.epoch.saturating_add(1);
+
+        let mut released = 0u64;
+        for account in self.accounts.iter_mut().filter(|a| a.remaining != 0) {
+            let delta = calculate_deactivation_allowance::<T>(
+                current_epoch,
+                account.remaining,
+                &prev_cluster_state,
+                new_rate_activation_epoch,
+            )
+            .max(1)
+            .min(account.remaining);
+            account.remaining -= delta;
+            released = released.saturating_add(delta);
+            if account.remaining == 0 {
+                account.completed_epoch = Some(current_epoch);
+            }
+        }
+
+        self.epoch = current_epoch;
+        released
+    }
+
+    /// Steps until every account completes or `max_epochs` pass. Returns
+    /// whether the queue drained.
+    pub fn run<T: StakeCalculator>(
+        &mut self,
+        cluster_effective: u64,
+        other_deactivating: u64,
+        max_epochs: u64,
+        new_rate_activation_epoch: Option<Epoch>,
+    ) -> bool {
+        for _ in 0..max_epochs {
+            if self.is_done() {
+                break;
+            }
+            self.step::<T>(
+                cluster_effective,
+                other_deactivating,
+                new_rate_activation_epoch,
+            );
+        }
+        self.is_done()
+    }
+}
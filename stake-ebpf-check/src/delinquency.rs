@@ -0,0 +1,67 @@
+//! Eligibility rules for `DeactivateDelinquent`.
+//!
+//! A stake may be force-deactivated once its vote account has not voted for
+//! [`MINIMUM_DELINQUENT_EPOCHS_FOR_DEACTIVATION`] epochs, provided a reference
+//! vote account voted in every one of those epochs.
+
+use crate::points::EpochCreditsEntry;
+use crate::Epoch;
+
+pub const MINIMUM_DELINQUENT_EPOCHS_FOR_DEACTIVATION: usize = 5;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeactivateDelinquentError {
+    InsufficientReferenceVotes,
+    MinimumDelinquentEpochsForDeactivationNotMet,
+}
+
+/// The reference vote account voted in each of the last
+/// [`MINIMUM_DELINQUENT_EPOCHS_FOR_DEACTIVATION`] epochs up to `current_epoch`.
+pub fn acceptable_reference_epoch_credits(
+    epoch_credits: &[EpochCreditsEntry],
+    current_epoch: Epoch,
+) -> bool {
+    let Some(epoch_index) = epoch_credits
+        .len()
+        .checked_sub(MINIMUM_DELINQUENT_EPOCHS_FOR_DEACTIVATION)
+    else {
+        return false;
+    };
+
+    let mut epoch = current_epoch;
+    for &(vote_epoch, ..) in epoch_credits[epoch_index..].iter().rev() {
+        if vote_epoch != epoch {
+            return false;
+        }
+        epoch = epoch.saturating_sub(1);
+    }
+    true
+}
+
+/// The delinquent vote account's last vote is old enough, or it never voted.
+pub fn eligible_for_deactivate_delinquent(
+    epoch_credits: &[EpochCreditsEntry],
+    current_epoch: Epoch,
+) -> bool {
+    match epoch_credits.last() {
+        None => true,
+        Some(&(epoch, ..)) => current_epoch
+            .checked_sub(MINIMUM_DELINQUENT_EPOCHS_FOR_DEACTIVATION as Epoch)
+            .is_some_and(|minimum_epoch| epoch <= minimum_epoch),
+    }
+}
+
+/// Both checks in the order the stake program applies them.
+pub fn check_deactivate_delinquent(
+    reference_epoch_credits: &[EpochCreditsEntry],
+    delinquent_epoch_credits: &[EpochCreditsEntry],
+    current_epoch: Epoch,
+) -> Result<(), DeactivateDelinquentError> {
+    if !acceptable_reference_epoch_credits(reference_epoch_credits, current_epoch) {
+        return Err(DeactivateDelinquentError::InsufficientReferenceVotes);
+    }
+    if !eligible_for_deactivate_delinquent(delinquent_epoch_credits, current_epoch) {
+        return Err(DeactivateDelinquentError::MinimumDelinquentEpochsForDeactivationNotMet);
+    }
+    Ok(())
+}
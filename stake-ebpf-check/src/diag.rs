@@ -0,0 +1,145 @@
+//! Ring buffer of intermediate values kept in a diagnostics account.
+//!
+//! When an instruction sets [`crate::instruction::FLAG_DIAG`], the program
+//! appends one record per allowance it computes, overwriting the oldest once
+//! the account is full:
+//!
+//! ```text
+//! 0..8    u64  records appended so far
+//!
+//! per slot at 8 + 64 * (sequence % capacity):
+//! 0..8    u64  sequence number
+//! 8       u8   backend id
+//! 9       u8   0 activation, 1 deactivation
+//! 10..16       reserved, zero
+//! 16..24  u64  epoch
+//! 24..32  u64  rate in basis points
+//! 32..40  u64  account portion
+//! 40..48  u64  cluster portion
+//! 48..56  u64  cluster effective
+//! 56..64  u64  allowance
+//! ```
+//!
+//! Capacity is however many whole records fit after the counter. All
+//! integers are little-endian.
+
+use crate::Epoch;
+
+pub const HEADER_LEN: usize = 8;
+pub const RECORD_LEN: usize = 64;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum DiagKind {
+    Activation = 0,
+    Deactivation = 1,
+}
+
+/// The operands one `rate_limited_stake_change_bps` call saw, and its result.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DiagRecord {
+    pub sequence: u64,
+    pub backend_id: u8,
+    pub kind: DiagKind,
+    pub epoch: Epoch,
+    pub rate_bps: u64,
+    pub account_portion: u64,
+    pub cluster_portion: u64,
+    pub cluster_effective: u64,
+    pub allowance: u64,
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&data[offset..offset + 8]);
+    u64::from_le_bytes(bytes)
+}
+
+impl DiagRecord {
+    fn write(&self, out: &mut [u8]) {
+        out[0..8].copy_from_slice(&self.sequence.to_le_bytes());
+        out[8] = self.backend_id;
+        out[9] = self.kind as u8;
+        out[10..16].fill(0);
+        let fields = [
+            self.epoch,
+            self.rate_bps,
+            self.account_portion,
+            self.cluster_portion,
+            self.cluster_effective,
+            self.allowance,
+        ];
+        for (chunk, field) in out[16..RECORD_LEN].chunks_exact_mut(8).zip(fields) {
+            chunk.copy_from_slice(&field.to_le_bytes());
+        }
+    }
+
+    /// `None` for an unknown kind byte.
+    fn read(data: &[u8]) -> Option<Self> {
+        let kind = match data[9] {
+            0 => DiagKind::Activation,
+            1 => DiagKind::Deactivation,
+            _ => return None,
+        };
+        Some(Self {
+            sequence: read_u64(data, 0),
+            backend_id: data[8],
+            kind,
+            epoch: read_u64(data, 16),
+            rate_bps: read_u64(data, 24),
+            account_portion: read_u64(data, 32),
+            cluster_portion: read_u64(data, 40),
+            cluster_effective: read_u64(data, 48),
+            allowance: read_u64(data, 56),
+        })
+    }
+}
+
+/// Mutable view over a diagnostics account's data.
+pub struct DiagLog<'a> {
+    data: &'a mut [u8],
+}
+
+impl<'a> DiagLog<'a> {
+    /// `None` if `data` cannot hold even one record.
+    pub fn new(data: &'a mut [u8]) -> Option<Self> {
+        if data.len() < HEADER_LEN + RECORD_LEN {
+            return None;
+        }
+        Some(Self { data })
+    }
+
+    pub fn capacity(&self) -> usize {
+        (self.data.len() - HEADER_LEN) / RECORD_LEN
+    }
+
+    /// Records appended over the account's lifetime, including overwritten
+    /// ones.
+    pub fn written(&self) -> u64 {
+        read_u64(self.data, 0)
+    }
+
+    fn slot(&self, sequence: u64) -> usize {
+        HEADER_LEN + (sequence % self.capacity() as u64) as usize * RECORD_LEN
+    }
+
+    /// Stores `record` under the next sequence number, which it returns.
+    /// `record.sequence` is ignored.
+    pub fn append(&mut self, record: DiagRecord) -> u64 {
+        let sequence = self.written();
+        let offset = self.slot(sequence);
+        DiagRecord { sequence, ..record }.write(&mut self.data[offset..offset + RECORD_LEN]);
+        self.data[0..8].copy_from_slice(&sequence.wrapping_add(1).to_le_bytes());
+        sequence
+    }
+
+    /// The record with `sequence`, if it has not been overwritten yet.
+    pub fn get(&self, sequence: u64) -> Option<DiagRecord> {
+        let written = self.written();
+        if sequence >= written || written - sequence > self.capacity() as u64 {
+            return None;
+        }
+        let offset = self.slot(sequence);
+        DiagRecord::read(&self.data[offset..offset + RECORD_LEN])
+    }
+}
@@ -0,0 +1,289 @@
+//! Shrinking backend disagreements and writing them up for triage.
+//!
+//! Every comparison tool (`compare-backends`, the exhaustive sweep, the
+//! `packed_arg` fuzz target) hands the operands it caught to [`minimize`]
+//! and writes a [`DivergenceReport`] with [`write_report`]: the operands as
+//! found and as shrunk, every backend's answer on the shrunk ones, and each
+//! intermediate value of the streaming calculator, whose result is also put
+//! through the independent [`consensus`] check. Reports go to
+//! `$DIVERGENCE_DIR`, or `divergences/` under the working directory, as
+//! JSON:
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "source": "compare-backends seed 1 case 42",
+//!   "found": { "rate_bps": 900, "account_portion": 1, "cluster_portion": 1, "cluster_effective": 1 },
+//!   "minimized": { "rate_bps": 900, "account_portion": 1, "cluster_portion": 1, "cluster_effective": 1 },
+//!   "outputs": { "Bnum": 0, "Streaming": 0 },
+//!   "streaming": {
+//!     "numerator": [0, 1], "denominator": [0, 10000], "q1": 0,
+//!     "remainder": [0, 1], "t2": 0, "delta": 0, "result": 0
+//!   },
+//!   "consensus_verified": true
+//! }
+//! ```
+//!
+//! Wide values are `[hi, lo]` pairs of 64-bit limbs; `q1` is `null` when
+//! the quotient does not fit in 64 bits. The file name hashes the shrunk
+//! operands, so the same minimal case found twice overwrites one report.
+
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+use crate::consensus;
+use crate::packed_args::PackedOperands;
+use crate::streaming::{div_rem_wide, mul_div_wide, mul_wide};
+use crate::stress::{RollingHash, StressOperands};
+use crate::{Backend, BASIS_POINTS_PER_UNIT};
+
+pub const REPORT_VERSION: u64 = 1;
+
+/// Bounds shrinking where a divergence only survives single-unit steps.
+const MAX_SHRINK_ROUNDS: u32 = 1_000;
+
+/// Every compiled-in backend's answer for `operands`.
+pub fn outputs(operands: &StressOperands) -> Vec<(Backend, u64)> {
+    Backend::ALL
+        .iter()
+        .map(|&backend| {
+            let result = backend.rate_limited_stake_change_bps(
+                operands.rate_bps,
+                operands.account_portion,
+                operands.cluster_portion,
+                operands.cluster_effective,
+            );
+            (backend, result)
+        })
+        .collect()
+}
+
+pub fn diverges(operands: &StressOperands) -> bool {
+    let outputs = outputs(operands);
+    outputs.iter().any(|(_, result)| *result != outputs[0].1)
+}
+
+/// Smaller values to try in place of `value`, largest step first: `min`,
+/// then `value` less half, a quarter, ... of the distance down to it.
+fn candidates(value: u64, min: u64) -> impl Iterator<Item = u64> {
+    let distance = value.saturating_sub(min);
+    (0..u64::BITS)
+        .map(move |shift| value - (distance >> shift))
+        .filter(move |candidate| *candidate < value)
+}
+
+/// Shrinks each stake operand in turn, keeping any smaller value on which
+/// the backends still disagree, until no operand shrinks further or
+/// [`MAX_SHRINK_ROUNDS`] rounds have run. The cluster portion stays
+/// non-zero, since the instruction rejects zero and `plain` divides by it.
+pub fn minimize(mut operands: StressOperands) -> StressOperands {
+    for _ in 0..MAX_SHRINK_ROUNDS {
+        let mut shrunk = false;
+        for field in 0..3 {
+            let (value, min) = match field {
+                0 => (operands.account_portion, 0),
+                1 => (operands.cluster_portion, 1),
+                _ => (operands.cluster_effective, 0),
+            };
+            for candidate in candidates(value, min) {
+                let mut trial = operands;
+                match field {
+                    0 => trial.account_portion = candidate,
+                    1 => trial.cluster_portion = candidate,
+                    _ => trial.cluster_effective = candidate,
+                }
+                if diverges(&trial) {
+                    operands = trial;
+                    shrunk = true;
+                    break;
+                }
+            }
+        }
+        if !shrunk {
+            break;
+        }
+    }
+    operands
+}
+
+/// The streaming calculator's intermediate values, step for step as
+/// `EbpfStreamingCalculator` computes them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StreamingTrace {
+    /// `account * effective`.
+    pub numerator: (u64, u64),
+    /// `cluster * 10_000`.
+    pub denominator: (u64, u64),
+    /// `numerator / denominator`, `None` past 64 bits.
+    pub q1: Option<u64>,
+    pub remainder: (u64, u64),
+    /// `remainder * rate / denominator`.
+    pub t2: u64,
+    /// `q1 * rate + t2`, saturating.
+    pub delta: u64,
+    pub result: u64,
+}
+
+impl StreamingTrace {
+    pub fn new(operands: &StressOperands) -> Self {
+        let StressOperands {
+            rate_bps,
+            account_portion,
+            cluster_portion,
+            cluster_effective,
+        } = *operands;
+        if account_portion == 0 || cluster_portion == 0 || cluster_effective == 0 {
+            return Self::default();
+        }
+
+        let numerator = mul_wide(account_portion, cluster_effective);
+        let denominator = mul_wide(cluster_portion, BASIS_POINTS_PER_UNIT);
+        let mut trace = Self {
+            numerator,
+            denominator,
+            ..Self::default()
+        };
+        let Some((q1, remainder)) = div_rem_wide(numerator, denominator) else {
+            trace.result = if rate_bps == 0 { 0 } else { account_portion };
+            return trace;
+        };
+        trace.q1 = Some(q1);
+        trace.remainder = remainder;
+        trace.t2 = mul_div_wide(remainder, rate_bps, denominator).unwrap_or(0);
+        trace.delta = q1.saturating_mul(rate_bps).saturating_add(trace.t2);
+        trace.result = trace.delta.min(account_portion);
+        trace
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DivergenceReport {
+    /// Which tool found it and how to find it again.
+    pub source: String,
+    pub found: StressOperands,
+    pub minimized: StressOperands,
+    /// On `minimized`.
+    pub outputs: Vec<(Backend, u64)>,
+    /// On `minimized`.
+    pub streaming: StreamingTrace,
+    /// Whether the streaming result passes [`consensus::verify`].
+    pub consensus_verified: bool,
+}
+
+impl DivergenceReport {
+    /// Shrinks `found` and records everything about the result.
+    pub fn new(source: impl Into<String>, found: StressOperands) -> Self {
+        let minimized = minimize(found);
+        let streaming = StreamingTrace::new(&minimized);
+        Self {
+            source: source.into(),
+            found,
+            minimized,
+            outputs: outputs(&minimized),
+            streaming,
+            consensus_verified: consensus::verify(
+                minimized.rate_bps,
+                minimized.account_portion,
+                minimized.cluster_portion,
+                minimized.cluster_effective,
+                streaming.result,
+            ),
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        fn operands(o: &StressOperands) -> String {
+            format!(
+                "{{ \"rate_bps\": {}, \"account_portion\": {}, \"cluster_portion\": {}, \
+                 \"cluster_effective\": {} }}",
+                o.rate_bps, o.account_portion, o.cluster_portion, o.cluster_effective
+            )
+        }
+        fn wide((hi, lo): (u64, u64)) -> String {
+            format!("[{hi}, {lo}]")
+        }
+
+        let outputs = self
+            .outputs
+            .iter()
+            .map(|(backend, result)| format!("\"{backend:?}\": {result}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let s = &self.streaming;
+        let q1 = s.q1.map_or("null".to_owned(), |q1| q1.to_string());
+
+        let mut json = String::new();
+        writeln!(json, "{{").unwrap();
+        writeln!(json, "  \"version\": {REPORT_VERSION},").unwrap();
+        writeln!(json, "  \"source\": {:?},", self.source).unwrap();
+        writeln!(json, "  \"found\": {},", operands(&self.found)).unwrap();
+        writeln!(json, "  \"minimized\": {},", operands(&self.minimized)).unwrap();
+        writeln!(json, "  \"outputs\": {{ {outputs} }},").unwrap();
+        writeln!(json, "  \"streaming\": {{").unwrap();
+        writeln!(
+            json,
+            "    \"numerator\": {}, \"denominator\": {}, \"q1\": {q1},",
+            wide(s.numerator),
+            wide(s.denominator)
+        )
+        .unwrap();
+        writeln!(
+            json,
+            "    \"remainder\": {}, \"t2\": {}, \"delta\": {}, \"result\": {}",
+            wide(s.remainder),
+            s.t2,
+            s.delta,
+            s.result
+        )
+        .unwrap();
+        writeln!(json, "  }},").unwrap();
+        writeln!(
+            json,
+            "  \"consensus_verified\": {}",
+            self.consensus_verified
+        )
+        .unwrap();
+        writeln!(json, "}}").unwrap();
+        json
+    }
+
+    pub fn file_name(&self) -> String {
+        let mut hash = RollingHash::new();
+        let m = &self.minimized;
+        for value in [
+            m.rate_bps,
+            m.account_portion,
+            m.cluster_portion,
+            m.cluster_effective,
+        ] {
+            hash.update(value);
+        }
+        format!("divergence-{:016x}.json", hash.finish())
+    }
+}
+
+/// Reports on whichever of a packed arg's activation and deactivation
+/// calculations the backends disagree on.
+pub fn packed_reports(arg: u64, source: &str) -> Vec<DivergenceReport> {
+    let (activation, deactivation) = PackedOperands::unpack(arg).calculator_operands();
+    [("activation", activation), ("deactivation", deactivation)]
+        .into_iter()
+        .filter(|(_, operands)| diverges(operands))
+        .map(|(side, operands)| {
+            DivergenceReport::new(format!("{source} arg {arg:#x} {side}"), operands)
+        })
+        .collect()
+}
+
+/// `$DIVERGENCE_DIR`, or `divergences/` in the working directory.
+pub fn report_dir() -> PathBuf {
+    std::env::var_os("DIVERGENCE_DIR").map_or_else(|| PathBuf::from("divergences"), PathBuf::from)
+}
+
+/// Writes `report` into `dir`, creating it if needed.
+pub fn write_report(dir: &Path, report: &DivergenceReport) -> std::io::Result<PathBuf> {
+    std::fs::create_dir_all(dir)?;
+    let path = dir.join(report.file_name());
+    std::fs::write(&path, report.to_json())?;
+    Ok(path)
+}
@@ -0,0 +1,181 @@
+//! SBF loader input deserialization, without `solana-program`.
+//!
+//! The loader calls `entrypoint(input: *mut u8) -> u64` with a single pointer
+//! to a serialized input region; [`deserialize`] takes it apart by hand,
+//! since `solana-program` does not build for the bare `bpfel` target. The
+//! region is laid out as:
+//!
+//! ```text
+//! u64 num_accounts
+//! per account:
+//!   u8 dup_info (0xff = not a duplicate)
+//!   if a duplicate: [u8; 7] padding
+//!   else:
+//!     u8 is_signer, u8 is_writable, u8 executable, [u8; 4] padding
+//!     [u8; 32] key, [u8; 32] owner, u64 lamports, u64 data_len
+//!     data, MAX_PERMITTED_DATA_INCREASE bytes, padding to 8, u64 rent_epoch
+//! u64 instruction_data_len, instruction_data
+//! [u8; 32] program_id
+//! ```
+//!
+//! A duplicate's `dup_info` is the index of the account it repeats. Handing
+//! out a second `AccountInfo` over the same data would alias it mutably, so
+//! its slot is left `None` and an instruction that reads that position
+//! fails with `NotEnoughAccountKeys`; instructions that take any accounts,
+//! like `Probe`, accept duplicates. More than [`MAX_ACCOUNTS`] accounts fail
+//! with `InvalidArgument` before any is read.
+
+use core::marker::PhantomData;
+
+use crate::state::Pubkey;
+use crate::syscalls::SolAccountInfo;
+
+pub const MAX_PERMITTED_DATA_INCREASE: usize = 10 * 1024;
+pub const MAX_ACCOUNTS: usize = 16;
+const NON_DUP_MARKER: u8 = u8::MAX;
+const BPF_ALIGN_OF_U128: usize = 8;
+
+pub const SUCCESS: u64 = 0;
+
+/// Built-in program errors, encoded the way the runtime expects them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProgramError {
+    InvalidArgument,
+    InvalidInstructionData,
+    InvalidAccountData,
+    AccountDataTooSmall,
+    IncorrectProgramId,
+    NotEnoughAccountKeys,
+    /// A program-specific error, such as an
+    /// [`InstructionError`](crate::instruction::InstructionError).
+    Custom(u32),
+}
+
+impl From<ProgramError> for u64 {
+    fn from(e: ProgramError) -> u64 {
+        match e {
+            ProgramError::InvalidArgument => 2 << 32,
+            ProgramError::InvalidInstructionData => 3 << 32,
+            ProgramError::InvalidAccountData => 4 << 32,
+            ProgramError::AccountDataTooSmall => 5 << 32,
+            ProgramError::IncorrectProgramId => 7 << 32,
+            ProgramError::NotEnoughAccountKeys => 11 << 32,
+            ProgramError::Custom(code) => code as u64,
+        }
+    }
+}
+
+pub type ProgramResult = Result<(), ProgramError>;
+
+/// An account as laid out in the input region. Lamports and data point
+/// straight into the region, so writes are seen by the runtime.
+pub struct AccountInfo<'a> {
+    pub key: &'a Pubkey,
+    pub owner: &'a Pubkey,
+    pub is_signer: bool,
+    pub is_writable: bool,
+    pub executable: bool,
+    lamports: *mut u64,
+    data: *mut u8,
+    data_len: usize,
+    _region: PhantomData<&'a mut [u8]>,
+}
+
+impl<'a> AccountInfo<'a> {
+    pub fn lamports(&self) -> u64 {
+        unsafe { self.lamports.read_unaligned() }
+    }
+
+    pub fn data(&self) -> &[u8] {
+        unsafe { core::slice::from_raw_parts(self.data, self.data_len) }
+    }
+
+    pub fn data_mut(&mut self) -> &mut [u8] {
+        unsafe { core::slice::from_raw_parts_mut(self.data, self.data_len) }
+    }
+
+    /// The C ABI view of this account that CPI expects.
+    pub(crate) fn to_sol_account_info(&self) -> SolAccountInfo {
+        SolAccountInfo {
+            key: self.key,
+            lamports: self.lamports,
+            data_len: self.data_len as u64,
+            data: self.data,
+            owner: self.owner,
+            rent_epoch: 0,
+            is_signer: self.is_signer,
+            is_writable: self.is_writable,
+            executable: self.executable,
+        }
+    }
+}
+
+unsafe fn read_u64(input: *const u8, offset: &mut usize) -> u64 {
+    let value = (input.add(*offset) as *const u64).read_unaligned();
+    *offset += 8;
+    value
+}
+
+/// Deserializes the input region into `accounts`, returning the number of
+/// accounts, the instruction data and the program id. Duplicate accounts
+/// leave their slot `None`.
+///
+/// # Safety
+///
+/// `input` must point at a loader-serialized input region.
+pub unsafe fn deserialize<'a>(
+    input: *mut u8,
+    accounts: &mut [Option<AccountInfo<'a>>; MAX_ACCOUNTS],
+) -> Result<(usize, &'a [u8], &'a Pubkey), ProgramError> {
+    let mut offset = 0;
+
+    let num_accounts = read_u64(input, &mut offset) as usize;
+    if num_accounts > MAX_ACCOUNTS {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    for slot in accounts.iter_mut().take(num_accounts) {
+        let dup_info = *input.add(offset);
+        if dup_info != NON_DUP_MARKER {
+            *slot = None;
+            offset += 8;
+            continue;
+        }
+        let is_signer = *input.add(offset + 1) != 0;
+        let is_writable = *input.add(offset + 2) != 0;
+        let executable = *input.add(offset + 3) != 0;
+        offset += 8;
+
+        let key = &*(input.add(offset) as *const Pubkey);
+        offset += 32;
+        let owner = &*(input.add(offset) as *const Pubkey);
+        offset += 32;
+        let lamports = input.add(offset) as *mut u64;
+        offset += 8;
+        let data_len = read_u64(input, &mut offset) as usize;
+        let data = input.add(offset);
+        offset += data_len + MAX_PERMITTED_DATA_INCREASE;
+        offset += offset.wrapping_neg() & (BPF_ALIGN_OF_U128 - 1);
+        // rent_epoch
+        offset += 8;
+
+        *slot = Some(AccountInfo {
+            key,
+            owner,
+            is_signer,
+            is_writable,
+            executable,
+            lamports,
+            data,
+            data_len,
+            _region: PhantomData,
+        });
+    }
+
+    let data_len = read_u64(input, &mut offset) as usize;
+    let instruction_data = core::slice::from_raw_parts(input.add(offset), data_len);
+    offset += data_len;
+    let program_id = &*(input.add(offset) as *const Pubkey);
+
+    Ok((num_accounts, instruction_data, program_id))
+}
@@ -0,0 +1,226 @@
+//! C ABI over the allowance and delegation-status math, for linking the
+//! host cdylib (`libstake_ebpf_check.so`, `.dylib`, `.dll`) from C, Go and
+//! anything else with a C FFI.
+//!
+//! ```text
+//! cargo build --release --target <host-triple> -p stake-ebpf-check --features ffi,<backends>
+//! ```
+//!
+//! `include/stake_ebpf_check.h` declares everything here; it is generated
+//! by cbindgen from this file (`cargo xtask ffi-header`), so change the
+//! Rust and regenerate rather than editing it.
+//!
+//! Every function returns a `STAKE_*` status and writes its result through
+//! an out pointer only on [`STAKE_OK`]. Absent epochs (no rate switch yet,
+//! never deactivated) are [`STAKE_EPOCH_NONE`], as on the wire.
+
+use crate::delegation::StakeActivationStatus;
+use crate::stake_history::{StakeHistoryEntry, StakeHistoryGetEntry};
+use crate::state::Delegation;
+use crate::{Backend, BackendVisitor, Epoch, StakeCalculator};
+
+pub const STAKE_OK: i32 = 0;
+/// The backend id names a backend not compiled into the library.
+pub const STAKE_ERR_BACKEND: i32 = 1;
+/// A required pointer was null.
+pub const STAKE_ERR_NULL: i32 = 2;
+
+/// Stands in for an absent epoch.
+pub const STAKE_EPOCH_NONE: u64 = u64::MAX;
+
+/// The cluster's stake in one epoch, as the StakeHistory sysvar records it.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StakeClusterState {
+    pub effective: u64,
+    pub activating: u64,
+    pub deactivating: u64,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StakeHistoryItem {
+    pub epoch: u64,
+    pub state: StakeClusterState,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StakeStatus {
+    pub effective: u64,
+    pub activating: u64,
+    pub deactivating: u64,
+}
+
+impl From<StakeClusterState> for StakeHistoryEntry {
+    fn from(state: StakeClusterState) -> Self {
+        Self {
+            effective: state.effective,
+            activating: state.activating,
+            deactivating: state.deactivating,
+        }
+    }
+}
+
+impl From<StakeActivationStatus> for StakeStatus {
+    fn from(status: StakeActivationStatus) -> Self {
+        Self {
+            effective: status.effective,
+            activating: status.activating,
+            deactivating: status.deactivating,
+        }
+    }
+}
+
+fn optional_epoch(epoch: u64) -> Option<Epoch> {
+    (epoch != STAKE_EPOCH_NONE).then_some(epoch)
+}
+
+/// Caller-owned history entries, newest first as in the sysvar.
+struct History<'a>(&'a [StakeHistoryItem]);
+
+impl StakeHistoryGetEntry for History<'_> {
+    fn get_entry(&self, epoch: Epoch) -> Option<StakeHistoryEntry> {
+        self.0
+            .binary_search_by(|item| epoch.cmp(&item.epoch))
+            .ok()
+            .map(|index| self.0[index].state.into())
+    }
+}
+
+/// Shared body of the two allowance functions.
+unsafe fn allowance(
+    backend_id: u8,
+    cluster: *const StakeClusterState,
+    out: *mut u64,
+    calculate: impl FnOnce(Backend, &StakeHistoryEntry) -> u64,
+) -> i32 {
+    let Some(backend) = Backend::from_id(backend_id) else {
+        return STAKE_ERR_BACKEND;
+    };
+    if cluster.is_null() || out.is_null() {
+        return STAKE_ERR_NULL;
+    }
+    let cluster = StakeHistoryEntry::from(*cluster);
+    *out = calculate(backend, &cluster);
+    STAKE_OK
+}
+
+/// Stake of `account_stake` that becomes effective at the end of `epoch`,
+/// given the cluster's state in the previous epoch.
+///
+/// # Safety
+///
+/// `cluster` must point to a readable [`StakeClusterState`] and `out` to a
+/// writable `u64`.
+#[no_mangle]
+pub unsafe extern "C" fn stake_activation_allowance(
+    backend_id: u8,
+    epoch: u64,
+    account_stake: u64,
+    cluster: *const StakeClusterState,
+    new_rate_activation_epoch: u64,
+    out: *mut u64,
+) -> i32 {
+    allowance(backend_id, cluster, out, |backend, cluster| {
+        backend.calculate_activation_allowance(
+            epoch,
+            account_stake,
+            cluster,
+            optional_epoch(new_rate_activation_epoch),
+        )
+    })
+}
+
+/// Stake of `account_stake` that cools down at the end of `epoch`, given
+/// the cluster's state in the previous epoch.
+///
+/// # Safety
+///
+/// As for [`stake_activation_allowance`].
+#[no_mangle]
+pub unsafe extern "C" fn stake_deactivation_allowance(
+    backend_id: u8,
+    epoch: u64,
+    account_stake: u64,
+    cluster: *const StakeClusterState,
+    new_rate_activation_epoch: u64,
+    out: *mut u64,
+) -> i32 {
+    allowance(backend_id, cluster, out, |backend, cluster| {
+        backend.calculate_deactivation_allowance(
+            epoch,
+            account_stake,
+            cluster,
+            optional_epoch(new_rate_activation_epoch),
+        )
+    })
+}
+
+struct Status<'a> {
+    delegation: Delegation,
+    epoch: Epoch,
+    history: History<'a>,
+    new_rate_activation_epoch: Option<Epoch>,
+}
+
+impl BackendVisitor for Status<'_> {
+    type Output = StakeActivationStatus;
+
+    fn visit<T: StakeCalculator>(self) -> StakeActivationStatus {
+        self.delegation.stake_activating_and_deactivating::<T>(
+            self.epoch,
+            &self.history,
+            self.new_rate_activation_epoch,
+        )
+    }
+}
+
+/// Effective, activating and deactivating stake of a delegation at `epoch`.
+///
+/// `history` holds `history_len` entries, newest first with no repeated
+/// epoch, as in the StakeHistory sysvar; it may be null if `history_len`
+/// is zero.
+///
+/// # Safety
+///
+/// `history` must point to `history_len` readable [`StakeHistoryItem`]s and
+/// `out` to a writable [`StakeStatus`].
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn stake_delegation_status(
+    backend_id: u8,
+    stake: u64,
+    activation_epoch: u64,
+    deactivation_epoch: u64,
+    epoch: u64,
+    history: *const StakeHistoryItem,
+    history_len: usize,
+    new_rate_activation_epoch: u64,
+    out: *mut StakeStatus,
+) -> i32 {
+    let Some(backend) = Backend::from_id(backend_id) else {
+        return STAKE_ERR_BACKEND;
+    };
+    if out.is_null() || (history.is_null() && history_len != 0) {
+        return STAKE_ERR_NULL;
+    }
+    let history = match history_len {
+        0 => &[],
+        len => core::slice::from_raw_parts(history, len),
+    };
+
+    let status = backend.visit(Status {
+        delegation: Delegation {
+            stake,
+            activation_epoch,
+            deactivation_epoch,
+            ..Delegation::default()
+        },
+        epoch,
+        history: History(history),
+        new_rate_activation_epoch: optional_epoch(new_rate_activation_epoch),
+    });
+    *out = status.into();
+    STAKE_OK
+}
@@ -0,0 +1,71 @@
+//! [`Arbitrary`] for the domain types, so fuzz targets can ask for
+//! delegations and cluster states instead of decoding raw integers.
+//!
+//! Values are drawn where the runtime keeps them rather than from the whole
+//! `u64` range: epochs within [`EPOCH_SPAN`] of zero, so delegations
+//! generated together warm up and cool down across the same epochs, and
+//! stakes up to [`MAX_STAKE`], so a few hundred of them still sum without
+//! saturating.
+
+use arbitrary::{Arbitrary, Result, Unstructured};
+
+use crate::stake_history::StakeHistoryEntry;
+use crate::state::Delegation;
+use crate::{Epoch, RateSchedule};
+
+/// Generated epochs are at most this far past zero, or past the activation
+/// for a deactivation.
+pub const EPOCH_SPAN: Epoch = 64;
+
+/// About 72M SOL, past any single delegation on mainnet.
+pub const MAX_STAKE: u64 = 1 << 56;
+
+fn epoch(u: &mut Unstructured<'_>) -> Result<Epoch> {
+    u.int_in_range(0..=EPOCH_SPAN)
+}
+
+/// Deactivating stake is still effective, so never more than it.
+impl<'a> Arbitrary<'a> for StakeHistoryEntry {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let effective = u.int_in_range(0..=MAX_STAKE)?;
+        Ok(Self {
+            activating: u.int_in_range(0..=MAX_STAKE)?,
+            deactivating: u.int_in_range(0..=effective)?,
+            effective,
+        })
+    }
+}
+
+/// One in eight is a bootstrap stake, and one in two is never deactivated.
+impl<'a> Arbitrary<'a> for Delegation {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let activation_epoch = match u.ratio(1, 8)? {
+            true => u64::MAX,
+            false => epoch(u)?,
+        };
+        let deactivation_epoch = match u.arbitrary()? {
+            true => u64::MAX,
+            false => activation_epoch.min(EPOCH_SPAN).saturating_add(epoch(u)?),
+        };
+        Ok(Self {
+            voter_pubkey: u.arbitrary()?,
+            stake: u.int_in_range(1..=MAX_STAKE)?,
+            activation_epoch,
+            deactivation_epoch,
+            ..Self::default()
+        })
+    }
+}
+
+/// Always [`RateSchedule::TwoRate`]: `Events` borrows a table of pairs,
+/// which an [`Unstructured`] can't lend, and the delegation path only takes
+/// the two-rate schedule anyway. The switch epoch is `None` one time in
+/// four.
+impl<'a> Arbitrary<'a> for RateSchedule<'a> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Self::two_rate(match u.ratio(1, 4)? {
+            true => None,
+            false => Some(epoch(u)?),
+        }))
+    }
+}
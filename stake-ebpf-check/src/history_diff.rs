@@ -0,0 +1,76 @@
+//! Per-epoch comparison of two stake histories, e.g. a simulated projection
+//! against what the cluster recorded.
+
+use crate::stake_history::{StakeHistory, StakeHistoryEntry};
+use crate::Epoch;
+
+/// `b - a` per field. Exact as long as the difference fits in an `i64`, which
+/// any real lamport amount does.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct EntryDelta {
+    pub effective: i64,
+    pub activating: i64,
+    pub deactivating: i64,
+}
+
+impl EntryDelta {
+    pub fn between(a: &StakeHistoryEntry, b: &StakeHistoryEntry) -> Self {
+        Self {
+            effective: b.effective.wrapping_sub(a.effective) as i64,
+            activating: b.activating.wrapping_sub(a.activating) as i64,
+            deactivating: b.deactivating.wrapping_sub(a.deactivating) as i64,
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EpochDiff {
+    pub epoch: Epoch,
+    pub a: Option<StakeHistoryEntry>,
+    pub b: Option<StakeHistoryEntry>,
+    /// Missing entries count as all zero.
+    pub delta: EntryDelta,
+}
+
+/// Iterator over the union of both histories' epochs, newest first.
+pub struct HistoryDiff<'a> {
+    a: &'a StakeHistory,
+    b: &'a StakeHistory,
+}
+
+impl Iterator for HistoryDiff<'_> {
+    type Item = EpochDiff;
+
+    fn next(&mut self) -> Option<EpochDiff> {
+        let epoch = match (self.a.first(), self.b.first()) {
+            (None, None) => return None,
+            (Some(&(epoch, _)), None) | (None, Some(&(epoch, _))) => epoch,
+            (Some(&(a, _)), Some(&(b, _))) => a.max(b),
+        };
+
+        let take = |history: &mut &StakeHistory| match history.split_first() {
+            Some((&(e, entry), rest)) if e == epoch => {
+                *history = rest;
+                Some(entry)
+            }
+            _ => None,
+        };
+        let a = take(&mut self.a);
+        let b = take(&mut self.b);
+
+        Some(EpochDiff {
+            epoch,
+            a,
+            b,
+            delta: EntryDelta::between(&a.unwrap_or_default(), &b.unwrap_or_default()),
+        })
+    }
+}
+
+pub fn diff_histories<'a>(a: &'a StakeHistory, b: &'a StakeHistory) -> HistoryDiff<'a> {
+    HistoryDiff { a, b }
+}
@@ -0,0 +1,120 @@
+//! Native stand-in for the SBF loader.
+//!
+//! Serializes accounts and instruction data into the same input region the
+//! loader builds (see [`crate::entrypoint_raw`]), calls the real entrypoint
+//! on it and copies account data back out. Syscalls resolve to the host mocks in
+//! [`crate::syscalls::mock`].
+
+use crate::clock_sysvar::{self, ClockSysvar};
+use crate::entrypoint_raw::MAX_PERMITTED_DATA_INCREASE;
+use crate::program;
+use crate::state::Pubkey;
+use crate::Epoch;
+
+const NON_DUP_MARKER: u8 = u8::MAX;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SimAccount {
+    pub key: Pubkey,
+    pub owner: Pubkey,
+    pub lamports: u64,
+    pub data: Vec<u8>,
+    pub is_signer: bool,
+    pub is_writable: bool,
+    pub executable: bool,
+}
+
+impl SimAccount {
+    /// A writable, program-owned account with `len` zeroed bytes of data.
+    pub fn result(key: Pubkey, program_id: Pubkey, len: usize) -> Self {
+        Self {
+            key,
+            owner: program_id,
+            data: vec![0; len],
+            is_writable: true,
+            ..Self::default()
+        }
+    }
+
+    /// The Clock sysvar at `epoch`, other fields zeroed.
+    pub fn clock(epoch: Epoch) -> Self {
+        Self {
+            key: clock_sysvar::ID,
+            data: ClockSysvar {
+                epoch,
+                ..ClockSysvar::default()
+            }
+            .to_bytes()
+            .to_vec(),
+            ..Self::default()
+        }
+    }
+}
+
+/// A serialized input region and where each account's data landed in it.
+pub struct InputRegion {
+    buffer: Vec<u8>,
+    data_offsets: Vec<usize>,
+}
+
+impl InputRegion {
+    pub fn serialize(
+        accounts: &[SimAccount],
+        instruction_data: &[u8],
+        program_id: &Pubkey,
+    ) -> Self {
+        let mut buffer = Vec::new();
+        let mut data_offsets = Vec::with_capacity(accounts.len());
+
+        buffer.extend_from_slice(&(accounts.len() as u64).to_le_bytes());
+        for account in accounts {
+            buffer.push(NON_DUP_MARKER);
+            buffer.push(account.is_signer as u8);
+            buffer.push(account.is_writable as u8);
+            buffer.push(account.executable as u8);
+            buffer.extend_from_slice(&[0; 4]);
+            buffer.extend_from_slice(&account.key);
+            buffer.extend_from_slice(&account.owner);
+            buffer.extend_from_slice(&account.lamports.to_le_bytes());
+            buffer.extend_from_slice(&(account.data.len() as u64).to_le_bytes());
+            data_offsets.push(buffer.len());
+            buffer.extend_from_slice(&account.data);
+            buffer.resize(buffer.len() + MAX_PERMITTED_DATA_INCREASE, 0);
+            buffer.resize(buffer.len().next_multiple_of(8), 0);
+            // rent_epoch
+            buffer.extend_from_slice(&u64::MAX.to_le_bytes());
+        }
+        buffer.extend_from_slice(&(instruction_data.len() as u64).to_le_bytes());
+        buffer.extend_from_slice(instruction_data);
+        buffer.extend_from_slice(program_id);
+
+        Self {
+            buffer,
+            data_offsets,
+        }
+    }
+
+    pub fn as_mut_ptr(&mut self) -> *mut u8 {
+        self.buffer.as_mut_ptr()
+    }
+
+    /// The first `len` bytes of account `index`'s data.
+    pub fn account_data(&self, index: usize, len: usize) -> &[u8] {
+        let offset = self.data_offsets[index];
+        &self.buffer[offset..offset + len]
+    }
+}
+
+/// Runs the program entrypoint over `accounts`, writing any data changes
+/// back into them. Returns the entrypoint's return code.
+pub fn invoke(accounts: &mut [SimAccount], instruction_data: &[u8], program_id: &Pubkey) -> u64 {
+    let mut region = InputRegion::serialize(accounts, instruction_data, program_id);
+    let code = unsafe { program::entrypoint(region.as_mut_ptr()) };
+    for (index, account) in accounts.iter_mut().enumerate() {
+        let len = account.data.len();
+        account
+            .data
+            .copy_from_slice(region.account_data(index, len));
+    }
+    code
+}
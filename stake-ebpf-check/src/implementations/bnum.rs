@@ -1,14 +1,5 @@
-use crate::{StakeCalculator, warmup_cooldown_rate_bps, Epoch, BASIS_POINTS_PER_UNIT};
+use crate::{StakeCalculator, BASIS_POINTS_PER_UNIT};
 use bnum::{BUintD32};
-use core::alloc::{GlobalAlloc, Layout};
-
-struct NoAlloc;
-unsafe impl GlobalAlloc for NoAlloc {
-    unsafe fn alloc(&self, _layout: Layout) -> *mut u8 { core::ptr::null_mut() }
-    unsafe fn dealloc(&self, _ptr: *mut u8, _layout: Layout) {}
-}
-#[global_allocator]
-static GLOBAL: NoAlloc = NoAlloc;
 
 type U = BUintD32<2>;
 
@@ -16,19 +7,16 @@ pub struct BnumCalculator;
 
 impl StakeCalculator for BnumCalculator {
     #[inline(never)]
-    fn rate_limited_stake_change(
-        epoch: Epoch,
+    fn rate_limited_stake_change_bps(
+        rate_bps: u64,
         account_portion: u64,
         cluster_portion: u64,
         cluster_effective: u64,
-        new_rate_activation_epoch: Option<Epoch>,
     ) -> u64 {
         if account_portion == 0 || cluster_portion == 0 || cluster_effective == 0 {
             return 0;
         }
 
-        let rate_bps = warmup_cooldown_rate_bps(epoch, new_rate_activation_epoch);
-
         let a    = U::from(account_portion);
         let ce   = U::from(cluster_effective);
         let rate = U::from(rate_bps);
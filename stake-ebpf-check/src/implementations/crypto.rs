@@ -1,4 +1,4 @@
-use crate::{StakeCalculator, warmup_cooldown_rate_bps, Epoch, BASIS_POINTS_PER_UNIT};
+use crate::{StakeCalculator, BASIS_POINTS_PER_UNIT};
 use crypto_bigint::U256;
 
 pub struct CryptoCalculator;
@@ -20,19 +20,16 @@ fn u256_floor_to_u64(x: U256) -> u64 {
 
 impl StakeCalculator for CryptoCalculator {
     #[inline(never)]
-    fn rate_limited_stake_change(
-        epoch: Epoch,
+    fn rate_limited_stake_change_bps(
+        rate_bps: u64,
         account_portion: u64,
         cluster_portion: u64,
         cluster_effective: u64,
-        new_rate_activation_epoch: Option<Epoch>,
     ) -> u64 {
         if account_portion == 0 || cluster_portion == 0 || cluster_effective == 0 {
             return 0;
         }
 
-        let rate_bps = warmup_cooldown_rate_bps(epoch, new_rate_activation_epoch);
-
         let a   = U256::from(account_portion);
         let ce  = U256::from(cluster_effective);
         let r   = U256::from(rate_bps);
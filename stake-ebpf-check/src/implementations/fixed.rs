@@ -1,4 +1,4 @@
-use crate::{StakeCalculator, warmup_cooldown_rate_bps, Epoch, BASIS_POINTS_PER_UNIT};
+use crate::{StakeCalculator, BASIS_POINTS_PER_UNIT};
 use core::ops::{DivAssign, MulAssign};
 use fixed_bigint::fixeduint::FixedUInt;
 use fixed_bigint::num_traits::ToPrimitive;
@@ -17,19 +17,16 @@ fn u256_floor_to_u64(x: &U256x16) -> u64 {
 
 impl StakeCalculator for FixedCalculator {
     #[inline(never)]
-    fn rate_limited_stake_change(
-        epoch: Epoch,
+    fn rate_limited_stake_change_bps(
+        rate_bps: u64,
         account_portion: u64,
         cluster_portion: u64,
         cluster_effective: u64,
-        new_rate_activation_epoch: Option<Epoch>,
     ) -> u64 {
         if account_portion == 0 || cluster_portion == 0 || cluster_effective == 0 {
             return 0;
         }
 
-        let rate_bps = warmup_cooldown_rate_bps(epoch, new_rate_activation_epoch);
-
         let mut num = U256x16::from(account_portion);
         let ce = U256x16::from(cluster_effective);
         let r = U256x16::from(rate_bps);
@@ -15,3 +15,221 @@ pub mod plain;
 
 #[cfg(feature = "manual")]
 pub mod manual;
+
+#[cfg(feature = "streaming")]
+pub mod streaming;
+
+use core::hint::black_box;
+
+use crate::stake_history::StakeHistoryEntry;
+use crate::{warmup_cooldown_rate_bps, Epoch, StakeCalculator};
+
+/// What [`Backend`] dispatches to for backend `ID`: the calculator itself,
+/// or with the `trace` feature the calculator wrapped to record each call.
+#[cfg(not(feature = "trace"))]
+type Dispatch<T, const ID: u8> = T;
+#[cfg(feature = "trace")]
+type Dispatch<T, const ID: u8> = crate::trace::Traced<T, ID>;
+
+#[cfg(feature = "bnum")]
+type BnumCalculator = Dispatch<bnum::BnumCalculator, { Backend::Bnum as u8 }>;
+#[cfg(feature = "crypto")]
+type CryptoCalculator = Dispatch<crypto::CryptoCalculator, { Backend::Crypto as u8 }>;
+#[cfg(feature = "fixed")]
+type FixedCalculator = Dispatch<fixed::FixedCalculator, { Backend::Fixed as u8 }>;
+#[cfg(feature = "uint")]
+type UintCalculator = Dispatch<uint_impl::UintCalculator, { Backend::Uint as u8 }>;
+#[cfg(feature = "plain")]
+type PlainCalculator = Dispatch<plain::PlainCalculator, { Backend::Plain as u8 }>;
+#[cfg(feature = "manual")]
+type ManualCalculator = Dispatch<manual::ManualCalculator, { Backend::Manual as u8 }>;
+#[cfg(feature = "streaming")]
+type EbpfStreamingCalculator =
+    Dispatch<streaming::EbpfStreamingCalculator, { Backend::Streaming as u8 }>;
+
+/// Runs generic code against whichever calculator a [`Backend`] names.
+pub trait BackendVisitor {
+    type Output;
+
+    fn visit<T: StakeCalculator>(self) -> Self::Output;
+}
+
+/// Calculator backends compiled into this build, by wire id.
+///
+/// Every backend takes any operands without panicking: a zero account
+/// portion, cluster portion or cluster effective stake allows nothing. Only
+/// the exact backends answer correctly for all of them, though.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Backend {
+    /// Exact only while `account * effective * rate` fits in 64 bits; wider
+    /// products overflow.
+    #[cfg(feature = "bnum")]
+    Bnum = 0,
+    #[cfg(feature = "crypto")]
+    Crypto = 1,
+    #[cfg(feature = "fixed")]
+    Fixed = 2,
+    #[cfg(feature = "uint")]
+    Uint = 3,
+    /// A placeholder that compiles to the cheapest program; its nonzero
+    /// answers mean nothing.
+    #[cfg(feature = "plain")]
+    Plain = 4,
+    /// Allows the whole account portion once `account * effective * rate`
+    /// passes 128 bits.
+    #[cfg(feature = "manual")]
+    Manual = 5,
+    #[cfg(feature = "streaming")]
+    Streaming = 6,
+}
+
+impl Backend {
+    /// Every backend compiled into this build, in id order.
+    pub const ALL: &'static [Backend] = &[
+        #[cfg(feature = "bnum")]
+        Self::Bnum,
+        #[cfg(feature = "crypto")]
+        Self::Crypto,
+        #[cfg(feature = "fixed")]
+        Self::Fixed,
+        #[cfg(feature = "uint")]
+        Self::Uint,
+        #[cfg(feature = "plain")]
+        Self::Plain,
+        #[cfg(feature = "manual")]
+        Self::Manual,
+        #[cfg(feature = "streaming")]
+        Self::Streaming,
+    ];
+
+    pub fn from_id(id: u8) -> Option<Self> {
+        match id {
+            #[cfg(feature = "bnum")]
+            0 => Some(Self::Bnum),
+            #[cfg(feature = "crypto")]
+            1 => Some(Self::Crypto),
+            #[cfg(feature = "fixed")]
+            2 => Some(Self::Fixed),
+            #[cfg(feature = "uint")]
+            3 => Some(Self::Uint),
+            #[cfg(feature = "plain")]
+            4 => Some(Self::Plain),
+            #[cfg(feature = "manual")]
+            5 => Some(Self::Manual),
+            #[cfg(feature = "streaming")]
+            6 => Some(Self::Streaming),
+            _ => None,
+        }
+    }
+
+    pub fn id(self) -> u8 {
+        self as u8
+    }
+
+    pub fn visit<V: BackendVisitor>(self, visitor: V) -> V::Output {
+        match self {
+            #[cfg(feature = "bnum")]
+            Self::Bnum => visitor.visit::<BnumCalculator>(),
+            #[cfg(feature = "crypto")]
+            Self::Crypto => visitor.visit::<CryptoCalculator>(),
+            #[cfg(feature = "fixed")]
+            Self::Fixed => visitor.visit::<FixedCalculator>(),
+            #[cfg(feature = "uint")]
+            Self::Uint => visitor.visit::<UintCalculator>(),
+            #[cfg(feature = "plain")]
+            Self::Plain => visitor.visit::<PlainCalculator>(),
+            #[cfg(feature = "manual")]
+            Self::Manual => visitor.visit::<ManualCalculator>(),
+            #[cfg(feature = "streaming")]
+            Self::Streaming => visitor.visit::<EbpfStreamingCalculator>(),
+        }
+    }
+
+    pub fn calculate_activation_allowance(
+        self,
+        current_epoch: Epoch,
+        account_activating_stake: u64,
+        prev_epoch_cluster_state: &StakeHistoryEntry,
+        new_rate_activation_epoch: Option<Epoch>,
+    ) -> u64 {
+        black_box(self.rate_limited_stake_change_bps(
+            warmup_cooldown_rate_bps(current_epoch, new_rate_activation_epoch),
+            account_activating_stake,
+            prev_epoch_cluster_state.activating,
+            prev_epoch_cluster_state.effective,
+        ))
+    }
+
+    pub fn calculate_deactivation_allowance(
+        self,
+        current_epoch: Epoch,
+        account_deactivating_stake: u64,
+        prev_epoch_cluster_state: &StakeHistoryEntry,
+        new_rate_activation_epoch: Option<Epoch>,
+    ) -> u64 {
+        black_box(self.rate_limited_stake_change_bps(
+            warmup_cooldown_rate_bps(current_epoch, new_rate_activation_epoch),
+            account_deactivating_stake,
+            prev_epoch_cluster_state.deactivating,
+            prev_epoch_cluster_state.effective,
+        ))
+    }
+
+    pub fn rate_limited_stake_change_bps(
+        self,
+        rate_bps: u64,
+        account_portion: u64,
+        cluster_portion: u64,
+        cluster_effective: u64,
+    ) -> u64 {
+        let args = (
+            rate_bps,
+            account_portion,
+            cluster_portion,
+            cluster_effective,
+        );
+        match self {
+            #[cfg(feature = "bnum")]
+            Self::Bnum => call::<BnumCalculator>(args),
+            #[cfg(feature = "crypto")]
+            Self::Crypto => call::<CryptoCalculator>(args),
+            #[cfg(feature = "fixed")]
+            Self::Fixed => call::<FixedCalculator>(args),
+            #[cfg(feature = "uint")]
+            Self::Uint => call::<UintCalculator>(args),
+            #[cfg(feature = "plain")]
+            Self::Plain => call::<PlainCalculator>(args),
+            #[cfg(feature = "manual")]
+            Self::Manual => call::<ManualCalculator>(args),
+            #[cfg(feature = "streaming")]
+            Self::Streaming => call::<EbpfStreamingCalculator>(args),
+        }
+    }
+}
+
+#[allow(dead_code)]
+#[inline(always)]
+fn call<T: StakeCalculator>(
+    (rate_bps, account, cluster_portion, cluster_effective): (u64, u64, u64, u64),
+) -> u64 {
+    T::rate_limited_stake_change_bps(rate_bps, account, cluster_portion, cluster_effective)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zero_operands_allow_nothing_on_every_backend() {
+        for &backend in Backend::ALL {
+            for (account, cluster, effective) in [(0, 1, 1), (1, 0, 1), (1, 1, 0), (0, 0, 0)] {
+                assert_eq!(
+                    backend.rate_limited_stake_change_bps(2_500, account, cluster, effective),
+                    0,
+                    "{backend:?} on {account}, {cluster}, {effective}"
+                );
+            }
+        }
+    }
+}
@@ -1,17 +1,20 @@
-use crate::{StakeCalculator, Epoch, BASIS_POINTS_PER_UNIT};
+use crate::{StakeCalculator, BASIS_POINTS_PER_UNIT};
 
 pub struct PlainCalculator;
 
 impl StakeCalculator for PlainCalculator {
     #[inline(never)]
-    fn rate_limited_stake_change(
-        epoch: Epoch,
+    fn rate_limited_stake_change_bps(
+        rate_bps: u64,
         account_portion: u64,
         cluster_portion: u64,
         cluster_effective: u64,
-        _new_rate_activation_epoch: Option<Epoch>,
     ) -> u64 {
+        if account_portion == 0 || cluster_portion == 0 || cluster_effective == 0 {
+            return 0;
+        }
+
         // Not accurate, but to just get something that compiles
-        return epoch / account_portion / cluster_portion / cluster_effective / BASIS_POINTS_PER_UNIT;
+        rate_bps / account_portion / cluster_portion / cluster_effective / BASIS_POINTS_PER_UNIT
     }
 }
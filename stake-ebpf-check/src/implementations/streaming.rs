@@ -0,0 +1,50 @@
+use crate::streaming::{div_rem_wide, mul_div_wide, mul_wide};
+#[cfg(feature = "bpf-logging")]
+use crate::syscalls;
+use crate::{StakeCalculator, BASIS_POINTS_PER_UNIT};
+
+/// `u128`-free calculator built on the limb arithmetic in [`crate::streaming`].
+///
+/// With `D = cluster_portion * 10_000`, the product `account * effective` is
+/// split as `q1 * D + rem`, so the allowance is `q1 * rate + rem * rate / D`
+/// and only the second term (`t2`, always below `rate`) needs wide division.
+pub struct EbpfStreamingCalculator;
+
+impl StakeCalculator for EbpfStreamingCalculator {
+    #[inline(never)]
+    fn rate_limited_stake_change_bps(
+        rate_bps: u64,
+        account_portion: u64,
+        cluster_portion: u64,
+        cluster_effective: u64,
+    ) -> u64 {
+        #[cfg(feature = "bpf-logging")]
+        syscalls::log_64(
+            rate_bps,
+            account_portion,
+            cluster_portion,
+            cluster_effective,
+            0,
+        );
+
+        if account_portion == 0 || cluster_portion == 0 || cluster_effective == 0 {
+            return 0;
+        }
+
+        let numerator = mul_wide(account_portion, cluster_effective);
+        let denominator = mul_wide(cluster_portion, BASIS_POINTS_PER_UNIT);
+
+        // A quotient past 64 bits already exceeds the account portion.
+        let Some((q1, (rem_hi, rem_lo))) = div_rem_wide(numerator, denominator) else {
+            return if rate_bps == 0 { 0 } else { account_portion };
+        };
+        let t2 = mul_div_wide((rem_hi, rem_lo), rate_bps, denominator).unwrap_or(0);
+        let delta = q1.saturating_mul(rate_bps).saturating_add(t2);
+        let result = delta.min(account_portion);
+
+        #[cfg(feature = "bpf-logging")]
+        syscalls::log_64(q1, rem_hi, rem_lo, t2, result);
+
+        result
+    }
+}
@@ -0,0 +1,662 @@
+//! Instruction data understood by the program entrypoint.
+//!
+//! Byte 0 is the instruction tag and byte 1 the backend id (see `Backend`).
+//! Setting `FLAG_DIAG` in the tag byte asks `Allowance`, `Batch` and
+//! `Differential` to append their intermediate values to a `diag` account
+//! passed after `result` (see `diag`); other instructions ignore it.
+//! All integers are little-endian and `u64::MAX` encodes an absent
+//! `new_rate_activation_epoch`.
+//!
+//! Malformed data fails the instruction with a custom error code:
+//!
+//! ```text
+//! 1  shorter than the two header bytes
+//! 2  unknown tag
+//! 3  wrong length for the tag
+//! 4  zero cluster activating or deactivating stake (Allowance operands)
+//! 5  backend not compiled into the program (never for Allowance and Batch
+//!    in consensus builds, which use streaming whatever the id)
+//! 6  more than `MAX_BATCH_LEN` accounts in a batch
+//! 7  a result failed its `consensus` check (consensus builds only)
+//! 8  the two `Differential` backends disagree
+//! ```
+//!
+//! `Allowance` (tag 0), accounts: `[clock_sysvar, result]`:
+//!
+//! ```text
+//! 2..10   u64  epoch
+//! 10..18  u64  account portion
+//! 18..26  u64  cluster effective
+//! 26..34  u64  cluster activating
+//! 34..42  u64  cluster deactivating
+//! 42..50  u64  new_rate_activation_epoch
+//! ```
+//!
+//! The account portion is used as-is for activation and halved (plus one)
+//! for deactivation, as the packed entrypoint does.
+//!
+//! The current epoch comes from the Clock sysvar and the epoch field is
+//! ignored. With the `instruction-epoch` feature the field is used instead
+//! and `clock_sysvar` is dropped from the accounts, for deterministic tests;
+//! the same holds for `MeasureComputeUnits`, `Batch` and `Differential`.
+//!
+//! In `consensus` builds `Allowance` and `Batch` always compute with the
+//! streaming backend, whatever byte 1 names, and fail with code 7 rather
+//! than write a result that does not pass `consensus::verify`.
+//!
+//! The result account must be writable and owned by the program; see
+//! `results` for what is written to it.
+//!
+//! `DelegationStatus` (tag 1), accounts: `[stake_history_sysvar, result]`:
+//!
+//! ```text
+//! 2..10   u64  target epoch
+//! 10..18  u64  delegated stake
+//! 18..26  u64  activation epoch
+//! 26..34  u64  deactivation epoch
+//! 34..42  u64  new_rate_activation_epoch
+//! ```
+//!
+//! `MeasureComputeUnits` (tag 2), accounts: `[clock_sysvar, result]`, takes the same
+//! operands as `Allowance` followed by a repetition count, and records the
+//! compute units each allowance consumed instead of its value:
+//!
+//! ```text
+//! 2..50   Allowance operands
+//! 50..58  u64  repetitions (0 runs once)
+//! ```
+//!
+//! `SelfTest` (tag 3), accounts: `[result]`, has no operands and runs the
+//! embedded `self_test` vectors on the selected backend.
+//!
+//! `Stress` (tag 4), accounts: `[result]`:
+//!
+//! ```text
+//! 2..10   u64  seed
+//! 10..18  u64  iterations
+//! ```
+//!
+//! `ValidateSplit` (tag 5), accounts: `[stake_program, result]`, asks the
+//! Stake program for its minimum delegation and validates a split against
+//! it. The backend id is not used.
+//!
+//! ```text
+//! 2..10   u64  source delegated stake
+//! 10..18  u64  split lamports
+//! 18..26  u64  rent-exempt reserve
+//! ```
+//!
+//! `Batch` (tag 6), accounts: `[clock_sysvar, result]`, runs `Allowance` for up to
+//! `MAX_BATCH_LEN` accounts sharing one cluster state; the account count
+//! follows from the length:
+//!
+//! ```text
+//! 2..10   u64  epoch
+//! 10..18  u64  cluster effective
+//! 18..26  u64  cluster activating
+//! 26..34  u64  cluster deactivating
+//! 34..42  u64  new_rate_activation_epoch
+//! 42..    u64  account portion, one per account
+//! ```
+//!
+//! `Differential` (tag 7), accounts: `[clock_sysvar, result]`, runs
+//! `Allowance` on the backend in byte 1 and on a second one, and records
+//! whether they agree along with both results. If they disagree it also logs
+//! the divergence byte and both results with `sol_log_64`, then fails with
+//! code 8, so the mismatch survives the runtime discarding the result:
+//!
+//! ```text
+//! 2..50   Allowance operands
+//! 50      u8   second backend id
+//! ```
+//!
+//! `Probe` (tag 8) takes the same data as `Allowance` and any accounts, up
+//! to `MAX_ACCOUNTS` and duplicates included, and only decodes it. Its compute units are the fixed transaction, entrypoint
+//! and decoding overhead to subtract from other measurements.
+
+use bytemuck::{Pod, Zeroable};
+
+use crate::stake_history::StakeHistoryEntry;
+use crate::Epoch;
+
+const HEADER_LEN: usize = 2;
+pub const ALLOWANCE_INSTRUCTION_LEN: usize = HEADER_LEN + OPERAND_BLOCK_LEN;
+pub const DELEGATION_STATUS_INSTRUCTION_LEN: usize = HEADER_LEN + 40;
+pub const MEASURE_COMPUTE_UNITS_INSTRUCTION_LEN: usize = ALLOWANCE_INSTRUCTION_LEN + 8;
+pub const SELF_TEST_INSTRUCTION_LEN: usize = HEADER_LEN;
+pub const STRESS_INSTRUCTION_LEN: usize = HEADER_LEN + 16;
+pub const VALIDATE_SPLIT_INSTRUCTION_LEN: usize = HEADER_LEN + 24;
+pub const MAX_BATCH_LEN: usize = 32;
+const BATCH_HEADER_LEN: usize = HEADER_LEN + 40;
+pub const DIFFERENTIAL_INSTRUCTION_LEN: usize = ALLOWANCE_INSTRUCTION_LEN + 1;
+/// Room for any instruction, the largest being a full batch.
+pub const MAX_INSTRUCTION_LEN: usize = BATCH_HEADER_LEN + 8 * MAX_BATCH_LEN;
+
+const TAG_ALLOWANCE: u8 = 0;
+const TAG_DELEGATION_STATUS: u8 = 1;
+const TAG_MEASURE_COMPUTE_UNITS: u8 = 2;
+const TAG_SELF_TEST: u8 = 3;
+const TAG_STRESS: u8 = 4;
+const TAG_VALIDATE_SPLIT: u8 = 5;
+const TAG_BATCH: u8 = 6;
+const TAG_DIFFERENTIAL: u8 = 7;
+const TAG_PROBE: u8 = 8;
+
+/// Tag-byte bit requesting diagnostics records.
+pub const FLAG_DIAG: u8 = 0x80;
+
+/// Options carried in the tag byte alongside the tag.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct InstructionFlags {
+    pub diag: bool,
+}
+
+impl InstructionFlags {
+    /// Flags in `data`'s tag byte; none if `data` is empty.
+    pub fn from_data(data: &[u8]) -> Self {
+        Self {
+            diag: data.first().is_some_and(|tag| tag & FLAG_DIAG != 0),
+        }
+    }
+}
+
+/// Why an instruction was rejected, returned by the program as a custom
+/// error with the discriminant as its code.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u32)]
+pub enum InstructionError {
+    /// Shorter than the two header bytes.
+    MissingHeader = 1,
+    UnknownTag = 2,
+    /// Not exactly the length the tag's layout calls for.
+    WrongLength = 3,
+    /// Cluster activating or deactivating stake is zero.
+    ZeroDivisor = 4,
+    /// The backend id names no backend compiled into the program.
+    UnknownBackend = 5,
+    /// More than [`MAX_BATCH_LEN`] account portions.
+    BatchTooLarge = 6,
+    /// A computed allowance failed [`crate::consensus::verify`]; nothing was
+    /// written.
+    ConsensusMismatch = 7,
+    /// The `Differential` backends returned different allowances; both are
+    /// in the program log.
+    Divergence = 8,
+}
+
+impl InstructionError {
+    pub fn code(self) -> u32 {
+        self as u32
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+pub struct Operands {
+    pub epoch: Epoch,
+    pub account_portion: u64,
+    pub cluster_state: StakeHistoryEntry,
+    pub new_rate_activation_epoch: Option<Epoch>,
+}
+
+/// The 48 operand bytes of `Allowance`, `MeasureComputeUnits`,
+/// `Differential` and `Probe` exactly as they sit in instruction data, so
+/// client and program share one definition instead of matching offsets.
+///
+/// Fields hold the little-endian wire values; convert through [`Operands`]
+/// rather than reading them directly on a big-endian host.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Pod, Zeroable)]
+#[repr(C)]
+pub struct OperandBlock {
+    pub epoch: u64,
+    pub account_portion: u64,
+    pub cluster_effective: u64,
+    pub cluster_activating: u64,
+    pub cluster_deactivating: u64,
+    /// `u64::MAX` for none.
+    pub new_rate_activation_epoch: u64,
+}
+
+pub const OPERAND_BLOCK_LEN: usize = core::mem::size_of::<OperandBlock>();
+
+impl OperandBlock {
+    /// Borrows `data` in place. `None` unless it is exactly
+    /// [`OPERAND_BLOCK_LEN`] bytes and 8-byte aligned; operands at byte 2 of
+    /// instruction data usually are not.
+    pub fn cast(data: &[u8]) -> Option<&Self> {
+        bytemuck::try_from_bytes(data).ok()
+    }
+
+    /// Casts `data` when aligned and copies it out otherwise. `None` unless
+    /// it is exactly [`OPERAND_BLOCK_LEN`] bytes.
+    pub fn read(data: &[u8]) -> Option<Self> {
+        match Self::cast(data) {
+            Some(block) => Some(*block),
+            None => bytemuck::try_pod_read_unaligned(data).ok(),
+        }
+    }
+}
+
+impl From<OperandBlock> for Operands {
+    fn from(block: OperandBlock) -> Self {
+        Operands {
+            epoch: u64::from_le(block.epoch),
+            account_portion: u64::from_le(block.account_portion),
+            cluster_state: StakeHistoryEntry {
+                effective: u64::from_le(block.cluster_effective),
+                activating: u64::from_le(block.cluster_activating),
+                deactivating: u64::from_le(block.cluster_deactivating),
+            },
+            new_rate_activation_epoch: match u64::from_le(block.new_rate_activation_epoch) {
+                u64::MAX => None,
+                epoch => Some(epoch),
+            },
+        }
+    }
+}
+
+impl From<&Operands> for OperandBlock {
+    fn from(operands: &Operands) -> Self {
+        OperandBlock {
+            epoch: operands.epoch.to_le(),
+            account_portion: operands.account_portion.to_le(),
+            cluster_effective: operands.cluster_state.effective.to_le(),
+            cluster_activating: operands.cluster_state.activating.to_le(),
+            cluster_deactivating: operands.cluster_state.deactivating.to_le(),
+            new_rate_activation_epoch: operands
+                .new_rate_activation_epoch
+                .unwrap_or(u64::MAX)
+                .to_le(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+pub struct DelegationOperands {
+    pub target_epoch: Epoch,
+    pub stake: u64,
+    pub activation_epoch: Epoch,
+    pub deactivation_epoch: Epoch,
+    pub new_rate_activation_epoch: Option<Epoch>,
+}
+
+/// Accounts sharing one epoch and cluster state.
+///
+/// The account portions stay the little-endian bytes they arrived as and
+/// are decoded as they are read, so a batch borrows its instruction data
+/// instead of copying up to [`MAX_BATCH_LEN`] values out of it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BatchOperands<'a> {
+    pub epoch: Epoch,
+    pub cluster_state: StakeHistoryEntry,
+    pub new_rate_activation_epoch: Option<Epoch>,
+    account_portions: &'a [u8],
+}
+
+impl<'a> BatchOperands<'a> {
+    /// `account_portions` is little-endian `u64`s, as in the instruction;
+    /// `None` unless it holds a whole number of them, at most
+    /// [`MAX_BATCH_LEN`].
+    pub fn new(
+        epoch: Epoch,
+        cluster_state: StakeHistoryEntry,
+        new_rate_activation_epoch: Option<Epoch>,
+        account_portions: &'a [u8],
+    ) -> Option<Self> {
+        if !account_portions.len().is_multiple_of(8) || account_portions.len() > 8 * MAX_BATCH_LEN {
+            return None;
+        }
+        Some(Self {
+            epoch,
+            cluster_state,
+            new_rate_activation_epoch,
+            account_portions,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.account_portions.len() / 8
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.account_portions.is_empty()
+    }
+
+    pub fn account_portions(&self) -> impl Iterator<Item = u64> + 'a {
+        self.account_portions
+            .chunks_exact(8)
+            .map(|chunk| read_u64(chunk, 0))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Instruction<'a> {
+    Allowance {
+        backend_id: u8,
+        operands: Operands,
+    },
+    DelegationStatus {
+        backend_id: u8,
+        operands: DelegationOperands,
+    },
+    MeasureComputeUnits {
+        backend_id: u8,
+        operands: Operands,
+        repetitions: u64,
+    },
+    SelfTest {
+        backend_id: u8,
+    },
+    Stress {
+        backend_id: u8,
+        seed: u64,
+        iterations: u64,
+    },
+    ValidateSplit {
+        backend_id: u8,
+        stake: u64,
+        split_lamports: u64,
+        rent_exempt_reserve: u64,
+    },
+    Batch {
+        backend_id: u8,
+        operands: BatchOperands<'a>,
+    },
+    Differential {
+        backend_id: u8,
+        other_backend_id: u8,
+        operands: Operands,
+    },
+    Probe {
+        backend_id: u8,
+        operands: Operands,
+    },
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&data[offset..offset + 8]);
+    u64::from_le_bytes(bytes)
+}
+
+fn read_epoch_option(data: &[u8], offset: usize) -> Option<Epoch> {
+    match read_u64(data, offset) {
+        u64::MAX => None,
+        epoch => Some(epoch),
+    }
+}
+
+fn read_operands(data: &[u8]) -> Operands {
+    OperandBlock::read(&data[HEADER_LEN..HEADER_LEN + OPERAND_BLOCK_LEN])
+        .expect("length checked by unpack")
+        .into()
+}
+
+fn write_fields(data: &mut [u8], fields: &[u64]) {
+    for (chunk, field) in data[HEADER_LEN..].chunks_exact_mut(8).zip(fields) {
+        chunk.copy_from_slice(&field.to_le_bytes());
+    }
+}
+
+fn write_operands(data: &mut [u8], operands: &Operands) {
+    data[HEADER_LEN..HEADER_LEN + OPERAND_BLOCK_LEN]
+        .copy_from_slice(bytemuck::bytes_of(&OperandBlock::from(operands)));
+}
+
+impl<'a> Instruction<'a> {
+    pub fn backend_id(&self) -> u8 {
+        match self {
+            Self::Allowance { backend_id, .. }
+            | Self::DelegationStatus { backend_id, .. }
+            | Self::MeasureComputeUnits { backend_id, .. }
+            | Self::SelfTest { backend_id }
+            | Self::Stress { backend_id, .. }
+            | Self::ValidateSplit { backend_id, .. }
+            | Self::Batch { backend_id, .. }
+            | Self::Differential { backend_id, .. }
+            | Self::Probe { backend_id, .. } => *backend_id,
+        }
+    }
+
+    pub fn unpack(data: &'a [u8]) -> Result<Self, InstructionError> {
+        let [tag, backend_id, ..] = *data else {
+            return Err(InstructionError::MissingHeader);
+        };
+        let tag = tag & !FLAG_DIAG;
+        let expected_len = match tag {
+            TAG_ALLOWANCE => ALLOWANCE_INSTRUCTION_LEN,
+            TAG_DELEGATION_STATUS => DELEGATION_STATUS_INSTRUCTION_LEN,
+            TAG_MEASURE_COMPUTE_UNITS => MEASURE_COMPUTE_UNITS_INSTRUCTION_LEN,
+            TAG_SELF_TEST => SELF_TEST_INSTRUCTION_LEN,
+            TAG_STRESS => STRESS_INSTRUCTION_LEN,
+            TAG_VALIDATE_SPLIT => VALIDATE_SPLIT_INSTRUCTION_LEN,
+            TAG_DIFFERENTIAL => DIFFERENTIAL_INSTRUCTION_LEN,
+            TAG_PROBE => ALLOWANCE_INSTRUCTION_LEN,
+            TAG_BATCH => {
+                let portions_len = data
+                    .len()
+                    .checked_sub(BATCH_HEADER_LEN)
+                    .filter(|len| *len > 0 && len % 8 == 0)
+                    .ok_or(InstructionError::WrongLength)?;
+                if portions_len / 8 > MAX_BATCH_LEN {
+                    return Err(InstructionError::BatchTooLarge);
+                }
+                data.len()
+            }
+            _ => return Err(InstructionError::UnknownTag),
+        };
+        if data.len() != expected_len {
+            return Err(InstructionError::WrongLength);
+        }
+
+        let instruction = match tag {
+            TAG_ALLOWANCE => Self::Allowance {
+                backend_id,
+                operands: read_operands(data),
+            },
+            TAG_MEASURE_COMPUTE_UNITS => Self::MeasureComputeUnits {
+                backend_id,
+                operands: read_operands(data),
+                repetitions: read_u64(data, ALLOWANCE_INSTRUCTION_LEN),
+            },
+            TAG_DELEGATION_STATUS => Self::DelegationStatus {
+                backend_id,
+                operands: DelegationOperands {
+                    target_epoch: read_u64(data, 2),
+                    stake: read_u64(data, 10),
+                    activation_epoch: read_u64(data, 18),
+                    deactivation_epoch: read_u64(data, 26),
+                    new_rate_activation_epoch: read_epoch_option(data, 34),
+                },
+            },
+            TAG_SELF_TEST => Self::SelfTest { backend_id },
+            TAG_STRESS => Self::Stress {
+                backend_id,
+                seed: read_u64(data, 2),
+                iterations: read_u64(data, 10),
+            },
+            TAG_VALIDATE_SPLIT => Self::ValidateSplit {
+                backend_id,
+                stake: read_u64(data, 2),
+                split_lamports: read_u64(data, 10),
+                rent_exempt_reserve: read_u64(data, 18),
+            },
+            TAG_DIFFERENTIAL => Self::Differential {
+                backend_id,
+                other_backend_id: data[ALLOWANCE_INSTRUCTION_LEN],
+                operands: read_operands(data),
+            },
+            TAG_PROBE => Self::Probe {
+                backend_id,
+                operands: read_operands(data),
+            },
+            _ => Self::Batch {
+                backend_id,
+                operands: BatchOperands {
+                    epoch: read_u64(data, 2),
+                    cluster_state: StakeHistoryEntry {
+                        effective: read_u64(data, 10),
+                        activating: read_u64(data, 18),
+                        deactivating: read_u64(data, 26),
+                    },
+                    new_rate_activation_epoch: read_epoch_option(data, 34),
+                    account_portions: &data[BATCH_HEADER_LEN..],
+                },
+            },
+        };
+        instruction.validate()?;
+        Ok(instruction)
+    }
+
+    /// Rejects a zero cluster activating or deactivating stake, the divisor
+    /// of the allowance. Zero account portions and cluster effective stake
+    /// are valid on every backend and allow nothing; see [`crate::Backend`]
+    /// for the range each backend is exact over.
+    fn validate(&self) -> Result<(), InstructionError> {
+        match self {
+            Self::Allowance { operands, .. }
+            | Self::MeasureComputeUnits { operands, .. }
+            | Self::Differential { operands, .. }
+                if operands.cluster_state.activating == 0
+                    || operands.cluster_state.deactivating == 0 =>
+            {
+                Err(InstructionError::ZeroDivisor)
+            }
+            Self::Batch { operands, .. }
+                if operands.cluster_state.activating == 0
+                    || operands.cluster_state.deactivating == 0 =>
+            {
+                Err(InstructionError::ZeroDivisor)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// [`Instruction::pack`] with `flags` set in the tag byte.
+    pub fn pack_with_flags(&self, flags: InstructionFlags, data: &mut [u8]) -> usize {
+        let len = self.pack(data);
+        if flags.diag {
+            data[0] |= FLAG_DIAG;
+        }
+        len
+    }
+
+    /// Writes the instruction into `data`, returning the encoded length.
+    /// `data` must be long enough; [`MAX_INSTRUCTION_LEN`] always is.
+    pub fn pack(&self, data: &mut [u8]) -> usize {
+        match self {
+            Self::Allowance {
+                backend_id,
+                operands,
+            } => {
+                data[0] = TAG_ALLOWANCE;
+                data[1] = *backend_id;
+                write_operands(data, operands);
+                ALLOWANCE_INSTRUCTION_LEN
+            }
+            Self::MeasureComputeUnits {
+                backend_id,
+                operands,
+                repetitions,
+            } => {
+                data[0] = TAG_MEASURE_COMPUTE_UNITS;
+                data[1] = *backend_id;
+                write_operands(data, operands);
+                data[ALLOWANCE_INSTRUCTION_LEN..MEASURE_COMPUTE_UNITS_INSTRUCTION_LEN]
+                    .copy_from_slice(&repetitions.to_le_bytes());
+                MEASURE_COMPUTE_UNITS_INSTRUCTION_LEN
+            }
+            Self::DelegationStatus {
+                backend_id,
+                operands,
+            } => {
+                data[0] = TAG_DELEGATION_STATUS;
+                data[1] = *backend_id;
+                write_fields(
+                    data,
+                    &[
+                        operands.target_epoch,
+                        operands.stake,
+                        operands.activation_epoch,
+                        operands.deactivation_epoch,
+                        operands.new_rate_activation_epoch.unwrap_or(u64::MAX),
+                    ],
+                );
+                DELEGATION_STATUS_INSTRUCTION_LEN
+            }
+            Self::SelfTest { backend_id } => {
+                data[0] = TAG_SELF_TEST;
+                data[1] = *backend_id;
+                SELF_TEST_INSTRUCTION_LEN
+            }
+            Self::Stress {
+                backend_id,
+                seed,
+                iterations,
+            } => {
+                data[0] = TAG_STRESS;
+                data[1] = *backend_id;
+                write_fields(data, &[*seed, *iterations]);
+                STRESS_INSTRUCTION_LEN
+            }
+            Self::ValidateSplit {
+                backend_id,
+                stake,
+                split_lamports,
+                rent_exempt_reserve,
+            } => {
+                data[0] = TAG_VALIDATE_SPLIT;
+                data[1] = *backend_id;
+                write_fields(data, &[*stake, *split_lamports, *rent_exempt_reserve]);
+                VALIDATE_SPLIT_INSTRUCTION_LEN
+            }
+            Self::Batch {
+                backend_id,
+                operands,
+            } => {
+                data[0] = TAG_BATCH;
+                data[1] = *backend_id;
+                write_fields(
+                    data,
+                    &[
+                        operands.epoch,
+                        operands.cluster_state.effective,
+                        operands.cluster_state.activating,
+                        operands.cluster_state.deactivating,
+                        operands.new_rate_activation_epoch.unwrap_or(u64::MAX),
+                    ],
+                );
+                let len = BATCH_HEADER_LEN + operands.account_portions.len();
+                data[BATCH_HEADER_LEN..len].copy_from_slice(operands.account_portions);
+                len
+            }
+            Self::Differential {
+                backend_id,
+                other_backend_id,
+                operands,
+            } => {
+                data[0] = TAG_DIFFERENTIAL;
+                data[1] = *backend_id;
+                write_operands(data, operands);
+                data[ALLOWANCE_INSTRUCTION_LEN] = *other_backend_id;
+                DIFFERENTIAL_INSTRUCTION_LEN
+            }
+            Self::Probe {
+                backend_id,
+                operands,
+            } => {
+                data[0] = TAG_PROBE;
+                data[1] = *backend_id;
+                write_operands(data, operands);
+                ALLOWANCE_INSTRUCTION_LEN
+            }
+        }
+    }
+}
@@ -0,0 +1,44 @@
+//! The rate limit's safety property: one epoch's grants across all accounts
+//! stay within `effective * rate_bps / 10_000`.
+//!
+//! Each account's grant is floored, which can only lower the total, but the
+//! runtime also grants at least one lamport per account per epoch. The
+//! allowed slack is therefore one lamport per account.
+
+use crate::stake_history::StakeHistoryEntry;
+use crate::streaming::{mul_div, Rounding};
+use crate::BASIS_POINTS_PER_UNIT;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InvariantViolation {
+    pub granted: u64,
+    pub limit: u64,
+}
+
+/// Cluster-wide limit for one epoch, before per-account slack.
+pub fn epoch_rate_limit(cluster_state: &StakeHistoryEntry, rate_bps: u64) -> u64 {
+    mul_div(
+        cluster_state.effective,
+        rate_bps,
+        BASIS_POINTS_PER_UNIT,
+        Rounding::Down,
+    )
+    .unwrap_or(u64::MAX)
+}
+
+pub fn check_epoch_invariant(
+    account_deltas: &[u64],
+    cluster_state: &StakeHistoryEntry,
+    rate_bps: u64,
+) -> Result<(), InvariantViolation> {
+    let limit =
+        epoch_rate_limit(cluster_state, rate_bps).saturating_add(account_deltas.len() as u64);
+    let granted = account_deltas
+        .iter()
+        .fold(0u64, |acc, &delta| acc.saturating_add(delta));
+
+    if granted > limit {
+        return Err(InvariantViolation { granted, limit });
+    }
+    Ok(())
+}
@@ -1,16 +1,92 @@
-#![no_std]
-use core::{cmp::max, panic::PanicInfo};
+#![cfg_attr(not(feature = "host-sim"), no_std)]
 use core::hint::black_box;
 
 pub type Epoch = u64;
 
 pub mod stake_history {
-    #[derive(Clone, Copy)]
+    use super::Epoch;
+    use core::ops::{Add, Sub};
+
+    /// Entries ordered newest first, as in the StakeHistory sysvar.
+    pub type StakeHistory = [(Epoch, StakeHistoryEntry)];
+
+    pub trait StakeHistoryGetEntry {
+        fn get_entry(&self, epoch: Epoch) -> Option<StakeHistoryEntry>;
+    }
+
+    impl StakeHistoryGetEntry for StakeHistory {
+        fn get_entry(&self, epoch: Epoch) -> Option<StakeHistoryEntry> {
+            self.binary_search_by(|(e, _)| epoch.cmp(e))
+                .ok()
+                .map(|index| self[index].1)
+        }
+    }
+
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    #[cfg_attr(
+        feature = "borsh",
+        derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+    )]
     pub struct StakeHistoryEntry {
         pub activating: u64,
         pub deactivating: u64,
         pub effective: u64,
     }
+
+    impl StakeHistoryEntry {
+        pub fn with_effective(effective: u64) -> Self {
+            Self {
+                effective,
+                ..Self::default()
+            }
+        }
+
+        pub fn with_effective_and_activating(effective: u64, activating: u64) -> Self {
+            Self {
+                effective,
+                activating,
+                ..Self::default()
+            }
+        }
+
+        /// Deactivating stake is still effective until it cools down.
+        pub fn with_deactivating(deactivating: u64) -> Self {
+            Self {
+                effective: deactivating,
+                deactivating,
+                ..Self::default()
+            }
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.activating == 0 && self.deactivating == 0 && self.effective == 0
+        }
+    }
+
+    impl Add for StakeHistoryEntry {
+        type Output = StakeHistoryEntry;
+
+        fn add(self, rhs: StakeHistoryEntry) -> Self::Output {
+            Self {
+                activating: self.activating.saturating_add(rhs.activating),
+                deactivating: self.deactivating.saturating_add(rhs.deactivating),
+                effective: self.effective.saturating_add(rhs.effective),
+            }
+        }
+    }
+
+    impl Sub for StakeHistoryEntry {
+        type Output = StakeHistoryEntry;
+
+        fn sub(self, rhs: StakeHistoryEntry) -> Self::Output {
+            Self {
+                activating: self.activating.saturating_sub(rhs.activating),
+                deactivating: self.deactivating.saturating_sub(rhs.deactivating),
+                effective: self.effective.saturating_sub(rhs.effective),
+            }
+        }
+    }
 }
 use stake_history::StakeHistoryEntry;
 
@@ -19,22 +95,76 @@ pub const ORIGINAL_WARMUP_COOLDOWN_RATE_BPS: u64 = 2_500;
 pub const TOWER_WARMUP_COOLDOWN_RATE_BPS: u64 = 900;
 
 #[inline]
-pub fn warmup_cooldown_rate_bps(epoch: Epoch, new_rate_activation_epoch: Option<Epoch>) -> u64 {
-    if epoch < new_rate_activation_epoch.unwrap_or(u64::MAX) {
+pub const fn warmup_cooldown_rate_bps(
+    epoch: Epoch,
+    new_rate_activation_epoch: Option<Epoch>,
+) -> u64 {
+    let switch = match new_rate_activation_epoch {
+        Some(epoch) => epoch,
+        None => u64::MAX,
+    };
+    if epoch < switch {
         ORIGINAL_WARMUP_COOLDOWN_RATE_BPS
     } else {
         TOWER_WARMUP_COOLDOWN_RATE_BPS
     }
 }
 
+/// Warmup/cooldown rate changes as `(activation_epoch, rate_bps)` events.
+///
+/// Epochs before the first event use [`ORIGINAL_WARMUP_COOLDOWN_RATE_BPS`];
+/// each event applies from its activation epoch on. Events must be sorted by
+/// epoch.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RateSchedule<'a> {
+    /// The cluster's schedule: the original rate until the given epoch, the
+    /// tower rate from it on.
+    TwoRate(Option<Epoch>),
+    Events(&'a [(Epoch, u64)]),
+}
+
+impl RateSchedule<'_> {
+    pub const fn two_rate(new_rate_activation_epoch: Option<Epoch>) -> Self {
+        Self::TwoRate(new_rate_activation_epoch)
+    }
+
+    pub fn rate_bps(&self, epoch: Epoch) -> u64 {
+        match self {
+            Self::TwoRate(new_rate_activation_epoch) => {
+                warmup_cooldown_rate_bps(epoch, *new_rate_activation_epoch)
+            }
+            Self::Events(events) => events
+                .iter()
+                .take_while(|(activation_epoch, _)| *activation_epoch <= epoch)
+                .last()
+                .map_or(ORIGINAL_WARMUP_COOLDOWN_RATE_BPS, |&(_, rate_bps)| rate_bps),
+        }
+    }
+}
+
 pub trait StakeCalculator {
+    fn rate_limited_stake_change_bps(
+        rate_bps: u64,
+        account_portion: u64,
+        cluster_portion: u64,
+        cluster_effective: u64,
+    ) -> u64;
+
+    #[inline]
     fn rate_limited_stake_change(
         epoch: Epoch,
         account_portion: u64,
         cluster_portion: u64,
         cluster_effective: u64,
         new_rate_activation_epoch: Option<Epoch>,
-    ) -> u64;
+    ) -> u64 {
+        Self::rate_limited_stake_change_bps(
+            warmup_cooldown_rate_bps(epoch, new_rate_activation_epoch),
+            account_portion,
+            cluster_portion,
+            cluster_effective,
+        )
+    }
 }
 
 pub fn calculate_activation_allowance<T: StakeCalculator>(
@@ -67,51 +197,134 @@ pub fn calculate_deactivation_allowance<T: StakeCalculator>(
     ))
 }
 
+/// One warmup step of an account with `remaining_activating_stake` left.
+///
+/// The step is the rate-limited allowance, at least one lamport and at most
+/// what remains. A cluster with no effective stake allows nothing, so it
+/// warms up a lamport per epoch, as upstream does; activating everything at
+/// once there, as first asked of this function, diverged from mainnet. Only
+/// a missing history entry activates everything, and callers handle that
+/// before asking for a step.
+pub fn calculate_warmup_step<T: StakeCalculator>(
+    current_epoch: Epoch,
+    remaining_activating_stake: u64,
+    prev_epoch_cluster_state: &StakeHistoryEntry,
+    new_rate_activation_epoch: Option<Epoch>,
+) -> u64 {
+    calculate_activation_allowance::<T>(
+        current_epoch,
+        remaining_activating_stake,
+        prev_epoch_cluster_state,
+        new_rate_activation_epoch,
+    )
+    .max(1)
+    .min(remaining_activating_stake)
+}
+
+pub fn calculate_activation_allowance_with_schedule<T: StakeCalculator>(
+    current_epoch: Epoch,
+    account_activating_stake: u64,
+    prev_epoch_cluster_state: &StakeHistoryEntry,
+    schedule: &RateSchedule,
+) -> u64 {
+    black_box(T::rate_limited_stake_change_bps(
+        schedule.rate_bps(current_epoch),
+        account_activating_stake,
+        prev_epoch_cluster_state.activating,
+        prev_epoch_cluster_state.effective,
+    ))
+}
+
+pub fn calculate_deactivation_allowance_with_schedule<T: StakeCalculator>(
+    current_epoch: Epoch,
+    account_deactivating_stake: u64,
+    prev_epoch_cluster_state: &StakeHistoryEntry,
+    schedule: &RateSchedule,
+) -> u64 {
+    black_box(T::rate_limited_stake_change_bps(
+        schedule.rate_bps(current_epoch),
+        account_deactivating_stake,
+        prev_epoch_cluster_state.deactivating,
+        prev_epoch_cluster_state.effective,
+    ))
+}
+
+pub mod aggregate;
+pub mod apy;
+pub mod bench_vectors;
+pub mod boundary_sweep;
+pub mod clock_sysvar;
+pub mod concentration;
+pub mod condition;
+pub mod consensus;
+pub mod cooldown_queue;
+pub mod delegation;
+pub mod delinquency;
+pub mod diag;
+pub mod history_diff;
 mod implementations;
+pub mod instruction;
+pub mod invariant;
+pub mod merge;
+pub mod missing_epoch;
+pub mod packed_args;
+pub mod packed_return;
+pub mod planning;
+pub mod points;
+pub mod rate_switch;
+pub mod replay;
+pub mod result_cache;
+pub mod results;
+pub mod rewards;
+pub mod rounding;
+pub mod runtime;
+pub mod self_test;
+pub mod sol;
+pub mod split;
+pub mod stake_account;
+pub mod stake_flags;
+pub mod stake_history_sysvar;
+pub mod stake_pool;
+pub mod stake_program;
+pub mod state;
+pub mod streaming;
+pub mod stress;
+pub mod synthetic;
+pub mod syscalls;
+pub mod tranches;
 
-#[no_mangle]
-pub extern "C" fn entrypoint(arg: u64) -> u64 {
-    let account_stake = (arg & 0xffff) + 1;
-    let cluster_share = ((arg >> 16) & 0xffff) + 1;
-    let effective = max(cluster_share << 1, 1);
+pub use implementations::{Backend, BackendVisitor};
 
-    let cluster_state = StakeHistoryEntry {
-        activating: cluster_share,
-        deactivating: (cluster_share / 2) + 1,
-        effective,
-    };
+#[cfg(feature = "solana-program")]
+pub mod entrypoint_raw;
+#[cfg(feature = "solana-program")]
+pub mod program;
 
-    #[cfg(feature = "bnum")]
-    type Calculator = implementations::bnum::BnumCalculator;
-    
-    #[cfg(feature = "crypto")]
-    type Calculator = implementations::crypto::CryptoCalculator;
-    
-    #[cfg(feature = "fixed")]
-    type Calculator = implementations::fixed::FixedCalculator;
-
-    #[cfg(feature = "uint")]
-    type Calculator = implementations::uint_impl::UintCalculator;
-    
-    #[cfg(feature = "plain")]
-    type Calculator = implementations::plain::PlainCalculator;
-
-    #[cfg(feature = "manual")]
-    type Calculator = implementations::manual::ManualCalculator;
-
-    let activation =
-        calculate_activation_allowance::<Calculator>(arg, account_stake, &cluster_state, Some(arg / 3));
-    let deactivation = calculate_deactivation_allowance::<Calculator>(
-        arg,
-        (account_stake / 2) + 1,
-        &cluster_state,
-        Some(arg / 5),
-    );
-
-    activation ^ deactivation
-}
+#[cfg(feature = "arbitrary")]
+pub mod fuzzing;
 
-#[panic_handler]
-fn panic(_info: &PanicInfo) -> ! {
-    loop {}
-}
+#[cfg(feature = "host-sim")]
+pub mod divergence;
+#[cfg(feature = "host-sim")]
+pub mod host_sim;
+
+#[cfg(feature = "ffi")]
+pub mod ffi;
+
+#[cfg(feature = "oracle")]
+pub mod oracle;
+
+#[cfg(feature = "python")]
+mod python;
+
+#[cfg(feature = "rpc")]
+pub mod rpc;
+
+#[cfg(feature = "snapshots")]
+pub mod snapshot;
+
+#[cfg(feature = "trace")]
+pub mod trace;
+
+#[cfg(feature = "wasm")]
+pub mod wasm;
@@ -0,0 +1,106 @@
+//! Merge eligibility, following the stake program's `MergeKind`.
+
+use crate::stake_flags::StakeFlags;
+use crate::stake_history::StakeHistoryGetEntry;
+use crate::state::{Clock, Delegation, Meta, Stake, StakeStateV2};
+use crate::{Epoch, StakeCalculator};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MergeError {
+    InvalidAccountData,
+    /// Partially activated or deactivating stake cannot be merged.
+    MergeTransientStake,
+    AuthorizedMismatch,
+    LockupMismatch,
+    VoterMismatch,
+    DeactivatingDelegation,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MergeKind {
+    Inactive(Meta, u64, StakeFlags),
+    ActivationEpoch(Meta, Stake, StakeFlags),
+    FullyActive(Meta, Stake),
+}
+
+impl MergeKind {
+    pub fn meta(&self) -> &Meta {
+        match self {
+            Self::Inactive(meta, ..)
+            | Self::ActivationEpoch(meta, ..)
+            | Self::FullyActive(meta, _) => meta,
+        }
+    }
+
+    pub fn active_stake(&self) -> Option<&Stake> {
+        match self {
+            Self::Inactive(..) => None,
+            Self::ActivationEpoch(_, stake, _) | Self::FullyActive(_, stake) => Some(stake),
+        }
+    }
+
+    pub fn get_if_mergeable<T: StakeCalculator>(
+        stake_state: &StakeStateV2,
+        stake_lamports: u64,
+        clock: &Clock,
+        history: &(impl StakeHistoryGetEntry + ?Sized),
+        new_rate_activation_epoch: Option<Epoch>,
+    ) -> Result<Self, MergeError> {
+        match stake_state {
+            StakeStateV2::Stake(meta, stake, flags) => {
+                let status = stake.delegation.stake_activating_and_deactivating::<T>(
+                    clock.epoch,
+                    history,
+                    new_rate_activation_epoch,
+                );
+                match (status.effective, status.activating, status.deactivating) {
+                    (0, 0, 0) => Ok(Self::Inactive(*meta, stake_lamports, *flags)),
+                    (0, _, _) => Ok(Self::ActivationEpoch(*meta, *stake, *flags)),
+                    (_, 0, 0) => Ok(Self::FullyActive(*meta, *stake)),
+                    _ => Err(MergeError::MergeTransientStake),
+                }
+            }
+            StakeStateV2::Initialized(meta) => {
+                Ok(Self::Inactive(*meta, stake_lamports, StakeFlags::empty()))
+            }
+            _ => Err(MergeError::InvalidAccountData),
+        }
+    }
+
+    /// Checks that `source` can be merged into `self`: metadata first, then
+    /// delegations when both sides carry one.
+    pub fn check_compatible(&self, source: &Self, clock: &Clock) -> Result<(), MergeError> {
+        metas_can_merge(self.meta(), source.meta(), clock)?;
+        if let (Some(stake), Some(source)) = (self.active_stake(), source.active_stake()) {
+            active_delegations_can_merge(&stake.delegation, &source.delegation)?;
+        }
+        Ok(())
+    }
+}
+
+/// Authorities must match exactly; lockups must match unless neither is in
+/// force.
+pub fn metas_can_merge(stake: &Meta, source: &Meta, clock: &Clock) -> Result<(), MergeError> {
+    if stake.authorized != source.authorized {
+        return Err(MergeError::AuthorizedMismatch);
+    }
+    let can_merge_lockups = stake.lockup == source.lockup
+        || (!stake.lockup.is_in_force(clock, None) && !source.lockup.is_in_force(clock, None));
+    if !can_merge_lockups {
+        return Err(MergeError::LockupMismatch);
+    }
+    Ok(())
+}
+
+pub fn active_delegations_can_merge(
+    stake: &Delegation,
+    source: &Delegation,
+) -> Result<(), MergeError> {
+    if stake.voter_pubkey != source.voter_pubkey {
+        return Err(MergeError::VoterMismatch);
+    }
+    if stake.is_deactivated() || source.is_deactivated() {
+        return Err(MergeError::DeactivatingDelegation);
+    }
+    Ok(())
+}
@@ -0,0 +1,108 @@
+//! What to do when the stake history lacks an epoch the warmup or cooldown
+//! loop needs.
+//!
+//! The runtime treats the stake as fully activated (or fully deactivated)
+//! only when the activation (or deactivation) epoch's own entry is missing.
+//! A gap later in the walk just stops it, keeping the stake warmed up (or
+//! cooled down) so far as the status from then on. Research uses can instead
+//! fail loudly or fill the gap from the neighbouring entries.
+
+use core::cell::Cell;
+
+use crate::delegation::StakeActivationStatus;
+use crate::stake_history::{StakeHistory, StakeHistoryEntry, StakeHistoryGetEntry};
+use crate::state::Delegation;
+use crate::streaming::{mul_div, Rounding};
+use crate::{Epoch, StakeCalculator};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MissingEpochPolicy {
+    /// Consensus behaviour.
+    #[default]
+    UpstreamCompatible,
+    /// Report the first missing epoch instead of computing a status.
+    Error,
+    /// Linearly interpolate between the nearest older and newer entries, or
+    /// carry the newest entry forward past the end of the history. Epochs
+    /// older than the whole history still follow upstream.
+    Interpolate,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MissingEpochError {
+    pub epoch: Epoch,
+}
+
+fn lerp(a: u64, b: u64, num: u64, den: u64) -> u64 {
+    if b >= a {
+        a + mul_div(b - a, num, den, Rounding::Down).unwrap_or(0)
+    } else {
+        a - mul_div(a - b, num, den, Rounding::Down).unwrap_or(0)
+    }
+}
+
+fn interpolate(history: &StakeHistory, epoch: Epoch) -> Option<StakeHistoryEntry> {
+    // Newest first: everything before `index` is newer than `epoch`.
+    let index = history.partition_point(|&(e, _)| e > epoch);
+    let (older_epoch, older) = *history.get(index)?;
+    let Some(&(newer_epoch, newer)) = index.checked_sub(1).and_then(|i| history.get(i)) else {
+        return Some(older);
+    };
+
+    let num = epoch - older_epoch;
+    let den = newer_epoch - older_epoch;
+    Some(StakeHistoryEntry {
+        effective: lerp(older.effective, newer.effective, num, den),
+        activating: lerp(older.activating, newer.activating, num, den),
+        deactivating: lerp(older.deactivating, newer.deactivating, num, den),
+    })
+}
+
+struct PolicyHistory<'a> {
+    history: &'a StakeHistory,
+    policy: MissingEpochPolicy,
+    missing: Cell<Option<Epoch>>,
+}
+
+impl StakeHistoryGetEntry for PolicyHistory<'_> {
+    fn get_entry(&self, epoch: Epoch) -> Option<StakeHistoryEntry> {
+        if let Some(entry) = self.history.get_entry(epoch) {
+            return Some(entry);
+        }
+        match self.policy {
+            MissingEpochPolicy::UpstreamCompatible => None,
+            MissingEpochPolicy::Error => {
+                if self.missing.get().is_none() {
+                    self.missing.set(Some(epoch));
+                }
+                None
+            }
+            MissingEpochPolicy::Interpolate => interpolate(self.history, epoch),
+        }
+    }
+}
+
+impl Delegation {
+    pub fn stake_activating_and_deactivating_with_policy<T: StakeCalculator>(
+        &self,
+        target_epoch: Epoch,
+        history: &StakeHistory,
+        policy: MissingEpochPolicy,
+        new_rate_activation_epoch: Option<Epoch>,
+    ) -> Result<StakeActivationStatus, MissingEpochError> {
+        let history = PolicyHistory {
+            history,
+            policy,
+            missing: Cell::new(None),
+        };
+        let status = self.stake_activating_and_deactivating::<T>(
+            target_epoch,
+            &history,
+            new_rate_activation_epoch,
+        );
+        match history.missing.get() {
+            Some(epoch) => Err(MissingEpochError { epoch }),
+            None => Ok(status),
+        }
+    }
+}
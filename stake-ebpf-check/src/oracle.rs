@@ -0,0 +1,255 @@
+//! The reference calculation over HTTP, so implementations outside Rust can
+//! check themselves against this crate's math.
+//!
+//! A std-only HTTP/1.1 server, one thread per connection, answering with
+//! JSON. Request and reply bodies are in the shared vector format of the
+//! `test-vectors` crate, so an implementation's existing vector runner can
+//! feed it and read it back with the same parser.
+//!
+//! - `GET /vectors`: the checked-in known answers.
+//! - `POST /answers`: a vector file in, the same vectors out with
+//!   `expected` set to the oracle backend's answer. The format requires
+//!   `expected` in the request; its value is ignored.
+//! - `POST /check`: a vector file of the caller's answers in, a verdict out:
+//!
+//! ```json
+//! {
+//!   "backend": "streaming",
+//!   "checked": 18,
+//!   "skipped": ["near-billion"],
+//!   "mismatches": [{ "name": "mainnet", "submitted": 1710000001, "reference": 1710000000 }]
+//! }
+//! ```
+//!
+//! Vectors that exclude the oracle's backend are left out of `/answers`
+//! and listed under `skipped` by `/check`, since the backend is not held to
+//! them. Errors are a status with `{"error": "..."}`.
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use serde_json::{json, Value};
+use test_vectors::{Vector, BACKENDS};
+
+use crate::Backend;
+
+/// Request bodies past this are refused.
+pub const MAX_BODY: usize = 1 << 20;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+pub struct Response {
+    pub status: u16,
+    pub body: String,
+}
+
+impl Response {
+    fn error(status: u16, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            body: json!({ "error": message.into() }).to_string(),
+        }
+    }
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        413 => "Payload Too Large",
+        _ => "Internal Server Error",
+    }
+}
+
+pub struct Oracle {
+    backend: Backend,
+    /// Served by `GET /vectors`.
+    known_answers: Vec<Vector>,
+}
+
+impl Oracle {
+    pub fn new(backend: Backend, known_answers: Vec<Vector>) -> Self {
+        Self {
+            backend,
+            known_answers,
+        }
+    }
+
+    fn backend_name(&self) -> &'static str {
+        BACKENDS[usize::from(self.backend.id())]
+    }
+
+    /// The backend's answer for each vector not excluding it, in order, and
+    /// the names of those that do.
+    fn reference(&self, vectors: &[Vector]) -> Result<(Vec<u64>, Vec<String>), Response> {
+        let mut answers = Vec::new();
+        let mut skipped = Vec::new();
+        for vector in vectors {
+            if vector.excludes(self.backend.id()) {
+                skipped.push(vector.name.clone());
+                continue;
+            }
+            let answer = panic::catch_unwind(AssertUnwindSafe(|| {
+                self.backend.rate_limited_stake_change_bps(
+                    vector.rate_bps,
+                    vector.account_portion,
+                    vector.cluster_portion,
+                    vector.cluster_effective,
+                )
+            }))
+            .map_err(|_| {
+                Response::error(
+                    500,
+                    format!("{} panicked on `{}`", self.backend_name(), vector.name),
+                )
+            })?;
+            answers.push(answer);
+        }
+        Ok((answers, skipped))
+    }
+
+    fn answers(&self, vectors: Vec<Vector>) -> Result<Response, Response> {
+        let (answers, skipped) = self.reference(&vectors)?;
+        let answered: Vec<Vector> = vectors
+            .into_iter()
+            .filter(|vector| !vector.excludes(self.backend.id()))
+            .zip(answers)
+            .map(|(vector, expected)| Vector { expected, ..vector })
+            .collect();
+        let description = format!(
+            "Answers from the {} backend; {} vectors excluding it left out.",
+            self.backend_name(),
+            skipped.len()
+        );
+        Ok(Response {
+            status: 200,
+            body: test_vectors::to_json(&description, &answered),
+        })
+    }
+
+    fn check(&self, vectors: Vec<Vector>) -> Result<Response, Response> {
+        let (answers, skipped) = self.reference(&vectors)?;
+        let mismatches: Vec<Value> = vectors
+            .iter()
+            .filter(|vector| !vector.excludes(self.backend.id()))
+            .zip(&answers)
+            .filter(|(vector, &reference)| vector.expected != reference)
+            .map(|(vector, reference)| {
+                json!({
+                    "name": vector.name,
+                    "submitted": vector.expected,
+                    "reference": reference,
+                })
+            })
+            .collect();
+        let verdict = json!({
+            "backend": self.backend_name(),
+            "checked": answers.len(),
+            "skipped": skipped,
+            "mismatches": mismatches,
+        });
+        Ok(Response {
+            status: 200,
+            body: verdict.to_string(),
+        })
+    }
+
+    /// The reply to one request.
+    pub fn respond(&self, method: &str, path: &str, body: &[u8]) -> Response {
+        let parse = || {
+            let text =
+                std::str::from_utf8(body).map_err(|_| Response::error(400, "body is not UTF-8"))?;
+            test_vectors::parse(text).map_err(|e| Response::error(400, e))
+        };
+        let result = match (method, path) {
+            ("GET", "/vectors") => Ok(Response {
+                status: 200,
+                body: test_vectors::to_json("The checked-in known answers.", &self.known_answers),
+            }),
+            ("POST", "/answers") => parse().and_then(|vectors| self.answers(vectors)),
+            ("POST", "/check") => parse().and_then(|vectors| self.check(vectors)),
+            (_, "/vectors" | "/answers" | "/check") => {
+                Err(Response::error(405, format!("{method} not allowed")))
+            }
+            _ => Err(Response::error(404, format!("no route `{path}`"))),
+        };
+        result.unwrap_or_else(|error| error)
+    }
+}
+
+/// Reads one request: method, path and body.
+fn read_request(stream: &TcpStream) -> Result<(String, String, Vec<u8>), Response> {
+    let bad_request = |e: io::Error| Response::error(400, e.to_string());
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    reader.read_line(&mut line).map_err(bad_request)?;
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Err(Response::error(400, "malformed request line"));
+    };
+    let method = method.to_owned();
+    let path = target.split('?').next().unwrap_or_default().to_owned();
+
+    let mut content_length = 0;
+    loop {
+        line.clear();
+        reader.read_line(&mut line).map_err(bad_request)?;
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value
+                    .trim()
+                    .parse()
+                    .map_err(|_| Response::error(400, "invalid Content-Length"))?;
+            }
+        }
+    }
+    if content_length > MAX_BODY {
+        return Err(Response::error(413, format!("body over {MAX_BODY} bytes")));
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body).map_err(bad_request)?;
+    Ok((method, path, body))
+}
+
+fn handle(oracle: &Oracle, mut stream: TcpStream) -> io::Result<()> {
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    let response = match read_request(&stream) {
+        Ok((method, path, body)) => oracle.respond(&method, &path, &body),
+        Err(response) => response,
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+         Connection: close\r\n\r\n{}",
+        response.status,
+        reason(response.status),
+        response.body.len(),
+        response.body
+    )?;
+    stream.flush()
+}
+
+/// Serves `oracle` on `listener` until accepting fails.
+pub fn serve(listener: TcpListener, oracle: Arc<Oracle>) -> io::Result<()> {
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let oracle = Arc::clone(&oracle);
+        thread::spawn(move || {
+            if let Err(e) = handle(&oracle, stream) {
+                eprintln!("oracle: {e}");
+            }
+        });
+    }
+    Ok(())
+}
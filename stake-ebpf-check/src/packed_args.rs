@@ -0,0 +1,105 @@
+//! Operands the packed `entrypoint(arg)` symbols derive from `arg`.
+//!
+//! The low 32 bits pick the stakes: account stake and cluster share, 16 bits
+//! each, both offset by one so neither is zero. The whole of `arg` is also
+//! the epoch, with the rate switch at `arg / 3` for activation and `arg / 5`
+//! for deactivation, so every `arg` below [`DOMAIN`] runs at the tower rate
+//! except `0`.
+
+use crate::condition::{classify, Condition};
+use crate::stake_history::StakeHistoryEntry;
+use crate::stress::StressOperands;
+use crate::{
+    calculate_activation_allowance, calculate_deactivation_allowance, warmup_cooldown_rate_bps,
+    Epoch, StakeCalculator,
+};
+
+/// Every distinct `(account_stake, cluster_share)` pair, as `arg` values
+/// `0..DOMAIN`.
+pub const DOMAIN: u64 = 1 << 32;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PackedOperands {
+    pub epoch: Epoch,
+    pub account_stake: u64,
+    pub deactivating_stake: u64,
+    pub cluster_state: StakeHistoryEntry,
+    pub activation_rate_epoch: Option<Epoch>,
+    pub deactivation_rate_epoch: Option<Epoch>,
+}
+
+impl PackedOperands {
+    #[inline(always)]
+    pub fn unpack(arg: u64) -> Self {
+        let account_stake = (arg & 0xffff) + 1;
+        let cluster_share = ((arg >> 16) & 0xffff) + 1;
+        let effective = (cluster_share << 1).max(1);
+
+        Self {
+            epoch: arg,
+            account_stake,
+            deactivating_stake: (account_stake / 2) + 1,
+            cluster_state: StakeHistoryEntry {
+                activating: cluster_share,
+                deactivating: (cluster_share / 2) + 1,
+                effective,
+            },
+            activation_rate_epoch: Some(arg / 3),
+            deactivation_rate_epoch: Some(arg / 5),
+        }
+    }
+
+    /// `(activation, deactivation)` allowances under backend `T`.
+    #[inline(always)]
+    pub fn allowances<T: StakeCalculator>(&self) -> (u64, u64) {
+        let activation = calculate_activation_allowance::<T>(
+            self.epoch,
+            self.account_stake,
+            &self.cluster_state,
+            self.activation_rate_epoch,
+        );
+        let deactivation = calculate_deactivation_allowance::<T>(
+            self.epoch,
+            self.deactivating_stake,
+            &self.cluster_state,
+            self.deactivation_rate_epoch,
+        );
+        (activation, deactivation)
+    }
+
+    /// `(activation, deactivation)` calculator inputs, as the allowance
+    /// functions pass them to `rate_limited_stake_change_bps`.
+    pub fn calculator_operands(&self) -> (StressOperands, StressOperands) {
+        (
+            StressOperands {
+                rate_bps: warmup_cooldown_rate_bps(self.epoch, self.activation_rate_epoch),
+                account_portion: self.account_stake,
+                cluster_portion: self.cluster_state.activating,
+                cluster_effective: self.cluster_state.effective,
+            },
+            StressOperands {
+                rate_bps: warmup_cooldown_rate_bps(self.epoch, self.deactivation_rate_epoch),
+                account_portion: self.deactivating_stake,
+                cluster_portion: self.cluster_state.deactivating,
+                cluster_effective: self.cluster_state.effective,
+            },
+        )
+    }
+
+    pub fn conditions(&self) -> (Condition, Condition) {
+        (
+            classify(
+                warmup_cooldown_rate_bps(self.epoch, self.activation_rate_epoch),
+                self.account_stake,
+                self.cluster_state.activating,
+                self.cluster_state.effective,
+            ),
+            classify(
+                warmup_cooldown_rate_bps(self.epoch, self.deactivation_rate_epoch),
+                self.deactivating_stake,
+                self.cluster_state.deactivating,
+                self.cluster_state.effective,
+            ),
+        )
+    }
+}
@@ -0,0 +1,122 @@
+//! Return value of the packed `entrypoint(arg)` symbols.
+//!
+//! ```text
+//! 63..56  u8    status
+//! 55      bit   clamped: an allowance was capped at the account portion
+//! 54..52  u3    activation condition (see `condition`)
+//! 51..49  u3    deactivation condition
+//! 48            reserved, zero
+//! 47..0   u48   checksum of (activation, deactivation)
+//! ```
+//!
+//! The checksum is [`checksum`]; hosts recompute it from their own results
+//! and compare.
+
+use crate::condition::Condition;
+
+const STATUS_SHIFT: u32 = 56;
+const CLAMPED_BIT: u64 = 1 << 55;
+const ACTIVATION_CONDITION_SHIFT: u32 = 52;
+const DEACTIVATION_CONDITION_SHIFT: u32 = 49;
+const CONDITION_MASK: u64 = 0x7;
+const RESERVED_MASK: u64 = 1 << 48;
+pub const CHECKSUM_MASK: u64 = (1 << 48) - 1;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Status {
+    Ok = 0,
+    /// The build has no calculator backend.
+    NoBackend = 1,
+    /// The operand block is not a well-formed `Allowance` instruction.
+    InvalidInstruction = 2,
+    /// The backend id names no backend compiled into this build.
+    UnknownBackend = 3,
+}
+
+impl Status {
+    pub fn from_code(code: u8) -> Option<Self> {
+        match code {
+            0 => Some(Self::Ok),
+            1 => Some(Self::NoBackend),
+            2 => Some(Self::InvalidInstruction),
+            3 => Some(Self::UnknownBackend),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PackedReturn {
+    pub status: Status,
+    pub clamped: bool,
+    /// `(activation, deactivation)`; both rate-limited on errors.
+    pub conditions: (Condition, Condition),
+    pub checksum: u64,
+}
+
+impl PackedReturn {
+    /// `clamped` follows from `conditions`.
+    pub fn new(activation: u64, deactivation: u64, conditions: (Condition, Condition)) -> Self {
+        Self {
+            status: Status::Ok,
+            clamped: conditions.0.is_clamped() || conditions.1.is_clamped(),
+            conditions,
+            checksum: checksum(activation, deactivation),
+        }
+    }
+
+    pub fn error(status: Status) -> Self {
+        Self {
+            status,
+            clamped: false,
+            conditions: (Condition::RateLimited, Condition::RateLimited),
+            checksum: 0,
+        }
+    }
+
+    pub fn encode(&self) -> u64 {
+        let clamped = if self.clamped { CLAMPED_BIT } else { 0 };
+        ((self.status as u64) << STATUS_SHIFT)
+            | clamped
+            | ((self.conditions.0.code() as u64) << ACTIVATION_CONDITION_SHIFT)
+            | ((self.conditions.1.code() as u64) << DEACTIVATION_CONDITION_SHIFT)
+            | (self.checksum & CHECKSUM_MASK)
+    }
+
+    /// `None` for an unknown status or condition, or set reserved bits.
+    pub fn decode(value: u64) -> Option<Self> {
+        if value & RESERVED_MASK != 0 {
+            return None;
+        }
+        let condition =
+            |shift: u32| Condition::from_code(((value >> shift) & CONDITION_MASK) as u8);
+        Some(Self {
+            status: Status::from_code((value >> STATUS_SHIFT) as u8)?,
+            clamped: value & CLAMPED_BIT != 0,
+            conditions: (
+                condition(ACTIVATION_CONDITION_SHIFT)?,
+                condition(DEACTIVATION_CONDITION_SHIFT)?,
+            ),
+            checksum: value & CHECKSUM_MASK,
+        })
+    }
+
+    /// Whether this return value is an `Ok` for exactly these results.
+    pub fn matches(&self, activation: u64, deactivation: u64) -> bool {
+        self.status == Status::Ok && self.checksum == checksum(activation, deactivation)
+    }
+}
+
+/// 48-bit checksum of both allowances.
+///
+/// Each value is mixed with a distinct odd multiplier so swapping them
+/// changes the result, then the 64-bit sum is folded down to 48 bits.
+pub fn checksum(activation: u64, deactivation: u64) -> u64 {
+    let mixed = activation.wrapping_mul(0x9e37_79b9_7f4a_7c15).wrapping_add(
+        deactivation
+            .wrapping_mul(0xc2b2_ae3d_27d4_eb4f)
+            .rotate_left(31),
+    );
+    (mixed ^ (mixed >> 48)) & CHECKSUM_MASK
+}
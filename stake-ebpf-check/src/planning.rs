@@ -0,0 +1,122 @@
+//! Cheap planning helpers that treat the cluster entry as fixed.
+
+use crate::stake_history::StakeHistoryEntry;
+use crate::{calculate_activation_allowance, calculate_warmup_step, Epoch, StakeCalculator};
+
+/// Smallest activating stake whose allowance at `epoch` is at least
+/// `target_delta`, i.e. how much to delegate now to gain `target_delta`
+/// effective stake next epoch.
+///
+/// The allowance is floored and capped at the account's own stake, so the
+/// inverse is searched against `T` directly rather than solved in closed form.
+/// `None` when no stake amount reaches the target.
+pub fn required_activation_for_target<T: StakeCalculator>(
+    target_delta: u64,
+    cluster_state: &StakeHistoryEntry,
+    epoch: Epoch,
+    new_rate_activation_epoch: Option<Epoch>,
+) -> Option<u64> {
+    if target_delta == 0 {
+        return Some(0);
+    }
+
+    let allowance = |stake: u64| {
+        calculate_activation_allowance::<T>(epoch, stake, cluster_state, new_rate_activation_epoch)
+    };
+    if allowance(u64::MAX) < target_delta {
+        return None;
+    }
+
+    // The allowance never exceeds the stake itself.
+    let mut lo = target_delta;
+    let mut hi = u64::MAX;
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        if allowance(mid) >= target_delta {
+            hi = mid;
+        } else {
+            lo = mid + 1;
+        }
+    }
+    Some(lo)
+}
+
+/// Iterator over per-epoch newly-effective stake, see [`forecast_activation`].
+pub struct ActivationForecast<'a, T> {
+    epoch: Epoch,
+    remaining: u64,
+    epochs_left: usize,
+    cluster_state: &'a StakeHistoryEntry,
+    new_rate_activation_epoch: Option<Epoch>,
+    calculator: core::marker::PhantomData<T>,
+}
+
+impl<T: StakeCalculator> Iterator for ActivationForecast<'_, T> {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        if self.epochs_left == 0 {
+            return None;
+        }
+        self.epochs_left -= 1;
+
+        let delta = if self.remaining == 0 {
+            0
+        } else {
+            calculate_warmup_step::<T>(
+                self.epoch,
+                self.remaining,
+                self.cluster_state,
+                self.new_rate_activation_epoch,
+            )
+        };
+        self.remaining = self.remaining.saturating_sub(delta);
+        self.epoch = self.epoch.saturating_add(1);
+        Some(delta)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.epochs_left, Some(self.epochs_left))
+    }
+}
+
+/// Newly-effective stake for each of the `epochs` epochs starting at
+/// `start_epoch`, assuming `cluster_state` stays the same throughout.
+///
+/// Each step is the runtime's: at least a lamport while any stake remains,
+/// so an account too small for a nonzero allowance still warms up.
+pub fn forecast_activation<T: StakeCalculator>(
+    account_stake: u64,
+    cluster_state: &StakeHistoryEntry,
+    start_epoch: Epoch,
+    epochs: usize,
+    new_rate_activation_epoch: Option<Epoch>,
+) -> ActivationForecast<'_, T> {
+    ActivationForecast {
+        epoch: start_epoch,
+        remaining: account_stake,
+        epochs_left: epochs,
+        cluster_state,
+        new_rate_activation_epoch,
+        calculator: core::marker::PhantomData,
+    }
+}
+
+#[cfg(all(test, feature = "streaming"))]
+mod tests {
+    use super::*;
+    use crate::implementations::streaming::EbpfStreamingCalculator;
+
+    #[test]
+    fn forecast_activates_a_lamport_when_the_allowance_rounds_to_zero() {
+        let cluster_state = StakeHistoryEntry::with_effective_and_activating(1_000_000, 1 << 40);
+        assert_eq!(
+            calculate_activation_allowance::<EbpfStreamingCalculator>(0, 3, &cluster_state, None),
+            0
+        );
+
+        let forecast: Vec<u64> =
+            forecast_activation::<EbpfStreamingCalculator>(3, &cluster_state, 0, 5, None).collect();
+        assert_eq!(forecast, [1, 1, 1, 0, 0]);
+    }
+}
@@ -0,0 +1,209 @@
+//! Vote credits and stake points, following the runtime's epoch rewards pass.
+//!
+//! Points are `stake * credits` summed over epochs and can exceed 64 bits, so
+//! they are carried as a `(hi, lo)` pair.
+
+use crate::streaming::{add_wide, mul_div_wide, mul_wide};
+use crate::Epoch;
+
+/// One vote account `epoch_credits` entry: `(epoch, credits, prev_credits)`.
+pub type EpochCreditsEntry = (Epoch, u64, u64);
+
+/// Iterator over the credits a stake earned per epoch, see [`epoch_credits`].
+pub struct EarnedCredits<'a> {
+    entries: core::slice::Iter<'a, EpochCreditsEntry>,
+    /// What the stake had observed before the first entry; upstream decides
+    /// which case each entry falls in against this, not the running value.
+    credits_in_stake: u64,
+    credits_observed: u64,
+}
+
+impl EarnedCredits<'_> {
+    /// Credits observed after every entry yielded so far.
+    pub fn credits_observed(&self) -> u64 {
+        self.credits_observed
+    }
+}
+
+impl Iterator for EarnedCredits<'_> {
+    type Item = (Epoch, u64);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for &(epoch, final_credits, initial_credits) in self.entries.by_ref() {
+            let earned = if self.credits_in_stake < initial_credits {
+                final_credits.saturating_sub(initial_credits)
+            } else if self.credits_in_stake < final_credits {
+                final_credits.saturating_sub(self.credits_observed)
+            } else {
+                0
+            };
+            self.credits_observed = self.credits_observed.max(final_credits);
+            if earned > 0 {
+                return Some((epoch, earned));
+            }
+        }
+        None
+    }
+}
+
+/// Per-epoch earned credits for a stake that last observed `credits_observed`.
+///
+/// Credits already observed are not paid twice and epochs that earned nothing
+/// are skipped.
+pub fn epoch_credits(entries: &[EpochCreditsEntry], credits_observed: u64) -> EarnedCredits<'_> {
+    EarnedCredits {
+        entries: entries.iter(),
+        credits_in_stake: credits_observed,
+        credits_observed,
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PointsAndCredits {
+    pub points: (u64, u64),
+    pub credits_observed: u64,
+    /// Upstream's `force_credits_update_with_skipped_reward`: the stake must
+    /// move to `credits_observed` even though nothing is paid.
+    pub force_credits_update: bool,
+}
+
+/// Points earned since `credits_observed`, with `stake_at(epoch)` giving the
+/// effective stake for each epoch that earned credits.
+///
+/// `None` if the points overflow 128 bits.
+pub fn calculate_points(
+    entries: &[EpochCreditsEntry],
+    credits_observed: u64,
+    stake_at: impl Fn(Epoch) -> u64,
+) -> Option<PointsAndCredits> {
+    let vote_credits = entries.last().map_or(0, |&(_, credits, _)| credits);
+
+    // The vote account was recreated; restart from its credits.
+    if vote_credits < credits_observed {
+        return Some(PointsAndCredits {
+            points: (0, 0),
+            credits_observed: vote_credits,
+            force_credits_update: true,
+        });
+    }
+    if vote_credits == credits_observed {
+        return Some(PointsAndCredits {
+            points: (0, 0),
+            credits_observed,
+            force_credits_update: false,
+        });
+    }
+
+    let mut earned = epoch_credits(entries, credits_observed);
+    let mut points = (0, 0);
+    for (epoch, credits) in earned.by_ref() {
+        points = add_wide(points, mul_wide(stake_at(epoch), credits))?;
+    }
+
+    Some(PointsAndCredits {
+        points,
+        credits_observed: earned.credits_observed(),
+        force_credits_update: false,
+    })
+}
+
+/// Lamports paid per point for one rewards distribution.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PointValue {
+    pub rewards: u64,
+    pub points: (u64, u64),
+}
+
+impl PointValue {
+    /// Lamports per point as fixed point with `scale` units per lamport,
+    /// floored, e.g. `scale = 10_000` for basis points of a lamport.
+    pub fn scaled(&self, scale: u64) -> Option<u64> {
+        mul_div_wide((0, self.rewards), scale, self.points)
+    }
+}
+
+/// The exact rational value of one point; `None` without any points.
+pub fn point_value(total_rewards_lamports: u64, total_points: (u64, u64)) -> Option<PointValue> {
+    if total_points == (0, 0) {
+        return None;
+    }
+    Some(PointValue {
+        rewards: total_rewards_lamports,
+        points: total_points,
+    })
+}
+
+/// Pays out a [`PointValue`] account by account without losing lamports.
+///
+/// Each payout is the floored cumulative share minus what was already paid,
+/// so rounding remainders carry into later accounts and paying every point
+/// distributes exactly `rewards`.
+#[derive(Clone, Copy, Debug)]
+pub struct RewardDistributor {
+    point_value: PointValue,
+    points_paid: (u64, u64),
+    lamports_paid: u64,
+}
+
+impl RewardDistributor {
+    pub fn new(point_value: PointValue) -> Self {
+        Self {
+            point_value,
+            points_paid: (0, 0),
+            lamports_paid: 0,
+        }
+    }
+
+    /// Lamports for the next account's `points`. `None` if that would pay out
+    /// more than the distribution holds.
+    pub fn pay(&mut self, points: (u64, u64)) -> Option<u64> {
+        let points_paid = add_wide(self.points_paid, points)?;
+        let owed = mul_div_wide(
+            points_paid,
+            self.point_value.rewards,
+            self.point_value.points,
+        )?;
+        if owed > self.point_value.rewards {
+            return None;
+        }
+        let payout = owed.checked_sub(self.lamports_paid)?;
+        self.points_paid = points_paid;
+        self.lamports_paid = owed;
+        Some(payout)
+    }
+
+    pub fn lamports_paid(&self) -> u64 {
+        self.lamports_paid
+    }
+
+    /// Lamports not yet paid out.
+    pub fn remaining(&self) -> u64 {
+        self.point_value.rewards - self.lamports_paid
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An entry starting below credits already seen through an earlier one
+    /// is judged against what the stake had observed, as upstream does.
+    #[test]
+    fn earned_credits_compare_against_the_original_credits_observed() {
+        let entries = [(1, 10, 0), (3, 30, 8), (4, 35, 30)];
+        let mut earned = epoch_credits(&entries, 5);
+        assert_eq!(earned.next(), Some((1, 5)));
+        assert_eq!(earned.next(), Some((3, 22)));
+        assert_eq!(earned.next(), Some((4, 5)));
+        assert_eq!(earned.next(), None);
+        assert_eq!(earned.credits_observed(), 35);
+    }
+
+    #[test]
+    fn earned_credits_skip_what_was_already_observed() {
+        let entries = [(1, 10, 0), (2, 20, 10)];
+        let mut earned = epoch_credits(&entries, 20);
+        assert_eq!(earned.next(), None);
+        assert_eq!(earned.credits_observed(), 20);
+    }
+}
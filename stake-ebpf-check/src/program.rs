@@ -0,0 +1,526 @@
+//! Loader-compatible program entrypoint.
+//!
+//! Input deserialization lives in [`crate::entrypoint_raw`]; this module
+//! dispatches the decoded instruction.
+
+use core::hint::black_box;
+
+#[cfg(not(feature = "instruction-epoch"))]
+use crate::clock_sysvar::{self, ClockSysvar};
+use crate::condition::classify_allowances;
+#[cfg(feature = "consensus")]
+use crate::consensus;
+use crate::delegation::StakeActivationStatus;
+use crate::diag::{DiagKind, DiagLog, DiagRecord};
+pub use crate::entrypoint_raw::{
+    deserialize, AccountInfo, ProgramError, ProgramResult, MAX_ACCOUNTS,
+    MAX_PERMITTED_DATA_INCREASE, SUCCESS,
+};
+use crate::instruction::{Instruction, InstructionError, InstructionFlags, Operands};
+use crate::results::{
+    write_batch_entry, AllowanceResult, BatchResultHeader, ComputeUnitsResult,
+    DelegationStatusResult, DifferentialResult, SelfTestResult, StressResult, ValidateSplitResult,
+    ALGO_VERSION,
+};
+use crate::self_test::{self, SelfTest};
+use crate::split::validate_split_with_minimum;
+use crate::stake_history_sysvar::{self, StakeHistorySysvar};
+use crate::stake_program;
+use crate::state::{Delegation, Pubkey};
+use crate::stress::Stress;
+use crate::syscalls::{self, remaining_compute_units, SolInstruction};
+use crate::{warmup_cooldown_rate_bps, Backend, BackendVisitor, Epoch, StakeCalculator};
+
+impl From<InstructionError> for ProgramError {
+    fn from(e: InstructionError) -> Self {
+        ProgramError::Custom(e.code())
+    }
+}
+
+/// Compute units consumed by `f`.
+///
+/// A back-to-back pair of syscalls is timed first and subtracted, so the
+/// result excludes the cost of measuring.
+#[inline(always)]
+fn measure_compute_units(f: impl FnOnce()) -> u64 {
+    let start = remaining_compute_units();
+    let before = remaining_compute_units();
+    f();
+    let after = remaining_compute_units();
+    (before - after).saturating_sub(start - before)
+}
+
+/// Calls `f` `repetitions` times, XOR-ing the low bit of each result into the
+/// next call's input so no call can be hoisted, merged or elided.
+#[inline(always)]
+fn repeat_chained(repetitions: u64, input: u64, f: impl Fn(u64) -> u64) -> u64 {
+    let mut last = 0;
+    for _ in 0..repetitions {
+        last = f(input ^ (last & 1));
+    }
+    last
+}
+
+/// `(activation, deactivation)` for `operands`, halving (plus one) the
+/// account portion for deactivation as the packed entrypoint does. Both are
+/// appended to `diag` if given.
+fn allowances(backend: Backend, operands: &Operands, diag: &mut Option<DiagLog>) -> (u64, u64) {
+    let deactivating_portion = (operands.account_portion / 2) + 1;
+    let activation = backend.calculate_activation_allowance(
+        operands.epoch,
+        operands.account_portion,
+        &operands.cluster_state,
+        operands.new_rate_activation_epoch,
+    );
+    let deactivation = backend.calculate_deactivation_allowance(
+        operands.epoch,
+        deactivating_portion,
+        &operands.cluster_state,
+        operands.new_rate_activation_epoch,
+    );
+    if let Some(diag) = diag {
+        let record = |kind, account_portion, cluster_portion, allowance| DiagRecord {
+            sequence: 0,
+            backend_id: backend.id(),
+            kind,
+            epoch: operands.epoch,
+            rate_bps: warmup_cooldown_rate_bps(operands.epoch, operands.new_rate_activation_epoch),
+            account_portion,
+            cluster_portion,
+            cluster_effective: operands.cluster_state.effective,
+            allowance,
+        };
+        diag.append(record(
+            DiagKind::Activation,
+            operands.account_portion,
+            operands.cluster_state.activating,
+            activation,
+        ));
+        diag.append(record(
+            DiagKind::Deactivation,
+            deactivating_portion,
+            operands.cluster_state.deactivating,
+            deactivation,
+        ));
+    }
+    (activation, deactivation)
+}
+
+/// The backend `backend_id` names, if it is compiled in.
+fn named_backend(backend_id: u8) -> Result<Backend, InstructionError> {
+    Backend::from_id(backend_id).ok_or(InstructionError::UnknownBackend)
+}
+
+/// The backend `Allowance` and `Batch` compute with: always streaming in
+/// `consensus` builds, whatever the instruction names, even a backend left
+/// out of the build.
+#[cfg(feature = "consensus")]
+fn production_backend(_backend_id: u8) -> Result<Backend, InstructionError> {
+    Ok(Backend::Streaming)
+}
+
+#[cfg(not(feature = "consensus"))]
+fn production_backend(backend_id: u8) -> Result<Backend, InstructionError> {
+    named_backend(backend_id)
+}
+
+/// [`allowances`], each checked by [`consensus::verify`] in `consensus`
+/// builds.
+fn checked_allowances(
+    backend: Backend,
+    operands: &Operands,
+    diag: &mut Option<DiagLog>,
+) -> Result<(u64, u64), ProgramError> {
+    let (activation, deactivation) = allowances(backend, operands, diag);
+    #[cfg(feature = "consensus")]
+    {
+        let rate_bps = warmup_cooldown_rate_bps(operands.epoch, operands.new_rate_activation_epoch);
+        let cluster = &operands.cluster_state;
+        let agrees = consensus::verify(
+            rate_bps,
+            operands.account_portion,
+            cluster.activating,
+            cluster.effective,
+            activation,
+        ) && consensus::verify(
+            rate_bps,
+            (operands.account_portion / 2) + 1,
+            cluster.deactivating,
+            cluster.effective,
+            deactivation,
+        );
+        if !agrees {
+            return Err(InstructionError::ConsensusMismatch.into());
+        }
+    }
+    Ok((activation, deactivation))
+}
+
+/// The current epoch from the leading Clock sysvar account, and the
+/// accounts after it.
+#[cfg(not(feature = "instruction-epoch"))]
+fn current_epoch<'a, 'b>(
+    accounts: &'b mut [Option<AccountInfo<'a>>],
+    _instruction_epoch: Epoch,
+) -> Result<(Epoch, &'b mut [Option<AccountInfo<'a>>]), ProgramError> {
+    let (clock, rest) = accounts
+        .split_first_mut()
+        .ok_or(ProgramError::NotEnoughAccountKeys)?;
+    let clock = clock.as_ref().ok_or(ProgramError::NotEnoughAccountKeys)?;
+    if *clock.key != clock_sysvar::ID {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let clock = ClockSysvar::from_bytes(clock.data()).ok_or(ProgramError::InvalidAccountData)?;
+    Ok((clock.epoch, rest))
+}
+
+/// Trusts the epoch from instruction data and consumes no accounts.
+#[cfg(feature = "instruction-epoch")]
+fn current_epoch<'a, 'b>(
+    accounts: &'b mut [Option<AccountInfo<'a>>],
+    instruction_epoch: Epoch,
+) -> Result<(Epoch, &'b mut [Option<AccountInfo<'a>>]), ProgramError> {
+    Ok((instruction_epoch, accounts))
+}
+
+/// Asks the Stake program for its current minimum delegation.
+fn get_minimum_delegation(stake_program_account: &AccountInfo) -> Result<u64, ProgramError> {
+    let data = stake_program::get_minimum_delegation_data();
+    let instruction = SolInstruction {
+        program_id: stake_program_account.key,
+        accounts: core::ptr::null(),
+        account_len: 0,
+        data: data.as_ptr(),
+        data_len: data.len() as u64,
+    };
+    let account_infos = [stake_program_account.to_sol_account_info()];
+    // A failing callee normally aborts the whole transaction already.
+    if unsafe { syscalls::invoke(&instruction, &account_infos) } != SUCCESS {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let mut return_data = [0u8; 8];
+    match syscalls::get_return_data(&mut return_data) {
+        Some((len, program_id)) if program_id == stake_program::ID => {
+            stake_program::parse_minimum_delegation(return_data.get(..len).unwrap_or(&[]))
+                .ok_or(ProgramError::InvalidAccountData)
+        }
+        _ => Err(ProgramError::InvalidAccountData),
+    }
+}
+
+struct DelegationStatus<'a> {
+    delegation: Delegation,
+    target_epoch: Epoch,
+    history: &'a StakeHistorySysvar<'a>,
+    new_rate_activation_epoch: Option<Epoch>,
+}
+
+impl BackendVisitor for DelegationStatus<'_> {
+    type Output = StakeActivationStatus;
+
+    fn visit<T: StakeCalculator>(self) -> StakeActivationStatus {
+        self.delegation.stake_activating_and_deactivating::<T>(
+            self.target_epoch,
+            self.history,
+            self.new_rate_activation_epoch,
+        )
+    }
+}
+
+/// The writable, program-owned account results are written to.
+fn result_account<'a, 'b>(
+    program_id: &Pubkey,
+    account: Option<&'b mut Option<AccountInfo<'a>>>,
+) -> Result<&'b mut AccountInfo<'a>, ProgramError> {
+    let account = account
+        .and_then(Option::as_mut)
+        .ok_or(ProgramError::NotEnoughAccountKeys)?;
+    if !account.is_writable {
+        return Err(ProgramError::InvalidArgument);
+    }
+    if account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    Ok(account)
+}
+
+/// The leading result account and, if `flags` ask for diagnostics, the
+/// diagnostics log in the account after it. Both must be writable and owned
+/// by the program.
+fn result_and_diag<'a, 'b>(
+    program_id: &Pubkey,
+    accounts: &'b mut [Option<AccountInfo<'a>>],
+    flags: InstructionFlags,
+) -> Result<(&'b mut AccountInfo<'a>, Option<DiagLog<'b>>), ProgramError> {
+    let (result, rest) = accounts
+        .split_first_mut()
+        .ok_or(ProgramError::NotEnoughAccountKeys)?;
+    let result = result_account(program_id, Some(result))?;
+    let diag = if flags.diag {
+        let diag = result_account(program_id, rest.get_mut(0))?;
+        Some(DiagLog::new(diag.data_mut()).ok_or(ProgramError::AccountDataTooSmall)?)
+    } else {
+        None
+    };
+    Ok((result, diag))
+}
+
+/// Accounts: `DelegationStatus` takes `[stake_history_sysvar, result]`,
+/// `ValidateSplit` takes `[stake_program, result]`, `Allowance`,
+/// `MeasureComputeUnits`, `Batch` and `Differential` take
+/// `[clock_sysvar, result]` (just `[result]` with `instruction-epoch`),
+/// `Probe` takes any accounts and touches none, everything else takes
+/// `[result]`. With [`FLAG_DIAG`](crate::instruction::FLAG_DIAG),
+/// `Allowance`, `Batch` and `Differential` also take a trailing `diag`
+/// account. See [`crate::results`] for what is written.
+pub fn process_instruction(
+    program_id: &Pubkey,
+    accounts: &mut [Option<AccountInfo>],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let instruction = Instruction::unpack(instruction_data)?;
+    let flags = InstructionFlags::from_data(instruction_data);
+    let backend = match instruction {
+        Instruction::Allowance { backend_id, .. } | Instruction::Batch { backend_id, .. } => {
+            production_backend(backend_id)?
+        }
+        _ => named_backend(instruction.backend_id())?,
+    };
+
+    match instruction {
+        Instruction::Allowance { operands, .. } => {
+            let (epoch, accounts) = current_epoch(accounts, operands.epoch)?;
+            let operands = Operands { epoch, ..operands };
+            let (out, mut diag) = result_and_diag(program_id, accounts, flags)?;
+            let (activation, deactivation) = checked_allowances(backend, &operands, &mut diag)?;
+            let (activation_condition, deactivation_condition) = classify_allowances(&operands);
+            let result = AllowanceResult {
+                activation,
+                deactivation,
+                backend_id: backend.id(),
+                algo_version: ALGO_VERSION,
+                activation_condition,
+                deactivation_condition,
+            };
+            result
+                .write(out.data_mut())
+                .ok_or(ProgramError::AccountDataTooSmall)?;
+        }
+        Instruction::MeasureComputeUnits {
+            operands,
+            repetitions,
+            ..
+        } => {
+            let (epoch, accounts) = current_epoch(accounts, operands.epoch)?;
+            let operands = Operands { epoch, ..operands };
+            let repetitions = repetitions.max(1);
+            let result = ComputeUnitsResult {
+                activation_units: measure_compute_units(|| {
+                    black_box(repeat_chained(
+                        repetitions,
+                        operands.account_portion,
+                        |account| {
+                            backend.calculate_activation_allowance(
+                                operands.epoch,
+                                account,
+                                &operands.cluster_state,
+                                operands.new_rate_activation_epoch,
+                            )
+                        },
+                    ));
+                }),
+                deactivation_units: measure_compute_units(|| {
+                    black_box(repeat_chained(
+                        repetitions,
+                        (operands.account_portion / 2) + 1,
+                        |account| {
+                            backend.calculate_deactivation_allowance(
+                                operands.epoch,
+                                account,
+                                &operands.cluster_state,
+                                operands.new_rate_activation_epoch,
+                            )
+                        },
+                    ));
+                }),
+                backend_id: backend.id(),
+                algo_version: ALGO_VERSION,
+                repetitions,
+            };
+            let out = result_account(program_id, accounts.get_mut(0))?;
+            result
+                .write(out.data_mut())
+                .ok_or(ProgramError::AccountDataTooSmall)?;
+        }
+        Instruction::SelfTest { .. } => {
+            let result = SelfTestResult {
+                vectors: self_test::VECTORS.len() as u32,
+                mismatches: backend.visit(SelfTest),
+                backend_id: backend.id(),
+                algo_version: ALGO_VERSION,
+            };
+            let out = result_account(program_id, accounts.get_mut(0))?;
+            result
+                .write(out.data_mut())
+                .ok_or(ProgramError::AccountDataTooSmall)?;
+        }
+        Instruction::Stress {
+            seed, iterations, ..
+        } => {
+            let result = StressResult {
+                hash: backend.visit(Stress { seed, iterations }),
+                iterations,
+                backend_id: backend.id(),
+                algo_version: ALGO_VERSION,
+            };
+            let out = result_account(program_id, accounts.get_mut(0))?;
+            result
+                .write(out.data_mut())
+                .ok_or(ProgramError::AccountDataTooSmall)?;
+        }
+        Instruction::ValidateSplit {
+            stake,
+            split_lamports,
+            rent_exempt_reserve,
+            ..
+        } => {
+            let (stake_program_account, rest) = accounts
+                .split_first_mut()
+                .ok_or(ProgramError::NotEnoughAccountKeys)?;
+            let stake_program_account = stake_program_account
+                .as_ref()
+                .ok_or(ProgramError::NotEnoughAccountKeys)?;
+            if *stake_program_account.key != stake_program::ID {
+                return Err(ProgramError::IncorrectProgramId);
+            }
+            let minimum_delegation = get_minimum_delegation(stake_program_account)?;
+
+            let delegation = Delegation {
+                stake,
+                ..Delegation::default()
+            };
+            let result = ValidateSplitResult {
+                minimum_delegation,
+                outcome: validate_split_with_minimum(
+                    &delegation,
+                    split_lamports,
+                    rent_exempt_reserve,
+                    minimum_delegation,
+                ),
+                algo_version: ALGO_VERSION,
+            };
+            let out = result_account(program_id, rest.get_mut(0))?;
+            result
+                .write(out.data_mut())
+                .ok_or(ProgramError::AccountDataTooSmall)?;
+        }
+        Instruction::Batch { mut operands, .. } => {
+            let (epoch, accounts) = current_epoch(accounts, operands.epoch)?;
+            operands.epoch = epoch;
+            let (out, mut diag) = result_and_diag(program_id, accounts, flags)?;
+            let data = out.data_mut();
+            BatchResultHeader {
+                count: operands.len() as u8,
+                backend_id: backend.id(),
+                algo_version: ALGO_VERSION,
+            }
+            .write(data)
+            .ok_or(ProgramError::AccountDataTooSmall)?;
+
+            for (index, account_portion) in operands.account_portions().enumerate() {
+                let account = Operands {
+                    epoch: operands.epoch,
+                    account_portion,
+                    cluster_state: operands.cluster_state,
+                    new_rate_activation_epoch: operands.new_rate_activation_epoch,
+                };
+                let allowances = checked_allowances(backend, &account, &mut diag)?;
+                write_batch_entry(data, index, allowances)
+                    .ok_or(ProgramError::AccountDataTooSmall)?;
+            }
+        }
+        Instruction::DelegationStatus { operands, .. } => {
+            let (sysvar, rest) = accounts
+                .split_first_mut()
+                .ok_or(ProgramError::NotEnoughAccountKeys)?;
+            let sysvar = sysvar.as_ref().ok_or(ProgramError::NotEnoughAccountKeys)?;
+            if *sysvar.key != stake_history_sysvar::ID {
+                return Err(ProgramError::InvalidArgument);
+            }
+            let history = StakeHistorySysvar::from_bytes(sysvar.data())
+                .map_err(|_| ProgramError::InvalidAccountData)?;
+
+            let delegation = Delegation {
+                stake: operands.stake,
+                activation_epoch: operands.activation_epoch,
+                deactivation_epoch: operands.deactivation_epoch,
+                ..Delegation::default()
+            };
+            let result = DelegationStatusResult {
+                status: backend.visit(DelegationStatus {
+                    delegation,
+                    target_epoch: operands.target_epoch,
+                    history: &history,
+                    new_rate_activation_epoch: operands.new_rate_activation_epoch,
+                }),
+                backend_id: backend.id(),
+                algo_version: ALGO_VERSION,
+            };
+            let out = result_account(program_id, rest.get_mut(0))?;
+            result
+                .write(out.data_mut())
+                .ok_or(ProgramError::AccountDataTooSmall)?;
+        }
+        Instruction::Differential {
+            other_backend_id,
+            operands,
+            ..
+        } => {
+            let other = named_backend(other_backend_id)?;
+            let (epoch, accounts) = current_epoch(accounts, operands.epoch)?;
+            let operands = Operands { epoch, ..operands };
+            let (out, mut diag) = result_and_diag(program_id, accounts, flags)?;
+            let result = DifferentialResult {
+                first_backend_id: backend.id(),
+                second_backend_id: other.id(),
+                first: allowances(backend, &operands, &mut diag),
+                second: allowances(other, &operands, &mut diag),
+                algo_version: ALGO_VERSION,
+            };
+            result
+                .write(out.data_mut())
+                .ok_or(ProgramError::AccountDataTooSmall)?;
+            let divergence = result.divergence();
+            if divergence != 0 {
+                syscalls::log_64(
+                    divergence.into(),
+                    result.first.0,
+                    result.first.1,
+                    result.second.0,
+                    result.second.1,
+                );
+                return Err(InstructionError::Divergence.into());
+            }
+        }
+        Instruction::Probe { operands, .. } => {
+            black_box(operands);
+        }
+    }
+    Ok(())
+}
+
+/// # Safety
+///
+/// Called by the loader with its serialized input region.
+#[no_mangle]
+pub unsafe extern "C" fn entrypoint(input: *mut u8) -> u64 {
+    let mut accounts = [const { None }; MAX_ACCOUNTS];
+    let result = deserialize(input, &mut accounts).and_then(|(count, data, program_id)| {
+        process_instruction(program_id, &mut accounts[..count], data)
+    });
+    match result {
+        Ok(()) => SUCCESS,
+        Err(e) => e.into(),
+    }
+}
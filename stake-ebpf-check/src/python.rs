@@ -0,0 +1,249 @@
+//! Python extension module exposing the calculators, the delegation and
+//! cluster simulations and the StakeHistory sysvar parser.
+//!
+//! ```text
+//! cargo build --release --target <host-triple> -p stake-ebpf-check --features python,<backends>
+//! cp target/<host-triple>/release/libstake_ebpf_check.so stake_ebpf_check.so
+//! ```
+//!
+//! ```python
+//! import stake_ebpf_check as sec
+//! sec.activation_allowance(6, 800, 10 * 10**9, (380 * 10**15, 200 * 10**15, 150 * 10**15), 600)
+//! ```
+//!
+//! Stakes and epochs are Python ints checked into `u64` (an `OverflowError`
+//! otherwise), so results are the program's integer math exactly, not a
+//! `Decimal` or float approximation of it. A cluster entry is an
+//! `(effective, activating, deactivating)` tuple and a history entry an
+//! `(epoch, effective, activating, deactivating)` tuple. Backends are chosen
+//! by wire id; `backends()` lists those compiled in and any other raises
+//! `ValueError`.
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::stake_history::StakeHistoryEntry;
+use crate::stake_history_sysvar::StakeHistorySysvar;
+use crate::state::Delegation;
+use crate::synthetic::{synthetic_history, ChurnParams};
+use crate::{Backend, BackendVisitor, Epoch, StakeCalculator};
+
+/// `(effective, activating, deactivating)`.
+type Cluster = (u64, u64, u64);
+/// `(epoch, effective, activating, deactivating)`.
+type HistoryItem = (Epoch, u64, u64, u64);
+
+fn backend(id: u8) -> PyResult<Backend> {
+    Backend::from_id(id).ok_or_else(|| {
+        PyValueError::new_err(format!(
+            "backend {id} is not compiled in; have {:?}",
+            Backend::ALL
+        ))
+    })
+}
+
+fn entry((effective, activating, deactivating): Cluster) -> StakeHistoryEntry {
+    StakeHistoryEntry {
+        effective,
+        activating,
+        deactivating,
+    }
+}
+
+fn item((epoch, entry): (Epoch, StakeHistoryEntry)) -> HistoryItem {
+    (epoch, entry.effective, entry.activating, entry.deactivating)
+}
+
+/// Ids of the backends compiled into this build.
+#[pyfunction]
+fn backends() -> Vec<u32> {
+    // As `Vec<u8>` they would arrive as `bytes`.
+    Backend::ALL
+        .iter()
+        .map(|backend| backend.id().into())
+        .collect()
+}
+
+/// The calculator itself: `min(account * effective * rate / (cluster *
+/// 10_000), account)`, zero if any stake is.
+#[pyfunction]
+fn rate_limited_stake_change_bps(
+    backend_id: u8,
+    rate_bps: u64,
+    account_portion: u64,
+    cluster_portion: u64,
+    cluster_effective: u64,
+) -> PyResult<u64> {
+    Ok(backend(backend_id)?.rate_limited_stake_change_bps(
+        rate_bps,
+        account_portion,
+        cluster_portion,
+        cluster_effective,
+    ))
+}
+
+#[pyfunction]
+#[pyo3(signature = (backend_id, epoch, account_stake, cluster, new_rate_activation_epoch=None))]
+fn activation_allowance(
+    backend_id: u8,
+    epoch: Epoch,
+    account_stake: u64,
+    cluster: Cluster,
+    new_rate_activation_epoch: Option<Epoch>,
+) -> PyResult<u64> {
+    Ok(backend(backend_id)?.calculate_activation_allowance(
+        epoch,
+        account_stake,
+        &entry(cluster),
+        new_rate_activation_epoch,
+    ))
+}
+
+#[pyfunction]
+#[pyo3(signature = (backend_id, epoch, account_stake, cluster, new_rate_activation_epoch=None))]
+fn deactivation_allowance(
+    backend_id: u8,
+    epoch: Epoch,
+    account_stake: u64,
+    cluster: Cluster,
+    new_rate_activation_epoch: Option<Epoch>,
+) -> PyResult<u64> {
+    Ok(backend(backend_id)?.calculate_deactivation_allowance(
+        epoch,
+        account_stake,
+        &entry(cluster),
+        new_rate_activation_epoch,
+    ))
+}
+
+struct Schedule<'a> {
+    delegation: Delegation,
+    epochs: core::ops::RangeInclusive<Epoch>,
+    history: &'a [(Epoch, StakeHistoryEntry)],
+    new_rate_activation_epoch: Option<Epoch>,
+}
+
+impl BackendVisitor for Schedule<'_> {
+    type Output = Vec<HistoryItem>;
+
+    fn visit<T: StakeCalculator>(self) -> Self::Output {
+        self.epochs
+            .map(|epoch| {
+                let status = self.delegation.stake_activating_and_deactivating::<T>(
+                    epoch,
+                    self.history,
+                    self.new_rate_activation_epoch,
+                );
+                (
+                    epoch,
+                    status.effective,
+                    status.activating,
+                    status.deactivating,
+                )
+            })
+            .collect()
+    }
+}
+
+/// `(epoch, effective, activating, deactivating)` of a delegation at every
+/// epoch from `first_epoch` to `last_epoch` inclusive, against `history`
+/// (entries in any order).
+///
+/// `deactivation_epoch=None` is a delegation never deactivated.
+#[pyfunction]
+#[pyo3(signature = (
+    backend_id,
+    stake,
+    activation_epoch,
+    deactivation_epoch,
+    first_epoch,
+    last_epoch,
+    history,
+    new_rate_activation_epoch=None,
+))]
+#[allow(clippy::too_many_arguments)]
+fn delegation_schedule(
+    backend_id: u8,
+    stake: u64,
+    activation_epoch: Epoch,
+    deactivation_epoch: Option<Epoch>,
+    first_epoch: Epoch,
+    last_epoch: Epoch,
+    history: Vec<HistoryItem>,
+    new_rate_activation_epoch: Option<Epoch>,
+) -> PyResult<Vec<HistoryItem>> {
+    let backend = backend(backend_id)?;
+    let mut history: Vec<_> = history
+        .into_iter()
+        .map(|(epoch, effective, activating, deactivating)| {
+            (epoch, entry((effective, activating, deactivating)))
+        })
+        .collect();
+    history.sort_by_key(|&(epoch, _)| core::cmp::Reverse(epoch));
+
+    Ok(backend.visit(Schedule {
+        delegation: Delegation {
+            stake,
+            activation_epoch,
+            deactivation_epoch: deactivation_epoch.unwrap_or(u64::MAX),
+            ..Delegation::default()
+        },
+        epochs: first_epoch..=last_epoch,
+        history: &history,
+        new_rate_activation_epoch,
+    }))
+}
+
+/// `epochs` entries of a synthetic cluster starting from `start` at
+/// `start_epoch`, queuing `inflow_bps` and `deactivation_bps` of effective
+/// stake each epoch. Oldest first.
+#[pyfunction]
+#[pyo3(signature = (
+    start_epoch,
+    start,
+    epochs,
+    inflow_bps=0,
+    deactivation_bps=0,
+    new_rate_activation_epoch=None,
+))]
+fn simulate_cluster(
+    start_epoch: Epoch,
+    start: Cluster,
+    epochs: usize,
+    inflow_bps: u64,
+    deactivation_bps: u64,
+    new_rate_activation_epoch: Option<Epoch>,
+) -> Vec<HistoryItem> {
+    synthetic_history(
+        start_epoch,
+        entry(start),
+        ChurnParams {
+            inflow_bps,
+            deactivation_bps,
+        },
+        epochs,
+        new_rate_activation_epoch,
+    )
+    .map(item)
+    .collect()
+}
+
+/// Entries of raw StakeHistory sysvar account data, newest first.
+#[pyfunction]
+fn parse_stake_history(data: &[u8]) -> PyResult<Vec<HistoryItem>> {
+    let sysvar = StakeHistorySysvar::from_bytes(data)
+        .map_err(|e| PyValueError::new_err(format!("StakeHistory sysvar: {e:?}")))?;
+    Ok(sysvar.iter().map(item).collect())
+}
+
+#[pymodule]
+fn stake_ebpf_check(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_function(wrap_pyfunction!(backends, module)?)?;
+    module.add_function(wrap_pyfunction!(rate_limited_stake_change_bps, module)?)?;
+    module.add_function(wrap_pyfunction!(activation_allowance, module)?)?;
+    module.add_function(wrap_pyfunction!(deactivation_allowance, module)?)?;
+    module.add_function(wrap_pyfunction!(delegation_schedule, module)?)?;
+    module.add_function(wrap_pyfunction!(simulate_cluster, module)?)?;
+    module.add_function(wrap_pyfunction!(parse_stake_history, module)?)?;
+    Ok(())
+}
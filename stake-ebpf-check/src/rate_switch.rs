@@ -0,0 +1,133 @@
+//! Allowances over epoch ranges that straddle `new_rate_activation_epoch`.
+//!
+//! Boundary semantics: every epoch strictly before the activation epoch uses
+//! [`ORIGINAL_WARMUP_COOLDOWN_RATE_BPS`]; the activation epoch itself and every
+//! later epoch use [`TOWER_WARMUP_COOLDOWN_RATE_BPS`].
+
+use core::ops::Range;
+
+use crate::stake_history::StakeHistoryEntry;
+use crate::{
+    calculate_activation_allowance, calculate_deactivation_allowance, warmup_cooldown_rate_bps,
+    Epoch, StakeCalculator, ORIGINAL_WARMUP_COOLDOWN_RATE_BPS, TOWER_WARMUP_COOLDOWN_RATE_BPS,
+};
+
+const _: () = {
+    assert!(warmup_cooldown_rate_bps(9, Some(10)) == ORIGINAL_WARMUP_COOLDOWN_RATE_BPS);
+    assert!(warmup_cooldown_rate_bps(10, Some(10)) == TOWER_WARMUP_COOLDOWN_RATE_BPS);
+    assert!(warmup_cooldown_rate_bps(u64::MAX - 1, None) == ORIGINAL_WARMUP_COOLDOWN_RATE_BPS);
+};
+
+/// Splits `epochs` into the part using the original rate and the part using
+/// the new rate. Either half may be empty.
+pub fn split_at_rate_switch(
+    epochs: Range<Epoch>,
+    new_rate_activation_epoch: Option<Epoch>,
+) -> (Range<Epoch>, Range<Epoch>) {
+    let switch = new_rate_activation_epoch
+        .unwrap_or(u64::MAX)
+        .clamp(epochs.start, epochs.end.max(epochs.start));
+    (epochs.start..switch, switch..epochs.end)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EpochAllowance {
+    pub epoch: Epoch,
+    pub rate_bps: u64,
+    pub activation: u64,
+    pub deactivation: u64,
+}
+
+/// Per-epoch allowances for a fixed account and cluster state over `epochs`,
+/// each computed with the rate in force for that epoch.
+pub fn allowances_over_range<T: StakeCalculator>(
+    epochs: Range<Epoch>,
+    account_activating_stake: u64,
+    account_deactivating_stake: u64,
+    cluster_state: &StakeHistoryEntry,
+    new_rate_activation_epoch: Option<Epoch>,
+) -> impl Iterator<Item = EpochAllowance> + '_ {
+    epochs.map(move |epoch| {
+        let rate_bps = warmup_cooldown_rate_bps(epoch, new_rate_activation_epoch);
+        debug_assert_eq!(
+            rate_bps == TOWER_WARMUP_COOLDOWN_RATE_BPS,
+            new_rate_activation_epoch.is_some_and(|switch| epoch >= switch),
+        );
+        EpochAllowance {
+            epoch,
+            rate_bps,
+            activation: calculate_activation_allowance::<T>(
+                epoch,
+                account_activating_stake,
+                cluster_state,
+                new_rate_activation_epoch,
+            ),
+            deactivation: calculate_deactivation_allowance::<T>(
+                epoch,
+                account_deactivating_stake,
+                cluster_state,
+                new_rate_activation_epoch,
+            ),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_puts_the_switch_epoch_on_the_new_side() {
+        let cases = [
+            (None, 3..7, (3..7, 7..7)),
+            (Some(1), 3..7, (3..3, 3..7)),
+            (Some(3), 3..7, (3..3, 3..7)),
+            (Some(5), 3..7, (3..5, 5..7)),
+            (Some(7), 3..7, (3..7, 7..7)),
+            (Some(10), 3..7, (3..7, 7..7)),
+            (Some(5), 5..5, (5..5, 5..5)),
+            (Some(2), 5..5, (5..5, 5..5)),
+            (None, 5..5, (5..5, 5..5)),
+        ];
+        for (switch, epochs, expected) in cases {
+            assert_eq!(
+                split_at_rate_switch(epochs.clone(), switch),
+                expected,
+                "{epochs:?} switching at {switch:?}"
+            );
+        }
+    }
+
+    #[cfg(feature = "streaming")]
+    #[test]
+    fn allowances_change_rate_at_the_switch_epoch() {
+        use crate::implementations::streaming::EbpfStreamingCalculator;
+
+        let cluster_state = StakeHistoryEntry {
+            effective: 1_000_000,
+            activating: 1_000_000,
+            deactivating: 1_000_000,
+        };
+        let allowances = allowances_over_range::<EbpfStreamingCalculator>(
+            3..7,
+            1_000_000,
+            500_000,
+            &cluster_state,
+            Some(5),
+        )
+        .map(|allowance| {
+            (
+                allowance.epoch,
+                allowance.rate_bps,
+                allowance.activation,
+                allowance.deactivation,
+            )
+        });
+        assert!(allowances.eq([
+            (3, ORIGINAL_WARMUP_COOLDOWN_RATE_BPS, 250_000, 125_000),
+            (4, ORIGINAL_WARMUP_COOLDOWN_RATE_BPS, 250_000, 125_000),
+            (5, TOWER_WARMUP_COOLDOWN_RATE_BPS, 90_000, 45_000),
+            (6, TOWER_WARMUP_COOLDOWN_RATE_BPS, 90_000, 45_000),
+        ]));
+    }
+}
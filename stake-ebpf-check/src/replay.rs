@@ -0,0 +1,299 @@
+//! Deterministic replay of scripted stake operations.
+//!
+//! Steps are applied in order against caller-owned accounts, using the
+//! domain transitions in this crate and backend `T` for every activation
+//! status. Each outcome is reported to the caller's event sink, so the log
+//! needs no allocation.
+
+use crate::merge::{MergeError, MergeKind};
+use crate::split::{validate_split_with_minimum, SplitError, MINIMUM_DELEGATION};
+use crate::stake_flags::StakeFlags;
+use crate::stake_history::StakeHistory;
+use crate::state::{Clock, Delegation, Pubkey, Stake, StakeError, StakeStateV2};
+use crate::{Epoch, StakeCalculator};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReplayAccount {
+    pub state: StakeStateV2,
+    pub lamports: u64,
+}
+
+/// Accounts are referred to by their index in the replayed slice.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Op {
+    /// Delegates everything above the rent-exempt reserve.
+    Delegate {
+        account: usize,
+        voter: Pubkey,
+    },
+    Deactivate {
+        account: usize,
+    },
+    Split {
+        source: usize,
+        destination: usize,
+        lamports: u64,
+    },
+    Merge {
+        destination: usize,
+        source: usize,
+    },
+    Withdraw {
+        account: usize,
+        lamports: u64,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ScriptStep {
+    pub epoch: Epoch,
+    pub op: Op,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReplayError {
+    UnknownAccount,
+    Stake(StakeError),
+    Split(SplitError),
+    Merge(MergeError),
+}
+
+impl From<StakeError> for ReplayError {
+    fn from(e: StakeError) -> Self {
+        Self::Stake(e)
+    }
+}
+
+impl From<SplitError> for ReplayError {
+    fn from(e: SplitError) -> Self {
+        Self::Split(e)
+    }
+}
+
+impl From<MergeError> for ReplayError {
+    fn from(e: MergeError) -> Self {
+        Self::Merge(e)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Event {
+    EpochStarted(Epoch),
+    Applied(ScriptStep),
+    Rejected(ScriptStep, ReplayError),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReplayConfig {
+    pub minimum_delegation: u64,
+    pub new_rate_activation_epoch: Option<Epoch>,
+}
+
+impl Default for ReplayConfig {
+    fn default() -> Self {
+        Self {
+            minimum_delegation: MINIMUM_DELEGATION,
+            new_rate_activation_epoch: None,
+        }
+    }
+}
+
+struct Ctx<'a, T> {
+    clock: Clock,
+    history: &'a StakeHistory,
+    config: &'a ReplayConfig,
+    calculator: core::marker::PhantomData<T>,
+}
+
+impl<T: StakeCalculator> Ctx<'_, T> {
+    fn effective(&self, state: &StakeStateV2) -> u64 {
+        state.stake().map_or(0, |stake| {
+            stake.delegation.stake::<T>(
+                self.clock.epoch,
+                self.history,
+                self.config.new_rate_activation_epoch,
+            )
+        })
+    }
+
+    fn apply(&self, accounts: &mut [ReplayAccount], op: Op) -> Result<(), ReplayError> {
+        let get = |index: usize| {
+            accounts
+                .get(index)
+                .copied()
+                .ok_or(ReplayError::UnknownAccount)
+        };
+
+        match op {
+            Op::Delegate { account, voter } => {
+                let mut a = get(account)?;
+                let reserve = a
+                    .state
+                    .meta()
+                    .ok_or(StakeError::InvalidAccountData)?
+                    .rent_exempt_reserve;
+                let stake = a
+                    .lamports
+                    .checked_sub(reserve)
+                    .ok_or(StakeError::InsufficientFunds)?;
+                let effective = self.effective(&a.state);
+                a.state.delegate(&voter, stake, 0, &self.clock, effective)?;
+                accounts[account] = a;
+            }
+            Op::Deactivate { account } => {
+                let mut a = get(account)?;
+                let effective = self.effective(&a.state);
+                a.state.deactivate(&self.clock, effective)?;
+                accounts[account] = a;
+            }
+            Op::Withdraw { account, lamports } => {
+                let mut a = get(account)?;
+                let effective = self.effective(&a.state);
+                a.state
+                    .withdraw(lamports, a.lamports, &self.clock, None, effective)?;
+                a.lamports -= lamports;
+                accounts[account] = a;
+            }
+            Op::Split {
+                source,
+                destination,
+                lamports,
+            } => {
+                let mut src = get(source)?;
+                let mut dst = get(destination)?;
+                if dst.state != StakeStateV2::Uninitialized || source == destination {
+                    return Err(StakeError::InvalidAccountData.into());
+                }
+                match src.state {
+                    StakeStateV2::Stake(meta, stake, flags) => {
+                        validate_split_with_minimum(
+                            &stake.delegation,
+                            lamports,
+                            meta.rent_exempt_reserve,
+                            self.config.minimum_delegation,
+                        )?;
+                        let mut source_stake = stake;
+                        source_stake.delegation.stake -= lamports.min(stake.delegation.stake);
+                        let split_stake = Stake {
+                            delegation: Delegation {
+                                stake: lamports - meta.rent_exempt_reserve,
+                                ..stake.delegation
+                            },
+                            ..stake
+                        };
+                        src.state = StakeStateV2::Stake(meta, source_stake, flags);
+                        dst.state = StakeStateV2::Stake(meta, split_stake, flags);
+                    }
+                    StakeStateV2::Initialized(meta) => {
+                        if lamports > src.lamports {
+                            return Err(StakeError::InsufficientFunds.into());
+                        }
+                        dst.state = StakeStateV2::Initialized(meta);
+                    }
+                    _ => return Err(StakeError::InvalidAccountData.into()),
+                }
+                src.lamports -= lamports;
+                dst.lamports += lamports;
+                if src.lamports == 0 {
+                    src.state = StakeStateV2::Uninitialized;
+                }
+                accounts[source] = src;
+                accounts[destination] = dst;
+            }
+            Op::Merge {
+                destination,
+                source,
+            } => {
+                let mut dst = get(destination)?;
+                let src = get(source)?;
+                if source == destination {
+                    return Err(StakeError::InvalidAccountData.into());
+                }
+                let nrae = self.config.new_rate_activation_epoch;
+                let dst_kind = MergeKind::get_if_mergeable::<T>(
+                    &dst.state,
+                    dst.lamports,
+                    &self.clock,
+                    self.history,
+                    nrae,
+                )?;
+                let src_kind = MergeKind::get_if_mergeable::<T>(
+                    &src.state,
+                    src.lamports,
+                    &self.clock,
+                    self.history,
+                    nrae,
+                )?;
+                dst_kind.check_compatible(&src_kind, &self.clock)?;
+
+                let merged = match (dst_kind, src_kind) {
+                    (MergeKind::Inactive(..), MergeKind::Inactive(..))
+                    | (MergeKind::Inactive(..), MergeKind::ActivationEpoch(..)) => None,
+                    (
+                        MergeKind::ActivationEpoch(meta, mut stake, flags),
+                        MergeKind::Inactive(_, lamports, src_flags),
+                    ) => {
+                        stake.delegation.stake += lamports;
+                        Some(StakeStateV2::Stake(meta, stake, flags.union(src_flags)))
+                    }
+                    (
+                        MergeKind::ActivationEpoch(meta, mut stake, flags),
+                        MergeKind::ActivationEpoch(src_meta, src_stake, src_flags),
+                    ) => {
+                        stake.delegation.stake +=
+                            src_stake.delegation.stake + src_meta.rent_exempt_reserve;
+                        Some(StakeStateV2::Stake(meta, stake, flags.union(src_flags)))
+                    }
+                    (
+                        MergeKind::FullyActive(meta, mut stake),
+                        MergeKind::FullyActive(_, src_stake),
+                    ) => {
+                        stake.delegation.stake += src_stake.delegation.stake;
+                        Some(StakeStateV2::Stake(meta, stake, StakeFlags::empty()))
+                    }
+                    _ => return Err(MergeError::MergeTransientStake.into()),
+                };
+                if let Some(state) = merged {
+                    dst.state = state;
+                }
+                dst.lamports += src.lamports;
+                accounts[destination] = dst;
+                accounts[source] = ReplayAccount {
+                    state: StakeStateV2::Uninitialized,
+                    lamports: 0,
+                };
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Applies `script` (sorted by epoch) to `accounts`. Rejected steps leave the
+/// accounts untouched and replay continues.
+pub fn replay<T: StakeCalculator>(
+    script: &[ScriptStep],
+    accounts: &mut [ReplayAccount],
+    history: &StakeHistory,
+    config: &ReplayConfig,
+    mut on_event: impl FnMut(&Event),
+) {
+    let mut ctx = Ctx::<T> {
+        clock: Clock::default(),
+        history,
+        config,
+        calculator: core::marker::PhantomData,
+    };
+    let mut current_epoch = None;
+
+    for step in script {
+        if current_epoch != Some(step.epoch) {
+            current_epoch = Some(step.epoch);
+            ctx.clock.epoch = step.epoch;
+            on_event(&Event::EpochStarted(step.epoch));
+        }
+        match ctx.apply(accounts, step.op) {
+            Ok(()) => on_event(&Event::Applied(*step)),
+            Err(e) => on_event(&Event::Rejected(*step, e)),
+        }
+    }
+}
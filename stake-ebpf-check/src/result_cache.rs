@@ -0,0 +1,202 @@
+//! Versioned account layout for allowances precomputed at epoch start.
+//!
+//! ```text
+//! 0       u8   schema version
+//! 1       u8   backend id
+//! 2       u8   algorithm version
+//! 3            reserved, zero
+//! 4..8    u32  entry count
+//! 8..16   u64  epoch the allowances were computed for
+//!
+//! per entry at 16 + 48 * i:
+//! 0..32        stake account
+//! 32..40  u64  activation allowance
+//! 40..48  u64  deactivation allowance
+//! ```
+//!
+//! All integers are little-endian. Readers reject any schema version other
+//! than [`SCHEMA_VERSION`]; a layout change bumps it rather than
+//! reinterpreting existing accounts.
+
+use crate::state::Pubkey;
+use crate::Epoch;
+
+pub const SCHEMA_VERSION: u8 = 1;
+
+pub const HEADER_LEN: usize = 16;
+pub const ENTRY_LEN: usize = 48;
+
+pub const fn cache_len(count: usize) -> usize {
+    HEADER_LEN + ENTRY_LEN * count
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CacheError {
+    /// Shorter than the header, or than the entries it declares.
+    TooShort,
+    /// Written by a schema this build does not understand.
+    UnsupportedSchema(u8),
+    /// Past the last entry the header declares.
+    IndexOutOfRange,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CacheHeader {
+    pub backend_id: u8,
+    pub algo_version: u8,
+    pub count: u32,
+    pub epoch: Epoch,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CacheEntry {
+    pub stake_account: Pubkey,
+    pub activation: u64,
+    pub deactivation: u64,
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&data[offset..offset + 8]);
+    u64::from_le_bytes(bytes)
+}
+
+impl CacheHeader {
+    fn write(&self, out: &mut [u8]) {
+        out[0] = SCHEMA_VERSION;
+        out[1] = self.backend_id;
+        out[2] = self.algo_version;
+        out[3] = 0;
+        out[4..8].copy_from_slice(&self.count.to_le_bytes());
+        out[8..16].copy_from_slice(&self.epoch.to_le_bytes());
+    }
+
+    fn read(data: &[u8]) -> Result<Self, CacheError> {
+        let data = data.get(..HEADER_LEN).ok_or(CacheError::TooShort)?;
+        if data[0] != SCHEMA_VERSION {
+            return Err(CacheError::UnsupportedSchema(data[0]));
+        }
+        let mut count = [0u8; 4];
+        count.copy_from_slice(&data[4..8]);
+        Ok(Self {
+            backend_id: data[1],
+            algo_version: data[2],
+            count: u32::from_le_bytes(count),
+            epoch: read_u64(data, 8),
+        })
+    }
+}
+
+impl CacheEntry {
+    fn write(&self, out: &mut [u8]) {
+        out[0..32].copy_from_slice(&self.stake_account);
+        out[32..40].copy_from_slice(&self.activation.to_le_bytes());
+        out[40..48].copy_from_slice(&self.deactivation.to_le_bytes());
+    }
+
+    fn read(data: &[u8]) -> Self {
+        let mut stake_account = [0u8; 32];
+        stake_account.copy_from_slice(&data[0..32]);
+        Self {
+            stake_account,
+            activation: read_u64(data, 32),
+            deactivation: read_u64(data, 40),
+        }
+    }
+}
+
+/// Borrowed view over a cache account's data.
+#[derive(Clone, Copy)]
+pub struct ResultCache<'a> {
+    header: CacheHeader,
+    entries: &'a [u8],
+}
+
+impl<'a> ResultCache<'a> {
+    /// Borrows `data`, which may be longer than the encoded entries.
+    pub fn from_bytes(data: &'a [u8]) -> Result<Self, CacheError> {
+        let header = CacheHeader::read(data)?;
+        let entries = data
+            .get(HEADER_LEN..cache_len(header.count as usize))
+            .ok_or(CacheError::TooShort)?;
+        Ok(Self { header, entries })
+    }
+
+    pub fn header(&self) -> CacheHeader {
+        self.header
+    }
+
+    pub fn len(&self) -> usize {
+        self.header.count as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.header.count == 0
+    }
+
+    pub fn get(&self, index: usize) -> Option<CacheEntry> {
+        let offset = index.checked_mul(ENTRY_LEN)?;
+        self.entries
+            .get(offset..offset + ENTRY_LEN)
+            .map(CacheEntry::read)
+    }
+
+    /// Linear scan; caches are written in whatever order the accounts came.
+    pub fn find(&self, stake_account: &Pubkey) -> Option<CacheEntry> {
+        self.entries
+            .chunks_exact(ENTRY_LEN)
+            .find(|entry| entry[0..32] == stake_account[..])
+            .map(CacheEntry::read)
+    }
+
+    /// The entry for `stake_account` if the cache was computed for `epoch`.
+    pub fn find_for_epoch(&self, stake_account: &Pubkey, epoch: Epoch) -> Option<CacheEntry> {
+        if self.header.epoch != epoch {
+            return None;
+        }
+        self.find(stake_account)
+    }
+}
+
+/// Writes a header for `entries.len()` entries followed by the entries.
+/// Returns the number of bytes written; trailing bytes are left untouched.
+pub fn write_cache(
+    data: &mut [u8],
+    backend_id: u8,
+    algo_version: u8,
+    epoch: Epoch,
+    entries: &[CacheEntry],
+) -> Result<usize, CacheError> {
+    let len = cache_len(entries.len());
+    let out = data.get_mut(..len).ok_or(CacheError::TooShort)?;
+    let count = u32::try_from(entries.len()).map_err(|_| CacheError::TooShort)?;
+    CacheHeader {
+        backend_id,
+        algo_version,
+        count,
+        epoch,
+    }
+    .write(&mut out[..HEADER_LEN]);
+    for (chunk, entry) in out[HEADER_LEN..].chunks_exact_mut(ENTRY_LEN).zip(entries) {
+        entry.write(chunk);
+    }
+    Ok(len)
+}
+
+/// Overwrites entry `index` of an existing cache in place.
+pub fn write_cache_entry(
+    data: &mut [u8],
+    index: usize,
+    entry: &CacheEntry,
+) -> Result<(), CacheError> {
+    let header = CacheHeader::read(data)?;
+    if index >= header.count as usize {
+        return Err(CacheError::IndexOutOfRange);
+    }
+    let offset = cache_len(index);
+    let out = data
+        .get_mut(offset..offset + ENTRY_LEN)
+        .ok_or(CacheError::TooShort)?;
+    entry.write(out);
+    Ok(())
+}
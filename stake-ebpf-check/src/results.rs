@@ -0,0 +1,465 @@
+//! Layouts the program writes into its result account.
+//!
+//! All integers are little-endian. Trailing bytes of the account are left
+//! untouched.
+//!
+//! Allowance, with each allowance's `condition` code:
+//!
+//! ```text
+//! 0..8    u64  activation allowance
+//! 8..16   u64  deactivation allowance
+//! 16      u8   backend id
+//! 17      u8   algorithm version
+//! 18      u8   activation condition
+//! 19      u8   deactivation condition
+//! ```
+//!
+//! Compute units, net of the measuring syscalls and summed over every
+//! repetition:
+//!
+//! ```text
+//! 0..8    u64  units consumed by the activation allowance
+//! 8..16   u64  units consumed by the deactivation allowance
+//! 16      u8   backend id
+//! 17      u8   algorithm version
+//! 18..26  u64  repetitions run
+//! ```
+//!
+//! Self-test:
+//!
+//! ```text
+//! 0..4    u32  vectors run
+//! 4..8    u32  mismatches
+//! 8       u8   backend id
+//! 9       u8   algorithm version
+//! ```
+//!
+//! Stress:
+//!
+//! ```text
+//! 0..8    u64  rolling hash of every result
+//! 8..16   u64  iterations run
+//! 16      u8   backend id
+//! 17      u8   algorithm version
+//! ```
+//!
+//! Split validation:
+//!
+//! ```text
+//! 0..8    u64  minimum delegation reported by the Stake program
+//! 8       u8   0 if the split is valid, else the `SplitError` code
+//! 9       u8   algorithm version
+//! ```
+//!
+//! Batch, followed by one 16-byte entry per account:
+//!
+//! ```text
+//! 0       u8   account count
+//! 1       u8   backend id
+//! 2       u8   algorithm version
+//! 3..8         reserved, zero
+//!
+//! per entry at 8 + 16 * i:
+//! 0..8    u64  activation allowance
+//! 8..16   u64  deactivation allowance
+//! ```
+//!
+//! Delegation status:
+//!
+//! ```text
+//! 0..8    u64  effective
+//! 8..16   u64  activating
+//! 16..24  u64  deactivating
+//! 24      u8   backend id
+//! 25      u8   algorithm version
+//! ```
+//!
+//! Differential, written whether or not the backends agree. On disagreement
+//! the instruction then fails, so a cluster rolls the write back and only
+//! the matching `sol_log_64` line (divergence byte, then the four
+//! allowances in this order) reaches the client:
+//!
+//! ```text
+//! 0       u8   0 if both agree, else bit 0 set if activation diverges and
+//!              bit 1 if deactivation does
+//! 1       u8   first backend id
+//! 2       u8   second backend id
+//! 3       u8   algorithm version
+//! 4..8         reserved, zero
+//! 8..16   u64  first backend's activation allowance
+//! 16..24  u64  first backend's deactivation allowance
+//! 24..32  u64  second backend's activation allowance
+//! 32..40  u64  second backend's deactivation allowance
+//! ```
+
+use crate::condition::Condition;
+use crate::delegation::StakeActivationStatus;
+use crate::split::SplitError;
+
+/// Bumped whenever a change alters any computed result.
+pub const ALGO_VERSION: u8 = 1;
+
+pub const ALLOWANCE_RESULT_LEN: usize = 20;
+pub const DELEGATION_STATUS_RESULT_LEN: usize = 26;
+pub const COMPUTE_UNITS_RESULT_LEN: usize = 26;
+pub const SELF_TEST_RESULT_LEN: usize = 10;
+pub const STRESS_RESULT_LEN: usize = 18;
+pub const VALIDATE_SPLIT_RESULT_LEN: usize = 10;
+pub const BATCH_RESULT_HEADER_LEN: usize = 8;
+pub const BATCH_RESULT_ENTRY_LEN: usize = 16;
+pub const DIFFERENTIAL_RESULT_LEN: usize = 40;
+
+pub const DIVERGENCE_ACTIVATION: u8 = 1 << 0;
+pub const DIVERGENCE_DEACTIVATION: u8 = 1 << 1;
+
+pub const fn batch_result_len(count: usize) -> usize {
+    BATCH_RESULT_HEADER_LEN + BATCH_RESULT_ENTRY_LEN * count
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+pub struct AllowanceResult {
+    pub activation: u64,
+    pub deactivation: u64,
+    pub backend_id: u8,
+    pub algo_version: u8,
+    pub activation_condition: Condition,
+    pub deactivation_condition: Condition,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+pub struct ComputeUnitsResult {
+    pub activation_units: u64,
+    pub deactivation_units: u64,
+    pub backend_id: u8,
+    pub algo_version: u8,
+    pub repetitions: u64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+pub struct SelfTestResult {
+    pub vectors: u32,
+    pub mismatches: u32,
+    pub backend_id: u8,
+    pub algo_version: u8,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+pub struct StressResult {
+    pub hash: u64,
+    pub iterations: u64,
+    pub backend_id: u8,
+    pub algo_version: u8,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+pub struct ValidateSplitResult {
+    pub minimum_delegation: u64,
+    pub outcome: Result<(), SplitError>,
+    pub algo_version: u8,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+pub struct BatchResultHeader {
+    pub count: u8,
+    pub backend_id: u8,
+    pub algo_version: u8,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+pub struct DelegationStatusResult {
+    pub status: StakeActivationStatus,
+    pub backend_id: u8,
+    pub algo_version: u8,
+}
+
+/// Both backends' `(activation, deactivation)` allowances for one set of
+/// operands.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+pub struct DifferentialResult {
+    pub first_backend_id: u8,
+    pub second_backend_id: u8,
+    pub first: (u64, u64),
+    pub second: (u64, u64),
+    pub algo_version: u8,
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&data[offset..offset + 8]);
+    u64::from_le_bytes(bytes)
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    let mut bytes = [0u8; 4];
+    bytes.copy_from_slice(&data[offset..offset + 4]);
+    u32::from_le_bytes(bytes)
+}
+
+impl AllowanceResult {
+    /// `None` if `data` is shorter than [`ALLOWANCE_RESULT_LEN`].
+    pub fn write(&self, data: &mut [u8]) -> Option<()> {
+        let out = data.get_mut(..ALLOWANCE_RESULT_LEN)?;
+        out[0..8].copy_from_slice(&self.activation.to_le_bytes());
+        out[8..16].copy_from_slice(&self.deactivation.to_le_bytes());
+        out[16] = self.backend_id;
+        out[17] = self.algo_version;
+        out[18] = self.activation_condition.code();
+        out[19] = self.deactivation_condition.code();
+        Some(())
+    }
+
+    /// `None` if `data` is too short or holds an unknown condition code.
+    pub fn read(data: &[u8]) -> Option<Self> {
+        let data = data.get(..ALLOWANCE_RESULT_LEN)?;
+        Some(Self {
+            activation: read_u64(data, 0),
+            deactivation: read_u64(data, 8),
+            backend_id: data[16],
+            algo_version: data[17],
+            activation_condition: Condition::from_code(data[18])?,
+            deactivation_condition: Condition::from_code(data[19])?,
+        })
+    }
+}
+
+impl ComputeUnitsResult {
+    /// `None` if `data` is shorter than [`COMPUTE_UNITS_RESULT_LEN`].
+    pub fn write(&self, data: &mut [u8]) -> Option<()> {
+        let out = data.get_mut(..COMPUTE_UNITS_RESULT_LEN)?;
+        out[0..8].copy_from_slice(&self.activation_units.to_le_bytes());
+        out[8..16].copy_from_slice(&self.deactivation_units.to_le_bytes());
+        out[16] = self.backend_id;
+        out[17] = self.algo_version;
+        out[18..26].copy_from_slice(&self.repetitions.to_le_bytes());
+        Some(())
+    }
+
+    pub fn read(data: &[u8]) -> Option<Self> {
+        let data = data.get(..COMPUTE_UNITS_RESULT_LEN)?;
+        Some(Self {
+            activation_units: read_u64(data, 0),
+            deactivation_units: read_u64(data, 8),
+            backend_id: data[16],
+            algo_version: data[17],
+            repetitions: read_u64(data, 18),
+        })
+    }
+}
+
+impl SelfTestResult {
+    /// `None` if `data` is shorter than [`SELF_TEST_RESULT_LEN`].
+    pub fn write(&self, data: &mut [u8]) -> Option<()> {
+        let out = data.get_mut(..SELF_TEST_RESULT_LEN)?;
+        out[0..4].copy_from_slice(&self.vectors.to_le_bytes());
+        out[4..8].copy_from_slice(&self.mismatches.to_le_bytes());
+        out[8] = self.backend_id;
+        out[9] = self.algo_version;
+        Some(())
+    }
+
+    pub fn read(data: &[u8]) -> Option<Self> {
+        let data = data.get(..SELF_TEST_RESULT_LEN)?;
+        Some(Self {
+            vectors: read_u32(data, 0),
+            mismatches: read_u32(data, 4),
+            backend_id: data[8],
+            algo_version: data[9],
+        })
+    }
+}
+
+impl StressResult {
+    /// `None` if `data` is shorter than [`STRESS_RESULT_LEN`].
+    pub fn write(&self, data: &mut [u8]) -> Option<()> {
+        let out = data.get_mut(..STRESS_RESULT_LEN)?;
+        out[0..8].copy_from_slice(&self.hash.to_le_bytes());
+        out[8..16].copy_from_slice(&self.iterations.to_le_bytes());
+        out[16] = self.backend_id;
+        out[17] = self.algo_version;
+        Some(())
+    }
+
+    pub fn read(data: &[u8]) -> Option<Self> {
+        let data = data.get(..STRESS_RESULT_LEN)?;
+        Some(Self {
+            hash: read_u64(data, 0),
+            iterations: read_u64(data, 8),
+            backend_id: data[16],
+            algo_version: data[17],
+        })
+    }
+}
+
+impl ValidateSplitResult {
+    /// `None` if `data` is shorter than [`VALIDATE_SPLIT_RESULT_LEN`].
+    pub fn write(&self, data: &mut [u8]) -> Option<()> {
+        let out = data.get_mut(..VALIDATE_SPLIT_RESULT_LEN)?;
+        out[0..8].copy_from_slice(&self.minimum_delegation.to_le_bytes());
+        out[8] = match self.outcome {
+            Ok(()) => 0,
+            Err(e) => e.code(),
+        };
+        out[9] = self.algo_version;
+        Some(())
+    }
+
+    /// `None` if `data` is too short or holds an unknown error code.
+    pub fn read(data: &[u8]) -> Option<Self> {
+        let data = data.get(..VALIDATE_SPLIT_RESULT_LEN)?;
+        let outcome = match data[8] {
+            0 => Ok(()),
+            code => Err(SplitError::from_code(code)?),
+        };
+        Some(Self {
+            minimum_delegation: read_u64(data, 0),
+            outcome,
+            algo_version: data[9],
+        })
+    }
+}
+
+impl BatchResultHeader {
+    /// `None` if `data` cannot hold the header and all `count` entries.
+    pub fn write(&self, data: &mut [u8]) -> Option<()> {
+        let out = data.get_mut(..batch_result_len(self.count as usize))?;
+        out[0] = self.count;
+        out[1] = self.backend_id;
+        out[2] = self.algo_version;
+        out[3..BATCH_RESULT_HEADER_LEN].fill(0);
+        Some(())
+    }
+
+    pub fn read(data: &[u8]) -> Option<Self> {
+        let data = data.get(..BATCH_RESULT_HEADER_LEN)?;
+        Some(Self {
+            count: data[0],
+            backend_id: data[1],
+            algo_version: data[2],
+        })
+    }
+}
+
+/// Writes entry `index` as `(activation, deactivation)`.
+pub fn write_batch_entry(data: &mut [u8], index: usize, allowances: (u64, u64)) -> Option<()> {
+    let offset = batch_result_len(index);
+    let out = data.get_mut(offset..offset + BATCH_RESULT_ENTRY_LEN)?;
+    out[0..8].copy_from_slice(&allowances.0.to_le_bytes());
+    out[8..16].copy_from_slice(&allowances.1.to_le_bytes());
+    Some(())
+}
+
+pub fn read_batch_entry(data: &[u8], index: usize) -> Option<(u64, u64)> {
+    let offset = batch_result_len(index);
+    let data = data.get(offset..offset + BATCH_RESULT_ENTRY_LEN)?;
+    Some((read_u64(data, 0), read_u64(data, 8)))
+}
+
+impl DelegationStatusResult {
+    /// `None` if `data` is shorter than [`DELEGATION_STATUS_RESULT_LEN`].
+    pub fn write(&self, data: &mut [u8]) -> Option<()> {
+        let out = data.get_mut(..DELEGATION_STATUS_RESULT_LEN)?;
+        out[0..8].copy_from_slice(&self.status.effective.to_le_bytes());
+        out[8..16].copy_from_slice(&self.status.activating.to_le_bytes());
+        out[16..24].copy_from_slice(&self.status.deactivating.to_le_bytes());
+        out[24] = self.backend_id;
+        out[25] = self.algo_version;
+        Some(())
+    }
+
+    pub fn read(data: &[u8]) -> Option<Self> {
+        let data = data.get(..DELEGATION_STATUS_RESULT_LEN)?;
+        Some(Self {
+            status: StakeActivationStatus {
+                effective: read_u64(data, 0),
+                activating: read_u64(data, 8),
+                deactivating: read_u64(data, 16),
+            },
+            backend_id: data[24],
+            algo_version: data[25],
+        })
+    }
+}
+
+impl DifferentialResult {
+    /// 0 on agreement, else the `DIVERGENCE_*` bits of the allowances that
+    /// differ.
+    pub fn divergence(&self) -> u8 {
+        let mut divergence = 0;
+        if self.first.0 != self.second.0 {
+            divergence |= DIVERGENCE_ACTIVATION;
+        }
+        if self.first.1 != self.second.1 {
+            divergence |= DIVERGENCE_DEACTIVATION;
+        }
+        divergence
+    }
+
+    /// `None` if `data` is shorter than [`DIFFERENTIAL_RESULT_LEN`].
+    pub fn write(&self, data: &mut [u8]) -> Option<()> {
+        let out = data.get_mut(..DIFFERENTIAL_RESULT_LEN)?;
+        out[0] = self.divergence();
+        out[1] = self.first_backend_id;
+        out[2] = self.second_backend_id;
+        out[3] = self.algo_version;
+        out[4..8].fill(0);
+        out[8..16].copy_from_slice(&self.first.0.to_le_bytes());
+        out[16..24].copy_from_slice(&self.first.1.to_le_bytes());
+        out[24..32].copy_from_slice(&self.second.0.to_le_bytes());
+        out[32..40].copy_from_slice(&self.second.1.to_le_bytes());
+        Some(())
+    }
+
+    /// `None` if `data` is too short or its divergence byte disagrees with
+    /// the values that follow.
+    pub fn read(data: &[u8]) -> Option<Self> {
+        let data = data.get(..DIFFERENTIAL_RESULT_LEN)?;
+        let result = Self {
+            first_backend_id: data[1],
+            second_backend_id: data[2],
+            first: (read_u64(data, 8), read_u64(data, 16)),
+            second: (read_u64(data, 24), read_u64(data, 32)),
+            algo_version: data[3],
+        };
+        (result.divergence() == data[0]).then_some(result)
+    }
+}
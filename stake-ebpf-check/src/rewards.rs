@@ -0,0 +1,138 @@
+//! Epoch rewards for a single stake, as `redeem_rewards` computes them.
+
+use crate::points::{PointValue, PointsAndCredits};
+use crate::streaming::{mul_div, mul_div_wide, Rounding};
+use crate::BASIS_POINTS_PER_UNIT;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CalculatedStakeRewards {
+    pub staker_rewards: u64,
+    pub voter_rewards: u64,
+    pub new_credits_observed: u64,
+}
+
+/// Splits `on` between voter and staker; each side is floored separately.
+///
+/// Returns `(voter, staker, is_split)`.
+pub fn commission_split(commission_bps: u64, on: u64) -> (u64, u64, bool) {
+    match commission_bps.min(BASIS_POINTS_PER_UNIT) {
+        0 => (0, on, false),
+        BASIS_POINTS_PER_UNIT => (on, 0, false),
+        bps => {
+            let voter = mul_div(on, bps, BASIS_POINTS_PER_UNIT, Rounding::Down).unwrap_or(0);
+            let staker = mul_div(
+                on,
+                BASIS_POINTS_PER_UNIT - bps,
+                BASIS_POINTS_PER_UNIT,
+                Rounding::Down,
+            )
+            .unwrap_or(0);
+            (voter, staker, true)
+        }
+    }
+}
+
+/// Rewards for the points a stake earned, split by `commission_bps`.
+///
+/// `None` when nothing is paid and `credits_observed` must not advance
+/// either. That includes a split where one side rounds to zero. A recreated
+/// vote account, flagged by [`calculate_points`], or a distribution with no
+/// rewards instead pays nothing but still moves the stake to the new
+/// credits, as upstream's forced credits update does.
+///
+/// [`calculate_points`]: crate::points::calculate_points
+pub fn calculate_stake_rewards(
+    points: &PointsAndCredits,
+    point_value: &PointValue,
+    commission_bps: u64,
+) -> Option<CalculatedStakeRewards> {
+    let new_credits_observed = points.credits_observed;
+    if points.force_credits_update || point_value.rewards == 0 {
+        return Some(CalculatedStakeRewards {
+            staker_rewards: 0,
+            voter_rewards: 0,
+            new_credits_observed,
+        });
+    }
+    if points.points == (0, 0) {
+        return None;
+    }
+
+    let rewards = mul_div_wide(points.points, point_value.rewards, point_value.points)?;
+    if rewards == 0 {
+        return None;
+    }
+
+    let (voter_rewards, staker_rewards, is_split) = commission_split(commission_bps, rewards);
+    if is_split && (voter_rewards == 0 || staker_rewards == 0) {
+        return None;
+    }
+
+    Some(CalculatedStakeRewards {
+        staker_rewards,
+        voter_rewards,
+        new_credits_observed,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::points::calculate_points;
+
+    const POINT_VALUE: PointValue = PointValue {
+        rewards: 1_000,
+        points: (0, 10_000),
+    };
+
+    fn points(credits_observed: u64) -> PointsAndCredits {
+        calculate_points(&[(1, 10, 0), (2, 20, 10)], credits_observed, |_| 500).unwrap()
+    }
+
+    #[test]
+    fn rewards_pay_points_and_advance_credits() {
+        assert_eq!(
+            calculate_stake_rewards(&points(0), &POINT_VALUE, 1_000),
+            Some(CalculatedStakeRewards {
+                staker_rewards: 900,
+                voter_rewards: 100,
+                new_credits_observed: 20,
+            })
+        );
+    }
+
+    #[test]
+    fn nothing_to_pay_leaves_credits_alone() {
+        assert_eq!(calculate_stake_rewards(&points(20), &POINT_VALUE, 0), None);
+    }
+
+    #[test]
+    fn recreated_vote_account_forces_a_credits_update() {
+        let points = points(25);
+        assert!(points.force_credits_update);
+        assert_eq!(
+            calculate_stake_rewards(&points, &POINT_VALUE, 0),
+            Some(CalculatedStakeRewards {
+                staker_rewards: 0,
+                voter_rewards: 0,
+                new_credits_observed: 20,
+            })
+        );
+    }
+
+    #[test]
+    fn no_rewards_still_advances_credits() {
+        let no_rewards = PointValue {
+            rewards: 0,
+            ..POINT_VALUE
+        };
+        assert_eq!(
+            calculate_stake_rewards(&points(0), &no_rewards, 0),
+            Some(CalculatedStakeRewards {
+                staker_rewards: 0,
+                voter_rewards: 0,
+                new_credits_observed: 20,
+            })
+        );
+    }
+}
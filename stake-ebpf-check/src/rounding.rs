@@ -0,0 +1,218 @@
+//! How many lamports per-account flooring withholds in one epoch.
+//!
+//! The entitlement is what the summed portions would be granted as a single
+//! account; the loss is the entitlement minus what the accounts get one by
+//! one. Running this per backend shows whether a backend rounds differently.
+//!
+//! The backends all floor. The `_with_mode` variants also price rounding
+//! each grant up or to nearest instead, by correcting the backend's floored
+//! result with the exact remainder.
+
+use crate::stake_history::StakeHistoryEntry;
+use crate::streaming::{add_wide, div_rem_wide, mul_rem_wide, mul_wide};
+use crate::{warmup_cooldown_rate_bps, Epoch, StakeCalculator, BASIS_POINTS_PER_UNIT};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RoundingMode {
+    /// What every backend does.
+    Floor,
+    Ceil,
+    /// Halves round up.
+    Nearest,
+}
+
+impl RoundingMode {
+    pub const ALL: [Self; 3] = [Self::Floor, Self::Ceil, Self::Nearest];
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RoundingLoss {
+    pub entitlement: u64,
+    pub granted: u64,
+    pub lost: u64,
+}
+
+/// Whether `mode` takes the exact `account * effective * rate / (cluster *
+/// 10_000)` above its floor, in limb arithmetic so the library never needs
+/// `u128`. The first remainder is below `cluster * 10_000`, so scaling it by
+/// any real rate leaves a 64-bit quotient; a first quotient past 64 bits
+/// already clamps the grant to the whole portion.
+fn rounds_up(
+    mode: RoundingMode,
+    rate_bps: u64,
+    account_portion: u64,
+    cluster_portion: u64,
+    cluster_effective: u64,
+) -> bool {
+    let denominator = mul_wide(cluster_portion, BASIS_POINTS_PER_UNIT);
+    let numerator = mul_wide(account_portion, cluster_effective);
+    let Some((_, remainder)) = div_rem_wide(numerator, denominator) else {
+        return false;
+    };
+    let Some(fraction) = mul_rem_wide(remainder, rate_bps, denominator) else {
+        return false;
+    };
+    match mode {
+        RoundingMode::Floor => false,
+        RoundingMode::Ceil => fraction != (0, 0),
+        RoundingMode::Nearest => {
+            add_wide(fraction, fraction).is_none_or(|twice| twice >= denominator)
+        }
+    }
+}
+
+fn rounding_loss<T: StakeCalculator>(
+    epoch: Epoch,
+    account_portions: &[u64],
+    cluster_portion: u64,
+    cluster_effective: u64,
+    new_rate_activation_epoch: Option<Epoch>,
+    mode: RoundingMode,
+) -> RoundingLoss {
+    let rate_bps = warmup_cooldown_rate_bps(epoch, new_rate_activation_epoch);
+    let step = |portion| {
+        let floor =
+            T::rate_limited_stake_change_bps(rate_bps, portion, cluster_portion, cluster_effective);
+        let up = rounds_up(mode, rate_bps, portion, cluster_portion, cluster_effective);
+        // A grant clamped to the whole portion has nothing to round.
+        (floor + u64::from(up && floor < portion)).min(portion)
+    };
+
+    let total = account_portions
+        .iter()
+        .fold(0u64, |acc, &portion| acc.saturating_add(portion));
+    let entitlement = step(total);
+    let granted = account_portions
+        .iter()
+        .fold(0u64, |acc, &portion| acc.saturating_add(step(portion)));
+
+    RoundingLoss {
+        entitlement,
+        granted,
+        lost: entitlement.saturating_sub(granted),
+    }
+}
+
+pub fn activation_rounding_loss<T: StakeCalculator>(
+    epoch: Epoch,
+    account_portions: &[u64],
+    prev_epoch_cluster_state: &StakeHistoryEntry,
+    new_rate_activation_epoch: Option<Epoch>,
+) -> RoundingLoss {
+    activation_rounding_loss_with_mode::<T>(
+        epoch,
+        account_portions,
+        prev_epoch_cluster_state,
+        new_rate_activation_epoch,
+        RoundingMode::Floor,
+    )
+}
+
+pub fn deactivation_rounding_loss<T: StakeCalculator>(
+    epoch: Epoch,
+    account_portions: &[u64],
+    prev_epoch_cluster_state: &StakeHistoryEntry,
+    new_rate_activation_epoch: Option<Epoch>,
+) -> RoundingLoss {
+    deactivation_rounding_loss_with_mode::<T>(
+        epoch,
+        account_portions,
+        prev_epoch_cluster_state,
+        new_rate_activation_epoch,
+        RoundingMode::Floor,
+    )
+}
+
+/// Rounding up can grant more than the entitlement, which `lost` floors at
+/// zero; compare `granted` with `entitlement` directly for that.
+pub fn activation_rounding_loss_with_mode<T: StakeCalculator>(
+    epoch: Epoch,
+    account_portions: &[u64],
+    prev_epoch_cluster_state: &StakeHistoryEntry,
+    new_rate_activation_epoch: Option<Epoch>,
+    mode: RoundingMode,
+) -> RoundingLoss {
+    rounding_loss::<T>(
+        epoch,
+        account_portions,
+        prev_epoch_cluster_state.activating,
+        prev_epoch_cluster_state.effective,
+        new_rate_activation_epoch,
+        mode,
+    )
+}
+
+pub fn deactivation_rounding_loss_with_mode<T: StakeCalculator>(
+    epoch: Epoch,
+    account_portions: &[u64],
+    prev_epoch_cluster_state: &StakeHistoryEntry,
+    new_rate_activation_epoch: Option<Epoch>,
+    mode: RoundingMode,
+) -> RoundingLoss {
+    rounding_loss::<T>(
+        epoch,
+        account_portions,
+        prev_epoch_cluster_state.deactivating,
+        prev_epoch_cluster_state.effective,
+        new_rate_activation_epoch,
+        mode,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The `u128` arithmetic `rounds_up` replaces, less the grants too large
+    /// to round, which the limb code skips.
+    fn rounds_up_u128(
+        mode: RoundingMode,
+        rate_bps: u64,
+        account: u64,
+        cluster: u64,
+        effective: u64,
+    ) -> bool {
+        let denominator = cluster as u128 * BASIS_POINTS_PER_UNIT as u128;
+        let product = account as u128 * effective as u128;
+        if denominator == 0 || product / denominator > u64::MAX as u128 {
+            return false;
+        }
+        let fraction = product % denominator * rate_bps as u128 % denominator;
+        match mode {
+            RoundingMode::Floor => false,
+            RoundingMode::Ceil => fraction != 0,
+            RoundingMode::Nearest => fraction >= denominator - fraction,
+        }
+    }
+
+    #[test]
+    fn rounds_up_matches_exact_arithmetic() {
+        let operands = [
+            0,
+            1,
+            3,
+            7,
+            999,
+            10_000,
+            1 << 32,
+            123_456_789_012,
+            u64::MAX / 3,
+            u64::MAX,
+        ];
+        for mode in RoundingMode::ALL {
+            for rate_bps in [0, 900, 2_500, 5_000] {
+                for account in operands {
+                    for cluster in operands {
+                        for effective in operands {
+                            assert_eq!(
+                                rounds_up(mode, rate_bps, account, cluster, effective),
+                                rounds_up_u128(mode, rate_bps, account, cluster, effective),
+                                "{mode:?} {rate_bps} {account} {cluster} {effective}"
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
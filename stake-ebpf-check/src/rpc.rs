@@ -0,0 +1,286 @@
+//! Blocking JSON-RPC reads for the host tools that look at a live cluster.
+//!
+//! Only what the tools need: raw accounts, stake accounts by vote account,
+//! the node's own view of a stake account's activation, the current epoch,
+//! the epoch schedule and the `reduce_stake_warmup_cooldown` feature's
+//! activation.
+
+use core::fmt;
+
+use data_encoding::BASE64;
+use serde_json::{json, Value};
+
+use crate::stake_account::STAKE_STATE_V2_SIZE;
+use crate::state::Pubkey;
+use crate::Epoch;
+
+/// `GwtDQBghCTBgmX2cpEGNPxTEBUTQRaDMGTr5qychdGMj`, the feature gating the
+/// 9% warmup/cooldown rate.
+pub const REDUCE_STAKE_WARMUP_COOLDOWN_ID: &str = "GwtDQBghCTBgmX2cpEGNPxTEBUTQRaDMGTr5qychdGMj";
+
+pub const STAKE_PROGRAM_ID: &str = "Stake11111111111111111111111111111111111111";
+
+/// Offset of `Delegation::voter_pubkey` in stake account data.
+const VOTER_PUBKEY_OFFSET: usize = 124;
+
+/// `EpochSchedule` warmup epochs are never shorter than this.
+const MINIMUM_SLOTS_PER_EPOCH: u64 = 32;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RpcError {
+    Transport(String),
+    /// The node answered with a JSON-RPC error object.
+    Rpc {
+        code: i64,
+        message: String,
+    },
+    /// The response is missing `field` or it has the wrong type.
+    Malformed(&'static str),
+}
+
+impl fmt::Display for RpcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Transport(e) => write!(f, "rpc transport: {e}"),
+            Self::Rpc { code, message } => write!(f, "rpc error {code}: {message}"),
+            Self::Malformed(field) => write!(f, "malformed rpc response: `{field}`"),
+        }
+    }
+}
+
+impl std::error::Error for RpcError {}
+
+pub fn parse_pubkey(text: &str) -> Option<Pubkey> {
+    let mut pubkey = Pubkey::default();
+    match bs58::decode(text).onto(&mut pubkey) {
+        Ok(32) => Some(pubkey),
+        _ => None,
+    }
+}
+
+pub fn pubkey_to_string(pubkey: &Pubkey) -> String {
+    bs58::encode(pubkey).into_string()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EpochSchedule {
+    pub slots_per_epoch: u64,
+    pub first_normal_epoch: Epoch,
+    pub first_normal_slot: u64,
+}
+
+impl EpochSchedule {
+    /// The epoch containing `slot`, with the power-of-two warmup epochs
+    /// before [`Self::first_normal_slot`].
+    pub fn epoch_of(&self, slot: u64) -> Epoch {
+        if slot < self.first_normal_slot {
+            let warmup = (slot + MINIMUM_SLOTS_PER_EPOCH + 1)
+                .next_power_of_two()
+                .trailing_zeros()
+                - MINIMUM_SLOTS_PER_EPOCH.trailing_zeros()
+                - 1;
+            return Epoch::from(warmup);
+        }
+        (slot - self.first_normal_slot) / self.slots_per_epoch.max(1) + self.first_normal_epoch
+    }
+}
+
+fn field<'v>(value: &'v Value, name: &'static str) -> Result<&'v Value, RpcError> {
+    value.get(name).ok_or(RpcError::Malformed(name))
+}
+
+fn u64_field(value: &Value, name: &'static str) -> Result<u64, RpcError> {
+    field(value, name)?
+        .as_u64()
+        .ok_or(RpcError::Malformed(name))
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RpcAccount {
+    pub lamports: u64,
+    pub data: Vec<u8>,
+}
+
+fn parse_account(account: &Value) -> Result<RpcAccount, RpcError> {
+    let encoded = field(account, "data")?
+        .get(0)
+        .and_then(Value::as_str)
+        .ok_or(RpcError::Malformed("data"))?;
+    Ok(RpcAccount {
+        lamports: u64_field(account, "lamports")?,
+        data: BASE64
+            .decode(encoded.as_bytes())
+            .map_err(|_| RpcError::Malformed("data"))?,
+    })
+}
+
+/// `getStakeActivation`'s `state`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StakeActivationState {
+    Activating,
+    Active,
+    Deactivating,
+    Inactive,
+}
+
+/// `getStakeActivation`'s answer: `active` is the effective stake, and
+/// `inactive` depends on `state` (see the `stake-cross-check` binary).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RpcStakeActivation {
+    pub state: StakeActivationState,
+    pub active: u64,
+    pub inactive: u64,
+}
+
+pub struct RpcClient {
+    url: String,
+}
+
+impl RpcClient {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self { url: url.into() }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// The `result` of a single JSON-RPC request.
+    pub fn call(&self, method: &str, params: Value) -> Result<Value, RpcError> {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": method,
+            "params": params,
+        });
+        let body = ureq::post(&self.url)
+            .header("Content-Type", "application/json")
+            .send(request.to_string())
+            .map_err(|e| RpcError::Transport(e.to_string()))?
+            .body_mut()
+            .read_to_string()
+            .map_err(|e| RpcError::Transport(e.to_string()))?;
+        let mut response: Value =
+            serde_json::from_str(&body).map_err(|e| RpcError::Transport(e.to_string()))?;
+
+        if let Some(error) = response.get("error") {
+            return Err(RpcError::Rpc {
+                code: error.get("code").and_then(Value::as_i64).unwrap_or(0),
+                message: error
+                    .get("message")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default()
+                    .to_owned(),
+            });
+        }
+        response
+            .get_mut("result")
+            .map(Value::take)
+            .ok_or(RpcError::Malformed("result"))
+    }
+
+    /// The account, or `None` if it doesn't exist.
+    pub fn account(&self, pubkey: &Pubkey) -> Result<Option<RpcAccount>, RpcError> {
+        let result = self.call(
+            "getAccountInfo",
+            json!([pubkey_to_string(pubkey), { "encoding": "base64" }]),
+        )?;
+        let account = field(&result, "value")?;
+        if account.is_null() {
+            return Ok(None);
+        }
+        parse_account(account).map(Some)
+    }
+
+    /// The account's data, or `None` if it doesn't exist.
+    pub fn account_data(&self, pubkey: &Pubkey) -> Result<Option<Vec<u8>>, RpcError> {
+        Ok(self.account(pubkey)?.map(|account| account.data))
+    }
+
+    /// Every stake account delegated to `voter`. Many public nodes refuse
+    /// `getProgramAccounts` on the stake program; filtering by vote account
+    /// keeps the answer small enough for those that allow it.
+    pub fn stake_accounts_by_voter(
+        &self,
+        voter: &Pubkey,
+    ) -> Result<Vec<(Pubkey, RpcAccount)>, RpcError> {
+        let result = self.call(
+            "getProgramAccounts",
+            json!([
+                STAKE_PROGRAM_ID,
+                {
+                    "encoding": "base64",
+                    "filters": [
+                        { "dataSize": STAKE_STATE_V2_SIZE },
+                        {
+                            "memcmp": {
+                                "offset": VOTER_PUBKEY_OFFSET,
+                                "bytes": pubkey_to_string(voter),
+                            },
+                        },
+                    ],
+                },
+            ]),
+        )?;
+        result
+            .as_array()
+            .ok_or(RpcError::Malformed("result"))?
+            .iter()
+            .map(|entry| {
+                let pubkey = field(entry, "pubkey")?
+                    .as_str()
+                    .and_then(parse_pubkey)
+                    .ok_or(RpcError::Malformed("pubkey"))?;
+                Ok((pubkey, parse_account(field(entry, "account")?)?))
+            })
+            .collect()
+    }
+
+    /// The node's view of a stake account at the current epoch. Agave 2.0
+    /// removed the method, so most current nodes answer with an error.
+    pub fn stake_activation(&self, pubkey: &Pubkey) -> Result<RpcStakeActivation, RpcError> {
+        let result = self.call("getStakeActivation", json!([pubkey_to_string(pubkey)]))?;
+        let state = match field(&result, "state")?.as_str() {
+            Some("activating") => StakeActivationState::Activating,
+            Some("active") => StakeActivationState::Active,
+            Some("deactivating") => StakeActivationState::Deactivating,
+            Some("inactive") => StakeActivationState::Inactive,
+            _ => return Err(RpcError::Malformed("state")),
+        };
+        Ok(RpcStakeActivation {
+            state,
+            active: u64_field(&result, "active")?,
+            inactive: u64_field(&result, "inactive")?,
+        })
+    }
+
+    pub fn epoch(&self) -> Result<Epoch, RpcError> {
+        u64_field(&self.call("getEpochInfo", json!([]))?, "epoch")
+    }
+
+    pub fn epoch_schedule(&self) -> Result<EpochSchedule, RpcError> {
+        let result = self.call("getEpochSchedule", json!([]))?;
+        Ok(EpochSchedule {
+            slots_per_epoch: u64_field(&result, "slotsPerEpoch")?,
+            first_normal_epoch: u64_field(&result, "firstNormalEpoch")?,
+            first_normal_slot: u64_field(&result, "firstNormalSlot")?,
+        })
+    }
+
+    /// The epoch the 9% rate took effect on this cluster, or `None` if the
+    /// feature isn't active yet.
+    ///
+    /// The feature account holds a bincode `Option<Slot>`; the runtime
+    /// switches rates from the epoch containing that slot.
+    pub fn new_rate_activation_epoch(&self) -> Result<Option<Epoch>, RpcError> {
+        let id = parse_pubkey(REDUCE_STAKE_WARMUP_COOLDOWN_ID).expect("valid feature id");
+        let Some(data) = self.account_data(&id)? else {
+            return Ok(None);
+        };
+        let slot = match data.get(..9) {
+            Some([1, slot @ ..]) => u64::from_le_bytes(slot.try_into().expect("8 bytes")),
+            _ => return Ok(None),
+        };
+        Ok(Some(self.epoch_schedule()?.epoch_of(slot)))
+    }
+}
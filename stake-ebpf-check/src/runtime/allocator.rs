@@ -0,0 +1,120 @@
+//! Global allocator.
+//!
+//! Nothing in this crate allocates, but some backend dependencies are built
+//! against `alloc`. By default they get a [`BumpAllocator`] that never frees,
+//! over the loader-provided heap on SBF and a static region elsewhere. With
+//! `forbid-alloc` every allocation is routed to an undefined symbol instead,
+//! so linking fails if one is reachable from an exported entry point.
+//!
+//! `host-sim` builds link `std` and keep its allocator.
+
+use core::alloc::{GlobalAlloc, Layout};
+use core::mem::size_of;
+use core::ptr::null_mut;
+
+/// Start of the heap region the SBF loader maps for every program.
+pub const HEAP_START_ADDRESS: usize = 0x3_0000_0000;
+/// Default heap size; programs may request more via the compute budget.
+pub const HEAP_LENGTH: usize = 32 * 1024;
+
+/// Allocates downwards from the end of `[start, start + len)`.
+///
+/// The current position is kept in the region's first word, so the allocator
+/// itself needs no mutable state. Zero means nothing has been allocated yet.
+pub struct BumpAllocator {
+    start: usize,
+    len: usize,
+}
+
+impl BumpAllocator {
+    /// # Safety
+    ///
+    /// The region must be writable, word-aligned, zero-initialised and used
+    /// by nothing else.
+    pub const unsafe fn new(start: usize, len: usize) -> Self {
+        Self { start, len }
+    }
+}
+
+unsafe impl GlobalAlloc for BumpAllocator {
+    #[inline]
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let pos_ptr = self.start as *mut usize;
+
+        let mut pos = *pos_ptr;
+        if pos == 0 {
+            pos = self.start + self.len;
+        }
+        pos = pos.saturating_sub(layout.size());
+        pos &= !(layout.align().wrapping_sub(1));
+        if pos < self.start + size_of::<usize>() {
+            return null_mut();
+        }
+        *pos_ptr = pos;
+        pos as *mut u8
+    }
+
+    #[inline]
+    unsafe fn dealloc(&self, _ptr: *mut u8, _layout: Layout) {}
+}
+
+#[cfg(all(
+    not(feature = "forbid-alloc"),
+    any(target_arch = "bpf", target_os = "solana")
+))]
+#[global_allocator]
+static GLOBAL: BumpAllocator = unsafe { BumpAllocator::new(HEAP_START_ADDRESS, HEAP_LENGTH) };
+
+#[cfg(all(
+    not(feature = "forbid-alloc"),
+    not(feature = "host-sim"),
+    not(any(target_arch = "bpf", target_os = "solana"))
+))]
+mod static_region {
+    use core::alloc::{GlobalAlloc, Layout};
+    use core::cell::UnsafeCell;
+
+    use super::{BumpAllocator, HEAP_LENGTH};
+
+    #[repr(align(8))]
+    struct Region(UnsafeCell<[u8; HEAP_LENGTH]>);
+
+    // Only touched through the allocator, which no target here calls from
+    // more than one thread.
+    unsafe impl Sync for Region {}
+
+    static REGION: Region = Region(UnsafeCell::new([0; HEAP_LENGTH]));
+
+    struct StaticBump;
+
+    unsafe impl GlobalAlloc for StaticBump {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            BumpAllocator::new(REGION.0.get() as usize, HEAP_LENGTH).alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, _ptr: *mut u8, _layout: Layout) {}
+    }
+
+    #[global_allocator]
+    static GLOBAL: StaticBump = StaticBump;
+}
+
+#[cfg(all(feature = "forbid-alloc", not(feature = "host-sim")))]
+struct NoAlloc;
+
+#[cfg(all(feature = "forbid-alloc", not(feature = "host-sim")))]
+unsafe impl GlobalAlloc for NoAlloc {
+    unsafe fn alloc(&self, _layout: Layout) -> *mut u8 {
+        extern "C" {
+            // Never defined, so any call that survives linking is an error.
+            fn __forbid_alloc_allocation_reachable() -> *mut u8;
+        }
+        __forbid_alloc_allocation_reachable()
+    }
+
+    unsafe fn dealloc(&self, _ptr: *mut u8, _layout: Layout) {}
+}
+
+#[cfg(all(feature = "forbid-alloc", not(feature = "host-sim")))]
+#[global_allocator]
+static GLOBAL: NoAlloc = NoAlloc;
@@ -0,0 +1,42 @@
+//! Scaffolding every build of the program shares: the global allocator, the
+//! panic handler and the packed entry points.
+
+pub mod allocator;
+#[cfg(not(feature = "solana-program"))]
+mod packed;
+
+#[cfg(not(feature = "host-sim"))]
+use core::panic::PanicInfo;
+
+/// Aborts through `sol_panic_`, which logs the location and fails the
+/// transaction instead of spinning until the compute budget runs out.
+#[cfg(any(target_arch = "bpf", target_os = "solana"))]
+#[panic_handler]
+fn panic(info: &PanicInfo) -> ! {
+    extern "C" {
+        fn sol_panic_(file: *const u8, len: u64, line: u64, column: u64) -> !;
+    }
+
+    let (file, line, column) = info.location().map_or(("", 0, 0), |location| {
+        (location.file(), location.line(), location.column())
+    });
+    unsafe { sol_panic_(file.as_ptr(), file.len() as u64, line as u64, column as u64) }
+}
+
+/// Targets without an abort syscall. `host-sim` builds use `std`'s.
+#[cfg(not(any(target_arch = "bpf", target_os = "solana", feature = "host-sim")))]
+#[panic_handler]
+fn panic(_info: &PanicInfo) -> ! {
+    loop {}
+}
+
+/// Needs an 8 KiB frame, twice the SBF limit, so a build with
+/// `stack-budget-violation` must fail to link; see `build.rs`.
+#[cfg(feature = "stack-budget-violation")]
+#[no_mangle]
+pub extern "C" fn entrypoint_stack_violation(arg: u64) -> u64 {
+    let mut frame = [0u64; 1024];
+    frame[(arg % 1024) as usize] = arg;
+    core::hint::black_box(&mut frame);
+    frame.iter().fold(0, |acc, word| acc ^ word)
+}
@@ -0,0 +1,142 @@
+//! Packed `entrypoint(arg)` symbols, for builds without the loader
+//! entrypoint.
+//!
+//! `arg` packs 16-bit operands (see [`crate::packed_args`]), which never
+//! reach the wide-math paths; [`entrypoint_full`] takes full-range operands
+//! from memory instead.
+
+use crate::boundary_sweep::{BoundarySweep, CASES};
+use crate::condition::classify_allowances;
+use crate::implementations;
+use crate::instruction::{Instruction, ALLOWANCE_INSTRUCTION_LEN};
+use crate::packed_args::PackedOperands;
+use crate::packed_return::{PackedReturn, Status};
+use crate::{Backend, BackendVisitor, StakeCalculator};
+
+/// Runs the first backend compiled in; see [`Backend::ALL`].
+#[no_mangle]
+pub extern "C" fn entrypoint(arg: u64) -> u64 {
+    match Backend::ALL.first() {
+        Some(backend) => backend.visit(Run(arg)),
+        None => PackedReturn::error(Status::NoBackend).encode(),
+    }
+}
+
+/// Runs an `Allowance` instruction (see [`crate::instruction`]) read from
+/// `input`, on the backend it names.
+///
+/// # Safety
+///
+/// `input` must point at [`ALLOWANCE_INSTRUCTION_LEN`] readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn entrypoint_full(input: *const u8) -> u64 {
+    let data = core::slice::from_raw_parts(input, ALLOWANCE_INSTRUCTION_LEN);
+    let Ok(Instruction::Allowance {
+        backend_id,
+        operands,
+    }) = Instruction::unpack(data)
+    else {
+        return PackedReturn::error(Status::InvalidInstruction).encode();
+    };
+    let Some(backend) = Backend::from_id(backend_id) else {
+        return PackedReturn::error(Status::UnknownBackend).encode();
+    };
+
+    let deactivating_stake = (operands.account_portion / 2) + 1;
+    let activation = backend.calculate_activation_allowance(
+        operands.epoch,
+        operands.account_portion,
+        &operands.cluster_state,
+        operands.new_rate_activation_epoch,
+    );
+    let deactivation = backend.calculate_deactivation_allowance(
+        operands.epoch,
+        deactivating_stake,
+        &operands.cluster_state,
+        operands.new_rate_activation_epoch,
+    );
+
+    PackedReturn::new(activation, deactivation, classify_allowances(&operands)).encode()
+}
+
+/// Runs boundary cases from `arg & 0xffff_ffff` on, `arg >> 32` of them or
+/// all remaining if that is zero, on the first backend compiled in, and
+/// returns their hash. Meant for `overflow-audit` builds, where a case that
+/// overflows traps instead; see [`crate::boundary_sweep`].
+#[no_mangle]
+pub extern "C" fn entrypoint_boundary_sweep(arg: u64) -> u64 {
+    let start = arg & 0xffff_ffff;
+    let count = match arg >> 32 {
+        0 => CASES,
+        count => count,
+    };
+    match Backend::ALL.first() {
+        Some(backend) => backend.visit(BoundarySweep { start, count }).0,
+        None => PackedReturn::error(Status::NoBackend).encode(),
+    }
+}
+
+// One symbol per backend, so a single build carrying several backends can
+// compare them side by side.
+
+#[cfg(feature = "bnum")]
+#[no_mangle]
+pub extern "C" fn entrypoint_bnum(arg: u64) -> u64 {
+    run::<implementations::bnum::BnumCalculator>(arg)
+}
+
+#[cfg(feature = "crypto")]
+#[no_mangle]
+pub extern "C" fn entrypoint_crypto(arg: u64) -> u64 {
+    run::<implementations::crypto::CryptoCalculator>(arg)
+}
+
+#[cfg(feature = "fixed")]
+#[no_mangle]
+pub extern "C" fn entrypoint_fixed(arg: u64) -> u64 {
+    run::<implementations::fixed::FixedCalculator>(arg)
+}
+
+#[cfg(feature = "uint")]
+#[no_mangle]
+pub extern "C" fn entrypoint_uint(arg: u64) -> u64 {
+    run::<implementations::uint_impl::UintCalculator>(arg)
+}
+
+#[cfg(feature = "plain")]
+#[no_mangle]
+pub extern "C" fn entrypoint_plain(arg: u64) -> u64 {
+    run::<implementations::plain::PlainCalculator>(arg)
+}
+
+#[cfg(feature = "manual")]
+#[no_mangle]
+pub extern "C" fn entrypoint_manual(arg: u64) -> u64 {
+    run::<implementations::manual::ManualCalculator>(arg)
+}
+
+#[cfg(feature = "streaming")]
+#[no_mangle]
+pub extern "C" fn entrypoint_streaming(arg: u64) -> u64 {
+    run::<implementations::streaming::EbpfStreamingCalculator>(arg)
+}
+
+struct Run(u64);
+
+impl BackendVisitor for Run {
+    type Output = u64;
+
+    fn visit<T: StakeCalculator>(self) -> u64 {
+        run::<T>(self.0)
+    }
+}
+
+/// Runs both allowances for operands packed into `arg` (see
+/// [`crate::packed_args`]) and packs the results as described in
+/// [`crate::packed_return`].
+#[inline(always)]
+fn run<T: StakeCalculator>(arg: u64) -> u64 {
+    let operands = PackedOperands::unpack(arg);
+    let (activation, deactivation) = operands.allowances::<T>();
+    PackedReturn::new(activation, deactivation, operands.conditions()).encode()
+}
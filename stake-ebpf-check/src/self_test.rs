@@ -0,0 +1,65 @@
+//! Known-answer vectors compiled into the program.
+//!
+//! Each expected value is `min(account * effective * rate / (cluster *
+//! 10_000), account)` computed exactly, or zero when any stake operand is
+//! zero. Running them on-chain checks the exact bytes the validator executes.
+//!
+//! [`VECTORS`] is generated by the build script from the shared vector files
+//! in `test-vectors/vectors/`. Their backend exclusions only matter to host
+//! tests: the on-chain count includes every vector, so a backend excluded
+//! from some still reports those it gets wrong.
+
+use crate::{BackendVisitor, StakeCalculator};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Vector {
+    pub rate_bps: u64,
+    pub account_portion: u64,
+    pub cluster_portion: u64,
+    pub cluster_effective: u64,
+    pub expected: u64,
+}
+
+const fn vector(
+    rate_bps: u64,
+    account_portion: u64,
+    cluster_portion: u64,
+    cluster_effective: u64,
+    expected: u64,
+) -> Vector {
+    Vector {
+        rate_bps,
+        account_portion,
+        cluster_portion,
+        cluster_effective,
+        expected,
+    }
+}
+
+include!(concat!(env!("OUT_DIR"), "/self_test_vectors.rs"));
+
+/// Number of [`VECTORS`] `T` gets wrong.
+pub fn count_mismatches<T: StakeCalculator>() -> u32 {
+    VECTORS
+        .iter()
+        .filter(|v| {
+            T::rate_limited_stake_change_bps(
+                v.rate_bps,
+                v.account_portion,
+                v.cluster_portion,
+                v.cluster_effective,
+            ) != v.expected
+        })
+        .count() as u32
+}
+
+/// Runs [`VECTORS`] on a [`crate::Backend`], yielding the mismatch count.
+pub struct SelfTest;
+
+impl BackendVisitor for SelfTest {
+    type Output = u32;
+
+    fn visit<T: StakeCalculator>(self) -> u32 {
+        count_mismatches::<T>()
+    }
+}
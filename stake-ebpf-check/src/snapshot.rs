@@ -0,0 +1,135 @@
+//! Downloaded stake-account snapshots, one per epoch, for the tools that
+//! check the math against what a cluster actually held.
+//!
+//! ```json
+//! {
+//!   "epoch": 601,
+//!   "new_rate_activation_epoch": 557,
+//!   "stake_history": "<base64 StakeHistory sysvar data>",
+//!   "accounts": [
+//!     { "pubkey": "<base58>", "data": "<base64 stake account data>",
+//!       "effective": 1000, "activating": 0, "deactivating": 0 }
+//!   ]
+//! }
+//! ```
+//!
+//! `data` is the account as `getProgramAccounts` returns it with `base64`
+//! encoding. The three amounts are optional, all or none: the status the
+//! cluster reported for the account in that epoch, e.g. `activeStake`,
+//! `activatingStake` and `deactivatingStake` from
+//! `solana stakes --output json`. `new_rate_activation_epoch` is the
+//! `reduce_stake_warmup_cooldown` activation, `null` or absent if it was
+//! not active.
+
+use std::path::Path;
+
+use data_encoding::BASE64;
+use serde_json::Value;
+
+use crate::delegation::StakeActivationStatus;
+use crate::stake_account::parse_stake_state;
+use crate::stake_history::StakeHistoryEntry;
+use crate::stake_history_sysvar::StakeHistorySysvar;
+use crate::state::{Delegation, StakeStateV2};
+use crate::Epoch;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SnapshotAccount {
+    /// Base58, as downloaded.
+    pub pubkey: String,
+    pub state: StakeStateV2,
+    /// What the cluster reported, if the snapshot has it.
+    pub observed: Option<StakeActivationStatus>,
+}
+
+impl SnapshotAccount {
+    pub fn delegation(&self) -> Option<&Delegation> {
+        self.state.stake().map(|stake| &stake.delegation)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Snapshot {
+    pub epoch: Epoch,
+    pub new_rate_activation_epoch: Option<Epoch>,
+    /// Newest first, as the sysvar.
+    pub history: Vec<(Epoch, StakeHistoryEntry)>,
+    /// In file order.
+    pub accounts: Vec<SnapshotAccount>,
+}
+
+fn field<'v>(value: &'v Value, name: &str) -> Result<&'v Value, String> {
+    value.get(name).ok_or_else(|| format!("missing `{name}`"))
+}
+
+fn u64_field(value: &Value, name: &str) -> Result<u64, String> {
+    field(value, name)?
+        .as_u64()
+        .ok_or_else(|| format!("`{name}` is not a u64"))
+}
+
+fn base64_field(value: &Value, name: &str) -> Result<Vec<u8>, String> {
+    let encoded = field(value, name)?
+        .as_str()
+        .ok_or_else(|| format!("`{name}` is not a string"))?;
+    BASE64
+        .decode(encoded.as_bytes())
+        .map_err(|e| format!("`{name}`: {e}"))
+}
+
+fn parse_account(value: &Value) -> Result<SnapshotAccount, String> {
+    let pubkey = field(value, "pubkey")?
+        .as_str()
+        .ok_or("`pubkey` is not a string")?
+        .to_owned();
+    let state = parse_stake_state(&base64_field(value, "data")?)
+        .map_err(|e| format!("{pubkey} is not a stake account: {e:?}"))?;
+    let observed = match ["effective", "activating", "deactivating"].map(|name| value.get(name)) {
+        [None, None, None] => None,
+        _ => Some(StakeActivationStatus {
+            effective: u64_field(value, "effective")?,
+            activating: u64_field(value, "activating")?,
+            deactivating: u64_field(value, "deactivating")?,
+        }),
+    };
+    Ok(SnapshotAccount {
+        pubkey,
+        state,
+        observed,
+    })
+}
+
+impl Snapshot {
+    pub fn from_json(text: &str) -> Result<Self, String> {
+        let value: Value = serde_json::from_str(text).map_err(|e| e.to_string())?;
+
+        let sysvar_data = base64_field(&value, "stake_history")?;
+        let history = StakeHistorySysvar::from_bytes(&sysvar_data)
+            .map_err(|e| format!("StakeHistory sysvar: {e:?}"))?
+            .iter()
+            .collect();
+        let new_rate_activation_epoch = match value.get("new_rate_activation_epoch") {
+            None | Some(Value::Null) => None,
+            Some(_) => Some(u64_field(&value, "new_rate_activation_epoch")?),
+        };
+        let accounts = field(&value, "accounts")?
+            .as_array()
+            .ok_or("`accounts` is not an array")?
+            .iter()
+            .map(parse_account)
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            epoch: u64_field(&value, "epoch")?,
+            new_rate_activation_epoch,
+            history,
+            accounts,
+        })
+    }
+
+    /// Reads and parses `path`; errors name the file.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+        Self::from_json(&text).map_err(|e| format!("{}: {e}", path.display()))
+    }
+}
@@ -0,0 +1,58 @@
+//! Integer-only lamport <-> SOL conversions.
+
+pub const LAMPORTS_PER_SOL: u64 = 1_000_000_000;
+const SOL_DECIMALS: usize = 9;
+
+/// `(whole_sol, fractional_lamports)`.
+pub const fn lamports_to_sol_parts(lamports: u64) -> (u64, u64) {
+    (lamports / LAMPORTS_PER_SOL, lamports % LAMPORTS_PER_SOL)
+}
+
+pub const fn sol_parts_to_lamports(whole_sol: u64, fractional_lamports: u64) -> Option<u64> {
+    if fractional_lamports >= LAMPORTS_PER_SOL {
+        return None;
+    }
+    match whole_sol.checked_mul(LAMPORTS_PER_SOL) {
+        Some(lamports) => lamports.checked_add(fractional_lamports),
+        None => None,
+    }
+}
+
+/// Writes `lamports` as a decimal SOL amount (`"1.5"`, `"0.000000001"`,
+/// `"42"`) into `buf`, returning the number of bytes written, or `None` if
+/// `buf` is too small. Trailing fractional zeros are dropped.
+pub fn format_sol(lamports: u64, buf: &mut [u8]) -> Option<usize> {
+    let (whole, mut frac) = lamports_to_sol_parts(lamports);
+
+    let mut digits = [0u8; 20];
+    let mut n = 0;
+    let mut rest = whole;
+    loop {
+        digits[n] = b'0' + (rest % 10) as u8;
+        n += 1;
+        rest /= 10;
+        if rest == 0 {
+            break;
+        }
+    }
+
+    let mut frac_digits = SOL_DECIMALS;
+    while frac_digits > 0 && frac % 10 == 0 {
+        frac /= 10;
+        frac_digits -= 1;
+    }
+
+    let len = n + if frac_digits > 0 { 1 + frac_digits } else { 0 };
+    let out = buf.get_mut(..len)?;
+    for (slot, &digit) in out.iter_mut().zip(digits[..n].iter().rev()) {
+        *slot = digit;
+    }
+    if frac_digits > 0 {
+        out[n] = b'.';
+        for slot in out[n + 1..].iter_mut().rev() {
+            *slot = b'0' + (frac % 10) as u8;
+            frac /= 10;
+        }
+    }
+    Some(len)
+}
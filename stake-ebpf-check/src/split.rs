@@ -0,0 +1,120 @@
+//! Split validation against the minimum delegation.
+//!
+//! The source account holds `delegation.stake + rent_exempt_reserve` lamports
+//! and the new account needs the same reserve, so `split_lamports` must fund
+//! both the destination's reserve and its delegation.
+
+use crate::sol::LAMPORTS_PER_SOL;
+use crate::state::Delegation;
+
+/// The minimum delegation while `stake_raise_minimum_delegation_to_1_sol`
+/// is inactive, as it is on mainnet.
+pub const MINIMUM_DELEGATION: u64 = 1;
+
+/// One SOL, the minimum delegation once that feature is active.
+pub const RAISED_MINIMUM_DELEGATION: u64 = LAMPORTS_PER_SOL;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+pub enum SplitError {
+    ZeroAmount,
+    InsufficientFunds,
+    SourceReserveNotRetained,
+    SourceBelowMinimumDelegation,
+    DestinationReserveNotFunded,
+    DestinationBelowMinimumDelegation,
+}
+
+impl SplitError {
+    /// Non-zero wire code, in declaration order from 1.
+    pub fn code(self) -> u8 {
+        self as u8 + 1
+    }
+
+    pub fn from_code(code: u8) -> Option<Self> {
+        match code {
+            1 => Some(Self::ZeroAmount),
+            2 => Some(Self::InsufficientFunds),
+            3 => Some(Self::SourceReserveNotRetained),
+            4 => Some(Self::SourceBelowMinimumDelegation),
+            5 => Some(Self::DestinationReserveNotFunded),
+            6 => Some(Self::DestinationBelowMinimumDelegation),
+            _ => None,
+        }
+    }
+}
+
+/// [`validate_split_with_minimum`] at the [`MINIMUM_DELEGATION`] in force on
+/// mainnet.
+pub fn validate_split(
+    source_delegation: &Delegation,
+    split_lamports: u64,
+    rent_exempt_reserve: u64,
+) -> Result<(), SplitError> {
+    validate_split_with_minimum(
+        source_delegation,
+        split_lamports,
+        rent_exempt_reserve,
+        MINIMUM_DELEGATION,
+    )
+}
+
+pub fn validate_split_with_minimum(
+    source_delegation: &Delegation,
+    split_lamports: u64,
+    rent_exempt_reserve: u64,
+    minimum_delegation: u64,
+) -> Result<(), SplitError> {
+    if split_lamports == 0 {
+        return Err(SplitError::ZeroAmount);
+    }
+
+    let source_lamports = source_delegation
+        .stake
+        .checked_add(rent_exempt_reserve)
+        .ok_or(SplitError::InsufficientFunds)?;
+    let remaining = source_lamports
+        .checked_sub(split_lamports)
+        .ok_or(SplitError::InsufficientFunds)?;
+
+    // Splitting everything empties the source, which is then just closed.
+    if remaining != 0 {
+        let remaining_stake = remaining
+            .checked_sub(rent_exempt_reserve)
+            .ok_or(SplitError::SourceReserveNotRetained)?;
+        if remaining_stake < minimum_delegation {
+            return Err(SplitError::SourceBelowMinimumDelegation);
+        }
+    }
+
+    let split_stake = split_lamports
+        .checked_sub(rent_exempt_reserve)
+        .ok_or(SplitError::DestinationReserveNotFunded)?;
+    if split_stake < minimum_delegation {
+        return Err(SplitError::DestinationBelowMinimumDelegation);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_minimum_is_the_active_one_lamport() {
+        let delegation = Delegation {
+            stake: 10,
+            ..Delegation::default()
+        };
+        assert_eq!(validate_split(&delegation, 5, 0), Ok(()));
+        assert_eq!(
+            validate_split_with_minimum(&delegation, 5, 0, RAISED_MINIMUM_DELEGATION),
+            Err(SplitError::SourceBelowMinimumDelegation)
+        );
+    }
+}
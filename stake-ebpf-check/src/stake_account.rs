@@ -0,0 +1,150 @@
+//! Zero-allocation reader and writer for the bincode stake account layout.
+//!
+//! ```text
+//! 0..4     u32 variant tag
+//! 4..124   Meta       (reserve, staker, withdrawer, lockup)
+//! 124..188 Delegation (voter, stake, activation, deactivation, rate)
+//! 188..196 credits_observed
+//! 196      StakeFlags
+//! ```
+
+use crate::stake_flags::StakeFlags;
+use crate::state::{Authorized, Delegation, Lockup, Meta, Stake, StakeStateV2};
+
+pub const STAKE_STATE_V2_SIZE: usize = 200;
+
+const META_OFFSET: usize = 4;
+const DELEGATION_OFFSET: usize = 124;
+const CREDITS_OBSERVED_OFFSET: usize = 188;
+const STAKE_FLAGS_OFFSET: usize = 196;
+
+const TAG_UNINITIALIZED: u32 = 0;
+const TAG_INITIALIZED: u32 = 1;
+const TAG_STAKE: u32 = 2;
+const TAG_REWARDS_POOL: u32 = 3;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParseError {
+    TooShort,
+    InvalidTag(u32),
+}
+
+fn read<const N: usize>(data: &[u8], offset: usize) -> [u8; N] {
+    let mut out = [0u8; N];
+    out.copy_from_slice(&data[offset..offset + N]);
+    out
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(read(data, offset))
+}
+
+fn read_meta(data: &[u8]) -> Meta {
+    let o = META_OFFSET;
+    Meta {
+        rent_exempt_reserve: read_u64(data, o),
+        authorized: Authorized {
+            staker: read::<32>(data, o + 8),
+            withdrawer: read::<32>(data, o + 40),
+        },
+        lockup: Lockup {
+            unix_timestamp: i64::from_le_bytes(read(data, o + 72)),
+            epoch: read_u64(data, o + 80),
+            custodian: read::<32>(data, o + 88),
+        },
+    }
+}
+
+fn read_stake(data: &[u8]) -> Stake {
+    let o = DELEGATION_OFFSET;
+    Stake {
+        delegation: Delegation {
+            voter_pubkey: read(data, o),
+            stake: read_u64(data, o + 32),
+            activation_epoch: read_u64(data, o + 40),
+            deactivation_epoch: read_u64(data, o + 48),
+            deprecated_warmup_cooldown_rate: read(data, o + 56),
+        },
+        credits_observed: read_u64(data, CREDITS_OBSERVED_OFFSET),
+    }
+}
+
+/// Parses a stake account's data. The buffer must be at least
+/// [`STAKE_STATE_V2_SIZE`] bytes; anything past that is ignored.
+pub fn parse_stake_state(data: &[u8]) -> Result<StakeStateV2, ParseError> {
+    if data.len() < STAKE_STATE_V2_SIZE {
+        return Err(ParseError::TooShort);
+    }
+
+    match u32::from_le_bytes(read(data, 0)) {
+        TAG_UNINITIALIZED => Ok(StakeStateV2::Uninitialized),
+        TAG_INITIALIZED => Ok(StakeStateV2::Initialized(read_meta(data))),
+        TAG_STAKE => Ok(StakeStateV2::Stake(
+            read_meta(data),
+            read_stake(data),
+            StakeFlags::from_bits(data[STAKE_FLAGS_OFFSET]),
+        )),
+        TAG_REWARDS_POOL => Ok(StakeStateV2::RewardsPool),
+        tag => Err(ParseError::InvalidTag(tag)),
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WriteError {
+    TooShort,
+}
+
+fn write(data: &mut [u8], offset: usize, bytes: &[u8]) {
+    data[offset..offset + bytes.len()].copy_from_slice(bytes);
+}
+
+fn write_meta(data: &mut [u8], meta: &Meta) {
+    let o = META_OFFSET;
+    write(data, o, &meta.rent_exempt_reserve.to_le_bytes());
+    write(data, o + 8, &meta.authorized.staker);
+    write(data, o + 40, &meta.authorized.withdrawer);
+    write(data, o + 72, &meta.lockup.unix_timestamp.to_le_bytes());
+    write(data, o + 80, &meta.lockup.epoch.to_le_bytes());
+    write(data, o + 88, &meta.lockup.custodian);
+}
+
+fn write_stake(data: &mut [u8], stake: &Stake) {
+    let o = DELEGATION_OFFSET;
+    let delegation = &stake.delegation;
+    write(data, o, &delegation.voter_pubkey);
+    write(data, o + 32, &delegation.stake.to_le_bytes());
+    write(data, o + 40, &delegation.activation_epoch.to_le_bytes());
+    write(data, o + 48, &delegation.deactivation_epoch.to_le_bytes());
+    write(data, o + 56, &delegation.deprecated_warmup_cooldown_rate);
+    write(
+        data,
+        CREDITS_OBSERVED_OFFSET,
+        &stake.credits_observed.to_le_bytes(),
+    );
+}
+
+/// Serializes `state` into `data` at the upstream offsets.
+///
+/// Like bincode, only the bytes belonging to the variant are written; the rest
+/// of the buffer is left as it was.
+pub fn write_stake_state(state: &StakeStateV2, data: &mut [u8]) -> Result<(), WriteError> {
+    if data.len() < STAKE_STATE_V2_SIZE {
+        return Err(WriteError::TooShort);
+    }
+
+    match state {
+        StakeStateV2::Uninitialized => write(data, 0, &TAG_UNINITIALIZED.to_le_bytes()),
+        StakeStateV2::Initialized(meta) => {
+            write(data, 0, &TAG_INITIALIZED.to_le_bytes());
+            write_meta(data, meta);
+        }
+        StakeStateV2::Stake(meta, stake, flags) => {
+            write(data, 0, &TAG_STAKE.to_le_bytes());
+            write_meta(data, meta);
+            write_stake(data, stake);
+            data[STAKE_FLAGS_OFFSET] = flags.bits();
+        }
+        StakeStateV2::RewardsPool => write(data, 0, &TAG_REWARDS_POOL.to_le_bytes()),
+    }
+    Ok(())
+}
@@ -0,0 +1,147 @@
+//! Zero-copy view over the raw StakeHistory sysvar bytes.
+//!
+//! ```text
+//! 0..8        u64 entry count
+//! 8 + 32 * i  (epoch, effective, activating, deactivating), newest first
+//! ```
+//!
+//! The sysvar holds consecutive epochs, so an epoch's position follows from
+//! the newest epoch in O(1); the slot is checked and falls back to a binary
+//! search if the history has gaps.
+
+use crate::stake_history::{StakeHistoryEntry, StakeHistoryGetEntry};
+use crate::state::Pubkey;
+use crate::Epoch;
+
+/// `SysvarStakeHistory1111111111111111111111111`
+pub const ID: Pubkey = [
+    6, 167, 213, 23, 25, 53, 132, 208, 254, 237, 155, 179, 67, 29, 19, 32, 107, 229, 68, 40, 27,
+    87, 184, 86, 108, 197, 55, 95, 244, 0, 0, 0,
+];
+
+const LEN_SIZE: usize = 8;
+const ENTRY_SIZE: usize = 32;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SysvarError {
+    TooShort,
+}
+
+#[derive(Clone, Copy)]
+pub struct StakeHistorySysvar<'a> {
+    entries: &'a [u8],
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&data[offset..offset + 8]);
+    u64::from_le_bytes(bytes)
+}
+
+impl<'a> StakeHistorySysvar<'a> {
+    /// Borrows `data`, which may be longer than the encoded entries.
+    pub fn from_bytes(data: &'a [u8]) -> Result<Self, SysvarError> {
+        if data.len() < LEN_SIZE {
+            return Err(SysvarError::TooShort);
+        }
+        let len = read_u64(data, 0) as usize;
+        let end = len
+            .checked_mul(ENTRY_SIZE)
+            .and_then(|size| size.checked_add(LEN_SIZE))
+            .ok_or(SysvarError::TooShort)?;
+        let entries = data.get(LEN_SIZE..end).ok_or(SysvarError::TooShort)?;
+        Ok(Self { entries })
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len() / ENTRY_SIZE
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn epoch_at(&self, index: usize) -> Epoch {
+        read_u64(self.entries, index * ENTRY_SIZE)
+    }
+
+    pub fn get(&self, index: usize) -> Option<(Epoch, StakeHistoryEntry)> {
+        if index >= self.len() {
+            return None;
+        }
+        let o = index * ENTRY_SIZE;
+        Some((
+            read_u64(self.entries, o),
+            StakeHistoryEntry {
+                effective: read_u64(self.entries, o + 8),
+                activating: read_u64(self.entries, o + 16),
+                deactivating: read_u64(self.entries, o + 24),
+            },
+        ))
+    }
+
+    pub fn newest_epoch(&self) -> Option<Epoch> {
+        (!self.is_empty()).then(|| self.epoch_at(0))
+    }
+
+    pub fn find_epoch(&self, epoch: Epoch) -> Option<StakeHistoryEntry> {
+        let newest = self.newest_epoch()?;
+        let guess = newest.checked_sub(epoch)? as usize;
+        if guess < self.len() && self.epoch_at(guess) == epoch {
+            return self.get(guess).map(|(_, entry)| entry);
+        }
+
+        let (mut lo, mut hi) = (0, self.len());
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            match self.epoch_at(mid) {
+                e if e == epoch => return self.get(mid).map(|(_, entry)| entry),
+                e if e > epoch => lo = mid + 1,
+                _ => hi = mid,
+            }
+        }
+        None
+    }
+
+    pub fn iter(&self) -> StakeHistorySysvarIter<'a> {
+        StakeHistorySysvarIter {
+            sysvar: *self,
+            index: 0,
+        }
+    }
+}
+
+impl StakeHistoryGetEntry for StakeHistorySysvar<'_> {
+    fn get_entry(&self, epoch: Epoch) -> Option<StakeHistoryEntry> {
+        self.find_epoch(epoch)
+    }
+}
+
+pub struct StakeHistorySysvarIter<'a> {
+    sysvar: StakeHistorySysvar<'a>,
+    index: usize,
+}
+
+impl Iterator for StakeHistorySysvarIter<'_> {
+    type Item = (Epoch, StakeHistoryEntry);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.sysvar.get(self.index)?;
+        self.index += 1;
+        Some(item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let left = self.sysvar.len() - self.index;
+        (left, Some(left))
+    }
+}
+
+impl<'a> IntoIterator for StakeHistorySysvar<'a> {
+    type Item = (Epoch, StakeHistoryEntry);
+    type IntoIter = StakeHistorySysvarIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
@@ -0,0 +1,61 @@
+//! Pool token exchange rate math.
+//!
+//! Deposits floor the pool tokens minted, withdrawals ceil the lamports owed
+//! for the burned tokens. An empty pool exchanges 1:1.
+
+use crate::streaming::{mul_div, Rounding};
+
+/// Pool tokens minted for depositing `lamports`.
+pub fn lamports_to_pool_tokens(
+    lamports: u64,
+    total_lamports: u64,
+    total_tokens: u64,
+) -> Option<u64> {
+    if total_lamports == 0 || total_tokens == 0 {
+        return Some(lamports);
+    }
+    mul_div(lamports, total_tokens, total_lamports, Rounding::Down)
+}
+
+/// Lamports owed for burning `pool_tokens`.
+pub fn pool_tokens_to_lamports(
+    pool_tokens: u64,
+    total_lamports: u64,
+    total_tokens: u64,
+) -> Option<u64> {
+    if total_lamports == 0 || total_tokens == 0 {
+        return Some(pool_tokens);
+    }
+    mul_div(pool_tokens, total_lamports, total_tokens, Rounding::Up)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deposits_floor_and_withdrawals_ceil() {
+        // 3 lamports for every 2 tokens.
+        assert_eq!(lamports_to_pool_tokens(5, 3_000, 2_000), Some(3));
+        assert_eq!(pool_tokens_to_lamports(3, 3_000, 2_000), Some(5));
+        assert_eq!(lamports_to_pool_tokens(1, 3_000, 2_000), Some(0));
+        assert_eq!(pool_tokens_to_lamports(1, 3_000, 2_000), Some(2));
+        // Exact exchanges round neither way.
+        assert_eq!(lamports_to_pool_tokens(6, 3_000, 2_000), Some(4));
+        assert_eq!(pool_tokens_to_lamports(4, 3_000, 2_000), Some(6));
+    }
+
+    #[test]
+    fn empty_pool_exchanges_one_to_one() {
+        for (total_lamports, total_tokens) in [(0, 0), (0, 2_000), (3_000, 0)] {
+            assert_eq!(
+                lamports_to_pool_tokens(7, total_lamports, total_tokens),
+                Some(7)
+            );
+            assert_eq!(
+                pool_tokens_to_lamports(7, total_lamports, total_tokens),
+                Some(7)
+            );
+        }
+    }
+}
@@ -0,0 +1,23 @@
+//! The native Stake program, as far as this crate calls into it.
+
+use crate::state::Pubkey;
+
+/// `Stake11111111111111111111111111111111111111`.
+pub const ID: Pubkey = [
+    6, 161, 216, 23, 145, 55, 84, 42, 152, 52, 55, 189, 254, 42, 122, 178, 85, 127, 83, 92, 138,
+    120, 114, 43, 104, 164, 157, 192, 0, 0, 0, 0,
+];
+
+/// `StakeInstruction::GetMinimumDelegation`, bincode-encoded as a `u32`
+/// variant index. Takes no accounts.
+pub const GET_MINIMUM_DELEGATION: u32 = 13;
+
+pub fn get_minimum_delegation_data() -> [u8; 4] {
+    GET_MINIMUM_DELEGATION.to_le_bytes()
+}
+
+/// The little-endian `u64` `GetMinimumDelegation` sets as return data.
+pub fn parse_minimum_delegation(return_data: &[u8]) -> Option<u64> {
+    let bytes: [u8; 8] = return_data.try_into().ok()?;
+    Some(u64::from_le_bytes(bytes))
+}
@@ -0,0 +1,382 @@
+//! Stake account state, modelled on the runtime's `StakeStateV2`.
+//!
+//! Transitions take the delegation's current effective stake from the caller,
+//! which keeps this module independent of how stake history is sourced.
+
+use crate::stake_flags::StakeFlags;
+use crate::Epoch;
+
+pub type Pubkey = [u8; 32];
+pub type UnixTimestamp = i64;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+pub struct Clock {
+    pub epoch: Epoch,
+    pub unix_timestamp: UnixTimestamp,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+pub struct Authorized {
+    pub staker: Pubkey,
+    pub withdrawer: Pubkey,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+pub struct Lockup {
+    pub unix_timestamp: UnixTimestamp,
+    pub epoch: Epoch,
+    pub custodian: Pubkey,
+}
+
+impl Lockup {
+    pub fn is_in_force(&self, clock: &Clock, custodian: Option<&Pubkey>) -> bool {
+        if custodian == Some(&self.custodian) {
+            return false;
+        }
+        self.unix_timestamp > clock.unix_timestamp || self.epoch > clock.epoch
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+pub struct Meta {
+    pub rent_exempt_reserve: u64,
+    pub authorized: Authorized,
+    pub lockup: Lockup,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+pub struct Delegation {
+    pub voter_pubkey: Pubkey,
+    pub stake: u64,
+    pub activation_epoch: Epoch,
+    pub deactivation_epoch: Epoch,
+    /// The deprecated `f64` warmup/cooldown rate, kept as raw bytes so
+    /// accounts round-trip without touching floats.
+    pub deprecated_warmup_cooldown_rate: [u8; 8],
+}
+
+impl Default for Delegation {
+    fn default() -> Self {
+        Self {
+            voter_pubkey: Pubkey::default(),
+            stake: 0,
+            activation_epoch: 0,
+            deactivation_epoch: u64::MAX,
+            // 0.25f64
+            deprecated_warmup_cooldown_rate: 0x3fd0_0000_0000_0000u64.to_le_bytes(),
+        }
+    }
+}
+
+impl Delegation {
+    pub fn new(voter_pubkey: &Pubkey, stake: u64, activation_epoch: Epoch) -> Self {
+        Self {
+            voter_pubkey: *voter_pubkey,
+            stake,
+            activation_epoch,
+            ..Self::default()
+        }
+    }
+
+    /// Genesis delegations carry `activation_epoch == u64::MAX`.
+    pub fn is_bootstrap(&self) -> bool {
+        self.activation_epoch == u64::MAX
+    }
+
+    /// `deactivation_epoch == u64::MAX` means the stake was never deactivated.
+    pub fn is_deactivated(&self) -> bool {
+        self.deactivation_epoch != u64::MAX
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+pub struct Stake {
+    pub delegation: Delegation,
+    pub credits_observed: u64,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StakeStateV2 {
+    #[default]
+    Uninitialized,
+    Initialized(Meta),
+    Stake(Meta, Stake, StakeFlags),
+    RewardsPool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StakeError {
+    InvalidAccountData,
+    TooSoonToRedelegate,
+    AlreadyDeactivated,
+    InsufficientFunds,
+    LockupInForce,
+    RedelegatedStakeMustFullyActivateBeforeDeactivationIsPermitted,
+}
+
+impl StakeStateV2 {
+    pub fn meta(&self) -> Option<&Meta> {
+        match self {
+            Self::Initialized(meta) | Self::Stake(meta, ..) => Some(meta),
+            _ => None,
+        }
+    }
+
+    pub fn stake(&self) -> Option<&Stake> {
+        match self {
+            Self::Stake(_, stake, _) => Some(stake),
+            _ => None,
+        }
+    }
+
+    pub fn initialize(&mut self, meta: Meta) -> Result<(), StakeError> {
+        match self {
+            Self::Uninitialized => {
+                *self = Self::Initialized(meta);
+                Ok(())
+            }
+            _ => Err(StakeError::InvalidAccountData),
+        }
+    }
+
+    /// Delegates `stake` lamports to `voter_pubkey`. `effective` is the
+    /// existing delegation's effective stake at `clock.epoch`, if any.
+    pub fn delegate(
+        &mut self,
+        voter_pubkey: &Pubkey,
+        stake: u64,
+        credits_observed: u64,
+        clock: &Clock,
+        effective: u64,
+    ) -> Result<(), StakeError> {
+        match self {
+            Self::Initialized(meta) => {
+                *self = Self::Stake(
+                    *meta,
+                    Stake {
+                        delegation: Delegation::new(voter_pubkey, stake, clock.epoch),
+                        credits_observed,
+                    },
+                    StakeFlags::empty(),
+                );
+                Ok(())
+            }
+            Self::Stake(_, existing, _) => {
+                let delegation = &mut existing.delegation;
+                if effective != 0 {
+                    // Re-delegating to the same voter in the deactivation
+                    // epoch rescinds the deactivation.
+                    if delegation.voter_pubkey == *voter_pubkey
+                        && clock.epoch == delegation.deactivation_epoch
+                    {
+                        delegation.deactivation_epoch = u64::MAX;
+                        return Ok(());
+                    }
+                    return Err(StakeError::TooSoonToRedelegate);
+                }
+                delegation.stake = stake;
+                delegation.activation_epoch = clock.epoch;
+                delegation.deactivation_epoch = u64::MAX;
+                delegation.voter_pubkey = *voter_pubkey;
+                existing.credits_observed = credits_observed;
+                Ok(())
+            }
+            _ => Err(StakeError::InvalidAccountData),
+        }
+    }
+
+    /// Starts cooling down the delegation. `effective` is the delegation's
+    /// effective stake at `clock.epoch`.
+    pub fn deactivate(&mut self, clock: &Clock, effective: u64) -> Result<(), StakeError> {
+        let Self::Stake(_, stake, flags) = self else {
+            return Err(StakeError::InvalidAccountData);
+        };
+
+        if stake.delegation.is_deactivated() {
+            return Err(StakeError::AlreadyDeactivated);
+        }
+        let must_fully_activate = StakeFlags::MUST_FULLY_ACTIVATE_BEFORE_DEACTIVATION_IS_PERMITTED;
+        if flags.contains(must_fully_activate) && effective != stake.delegation.stake {
+            return Err(StakeError::RedelegatedStakeMustFullyActivateBeforeDeactivationIsPermitted);
+        }
+
+        // Nothing changes on an error, as upstream never stores a failed
+        // transition.
+        flags.remove(must_fully_activate);
+        stake.delegation.deactivation_epoch = clock.epoch;
+        Ok(())
+    }
+
+    /// Checks a withdrawal of `lamports` from an account holding
+    /// `account_lamports` and resets the state if the account is emptied.
+    /// `effective` is the delegation's effective stake at `clock.epoch`.
+    pub fn withdraw(
+        &mut self,
+        lamports: u64,
+        account_lamports: u64,
+        clock: &Clock,
+        custodian: Option<&Pubkey>,
+        effective: u64,
+    ) -> Result<(), StakeError> {
+        let (lockup, reserve, is_staked) = match self {
+            Self::Stake(meta, stake, _) => {
+                // Stake is locked until its deactivation epoch has passed,
+                // and then only the still-effective part.
+                let staked = if clock.epoch >= stake.delegation.deactivation_epoch {
+                    effective
+                } else {
+                    stake.delegation.stake
+                };
+                let reserve = staked
+                    .checked_add(meta.rent_exempt_reserve)
+                    .ok_or(StakeError::InsufficientFunds)?;
+                (meta.lockup, reserve, staked != 0)
+            }
+            Self::Initialized(meta) => (meta.lockup, meta.rent_exempt_reserve, false),
+            Self::Uninitialized => (Lockup::default(), 0, false),
+            Self::RewardsPool => return Err(StakeError::InvalidAccountData),
+        };
+
+        if lockup.is_in_force(clock, custodian) {
+            return Err(StakeError::LockupInForce);
+        }
+
+        if lamports == account_lamports {
+            if is_staked {
+                return Err(StakeError::InsufficientFunds);
+            }
+            *self = Self::Uninitialized;
+            return Ok(());
+        }
+
+        let withdraw_total = lamports
+            .checked_add(reserve)
+            .ok_or(StakeError::InsufficientFunds)?;
+        if withdraw_total > account_lamports {
+            return Err(StakeError::InsufficientFunds);
+        }
+        Ok(())
+    }
+}
+
+/// Tagged with a `u32`, as the runtime's own borsh impl is, so the encoding
+/// is the account's bincode layout less its trailing padding.
+#[cfg(feature = "borsh")]
+impl borsh::BorshSerialize for StakeStateV2 {
+    fn serialize<W: borsh::io::Write>(&self, writer: &mut W) -> borsh::io::Result<()> {
+        match self {
+            Self::Uninitialized => 0u32.serialize(writer),
+            Self::Initialized(meta) => {
+                1u32.serialize(writer)?;
+                meta.serialize(writer)
+            }
+            Self::Stake(meta, stake, flags) => {
+                2u32.serialize(writer)?;
+                meta.serialize(writer)?;
+                stake.serialize(writer)?;
+                flags.serialize(writer)
+            }
+            Self::RewardsPool => 3u32.serialize(writer),
+        }
+    }
+}
+
+#[cfg(feature = "borsh")]
+impl borsh::BorshDeserialize for StakeStateV2 {
+    fn deserialize_reader<R: borsh::io::Read>(reader: &mut R) -> borsh::io::Result<Self> {
+        match u32::deserialize_reader(reader)? {
+            0 => Ok(Self::Uninitialized),
+            1 => Ok(Self::Initialized(Meta::deserialize_reader(reader)?)),
+            2 => Ok(Self::Stake(
+                Meta::deserialize_reader(reader)?,
+                Stake::deserialize_reader(reader)?,
+                StakeFlags::deserialize_reader(reader)?,
+            )),
+            3 => Ok(Self::RewardsPool),
+            _ => Err(borsh::io::Error::new(
+                borsh::io::ErrorKind::InvalidData,
+                "invalid StakeStateV2 tag",
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn redelegated(deactivation_epoch: Epoch) -> StakeStateV2 {
+        let stake = Stake {
+            delegation: Delegation {
+                deactivation_epoch,
+                ..Delegation::new(&[1; 32], 1_000, 10)
+            },
+            credits_observed: 0,
+        };
+        StakeStateV2::Stake(
+            Meta::default(),
+            stake,
+            StakeFlags::MUST_FULLY_ACTIVATE_BEFORE_DEACTIVATION_IS_PERMITTED,
+        )
+    }
+
+    #[test]
+    fn failed_deactivation_leaves_the_state_alone() {
+        let clock = Clock {
+            epoch: 12,
+            ..Clock::default()
+        };
+        for (mut state, effective, error) in [
+            (redelegated(11), 1_000, StakeError::AlreadyDeactivated),
+            (
+                redelegated(u64::MAX),
+                400,
+                StakeError::RedelegatedStakeMustFullyActivateBeforeDeactivationIsPermitted,
+            ),
+        ] {
+            let before = state;
+            assert_eq!(state.deactivate(&clock, effective), Err(error));
+            assert_eq!(state, before);
+        }
+    }
+
+    #[test]
+    fn deactivation_clears_the_redelegation_flag() {
+        let mut state = redelegated(u64::MAX);
+        let clock = Clock {
+            epoch: 12,
+            ..Clock::default()
+        };
+        assert_eq!(state.deactivate(&clock, 1_000), Ok(()));
+        let StakeStateV2::Stake(_, stake, flags) = state else {
+            unreachable!()
+        };
+        assert_eq!(stake.delegation.deactivation_epoch, 12);
+        assert_eq!(flags, StakeFlags::empty());
+    }
+}
@@ -0,0 +1,213 @@
+//! 64-bit-only wide arithmetic.
+//!
+//! Everything here works on pairs of `u64` limbs so the compiled program never
+//! calls the `__multi3` / `__udivti3` builtins that `u128` lowers to on eBPF.
+//!
+//! SBF v2's PQR instructions (`target_feature = "pqr"`, known only to the
+//! Solana toolchain's `sbf` targets) include a 64x64 high multiply, so there
+//! [`mul_wide`] and the 64-bit-quotient case of [`div_wide`] use native
+//! `u128` arithmetic instead. Building the same backend for v1 and v2 shows
+//! what the limb code costs against the hardware path.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rounding {
+    Down,
+    Up,
+}
+
+/// Full 64x64 -> 128 bit product, returned as `(hi, lo)`.
+#[cfg(target_feature = "pqr")]
+#[inline]
+pub fn mul_wide(a: u64, b: u64) -> (u64, u64) {
+    // Lowers to `mul64` + `uhmul64`, no libcall.
+    let product = a as u128 * b as u128;
+    ((product >> 64) as u64, product as u64)
+}
+
+/// Full 64x64 -> 128 bit product, returned as `(hi, lo)`.
+#[cfg(not(target_feature = "pqr"))]
+#[inline]
+pub fn mul_wide(a: u64, b: u64) -> (u64, u64) {
+    let (a_hi, a_lo) = (a >> 32, a & 0xffff_ffff);
+    let (b_hi, b_lo) = (b >> 32, b & 0xffff_ffff);
+
+    let ll = a_lo * b_lo;
+    let lh = a_lo * b_hi;
+    let hl = a_hi * b_lo;
+    let hh = a_hi * b_hi;
+
+    let mid = (ll >> 32) + (lh & 0xffff_ffff) + (hl & 0xffff_ffff);
+    let lo = (ll & 0xffff_ffff) | (mid << 32);
+    let hi = hh + (lh >> 32) + (hl >> 32) + (mid >> 32);
+    (hi, lo)
+}
+
+/// Divides the 128-bit value `(hi, lo)` by `d`, one bit per step.
+///
+/// Returns `(quotient, remainder)`, or `None` when `d == 0` or the quotient
+/// does not fit in 64 bits (`hi >= d`).
+#[inline]
+pub fn div_wide(hi: u64, lo: u64, d: u64) -> Option<(u64, u64)> {
+    if d == 0 || hi >= d {
+        return None;
+    }
+    // A 64-bit dividend is a single `udiv64` / `urem64` pair.
+    if cfg!(target_feature = "pqr") && hi == 0 {
+        return Some((lo / d, lo % d));
+    }
+
+    let mut rem = hi;
+    let mut q = 0u64;
+    for i in (0..64).rev() {
+        let carry = rem >> 63;
+        rem = (rem << 1) | ((lo >> i) & 1);
+        q <<= 1;
+        if carry != 0 || rem >= d {
+            rem = rem.wrapping_sub(d);
+            q |= 1;
+        }
+    }
+    Some((q, rem))
+}
+
+/// `a * b / d` with the rounding direction chosen by the caller.
+///
+/// `None` on division by zero or when the result does not fit in a `u64`.
+#[inline]
+pub fn mul_div(a: u64, b: u64, d: u64, rounding: Rounding) -> Option<u64> {
+    let (hi, lo) = mul_wide(a, b);
+    let (q, rem) = div_wide(hi, lo, d)?;
+    match rounding {
+        Rounding::Up if rem != 0 => q.checked_add(1),
+        _ => Some(q),
+    }
+}
+
+/// Growth factor `(1 + rate_bps / 10_000) ^ periods`, in basis points.
+///
+/// Square-and-multiply with every intermediate floored to whole basis points.
+/// `None` once the factor no longer fits in a `u64`.
+pub fn compound_bps(rate_bps: u64, periods: u64) -> Option<u64> {
+    let unit = crate::BASIS_POINTS_PER_UNIT;
+    let mut base = unit.checked_add(rate_bps)?;
+    let mut acc = unit;
+    let mut n = periods;
+    while n > 0 {
+        if n & 1 == 1 {
+            acc = mul_div(acc, base, unit, Rounding::Down)?;
+        }
+        n >>= 1;
+        if n > 0 {
+            base = mul_div(base, base, unit, Rounding::Down)?;
+        }
+    }
+    Some(acc)
+}
+
+/// Sum of two 128-bit `(hi, lo)` values, `None` on overflow.
+#[inline]
+pub fn add_wide(a: (u64, u64), b: (u64, u64)) -> Option<(u64, u64)> {
+    let (lo, carry) = a.1.overflowing_add(b.1);
+    let hi = a.0.checked_add(b.0)?.checked_add(carry as u64)?;
+    Some((hi, lo))
+}
+
+/// A 192-bit value as `[hi, mid, lo]`.
+pub type U192 = [u64; 3];
+
+/// 128x64 -> 192 bit product.
+#[inline]
+pub fn mul_128_by_64((hi, lo): (u64, u64), m: u64) -> U192 {
+    let (lo_hi, lo_lo) = mul_wide(lo, m);
+    let (hi_hi, hi_lo) = mul_wide(hi, m);
+    let (mid, carry) = hi_lo.overflowing_add(lo_hi);
+    [hi_hi + carry as u64, mid, lo_lo]
+}
+
+/// Sum of a 192-bit and a 128-bit value, `None` on overflow.
+#[inline]
+pub fn add_192_128(a: U192, (hi, lo): (u64, u64)) -> Option<U192> {
+    let (lo, carry) = a[2].overflowing_add(lo);
+    let (mid, carry_a) = a[1].overflowing_add(hi);
+    let (mid, carry_b) = mid.overflowing_add(carry as u64);
+    let top = a[0].checked_add(carry_a as u64 + carry_b as u64)?;
+    Some([top, mid, lo])
+}
+
+#[inline]
+fn lt_wide(a: (u64, u64), b: (u64, u64)) -> bool {
+    a.0 < b.0 || (a.0 == b.0 && a.1 < b.1)
+}
+
+#[inline]
+fn sub_wide(a: (u64, u64), b: (u64, u64)) -> (u64, u64) {
+    let (lo, borrow) = a.1.overflowing_sub(b.1);
+    (a.0.wrapping_sub(b.0).wrapping_sub(borrow as u64), lo)
+}
+
+/// Divides the 192-bit value `top:low` by `d` against a 128-bit running
+/// remainder. Requires `top < d` so the quotient fits in 64 bits.
+#[inline]
+fn div_192(top: (u64, u64), low: u64, d: (u64, u64)) -> (u64, (u64, u64)) {
+    let mut rem = top;
+    let mut q = 0u64;
+    for i in (0..64).rev() {
+        let carry = rem.0 >> 63;
+        rem = (
+            (rem.0 << 1) | (rem.1 >> 63),
+            (rem.1 << 1) | ((low >> i) & 1),
+        );
+        q <<= 1;
+        if carry != 0 || !lt_wide(rem, d) {
+            rem = sub_wide(rem, d);
+            q |= 1;
+        }
+    }
+    (q, rem)
+}
+
+/// Divides the 128-bit `n` by the 128-bit `d`.
+///
+/// Returns `(quotient, remainder)`, or `None` on division by zero or a
+/// quotient wider than 64 bits.
+#[inline]
+pub fn div_rem_wide(n: (u64, u64), d: (u64, u64)) -> Option<(u64, (u64, u64))> {
+    if d == (0, 0) || !lt_wide((0, n.0), d) {
+        return None;
+    }
+    Some(div_192((0, n.0), n.1, d))
+}
+
+/// `a * b % d` for a 128-bit `a` and `d`.
+///
+/// `None` on division by zero or a quotient wider than 64 bits.
+pub fn mul_rem_wide(a: (u64, u64), b: u64, d: (u64, u64)) -> Option<(u64, u64)> {
+    if d == (0, 0) {
+        return None;
+    }
+
+    let [p2, p1, p0] = mul_128_by_64(a, b);
+    if !lt_wide((p2, p1), d) {
+        return None;
+    }
+    Some(div_192((p2, p1), p0, d).1)
+}
+
+/// `a * b / d` for a 128-bit `a` and `d`, floored.
+///
+/// The 192-bit product is divided one bit per step against a 128-bit running
+/// remainder. `None` on division by zero or a quotient wider than 64 bits.
+pub fn mul_div_wide(a: (u64, u64), b: u64, d: (u64, u64)) -> Option<u64> {
+    if d == (0, 0) {
+        return None;
+    }
+
+    let (p1_hi, p0) = mul_wide(a.1, b);
+    let (p2, p1_lo) = mul_wide(a.0, b);
+    let (p2, p1) = add_wide((p2, p1_lo), (0, p1_hi))?;
+
+    if !lt_wide((p2, p1), d) {
+        return None;
+    }
+    Some(div_192((p2, p1), p0, d).0)
+}
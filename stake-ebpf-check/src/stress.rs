@@ -0,0 +1,127 @@
+//! Pseudo-random stress runs whose outcome the host can recompute.
+//!
+//! A xorshift64* generator seeded from the instruction produces each operand
+//! set; the results are folded into an FNV-1a style rolling hash over whole
+//! `u64` words. Host code running [`stress`] with the same calculator and
+//! seed gets the same hash.
+
+use crate::{
+    BackendVisitor, StakeCalculator, ORIGINAL_WARMUP_COOLDOWN_RATE_BPS,
+    TOWER_WARMUP_COOLDOWN_RATE_BPS,
+};
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// xorshift64* (Vigna), which never leaves or enters the all-zero state.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Xorshift64Star {
+    state: u64,
+}
+
+impl Xorshift64Star {
+    /// A zero seed is replaced by a fixed non-zero one.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            state: if seed == 0 { FNV_OFFSET_BASIS } else { seed },
+        }
+    }
+
+    /// Where the sequence is; `new` on it carries on from here.
+    pub fn state(&self) -> u64 {
+        self.state
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let mut x = self.state;
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        self.state = x;
+        x.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// A random value shifted right by a random amount, so small and large
+    /// magnitudes are drawn about equally often.
+    fn next_magnitude(&mut self) -> u64 {
+        let shift = self.next_u64() % 64;
+        self.next_u64() >> shift
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StressOperands {
+    pub rate_bps: u64,
+    pub account_portion: u64,
+    pub cluster_portion: u64,
+    pub cluster_effective: u64,
+}
+
+impl StressOperands {
+    pub fn generate(rng: &mut Xorshift64Star) -> Self {
+        let rate_bps = if rng.next_u64() & 1 == 0 {
+            ORIGINAL_WARMUP_COOLDOWN_RATE_BPS
+        } else {
+            TOWER_WARMUP_COOLDOWN_RATE_BPS
+        };
+        Self {
+            rate_bps,
+            account_portion: rng.next_magnitude(),
+            cluster_portion: rng.next_magnitude(),
+            cluster_effective: rng.next_magnitude(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RollingHash(u64);
+
+impl RollingHash {
+    pub fn new() -> Self {
+        Self(FNV_OFFSET_BASIS)
+    }
+
+    pub fn update(&mut self, value: u64) {
+        self.0 = (self.0 ^ value).wrapping_mul(FNV_PRIME);
+    }
+
+    pub fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+impl Default for RollingHash {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Hash of `T`'s results over `iterations` operand sets drawn from `seed`.
+pub fn stress<T: StakeCalculator>(seed: u64, iterations: u64) -> u64 {
+    let mut rng = Xorshift64Star::new(seed);
+    let mut hash = RollingHash::new();
+    for _ in 0..iterations {
+        let operands = StressOperands::generate(&mut rng);
+        hash.update(T::rate_limited_stake_change_bps(
+            operands.rate_bps,
+            operands.account_portion,
+            operands.cluster_portion,
+            operands.cluster_effective,
+        ));
+    }
+    hash.finish()
+}
+
+/// Runs [`stress`] on a [`crate::Backend`].
+pub struct Stress {
+    pub seed: u64,
+    pub iterations: u64,
+}
+
+impl BackendVisitor for Stress {
+    type Output = u64;
+
+    fn visit<T: StakeCalculator>(self) -> u64 {
+        stress::<T>(self.seed, self.iterations)
+    }
+}
@@ -0,0 +1,109 @@
+//! Deterministic synthetic stake histories.
+//!
+//! Each epoch the cluster warms up / cools down by at most the rate-limited
+//! share of its effective stake, but by at least a lamport, then new activations and deactivations
+//! proportional to the resulting effective stake are queued.
+
+use crate::stake_history::StakeHistoryEntry;
+use crate::streaming::{mul_div, Rounding};
+use crate::{warmup_cooldown_rate_bps, Epoch, BASIS_POINTS_PER_UNIT};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ChurnParams {
+    /// New activations queued each epoch, in bps of effective stake.
+    pub inflow_bps: u64,
+    /// New deactivations queued each epoch, in bps of effective stake.
+    pub deactivation_bps: u64,
+}
+
+fn bps_of(amount: u64, bps: u64) -> u64 {
+    mul_div(amount, bps, BASIS_POINTS_PER_UNIT, Rounding::Down).unwrap_or(u64::MAX)
+}
+
+/// Iterator over `(epoch, entry)` pairs, see [`synthetic_history`].
+pub struct SyntheticHistory {
+    epoch: Epoch,
+    entry: StakeHistoryEntry,
+    churn: ChurnParams,
+    new_rate_activation_epoch: Option<Epoch>,
+    epochs_left: usize,
+}
+
+impl Iterator for SyntheticHistory {
+    type Item = (Epoch, StakeHistoryEntry);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.epochs_left == 0 {
+            return None;
+        }
+        self.epochs_left -= 1;
+
+        let item = (self.epoch, self.entry);
+
+        let prev = self.entry;
+        // At least a lamport moves each epoch, as in the runtime, so a
+        // cluster with no effective stake still warms up.
+        let allowed = bps_of(
+            prev.effective,
+            warmup_cooldown_rate_bps(self.epoch, self.new_rate_activation_epoch),
+        )
+        .max(1);
+        let activated = prev.activating.min(allowed);
+        let deactivated = prev.deactivating.min(allowed);
+        let effective = prev
+            .effective
+            .saturating_add(activated)
+            .saturating_sub(deactivated);
+
+        self.entry = StakeHistoryEntry {
+            effective,
+            activating: (prev.activating - activated)
+                .saturating_add(bps_of(effective, self.churn.inflow_bps)),
+            deactivating: (prev.deactivating - deactivated)
+                .saturating_add(bps_of(effective, self.churn.deactivation_bps))
+                .min(effective),
+        };
+        self.epoch = self.epoch.saturating_add(1);
+
+        Some(item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.epochs_left, Some(self.epochs_left))
+    }
+}
+
+/// `epochs` entries starting with `start` at `start_epoch`.
+pub fn synthetic_history(
+    start_epoch: Epoch,
+    start: StakeHistoryEntry,
+    churn: ChurnParams,
+    epochs: usize,
+    new_rate_activation_epoch: Option<Epoch>,
+) -> SyntheticHistory {
+    SyntheticHistory {
+        epoch: start_epoch,
+        entry: start,
+        churn,
+        new_rate_activation_epoch,
+        epochs_left: epochs,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zero_effective_cluster_warms_up_a_lamport_at_a_time() {
+        let start = StakeHistoryEntry {
+            effective: 0,
+            activating: 3,
+            deactivating: 0,
+        };
+        let effective: Vec<u64> = synthetic_history(0, start, ChurnParams::default(), 5, None)
+            .map(|(_, entry)| entry.effective)
+            .collect();
+        assert_eq!(effective, [0, 1, 2, 3, 3]);
+    }
+}
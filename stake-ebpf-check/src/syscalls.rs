@@ -0,0 +1,239 @@
+//! Thin wrappers over the runtime syscalls this crate uses.
+//!
+//! On SBF they call the real syscalls. Everywhere else they resolve to host
+//! mocks whose state [`mock`] sets and inspects, so the full program path,
+//! logging and compute-unit reads included, runs under `cargo test`.
+
+pub use crate::clock_sysvar::ClockSysvar;
+use crate::state::Pubkey;
+
+/// Largest return data a program may set.
+pub const MAX_RETURN_DATA: usize = 1024;
+
+/// `SolAccountMeta` from the C SDK.
+#[repr(C)]
+pub struct SolAccountMeta {
+    pub pubkey: *const Pubkey,
+    pub is_writable: bool,
+    pub is_signer: bool,
+}
+
+/// `SolInstruction` from the C SDK.
+#[repr(C)]
+pub struct SolInstruction {
+    pub program_id: *const Pubkey,
+    pub accounts: *const SolAccountMeta,
+    pub account_len: u64,
+    pub data: *const u8,
+    pub data_len: u64,
+}
+
+/// `SolAccountInfo` from the C SDK.
+#[repr(C)]
+pub struct SolAccountInfo {
+    pub key: *const Pubkey,
+    pub lamports: *mut u64,
+    pub data_len: u64,
+    pub data: *mut u8,
+    pub owner: *const Pubkey,
+    pub rent_epoch: u64,
+    pub is_signer: bool,
+    pub is_writable: bool,
+    pub executable: bool,
+}
+
+#[cfg(any(target_arch = "bpf", target_os = "solana"))]
+mod sbf {
+    use super::{SolAccountInfo, SolInstruction};
+    use crate::state::Pubkey;
+
+    extern "C" {
+        pub fn sol_log_(message: *const u8, len: u64);
+        pub fn sol_log_64_(arg1: u64, arg2: u64, arg3: u64, arg4: u64, arg5: u64);
+        pub fn sol_remaining_compute_units() -> u64;
+        pub fn sol_get_clock_sysvar(addr: *mut u8) -> u64;
+        pub fn sol_invoke_signed_c(
+            instruction: *const SolInstruction,
+            account_infos: *const SolAccountInfo,
+            account_infos_len: u64,
+            signers_seeds: *const u8,
+            signers_seeds_len: u64,
+        ) -> u64;
+        pub fn sol_get_return_data(data: *mut u8, length: u64, program_id: *mut Pubkey) -> u64;
+    }
+}
+
+#[cfg(any(target_arch = "bpf", target_os = "solana"))]
+#[inline(always)]
+pub fn log(message: &str) {
+    unsafe { sbf::sol_log_(message.as_ptr(), message.len() as u64) }
+}
+
+#[cfg(any(target_arch = "bpf", target_os = "solana"))]
+#[inline(always)]
+pub fn log_64(arg1: u64, arg2: u64, arg3: u64, arg4: u64, arg5: u64) {
+    unsafe { sbf::sol_log_64_(arg1, arg2, arg3, arg4, arg5) }
+}
+
+#[cfg(any(target_arch = "bpf", target_os = "solana"))]
+#[inline(always)]
+pub fn remaining_compute_units() -> u64 {
+    unsafe { sbf::sol_remaining_compute_units() }
+}
+
+/// `None` if the runtime refuses the read.
+#[cfg(any(target_arch = "bpf", target_os = "solana"))]
+pub fn get_clock() -> Option<ClockSysvar> {
+    let mut clock = ClockSysvar::default();
+    match unsafe { sbf::sol_get_clock_sysvar(&mut clock as *mut ClockSysvar as *mut u8) } {
+        0 => Some(clock),
+        _ => None,
+    }
+}
+
+/// Cross-program invocation without signer seeds. Returns the callee's
+/// error, zero on success.
+///
+/// # Safety
+///
+/// Every pointer in `instruction` and `account_infos` must be valid for the
+/// lengths given.
+#[cfg(any(target_arch = "bpf", target_os = "solana"))]
+pub unsafe fn invoke(instruction: &SolInstruction, account_infos: &[SolAccountInfo]) -> u64 {
+    sbf::sol_invoke_signed_c(
+        instruction,
+        account_infos.as_ptr(),
+        account_infos.len() as u64,
+        core::ptr::null(),
+        0,
+    )
+}
+
+/// Copies the last return data into `buf`, returning its full length and
+/// the program that set it, or `None` if there is none.
+#[cfg(any(target_arch = "bpf", target_os = "solana"))]
+pub fn get_return_data(buf: &mut [u8]) -> Option<(usize, Pubkey)> {
+    let mut program_id = [0; 32];
+    let len =
+        unsafe { sbf::sol_get_return_data(buf.as_mut_ptr(), buf.len() as u64, &mut program_id) };
+    match len {
+        0 => None,
+        len => Some((len as usize, program_id)),
+    }
+}
+
+#[cfg(not(any(target_arch = "bpf", target_os = "solana")))]
+pub use mock::{get_clock, get_return_data, invoke, log, log_64, remaining_compute_units};
+
+/// Host stand-ins for the syscalls, backed by process-wide state.
+#[cfg(not(any(target_arch = "bpf", target_os = "solana")))]
+pub mod mock {
+    use core::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+
+    use super::{ClockSysvar, SolAccountInfo, SolInstruction};
+    use crate::state::Pubkey;
+
+    /// The default per-transaction compute budget.
+    pub const DEFAULT_COMPUTE_UNITS: u64 = 200_000;
+
+    static REMAINING_COMPUTE_UNITS: AtomicU64 = AtomicU64::new(DEFAULT_COMPUTE_UNITS);
+    static LOG_COUNT: AtomicU64 = AtomicU64::new(0);
+    static LAST_LOG_64: [AtomicU64; 5] = [const { AtomicU64::new(0) }; 5];
+    static CLOCK_SLOT: AtomicU64 = AtomicU64::new(0);
+    static CLOCK_EPOCH: AtomicU64 = AtomicU64::new(0);
+    static CLOCK_UNIX_TIMESTAMP: AtomicI64 = AtomicI64::new(0);
+    static INVOKE_COUNT: AtomicU64 = AtomicU64::new(0);
+    static RETURN_DATA: AtomicU64 = AtomicU64::new(0);
+    static RETURN_DATA_SET: AtomicU64 = AtomicU64::new(0);
+    static RETURN_PROGRAM_ID: [AtomicU64; 4] = [const { AtomicU64::new(0) }; 4];
+
+    pub fn log(_message: &str) {
+        LOG_COUNT.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn log_64(arg1: u64, arg2: u64, arg3: u64, arg4: u64, arg5: u64) {
+        LOG_COUNT.fetch_add(1, Ordering::Relaxed);
+        for (slot, value) in LAST_LOG_64.iter().zip([arg1, arg2, arg3, arg4, arg5]) {
+            slot.store(value, Ordering::Relaxed);
+        }
+    }
+
+    pub fn remaining_compute_units() -> u64 {
+        REMAINING_COMPUTE_UNITS.load(Ordering::Relaxed)
+    }
+
+    pub fn get_clock() -> Option<ClockSysvar> {
+        let epoch = CLOCK_EPOCH.load(Ordering::Relaxed);
+        Some(ClockSysvar {
+            slot: CLOCK_SLOT.load(Ordering::Relaxed),
+            epoch,
+            leader_schedule_epoch: epoch + 1,
+            unix_timestamp: CLOCK_UNIX_TIMESTAMP.load(Ordering::Relaxed),
+            ..ClockSysvar::default()
+        })
+    }
+
+    /// Counts the call and succeeds; the callee's effect is whatever
+    /// [`set_return_data_u64`] staged.
+    ///
+    /// # Safety
+    ///
+    /// Mirrors the real [`super::invoke`]; the mock reads nothing.
+    pub unsafe fn invoke(_instruction: &SolInstruction, _account_infos: &[SolAccountInfo]) -> u64 {
+        INVOKE_COUNT.fetch_add(1, Ordering::Relaxed);
+        0
+    }
+
+    pub fn get_return_data(buf: &mut [u8]) -> Option<(usize, Pubkey)> {
+        if RETURN_DATA_SET.load(Ordering::Relaxed) == 0 {
+            return None;
+        }
+        let data = RETURN_DATA.load(Ordering::Relaxed).to_le_bytes();
+        let len = buf.len().min(data.len());
+        buf[..len].copy_from_slice(&data[..len]);
+
+        let mut program_id = [0; 32];
+        for (chunk, word) in program_id.chunks_exact_mut(8).zip(&RETURN_PROGRAM_ID) {
+            chunk.copy_from_slice(&word.load(Ordering::Relaxed).to_le_bytes());
+        }
+        Some((data.len(), program_id))
+    }
+
+    /// Stages a `u64` as return data from `program_id`, as a callee would.
+    pub fn set_return_data_u64(program_id: &Pubkey, value: u64) {
+        for (word, chunk) in RETURN_PROGRAM_ID.iter().zip(program_id.chunks_exact(8)) {
+            let mut bytes = [0; 8];
+            bytes.copy_from_slice(chunk);
+            word.store(u64::from_le_bytes(bytes), Ordering::Relaxed);
+        }
+        RETURN_DATA.store(value, Ordering::Relaxed);
+        RETURN_DATA_SET.store(1, Ordering::Relaxed);
+    }
+
+    pub fn invoke_count() -> u64 {
+        INVOKE_COUNT.load(Ordering::Relaxed)
+    }
+
+    pub fn set_remaining_compute_units(units: u64) {
+        REMAINING_COMPUTE_UNITS.store(units, Ordering::Relaxed);
+    }
+
+    pub fn set_clock(slot: u64, epoch: u64, unix_timestamp: i64) {
+        CLOCK_SLOT.store(slot, Ordering::Relaxed);
+        CLOCK_EPOCH.store(epoch, Ordering::Relaxed);
+        CLOCK_UNIX_TIMESTAMP.store(unix_timestamp, Ordering::Relaxed);
+    }
+
+    /// Number of `log` and `log_64` calls so far.
+    pub fn log_count() -> u64 {
+        LOG_COUNT.load(Ordering::Relaxed)
+    }
+
+    pub fn last_log_64() -> [u64; 5] {
+        let mut values = [0; 5];
+        for (value, slot) in values.iter_mut().zip(&LAST_LOG_64) {
+            *value = slot.load(Ordering::Relaxed);
+        }
+        values
+    }
+}
@@ -0,0 +1,288 @@
+//! Records every calculator call made through a [`Backend`] to an
+//! append-only file, and reads the file back.
+//!
+//! With the `trace` feature each backend is wrapped in [`Traced`], which
+//! does nothing until [`start`] opens a trace file; from then on every
+//! call, whether through [`Backend::visit`] or
+//! [`Backend::rate_limited_stake_change_bps`], appends one record. The
+//! `host-sim` binary starts one when `$STAKE_TRACE` names a file, and
+//! `trace-replay` reruns a trace against other backends.
+//!
+//! ```text
+//! header  b"SETR" version:u8
+//! record  backend:u8 timestamp_micros rate_bps account_portion
+//!         cluster_portion cluster_effective result
+//! ```
+//!
+//! Every field after the backend id is an unsigned LEB128 varint, so the
+//! common small operands take a byte or two. Records go out whole under a
+//! lock; a file cut short mid-record reads back every record before the
+//! cut and then reports [`TraceError::Truncated`].
+
+use core::fmt;
+use core::marker::PhantomData;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, Read, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::StakeCalculator;
+
+pub const MAGIC: [u8; 4] = *b"SETR";
+pub const VERSION: u8 = 1;
+
+/// One calculator call.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TraceRecord {
+    /// Wire id; the backend need not be compiled into the reader.
+    pub backend_id: u8,
+    /// Microseconds since the Unix epoch.
+    pub timestamp_micros: u64,
+    pub rate_bps: u64,
+    pub account_portion: u64,
+    pub cluster_portion: u64,
+    pub cluster_effective: u64,
+    pub result: u64,
+}
+
+#[derive(Debug)]
+pub enum TraceError {
+    Io(io::Error),
+    /// The file does not start with [`MAGIC`].
+    BadMagic,
+    UnsupportedVersion(u8),
+    /// The file ends partway through a record.
+    Truncated,
+    /// A varint runs past 64 bits.
+    Overlong,
+}
+
+impl fmt::Display for TraceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "trace io: {e}"),
+            Self::BadMagic => write!(f, "not a trace file"),
+            Self::UnsupportedVersion(v) => write!(f, "unsupported trace version {v}"),
+            Self::Truncated => write!(f, "trace ends partway through a record"),
+            Self::Overlong => write!(f, "trace varint longer than 64 bits"),
+        }
+    }
+}
+
+impl std::error::Error for TraceError {}
+
+impl From<io::Error> for TraceError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+impl TraceRecord {
+    /// The record's bytes, as appended to a trace.
+    pub fn encode(&self, out: &mut Vec<u8>) {
+        out.push(self.backend_id);
+        for value in [
+            self.timestamp_micros,
+            self.rate_bps,
+            self.account_portion,
+            self.cluster_portion,
+            self.cluster_effective,
+            self.result,
+        ] {
+            write_varint(out, value);
+        }
+    }
+}
+
+/// Appends records to a trace, writing the header first if it is empty.
+pub struct TraceWriter<W: Write> {
+    out: W,
+    buf: Vec<u8>,
+}
+
+impl<W: Write> TraceWriter<W> {
+    /// `empty` says whether `out` still needs its header.
+    pub fn new(mut out: W, empty: bool) -> io::Result<Self> {
+        if empty {
+            out.write_all(&MAGIC)?;
+            out.write_all(&[VERSION])?;
+        }
+        Ok(Self {
+            out,
+            buf: Vec::new(),
+        })
+    }
+
+    pub fn append(&mut self, record: &TraceRecord) -> io::Result<()> {
+        self.buf.clear();
+        record.encode(&mut self.buf);
+        self.out.write_all(&self.buf)
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+/// Reads records back; an iterator that ends at a clean end of file.
+pub struct TraceReader<R: Read> {
+    input: R,
+    failed: bool,
+}
+
+impl<R: Read> TraceReader<R> {
+    /// Checks the header.
+    pub fn new(mut input: R) -> Result<Self, TraceError> {
+        let mut header = [0u8; 5];
+        input.read_exact(&mut header).map_err(|e| match e.kind() {
+            io::ErrorKind::UnexpectedEof => TraceError::BadMagic,
+            _ => TraceError::Io(e),
+        })?;
+        if header[..4] != MAGIC {
+            return Err(TraceError::BadMagic);
+        }
+        if header[4] != VERSION {
+            return Err(TraceError::UnsupportedVersion(header[4]));
+        }
+        Ok(Self {
+            input,
+            failed: false,
+        })
+    }
+
+    /// One byte, `None` at end of file.
+    fn byte(&mut self) -> Result<Option<u8>, TraceError> {
+        let mut byte = [0u8];
+        loop {
+            match self.input.read(&mut byte) {
+                Ok(0) => return Ok(None),
+                Ok(_) => return Ok(Some(byte[0])),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    fn varint(&mut self) -> Result<u64, TraceError> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?.ok_or(TraceError::Truncated)?;
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                if shift == 63 && byte > 1 {
+                    return Err(TraceError::Overlong);
+                }
+                return Ok(value);
+            }
+        }
+        Err(TraceError::Overlong)
+    }
+
+    fn record(&mut self) -> Result<Option<TraceRecord>, TraceError> {
+        let Some(backend_id) = self.byte()? else {
+            return Ok(None);
+        };
+        Ok(Some(TraceRecord {
+            backend_id,
+            timestamp_micros: self.varint()?,
+            rate_bps: self.varint()?,
+            account_portion: self.varint()?,
+            cluster_portion: self.varint()?,
+            cluster_effective: self.varint()?,
+            result: self.varint()?,
+        }))
+    }
+}
+
+impl<R: Read> Iterator for TraceReader<R> {
+    type Item = Result<TraceRecord, TraceError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let record = self.record().transpose();
+        self.failed = matches!(record, Some(Err(_)));
+        record
+    }
+}
+
+static RECORDER: Mutex<Option<TraceWriter<BufWriter<File>>>> = Mutex::new(None);
+
+/// Starts appending every call to `path`, creating it if needed. Replaces
+/// any trace already being recorded, flushing it first. Writes are
+/// buffered, so call [`stop`] before exiting or the tail is lost.
+pub fn start(path: &Path) -> Result<(), TraceError> {
+    let mut file = OpenOptions::new()
+        .read(true)
+        .append(true)
+        .create(true)
+        .open(path)?;
+    let empty = file.metadata()?.len() == 0;
+    if !empty {
+        // Appending to something else would leave it unreadable.
+        TraceReader::new(&mut file)?;
+    }
+    let writer = TraceWriter::new(BufWriter::new(file), empty)?;
+    if let Some(mut previous) = RECORDER.lock().unwrap().replace(writer) {
+        previous.flush()?;
+    }
+    Ok(())
+}
+
+/// Stops recording and flushes what was recorded.
+pub fn stop() -> io::Result<()> {
+    match RECORDER.lock().unwrap().take() {
+        Some(mut writer) => writer.flush(),
+        None => Ok(()),
+    }
+}
+
+/// `T` as backend `BACKEND_ID`, recording each call while a trace is open.
+pub struct Traced<T, const BACKEND_ID: u8>(PhantomData<T>);
+
+impl<T: StakeCalculator, const BACKEND_ID: u8> StakeCalculator for Traced<T, BACKEND_ID> {
+    fn rate_limited_stake_change_bps(
+        rate_bps: u64,
+        account_portion: u64,
+        cluster_portion: u64,
+        cluster_effective: u64,
+    ) -> u64 {
+        let result = T::rate_limited_stake_change_bps(
+            rate_bps,
+            account_portion,
+            cluster_portion,
+            cluster_effective,
+        );
+        let mut recorder = RECORDER.lock().unwrap();
+        if let Some(writer) = recorder.as_mut() {
+            let timestamp_micros = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_micros() as u64);
+            let record = TraceRecord {
+                backend_id: BACKEND_ID,
+                timestamp_micros,
+                rate_bps,
+                account_portion,
+                cluster_portion,
+                cluster_effective,
+                result,
+            };
+            // A failed write must not change what the calculator returns;
+            // drop the trace instead, so it ends at the last whole record.
+            if writer.append(&record).is_err() {
+                *recorder = None;
+            }
+        }
+        result
+    }
+}
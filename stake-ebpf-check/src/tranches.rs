@@ -0,0 +1,88 @@
+//! Accounts deactivated piecewise: deactivate part, wait, split, repeat.
+//!
+//! Each split-off tranche becomes its own delegation sharing the original
+//! activation epoch and cools down under the cluster-wide rate limit from its
+//! own deactivation epoch.
+
+use crate::delegation::StakeActivationStatus;
+use crate::stake_history::StakeHistoryGetEntry;
+use crate::state::Delegation;
+use crate::{Epoch, StakeCalculator};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Tranche {
+    pub stake: u64,
+    /// `u64::MAX` for the part that is still delegated.
+    pub deactivation_epoch: Epoch,
+}
+
+fn tranche_delegation(activation_epoch: Epoch, tranche: &Tranche) -> Delegation {
+    Delegation {
+        deactivation_epoch: tranche.deactivation_epoch,
+        ..Delegation::new(&[0; 32], tranche.stake, activation_epoch)
+    }
+}
+
+/// Combined status of all tranches at `target_epoch`.
+pub fn tranches_status<T: StakeCalculator>(
+    activation_epoch: Epoch,
+    tranches: &[Tranche],
+    target_epoch: Epoch,
+    history: &(impl StakeHistoryGetEntry + ?Sized),
+    new_rate_activation_epoch: Option<Epoch>,
+) -> StakeActivationStatus {
+    tranches
+        .iter()
+        .map(|tranche| {
+            tranche_delegation(activation_epoch, tranche).stake_activating_and_deactivating::<T>(
+                target_epoch,
+                history,
+                new_rate_activation_epoch,
+            )
+        })
+        .fold(StakeActivationStatus::default(), |acc, status| acc + status)
+}
+
+/// Lamports across all tranches that are neither effective nor activating
+/// at `target_epoch`.
+pub fn liquid_at<T: StakeCalculator>(
+    activation_epoch: Epoch,
+    tranches: &[Tranche],
+    target_epoch: Epoch,
+    history: &(impl StakeHistoryGetEntry + ?Sized),
+    new_rate_activation_epoch: Option<Epoch>,
+) -> u64 {
+    let total = tranches
+        .iter()
+        .fold(0u64, |acc, tranche| acc.saturating_add(tranche.stake));
+    let status = tranches_status::<T>(
+        activation_epoch,
+        tranches,
+        target_epoch,
+        history,
+        new_rate_activation_epoch,
+    );
+    total.saturating_sub(status.effective.saturating_add(status.activating))
+}
+
+/// First epoch in `from_epoch..=until_epoch` at which at least `target_liquid`
+/// lamports are liquid.
+pub fn first_epoch_with_liquid<T: StakeCalculator>(
+    activation_epoch: Epoch,
+    tranches: &[Tranche],
+    target_liquid: u64,
+    from_epoch: Epoch,
+    until_epoch: Epoch,
+    history: &(impl StakeHistoryGetEntry + ?Sized),
+    new_rate_activation_epoch: Option<Epoch>,
+) -> Option<Epoch> {
+    (from_epoch..=until_epoch).find(|&epoch| {
+        liquid_at::<T>(
+            activation_epoch,
+            tranches,
+            epoch,
+            history,
+            new_rate_activation_epoch,
+        ) >= target_liquid
+    })
+}
@@ -0,0 +1,233 @@
+//! JavaScript bindings for browsers and Node, built with `cargo build-wasm`
+//! and packaged with `wasm-bindgen --target web` (or `bundler`, `nodejs`).
+//!
+//! ```js
+//! import init, { activationAllowance, delegationStatus } from "./stake_ebpf_check.js";
+//! await init();
+//! const allowance = activationAllowance(6, 800n, 10_000_000_000n, 380n * 10n ** 17n,
+//!                                       2n * 10n ** 17n, 15n * 10n ** 16n, 600n);
+//! ```
+//!
+//! Every stake and epoch crosses the boundary as a `BigInt`, never a
+//! `Number`, so lamport amounts above 2^53 arrive exactly; arrays of them
+//! are `BigUint64Array`s. An absent `new_rate_activation_epoch` or
+//! `deactivation_epoch` is `undefined`. Backends are chosen by wire id (see
+//! [`Backend`]); `backends()` lists the ones compiled in, and an id that
+//! isn't throws.
+
+use wasm_bindgen::prelude::*;
+
+use crate::delegation::StakeActivationStatus;
+use crate::planning::forecast_activation;
+use crate::stake_history::StakeHistoryEntry;
+use crate::state::Delegation;
+use crate::synthetic::{synthetic_history, ChurnParams};
+use crate::{Backend, BackendVisitor, Epoch, StakeCalculator};
+
+/// `u64`s per entry in a flattened history: epoch, effective, activating,
+/// deactivating.
+const HISTORY_STRIDE: usize = 4;
+
+fn backend(id: u8) -> Result<Backend, JsError> {
+    Backend::from_id(id).ok_or_else(|| {
+        JsError::new(&format!(
+            "backend {id} is not compiled in; have {:?}",
+            Backend::ALL
+        ))
+    })
+}
+
+fn cluster_state(effective: u64, activating: u64, deactivating: u64) -> StakeHistoryEntry {
+    StakeHistoryEntry {
+        effective,
+        activating,
+        deactivating,
+    }
+}
+
+fn flatten(entries: impl Iterator<Item = (Epoch, StakeHistoryEntry)>) -> Vec<u64> {
+    entries
+        .flat_map(|(epoch, entry)| [epoch, entry.effective, entry.activating, entry.deactivating])
+        .collect()
+}
+
+/// Ids of the backends compiled into this build.
+#[wasm_bindgen]
+pub fn backends() -> Vec<u8> {
+    Backend::ALL.iter().map(|backend| backend.id()).collect()
+}
+
+/// Stake of `account_activating_stake` that becomes effective at the end of
+/// `epoch`, given the cluster's state in the previous epoch.
+#[wasm_bindgen(js_name = activationAllowance)]
+pub fn activation_allowance(
+    backend_id: u8,
+    epoch: Epoch,
+    account_activating_stake: u64,
+    cluster_effective: u64,
+    cluster_activating: u64,
+    cluster_deactivating: u64,
+    new_rate_activation_epoch: Option<Epoch>,
+) -> Result<u64, JsError> {
+    Ok(backend(backend_id)?.calculate_activation_allowance(
+        epoch,
+        account_activating_stake,
+        &cluster_state(cluster_effective, cluster_activating, cluster_deactivating),
+        new_rate_activation_epoch,
+    ))
+}
+
+/// Stake of `account_deactivating_stake` that cools down at the end of
+/// `epoch`, given the cluster's state in the previous epoch.
+#[wasm_bindgen(js_name = deactivationAllowance)]
+pub fn deactivation_allowance(
+    backend_id: u8,
+    epoch: Epoch,
+    account_deactivating_stake: u64,
+    cluster_effective: u64,
+    cluster_activating: u64,
+    cluster_deactivating: u64,
+    new_rate_activation_epoch: Option<Epoch>,
+) -> Result<u64, JsError> {
+    Ok(backend(backend_id)?.calculate_deactivation_allowance(
+        epoch,
+        account_deactivating_stake,
+        &cluster_state(cluster_effective, cluster_activating, cluster_deactivating),
+        new_rate_activation_epoch,
+    ))
+}
+
+struct Status<'a> {
+    delegation: Delegation,
+    epoch: Epoch,
+    history: &'a [(Epoch, StakeHistoryEntry)],
+    new_rate_activation_epoch: Option<Epoch>,
+}
+
+impl BackendVisitor for Status<'_> {
+    type Output = StakeActivationStatus;
+
+    fn visit<T: StakeCalculator>(self) -> StakeActivationStatus {
+        self.delegation.stake_activating_and_deactivating::<T>(
+            self.epoch,
+            self.history,
+            self.new_rate_activation_epoch,
+        )
+    }
+}
+
+/// `[effective, activating, deactivating]` of a delegation at `epoch`.
+///
+/// `history` is the StakeHistory sysvar flattened to `[epoch, effective,
+/// activating, deactivating, ...]`, entries in any order.
+#[wasm_bindgen(js_name = delegationStatus)]
+pub fn delegation_status(
+    backend_id: u8,
+    stake: u64,
+    activation_epoch: Epoch,
+    deactivation_epoch: Option<Epoch>,
+    epoch: Epoch,
+    history: &[u64],
+    new_rate_activation_epoch: Option<Epoch>,
+) -> Result<Vec<u64>, JsError> {
+    if !history.len().is_multiple_of(HISTORY_STRIDE) {
+        return Err(JsError::new(
+            "history must be [epoch, effective, activating, deactivating] repeated",
+        ));
+    }
+    let mut entries: Vec<_> = history
+        .chunks_exact(HISTORY_STRIDE)
+        .map(|entry| (entry[0], cluster_state(entry[1], entry[2], entry[3])))
+        .collect();
+    entries.sort_by_key(|&(epoch, _)| core::cmp::Reverse(epoch));
+
+    let status = backend(backend_id)?.visit(Status {
+        delegation: Delegation {
+            stake,
+            activation_epoch,
+            deactivation_epoch: deactivation_epoch.unwrap_or(u64::MAX),
+            ..Delegation::default()
+        },
+        epoch,
+        history: &entries,
+        new_rate_activation_epoch,
+    });
+    Ok(vec![
+        status.effective,
+        status.activating,
+        status.deactivating,
+    ])
+}
+
+struct Forecast {
+    account_stake: u64,
+    cluster_state: StakeHistoryEntry,
+    start_epoch: Epoch,
+    epochs: usize,
+    new_rate_activation_epoch: Option<Epoch>,
+}
+
+impl BackendVisitor for Forecast {
+    type Output = Vec<u64>;
+
+    fn visit<T: StakeCalculator>(self) -> Vec<u64> {
+        forecast_activation::<T>(
+            self.account_stake,
+            &self.cluster_state,
+            self.start_epoch,
+            self.epochs,
+            self.new_rate_activation_epoch,
+        )
+        .collect()
+    }
+}
+
+/// Newly effective stake in each of `epochs` epochs from `start_epoch`,
+/// holding the cluster's state fixed.
+#[wasm_bindgen(js_name = forecastActivation)]
+#[allow(clippy::too_many_arguments)]
+pub fn forecast(
+    backend_id: u8,
+    account_stake: u64,
+    cluster_effective: u64,
+    cluster_activating: u64,
+    cluster_deactivating: u64,
+    start_epoch: Epoch,
+    epochs: u32,
+    new_rate_activation_epoch: Option<Epoch>,
+) -> Result<Vec<u64>, JsError> {
+    Ok(backend(backend_id)?.visit(Forecast {
+        account_stake,
+        cluster_state: cluster_state(cluster_effective, cluster_activating, cluster_deactivating),
+        start_epoch,
+        epochs: epochs as usize,
+        new_rate_activation_epoch,
+    }))
+}
+
+/// A synthetic cluster history of `epochs` entries from `start_epoch`,
+/// flattened oldest first as `[epoch, effective, activating, deactivating,
+/// ...]`; see [`synthetic_history`].
+#[wasm_bindgen(js_name = simulateHistory)]
+#[allow(clippy::too_many_arguments)]
+pub fn simulate_history(
+    start_epoch: Epoch,
+    effective: u64,
+    activating: u64,
+    deactivating: u64,
+    inflow_bps: u64,
+    deactivation_bps: u64,
+    epochs: u32,
+    new_rate_activation_epoch: Option<Epoch>,
+) -> Vec<u64> {
+    flatten(synthetic_history(
+        start_epoch,
+        cluster_state(effective, activating, deactivating),
+        ChurnParams {
+            inflow_bps,
+            deactivation_bps,
+        },
+        epochs as usize,
+        new_rate_activation_epoch,
+    ))
+}
@@ -0,0 +1,77 @@
+//! The `borsh` encodings: round trips, and `StakeStateV2` against the
+//! account layout.
+//!
+//! ```text
+//! cargo test --release --target <host-triple> -p stake-ebpf-check \
+//!     --features host-sim,borsh,<backends> --test borsh
+//! ```
+
+use std::fmt::Debug;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use stake_ebpf_check::condition::Condition;
+use stake_ebpf_check::instruction::Operands;
+use stake_ebpf_check::results::AllowanceResult;
+use stake_ebpf_check::stake_account::{parse_stake_state, write_stake_state, STAKE_STATE_V2_SIZE};
+use stake_ebpf_check::stake_flags::StakeFlags;
+use stake_ebpf_check::stake_history::StakeHistoryEntry;
+use stake_ebpf_check::state::{Authorized, Delegation, Lockup, Meta, Stake, StakeStateV2};
+
+fn round_trip<T: BorshSerialize + BorshDeserialize + PartialEq + Debug>(value: T) -> Vec<u8> {
+    let bytes = borsh::to_vec(&value).unwrap();
+    assert_eq!(borsh::from_slice::<T>(&bytes).unwrap(), value);
+    bytes
+}
+
+#[test]
+fn types_round_trip() {
+    let bytes = round_trip(AllowanceResult {
+        activation: 10,
+        deactivation: 20,
+        backend_id: 6,
+        algo_version: 1,
+        activation_condition: Condition::RateLimited,
+        deactivation_condition: Condition::ZeroOperand,
+    });
+    assert_eq!(bytes[bytes.len() - 1], Condition::ZeroOperand.code());
+    round_trip(Operands {
+        epoch: 600,
+        account_portion: 1_000,
+        cluster_state: StakeHistoryEntry::with_effective_and_activating(5_000, 700),
+        new_rate_activation_epoch: Some(580),
+    });
+}
+
+#[test]
+fn stake_state_matches_the_account_layout() {
+    let state = StakeStateV2::Stake(
+        Meta {
+            rent_exempt_reserve: 2_282_880,
+            authorized: Authorized {
+                staker: [1; 32],
+                withdrawer: [2; 32],
+            },
+            lockup: Lockup {
+                unix_timestamp: -1,
+                epoch: 3,
+                custodian: [4; 32],
+            },
+        },
+        Stake {
+            delegation: Delegation {
+                deactivation_epoch: 650,
+                ..Delegation::new(&[5; 32], 9_000_000_000, 600)
+            },
+            credits_observed: 12_345,
+        },
+        StakeFlags::MUST_FULLY_ACTIVATE_BEFORE_DEACTIVATION_IS_PERMITTED,
+    );
+    let bytes = round_trip(state);
+
+    let mut account = [0u8; STAKE_STATE_V2_SIZE];
+    write_stake_state(&state, &mut account).unwrap();
+    assert_eq!(bytes, account[..bytes.len()]);
+    assert_eq!(parse_stake_state(&account), Ok(state));
+    let read = StakeStateV2::deserialize(&mut &account[..]).unwrap();
+    assert_eq!(read, state);
+}
@@ -0,0 +1,690 @@
+//! The program entrypoint run over loader-layout input regions.
+//!
+//! ```text
+//! cargo test --release --target <host-triple> -p stake-ebpf-check \
+//!     --features host-sim,streaming[,<backends>] --test entrypoint
+//! ```
+//!
+//! Every instruction is packed with [`Instruction::pack`], serialized with
+//! its accounts by [`host_sim::invoke`] and handed to the real entrypoint,
+//! so the return codes and result bytes are the ones a client sees. Build
+//! once with `instruction-epoch` as well to cover the epoch switch.
+//!
+//! The deserializer is also run over regions laid out the way the loader's
+//! own serializer writes them, duplicate accounts included.
+
+use stake_ebpf_check::entrypoint_raw::{
+    self, ProgramError, MAX_ACCOUNTS, MAX_PERMITTED_DATA_INCREASE,
+};
+use stake_ebpf_check::host_sim::{self, SimAccount};
+use stake_ebpf_check::instruction::{
+    BatchOperands, DelegationOperands, Instruction, InstructionError, InstructionFlags, Operands,
+    MAX_BATCH_LEN, MAX_INSTRUCTION_LEN,
+};
+use stake_ebpf_check::results::{
+    self, AllowanceResult, BatchResultHeader, ComputeUnitsResult, DelegationStatusResult,
+    DifferentialResult, SelfTestResult, StressResult, ALGO_VERSION, ALLOWANCE_RESULT_LEN,
+    COMPUTE_UNITS_RESULT_LEN, DELEGATION_STATUS_RESULT_LEN, DIFFERENTIAL_RESULT_LEN,
+    SELF_TEST_RESULT_LEN, STRESS_RESULT_LEN,
+};
+use stake_ebpf_check::stake_history::StakeHistoryEntry;
+use stake_ebpf_check::state::Pubkey;
+use stake_ebpf_check::{program, self_test, stake_history_sysvar, Backend, Epoch};
+
+const PROGRAM_ID: Pubkey = [7; 32];
+const RESULT_KEY: Pubkey = [9; 32];
+const STREAMING: u8 = 6;
+/// A backend id no build compiles in.
+const UNKNOWN_BACKEND: u8 = 200;
+
+/// The Clock sysvar's epoch, past the rate switch.
+const CLOCK_EPOCH: Epoch = 20;
+/// The epoch in instruction data, before the rate switch.
+const INSTRUCTION_EPOCH: Epoch = 5;
+const NEW_RATE_ACTIVATION_EPOCH: Epoch = 10;
+
+const CLUSTER: StakeHistoryEntry = StakeHistoryEntry {
+    effective: 1_000_000,
+    activating: 1_000_000,
+    deactivating: 1_000_000,
+};
+
+fn operands(account_portion: u64) -> Operands {
+    Operands {
+        epoch: INSTRUCTION_EPOCH,
+        account_portion,
+        cluster_state: CLUSTER,
+        new_rate_activation_epoch: Some(NEW_RATE_ACTIVATION_EPOCH),
+    }
+}
+
+/// Allowances of `operands(1_000_000)` at whichever epoch the build reads:
+/// 9% of the portion at the Clock's epoch, 25% at the instruction's.
+fn expected_allowances() -> (u64, u64) {
+    if cfg!(feature = "instruction-epoch") {
+        (250_000, 125_000)
+    } else {
+        (90_000, 45_000)
+    }
+}
+
+/// What a custom error code reaches the runtime as.
+fn code(e: InstructionError) -> u64 {
+    ProgramError::from(e).into()
+}
+
+/// `[clock_sysvar, result]`, or just `[result]` with `instruction-epoch`.
+fn epoch_accounts(result_len: usize) -> Vec<SimAccount> {
+    let mut accounts = Vec::new();
+    if !cfg!(feature = "instruction-epoch") {
+        accounts.push(SimAccount::clock(CLOCK_EPOCH));
+    }
+    accounts.push(SimAccount::result(RESULT_KEY, PROGRAM_ID, result_len));
+    accounts
+}
+
+fn result_only(result_len: usize) -> Vec<SimAccount> {
+    vec![SimAccount::result(RESULT_KEY, PROGRAM_ID, result_len)]
+}
+
+/// Packs `instruction`, runs it over `accounts` and returns the return
+/// code and the result account's data.
+fn run(instruction: &Instruction, mut accounts: Vec<SimAccount>) -> (u64, Vec<u8>) {
+    let mut data = [0u8; MAX_INSTRUCTION_LEN];
+    let len = instruction.pack(&mut data);
+    let code = host_sim::invoke(&mut accounts, &data[..len], &PROGRAM_ID);
+    let result = accounts.pop().expect("a result account").data;
+    (code, result)
+}
+
+fn run_raw(data: &[u8], mut accounts: Vec<SimAccount>) -> u64 {
+    host_sim::invoke(&mut accounts, data, &PROGRAM_ID)
+}
+
+fn portion_bytes(portions: &[u64]) -> Vec<u8> {
+    portions.iter().flat_map(|p| p.to_le_bytes()).collect()
+}
+
+#[test]
+fn malformed_data_returns_each_documented_code() {
+    let accounts = || epoch_accounts(ALLOWANCE_RESULT_LEN);
+    let mut allowance = [0u8; MAX_INSTRUCTION_LEN];
+    let allowance_len = Instruction::Allowance {
+        backend_id: STREAMING,
+        operands: operands(1_000_000),
+    }
+    .pack(&mut allowance);
+
+    assert_eq!(run_raw(&[0], accounts()), 1);
+    assert_eq!(run_raw(&[0x7f, STREAMING], accounts()), 2);
+    assert_eq!(run_raw(&allowance[..allowance_len - 1], accounts()), 3);
+
+    let zero_activating = Instruction::Allowance {
+        backend_id: STREAMING,
+        operands: Operands {
+            cluster_state: StakeHistoryEntry {
+                activating: 0,
+                ..CLUSTER
+            },
+            ..operands(1_000_000)
+        },
+    };
+    assert_eq!(run(&zero_activating, accounts()).0, 4);
+
+    let unknown_backend = Instruction::SelfTest {
+        backend_id: UNKNOWN_BACKEND,
+    };
+    assert_eq!(
+        run(&unknown_backend, result_only(SELF_TEST_RESULT_LEN)).0,
+        5
+    );
+
+    // `Instruction::pack` will not build an oversized batch, so append one
+    // more portion to a full one by hand.
+    let full = portion_bytes(&[1; MAX_BATCH_LEN]);
+    let batch = Instruction::Batch {
+        backend_id: STREAMING,
+        operands: BatchOperands::new(INSTRUCTION_EPOCH, CLUSTER, None, &full).unwrap(),
+    };
+    let mut data = [0u8; MAX_INSTRUCTION_LEN + 8];
+    let len = batch.pack(&mut data);
+    data[len..len + 8].copy_from_slice(&1u64.to_le_bytes());
+    assert_eq!(run_raw(&data[..len + 8], accounts()), 6);
+
+    // Streaming results always pass their own check, so no operands reach
+    // code 7; pin the value the entrypoint returns for it instead.
+    assert_eq!(code(InstructionError::ConsensusMismatch), 7);
+
+    for (error, expected) in [
+        (InstructionError::MissingHeader, 1),
+        (InstructionError::UnknownTag, 2),
+        (InstructionError::WrongLength, 3),
+        (InstructionError::ZeroDivisor, 4),
+        (InstructionError::UnknownBackend, 5),
+        (InstructionError::BatchTooLarge, 6),
+        (InstructionError::Divergence, 8),
+    ] {
+        assert_eq!(code(error), expected, "{error:?}");
+    }
+}
+
+/// Consensus builds run `Allowance` on streaming whatever byte 1 names.
+#[cfg(feature = "consensus")]
+#[test]
+fn consensus_allowance_ignores_the_backend_id() {
+    let instruction = Instruction::Allowance {
+        backend_id: UNKNOWN_BACKEND,
+        operands: operands(1_000_000),
+    };
+    let (code, data) = run(&instruction, epoch_accounts(ALLOWANCE_RESULT_LEN));
+    assert_eq!(code, 0);
+    assert_eq!(AllowanceResult::read(&data).unwrap().backend_id, STREAMING);
+}
+
+#[cfg(not(feature = "consensus"))]
+#[test]
+fn allowance_rejects_an_unknown_backend() {
+    let instruction = Instruction::Allowance {
+        backend_id: UNKNOWN_BACKEND,
+        operands: operands(1_000_000),
+    };
+    let (code, _) = run(&instruction, epoch_accounts(ALLOWANCE_RESULT_LEN));
+    assert_eq!(code, 5);
+}
+
+#[test]
+fn pack_and_unpack_are_inverse_for_every_instruction() {
+    let portions = portion_bytes(&[1, 2, u64::MAX]);
+    let batch = BatchOperands::new(7, CLUSTER, None, &portions).unwrap();
+    let instructions = [
+        Instruction::Allowance {
+            backend_id: 1,
+            operands: operands(42),
+        },
+        Instruction::DelegationStatus {
+            backend_id: 2,
+            operands: DelegationOperands {
+                target_epoch: 9,
+                stake: 1_000,
+                activation_epoch: 3,
+                deactivation_epoch: u64::MAX,
+                new_rate_activation_epoch: None,
+            },
+        },
+        Instruction::MeasureComputeUnits {
+            backend_id: 3,
+            operands: operands(43),
+            repetitions: 11,
+        },
+        Instruction::SelfTest { backend_id: 4 },
+        Instruction::Stress {
+            backend_id: 5,
+            seed: 0xdead_beef,
+            iterations: 12,
+        },
+        Instruction::ValidateSplit {
+            backend_id: 6,
+            stake: 5_000,
+            split_lamports: 2_000,
+            rent_exempt_reserve: 1_000,
+        },
+        Instruction::Batch {
+            backend_id: 0,
+            operands: batch,
+        },
+        Instruction::Differential {
+            backend_id: 1,
+            other_backend_id: 6,
+            operands: operands(44),
+        },
+        Instruction::Probe {
+            backend_id: 5,
+            operands: operands(45),
+        },
+    ];
+
+    for instruction in instructions {
+        let mut data = [0u8; MAX_INSTRUCTION_LEN];
+        let len = instruction.pack(&mut data);
+        assert_eq!(Instruction::unpack(&data[..len]), Ok(instruction));
+
+        let diag = InstructionFlags { diag: true };
+        let len = instruction.pack_with_flags(diag, &mut data);
+        assert_eq!(Instruction::unpack(&data[..len]), Ok(instruction));
+        assert_eq!(InstructionFlags::from_data(&data[..len]), diag);
+    }
+}
+
+#[test]
+fn batch_matches_one_allowance_per_account() {
+    let portions = [0, 1, 999, 1_000_000, 123_456_789];
+    let bytes = portion_bytes(&portions);
+    let instruction = Instruction::Batch {
+        backend_id: STREAMING,
+        operands: BatchOperands::new(
+            INSTRUCTION_EPOCH,
+            CLUSTER,
+            Some(NEW_RATE_ACTIVATION_EPOCH),
+            &bytes,
+        )
+        .unwrap(),
+    };
+    let (code, data) = run(
+        &instruction,
+        epoch_accounts(results::batch_result_len(portions.len())),
+    );
+    assert_eq!(code, 0);
+
+    let header = BatchResultHeader::read(&data).unwrap();
+    assert_eq!(header.count as usize, portions.len());
+    assert_eq!(header.backend_id, STREAMING);
+    assert_eq!(header.algo_version, ALGO_VERSION);
+    assert_eq!(data[3..8], [0; 5]);
+
+    for (index, portion) in portions.into_iter().enumerate() {
+        let single = Instruction::Allowance {
+            backend_id: STREAMING,
+            operands: operands(portion),
+        };
+        let (code, single) = run(&single, epoch_accounts(ALLOWANCE_RESULT_LEN));
+        assert_eq!(code, 0);
+        let single = AllowanceResult::read(&single).unwrap();
+        assert_eq!(
+            results::read_batch_entry(&data, index),
+            Some((single.activation, single.deactivation)),
+            "account {index}, portion {portion}"
+        );
+    }
+}
+
+#[test]
+fn epoch_comes_from_the_clock_unless_built_with_instruction_epoch() {
+    let instruction = Instruction::Allowance {
+        backend_id: STREAMING,
+        operands: operands(1_000_000),
+    };
+    let (code, data) = run(&instruction, epoch_accounts(ALLOWANCE_RESULT_LEN));
+    assert_eq!(code, 0);
+    let result = AllowanceResult::read(&data).unwrap();
+    assert_eq!(
+        (result.activation, result.deactivation),
+        expected_allowances()
+    );
+}
+
+/// Without `instruction-epoch` the Clock sysvar is required and checked.
+#[cfg(not(feature = "instruction-epoch"))]
+#[test]
+fn allowance_requires_the_clock_sysvar() {
+    let instruction = Instruction::Allowance {
+        backend_id: STREAMING,
+        operands: operands(1_000_000),
+    };
+    let not_clock = SimAccount {
+        key: [1; 32],
+        ..SimAccount::clock(CLOCK_EPOCH)
+    };
+    let accounts = vec![
+        not_clock,
+        SimAccount::result(RESULT_KEY, PROGRAM_ID, ALLOWANCE_RESULT_LEN),
+    ];
+    assert_eq!(
+        run(&instruction, accounts).0,
+        u64::from(ProgramError::InvalidArgument)
+    );
+}
+
+#[test]
+fn allowance_result_layout() {
+    let instruction = Instruction::Allowance {
+        backend_id: STREAMING,
+        operands: operands(1_000_000),
+    };
+    let (code, data) = run(&instruction, epoch_accounts(ALLOWANCE_RESULT_LEN + 4));
+    assert_eq!(code, 0);
+
+    let (activation, deactivation) = expected_allowances();
+    assert_eq!(data[0..8], activation.to_le_bytes());
+    assert_eq!(data[8..16], deactivation.to_le_bytes());
+    assert_eq!(data[16], STREAMING);
+    assert_eq!(data[17], ALGO_VERSION);
+    let result = AllowanceResult::read(&data).unwrap();
+    assert_eq!(data[18], result.activation_condition.code());
+    assert_eq!(data[19], result.deactivation_condition.code());
+    // Trailing bytes are left untouched.
+    assert_eq!(data[ALLOWANCE_RESULT_LEN..], [0; 4]);
+}
+
+#[test]
+fn compute_units_result_layout() {
+    let instruction = Instruction::MeasureComputeUnits {
+        backend_id: STREAMING,
+        operands: operands(1_000_000),
+        repetitions: 3,
+    };
+    let (code, data) = run(&instruction, epoch_accounts(COMPUTE_UNITS_RESULT_LEN));
+    assert_eq!(code, 0);
+
+    let result = ComputeUnitsResult::read(&data).unwrap();
+    assert_eq!(data[0..8], result.activation_units.to_le_bytes());
+    assert_eq!(data[8..16], result.deactivation_units.to_le_bytes());
+    assert_eq!(data[16], STREAMING);
+    assert_eq!(data[17], ALGO_VERSION);
+    assert_eq!(data[18..26], 3u64.to_le_bytes());
+}
+
+#[test]
+fn self_test_result_layout() {
+    let instruction = Instruction::SelfTest {
+        backend_id: STREAMING,
+    };
+    let (code, data) = run(&instruction, result_only(SELF_TEST_RESULT_LEN));
+    assert_eq!(code, 0);
+
+    assert_eq!(data[0..4], (self_test::VECTORS.len() as u32).to_le_bytes());
+    let result = SelfTestResult::read(&data).unwrap();
+    assert_eq!(data[4..8], result.mismatches.to_le_bytes());
+    assert_eq!(data[8], STREAMING);
+    assert_eq!(data[9], ALGO_VERSION);
+}
+
+#[test]
+fn stress_result_layout() {
+    let instruction = Instruction::Stress {
+        backend_id: STREAMING,
+        seed: 1,
+        iterations: 10,
+    };
+    let (code, data) = run(&instruction, result_only(STRESS_RESULT_LEN));
+    assert_eq!(code, 0);
+
+    let result = StressResult::read(&data).unwrap();
+    assert_eq!(data[0..8], result.hash.to_le_bytes());
+    assert_eq!(data[8..16], 10u64.to_le_bytes());
+    assert_eq!(data[16], STREAMING);
+    assert_eq!(data[17], ALGO_VERSION);
+}
+
+#[test]
+fn delegation_status_result_layout() {
+    let instruction = Instruction::DelegationStatus {
+        backend_id: STREAMING,
+        operands: DelegationOperands {
+            target_epoch: 10,
+            stake: 1_000,
+            activation_epoch: 9,
+            deactivation_epoch: u64::MAX,
+            new_rate_activation_epoch: None,
+        },
+    };
+    // One history entry for the activation epoch, with the delegation the
+    // only activating stake: it warms up by a quarter of the cluster's
+    // effective stake.
+    let mut history = 1u64.to_le_bytes().to_vec();
+    for field in [9, 400, 1_000, 0] {
+        history.extend_from_slice(&u64::to_le_bytes(field));
+    }
+    let sysvar = SimAccount {
+        key: stake_history_sysvar::ID,
+        data: history,
+        ..SimAccount::default()
+    };
+    let accounts = vec![
+        sysvar,
+        SimAccount::result(RESULT_KEY, PROGRAM_ID, DELEGATION_STATUS_RESULT_LEN),
+    ];
+    let (code, data) = run(&instruction, accounts);
+    assert_eq!(code, 0);
+
+    assert_eq!(data[0..8], 100u64.to_le_bytes());
+    assert_eq!(data[8..16], 900u64.to_le_bytes());
+    assert_eq!(data[16..24], 0u64.to_le_bytes());
+    assert_eq!(data[24], STREAMING);
+    assert_eq!(data[25], ALGO_VERSION);
+    let result = DelegationStatusResult::read(&data).unwrap();
+    assert_eq!(
+        (
+            result.status.effective,
+            result.status.activating,
+            result.status.deactivating
+        ),
+        (100, 900, 0)
+    );
+}
+
+#[test]
+fn differential_result_layout() {
+    let instruction = Instruction::Differential {
+        backend_id: STREAMING,
+        other_backend_id: STREAMING,
+        operands: operands(1_000_000),
+    };
+    let (code, data) = run(&instruction, epoch_accounts(DIFFERENTIAL_RESULT_LEN));
+    assert_eq!(code, 0);
+
+    let (activation, deactivation) = expected_allowances();
+    assert_eq!(data[0], 0);
+    assert_eq!(data[1..4], [STREAMING, STREAMING, ALGO_VERSION]);
+    assert_eq!(data[4..8], [0; 4]);
+    assert_eq!(data[8..16], activation.to_le_bytes());
+    assert_eq!(data[16..24], deactivation.to_le_bytes());
+    assert_eq!(data[24..32], activation.to_le_bytes());
+    assert_eq!(data[32..40], deactivation.to_le_bytes());
+    assert!(DifferentialResult::read(&data).is_some());
+}
+
+/// Every compiled-in backend apart from the placeholder agrees with
+/// streaming on operands well inside 64 bits.
+#[test]
+fn differential_agrees_across_backends() {
+    for backend in Backend::ALL.iter().filter(|b| b.id() != 4) {
+        let instruction = Instruction::Differential {
+            backend_id: STREAMING,
+            other_backend_id: backend.id(),
+            operands: operands(1_000_000),
+        };
+        let (code, data) = run(&instruction, epoch_accounts(DIFFERENTIAL_RESULT_LEN));
+        assert_eq!(code, 0, "{backend:?}");
+        assert_eq!(
+            DifferentialResult::read(&data).unwrap().divergence(),
+            0,
+            "{backend:?}"
+        );
+    }
+}
+
+/// The placeholder backend disagrees with streaming, which fails the
+/// instruction with code 8 and logs both results.
+#[cfg(feature = "plain")]
+#[test]
+fn differential_divergence_fails_and_logs_both_results() {
+    const PLAIN: u8 = 4;
+    let instruction = Instruction::Differential {
+        backend_id: STREAMING,
+        other_backend_id: PLAIN,
+        operands: operands(1_000_000),
+    };
+    let (code, data) = run(&instruction, epoch_accounts(DIFFERENTIAL_RESULT_LEN));
+    assert_eq!(code, 8);
+
+    let result = DifferentialResult::read(&data).unwrap();
+    let divergence = results::DIVERGENCE_ACTIVATION | results::DIVERGENCE_DEACTIVATION;
+    assert_eq!(data[0], divergence);
+    assert_eq!(result.first, expected_allowances());
+    assert_eq!(
+        stake_ebpf_check::syscalls::mock::last_log_64(),
+        [
+            divergence.into(),
+            result.first.0,
+            result.first.1,
+            result.second.0,
+            result.second.1,
+        ]
+    );
+}
+
+/// An input region entry: a whole account, or the index of an earlier one
+/// it repeats.
+enum Entry<'a> {
+    Account(&'a SimAccount),
+    Duplicate(u8),
+}
+
+/// The aligned input region the way the loader's own serializer writes it,
+/// built here independently of [`host_sim::InputRegion`].
+fn loader_region(entries: &[Entry], instruction_data: &[u8], program_id: &Pubkey) -> Vec<u8> {
+    let mut region = (entries.len() as u64).to_le_bytes().to_vec();
+    for entry in entries {
+        match entry {
+            Entry::Duplicate(index) => {
+                region.push(*index);
+                region.extend_from_slice(&[0; 7]);
+            }
+            Entry::Account(account) => {
+                region.extend_from_slice(&[
+                    u8::MAX,
+                    account.is_signer as u8,
+                    account.is_writable as u8,
+                    account.executable as u8,
+                    0,
+                    0,
+                    0,
+                    0,
+                ]);
+                region.extend_from_slice(&account.key);
+                region.extend_from_slice(&account.owner);
+                region.extend_from_slice(&account.lamports.to_le_bytes());
+                region.extend_from_slice(&(account.data.len() as u64).to_le_bytes());
+                region.extend_from_slice(&account.data);
+                let padding = MAX_PERMITTED_DATA_INCREASE + account.data.len().wrapping_neg() % 8;
+                region.resize(region.len() + padding, 0);
+                region.extend_from_slice(&u64::MAX.to_le_bytes());
+            }
+        }
+    }
+    region.extend_from_slice(&(instruction_data.len() as u64).to_le_bytes());
+    region.extend_from_slice(instruction_data);
+    region.extend_from_slice(program_id);
+    region
+}
+
+fn probe_data() -> Vec<u8> {
+    let mut data = [0u8; MAX_INSTRUCTION_LEN];
+    let len = Instruction::Probe {
+        backend_id: STREAMING,
+        operands: operands(1_000_000),
+    }
+    .pack(&mut data);
+    data[..len].to_vec()
+}
+
+#[test]
+fn deserialize_reads_a_loader_serialized_region() {
+    let clock = SimAccount {
+        lamports: 1_169_280,
+        ..SimAccount::clock(CLOCK_EPOCH)
+    };
+    let result = SimAccount {
+        lamports: 2,
+        ..SimAccount::result(RESULT_KEY, PROGRAM_ID, ALLOWANCE_RESULT_LEN)
+    };
+    // Odd-length data, so the next account starts after alignment padding.
+    let signer = SimAccount {
+        key: [3; 32],
+        owner: [4; 32],
+        lamports: u64::MAX,
+        data: vec![5, 6, 7],
+        is_signer: true,
+        is_writable: false,
+        executable: true,
+    };
+    let entries = [
+        Entry::Account(&clock),
+        Entry::Account(&result),
+        Entry::Duplicate(1),
+        Entry::Account(&signer),
+    ];
+    let instruction_data = probe_data();
+    let mut region = loader_region(&entries, &instruction_data, &PROGRAM_ID);
+
+    let mut accounts = [const { None }; MAX_ACCOUNTS];
+    let (count, data, program_id) =
+        unsafe { entrypoint_raw::deserialize(region.as_mut_ptr(), &mut accounts) }.unwrap();
+    assert_eq!(count, entries.len());
+    assert_eq!(data, instruction_data);
+    assert_eq!(*program_id, PROGRAM_ID);
+
+    for (index, entry) in entries.iter().enumerate() {
+        let slot = accounts[index].as_ref();
+        let Entry::Account(expected) = entry else {
+            assert!(slot.is_none(), "duplicate at {index} has a slot");
+            continue;
+        };
+        let info = slot.unwrap_or_else(|| panic!("account {index} missing"));
+        assert_eq!(*info.key, expected.key, "account {index}");
+        assert_eq!(*info.owner, expected.owner, "account {index}");
+        assert_eq!(info.lamports(), expected.lamports, "account {index}");
+        assert_eq!(info.data(), expected.data, "account {index}");
+        assert_eq!(
+            (info.is_signer, info.is_writable, info.executable),
+            (
+                expected.is_signer,
+                expected.is_writable,
+                expected.executable
+            ),
+            "account {index}"
+        );
+    }
+}
+
+#[test]
+fn probe_accepts_duplicate_accounts() {
+    let result = SimAccount::result(RESULT_KEY, PROGRAM_ID, ALLOWANCE_RESULT_LEN);
+    let entries = [
+        Entry::Account(&result),
+        Entry::Duplicate(0),
+        Entry::Duplicate(0),
+    ];
+    let mut region = loader_region(&entries, &probe_data(), &PROGRAM_ID);
+    assert_eq!(unsafe { program::entrypoint(region.as_mut_ptr()) }, 0);
+}
+
+/// A duplicate where an instruction expects an account reads as missing.
+#[test]
+fn duplicate_in_a_used_position_is_missing() {
+    let sysvar = SimAccount {
+        key: stake_history_sysvar::ID,
+        data: 0u64.to_le_bytes().to_vec(),
+        ..SimAccount::default()
+    };
+    let entries = [Entry::Account(&sysvar), Entry::Duplicate(0)];
+    let mut data = [0u8; MAX_INSTRUCTION_LEN];
+    let len = Instruction::DelegationStatus {
+        backend_id: STREAMING,
+        operands: DelegationOperands {
+            target_epoch: 10,
+            stake: 1_000,
+            activation_epoch: 9,
+            deactivation_epoch: u64::MAX,
+            new_rate_activation_epoch: None,
+        },
+    }
+    .pack(&mut data);
+    let mut region = loader_region(&entries, &data[..len], &PROGRAM_ID);
+    assert_eq!(
+        unsafe { program::entrypoint(region.as_mut_ptr()) },
+        u64::from(ProgramError::NotEnoughAccountKeys)
+    );
+}
+
+#[test]
+fn more_than_max_accounts_is_an_invalid_argument() {
+    let accounts = vec![SimAccount::default(); MAX_ACCOUNTS];
+    assert_eq!(run_raw(&probe_data(), accounts), 0);
+
+    let accounts = vec![SimAccount::default(); MAX_ACCOUNTS + 1];
+    assert_eq!(
+        run_raw(&probe_data(), accounts),
+        u64::from(ProgramError::InvalidArgument)
+    );
+}
@@ -0,0 +1,101 @@
+//! Every packed `entrypoint(arg)` input, on every compiled-in backend.
+//!
+//! ```text
+//! cargo test --release --target <host-triple> -p stake-ebpf-check \
+//!     --features exhaustive-sweep,<backends> --test exhaustive
+//! ```
+//!
+//! Covers all of [`DOMAIN`], the `(account_stake, cluster_share)` pairs
+//! the packed symbols can express, split across every available core. A
+//! release build with three backends checks about a million args a second
+//! per core.
+//! Fails listing the first few `arg`s on which any backend's allowances
+//! differ from the first backend's, each also shrunk and written out as a
+//! JSON report (see [`stake_ebpf_check::divergence`]).
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use stake_ebpf_check::divergence;
+use stake_ebpf_check::packed_args::{PackedOperands, DOMAIN};
+use stake_ebpf_check::{Backend, BackendVisitor, StakeCalculator};
+
+/// `arg`s handed to a thread at a time.
+const CHUNK: u64 = 1 << 20;
+const MAX_REPORTS: usize = 20;
+
+/// `(activation, deactivation)`.
+type Allowances = (u64, u64);
+
+struct Run(PackedOperands);
+
+impl BackendVisitor for Run {
+    type Output = Allowances;
+
+    fn visit<T: StakeCalculator>(self) -> Allowances {
+        self.0.allowances::<T>()
+    }
+}
+
+/// The first backend disagreeing with `Backend::ALL[0]` on `arg`, and both
+/// results.
+fn divergence(arg: u64) -> Option<(Backend, Allowances, Allowances)> {
+    let operands = PackedOperands::unpack(arg);
+    let (reference, others) = Backend::ALL.split_first()?;
+    let expected = reference.visit(Run(operands));
+    others.iter().find_map(|&backend| {
+        let computed = backend.visit(Run(operands));
+        (computed != expected).then_some((backend, computed, expected))
+    })
+}
+
+#[test]
+fn packed_domain_agrees() {
+    if Backend::ALL.len() < 2 {
+        eprintln!("skipping: needs at least two backends to compare");
+        return;
+    }
+
+    let next_chunk = AtomicU64::new(0);
+    let divergent = AtomicU64::new(0);
+    let reports = Mutex::new(Vec::new());
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+
+    std::thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(|| loop {
+                let start = next_chunk.fetch_add(CHUNK, Ordering::Relaxed);
+                if start >= DOMAIN {
+                    break;
+                }
+                for arg in start..(start + CHUNK).min(DOMAIN) {
+                    let Some((backend, computed, expected)) = divergence(arg) else {
+                        continue;
+                    };
+                    divergent.fetch_add(1, Ordering::Relaxed);
+                    let mut reports = reports.lock().unwrap();
+                    if reports.len() < MAX_REPORTS {
+                        reports.push(format!(
+                            "arg {arg:#010x} {backend:?}: {computed:?}, {:?}: {expected:?}",
+                            Backend::ALL[0]
+                        ));
+                        for report in divergence::packed_reports(arg, "exhaustive") {
+                            divergence::write_report(&divergence::report_dir(), &report)
+                                .expect("writing divergence report");
+                        }
+                    }
+                }
+            });
+        }
+    });
+
+    let divergent = divergent.into_inner();
+    let mut reports = reports.into_inner().unwrap();
+    reports.sort();
+    println!("{DOMAIN} args on {:?}: {divergent} divergent", Backend::ALL);
+    assert!(
+        divergent == 0,
+        "{divergent} args diverge, (activation, deactivation) per backend:\n{}",
+        reports.join("\n")
+    );
+}
@@ -0,0 +1,191 @@
+//! Replays delegations recorded on mainnet and checks every compiled-in
+//! backend reproduces the activation status the cluster reported.
+//!
+//! ```text
+//! cargo test --release --target <host-triple> -p stake-ebpf-check \
+//!     --features host-sim,<backends> --test golden
+//! ```
+//!
+//! Vectors are the `*.json` files under `golden/mainnet/`; see the README
+//! there for the format and how they are harvested. The test passes without
+//! checking anything, with a note on stderr, if there are none.
+
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+use stake_ebpf_check::delegation::StakeActivationStatus;
+use stake_ebpf_check::stake_history::StakeHistoryEntry;
+use stake_ebpf_check::state::Delegation;
+use stake_ebpf_check::{Backend, BackendVisitor, Epoch, StakeCalculator};
+
+/// One recorded delegation and what the cluster reported for it.
+struct GoldenVector {
+    /// Where the vector came from, for failure messages.
+    source: String,
+    new_rate_activation_epoch: Option<Epoch>,
+    delegation: Delegation,
+    /// Newest first, as the sysvar stores it.
+    history: Vec<(Epoch, StakeHistoryEntry)>,
+    observed: Vec<(Epoch, StakeActivationStatus)>,
+}
+
+fn u64_field(value: &Value, key: &str) -> Result<u64, String> {
+    value[key]
+        .as_u64()
+        .ok_or_else(|| format!("`{key}` missing or not a u64"))
+}
+
+fn optional_epoch(value: &Value, key: &str) -> Result<Option<Epoch>, String> {
+    match &value[key] {
+        Value::Null => Ok(None),
+        epoch => epoch
+            .as_u64()
+            .map(Some)
+            .ok_or_else(|| format!("`{key}` not an epoch or null")),
+    }
+}
+
+/// `[[epoch, {effective, activating, deactivating}], ...]`.
+fn by_epoch(value: &Value, key: &str) -> Result<Vec<(Epoch, [u64; 3])>, String> {
+    value[key]
+        .as_array()
+        .ok_or_else(|| format!("`{key}` missing or not an array"))?
+        .iter()
+        .map(|pair| {
+            let epoch = pair[0]
+                .as_u64()
+                .ok_or_else(|| format!("`{key}`: epoch not a u64"))?;
+            let entry = &pair[1];
+            Ok((
+                epoch,
+                [
+                    u64_field(entry, "effective")?,
+                    u64_field(entry, "activating")?,
+                    u64_field(entry, "deactivating")?,
+                ],
+            ))
+        })
+        .collect()
+}
+
+fn parse(source: String, json: &str) -> Result<GoldenVector, String> {
+    let value: Value = serde_json::from_str(json).map_err(|e| e.to_string())?;
+    if value["version"] != 1 {
+        return Err(format!("unsupported version {}", value["version"]));
+    }
+
+    let delegation = &value["delegation"];
+    let delegation = Delegation {
+        stake: u64_field(delegation, "stake")?,
+        activation_epoch: u64_field(delegation, "activation_epoch")?,
+        deactivation_epoch: optional_epoch(delegation, "deactivation_epoch")?.unwrap_or(u64::MAX),
+        ..Delegation::default()
+    };
+
+    let mut history: Vec<_> = by_epoch(&value, "history")?
+        .into_iter()
+        .map(|(epoch, [effective, activating, deactivating])| {
+            let entry = StakeHistoryEntry {
+                activating,
+                deactivating,
+                effective,
+            };
+            (epoch, entry)
+        })
+        .collect();
+    history.sort_by_key(|&(epoch, _)| std::cmp::Reverse(epoch));
+
+    let observed = by_epoch(&value, "observed")?
+        .into_iter()
+        .map(|(epoch, [effective, activating, deactivating])| {
+            let status = StakeActivationStatus {
+                effective,
+                activating,
+                deactivating,
+            };
+            (epoch, status)
+        })
+        .collect();
+
+    Ok(GoldenVector {
+        source,
+        new_rate_activation_epoch: optional_epoch(&value, "new_rate_activation_epoch")?,
+        delegation,
+        history,
+        observed,
+    })
+}
+
+fn golden_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("golden/mainnet")
+}
+
+fn load() -> Vec<GoldenVector> {
+    let mut paths: Vec<_> = std::fs::read_dir(golden_dir())
+        .into_iter()
+        .flatten()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "json")
+        })
+        .collect();
+    paths.sort();
+    paths
+        .into_iter()
+        .map(|path| {
+            let json = std::fs::read_to_string(&path).unwrap();
+            let source = path.file_name().unwrap().to_string_lossy().into_owned();
+            parse(source, &json).unwrap_or_else(|e| panic!("{}: {e}", path.display()))
+        })
+        .collect()
+}
+
+struct Status<'a> {
+    vector: &'a GoldenVector,
+    epoch: Epoch,
+}
+
+impl BackendVisitor for Status<'_> {
+    type Output = StakeActivationStatus;
+
+    fn visit<T: StakeCalculator>(self) -> StakeActivationStatus {
+        self.vector
+            .delegation
+            .stake_activating_and_deactivating::<T>(
+                self.epoch,
+                self.vector.history.as_slice(),
+                self.vector.new_rate_activation_epoch,
+            )
+    }
+}
+
+#[test]
+fn backends_reproduce_mainnet() {
+    let vectors = load();
+    if vectors.is_empty() {
+        eprintln!("skipping: no vectors in {}", golden_dir().display());
+        return;
+    }
+
+    let mut mismatches = Vec::new();
+    for vector in &vectors {
+        for &(epoch, observed) in &vector.observed {
+            for &backend in Backend::ALL {
+                let computed = backend.visit(Status { vector, epoch });
+                if computed != observed {
+                    mismatches.push(format!(
+                        "{} epoch {epoch} {backend:?}: computed {computed:?}, observed {observed:?}",
+                        vector.source
+                    ));
+                }
+            }
+        }
+    }
+    assert!(
+        mismatches.is_empty(),
+        "{} mismatches:\n{}",
+        mismatches.len(),
+        mismatches.join("\n")
+    );
+}
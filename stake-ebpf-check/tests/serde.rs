@@ -0,0 +1,52 @@
+//! The `serde` derives, round-tripped through JSON.
+//!
+//! ```text
+//! cargo test --release --target <host-triple> -p stake-ebpf-check \
+//!     --features host-sim,serde,<backends> --test serde
+//! ```
+
+use std::fmt::Debug;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use stake_ebpf_check::condition::Condition;
+use stake_ebpf_check::delegation::StakeActivationStatus;
+use stake_ebpf_check::results::{AllowanceResult, DelegationStatusResult, ValidateSplitResult};
+use stake_ebpf_check::split::SplitError;
+use stake_ebpf_check::stake_history::StakeHistoryEntry;
+use stake_ebpf_check::state::Delegation;
+
+fn round_trip<T: Serialize + DeserializeOwned + PartialEq + Debug>(value: T) {
+    let json = serde_json::to_string(&value).unwrap();
+    let back: T = serde_json::from_str(&json).unwrap_or_else(|e| panic!("{json}: {e}"));
+    assert_eq!(back, value, "{json}");
+}
+
+#[test]
+fn domain_types_round_trip_through_json() {
+    round_trip(StakeHistoryEntry {
+        activating: 1,
+        deactivating: 2,
+        effective: u64::MAX,
+    });
+    round_trip(Delegation::new(&[7; 32], 5_000_000_000, 12));
+    round_trip(StakeActivationStatus::with_deactivating(3));
+    round_trip(AllowanceResult {
+        activation: 10,
+        deactivation: 20,
+        backend_id: 6,
+        algo_version: 1,
+        activation_condition: Condition::RateLimited,
+        deactivation_condition: Condition::Clamped,
+    });
+    round_trip(DelegationStatusResult {
+        status: StakeActivationStatus::with_effective(9),
+        backend_id: 5,
+        algo_version: 1,
+    });
+    round_trip(ValidateSplitResult {
+        minimum_delegation: 1,
+        outcome: Err(SplitError::InsufficientFunds),
+        algo_version: 1,
+    });
+}
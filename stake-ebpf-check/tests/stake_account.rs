@@ -0,0 +1,130 @@
+//! The stake account reader and writer against upstream's own encoding.
+//!
+//! ```text
+//! cargo test --release --target <host-triple> -p stake-ebpf-check \
+//!     --features host-sim,<backends> --test stake_account
+//! ```
+//!
+//! Each `StakeStateV2` variant is built in `solana-stake-interface` and
+//! bincode-serialized the way the runtime stores it, then compared byte for
+//! byte with [`write_stake_state`] on the same state and parsed back with
+//! [`parse_stake_state`]. Every field holds distinct bytes, so a field at
+//! the wrong offset shows.
+
+use solana_stake_interface::stake_flags::StakeFlags as UpstreamFlags;
+use solana_stake_interface::state::{
+    Authorized as UpstreamAuthorized, Delegation as UpstreamDelegation, Lockup as UpstreamLockup,
+    Meta as UpstreamMeta, Stake as UpstreamStake, StakeStateV2 as UpstreamState,
+};
+use stake_ebpf_check::stake_account::{parse_stake_state, write_stake_state, STAKE_STATE_V2_SIZE};
+use stake_ebpf_check::stake_flags::StakeFlags;
+use stake_ebpf_check::state::{Authorized, Delegation, Lockup, Meta, Stake, StakeStateV2};
+
+fn meta() -> (Meta, UpstreamMeta) {
+    let ours = Meta {
+        rent_exempt_reserve: 2_282_880,
+        authorized: Authorized {
+            staker: [1; 32],
+            withdrawer: [2; 32],
+        },
+        lockup: Lockup {
+            unix_timestamp: -1_700_000_000,
+            epoch: 3,
+            custodian: [4; 32],
+        },
+    };
+    let upstream = UpstreamMeta {
+        rent_exempt_reserve: ours.rent_exempt_reserve,
+        authorized: UpstreamAuthorized {
+            staker: ours.authorized.staker.into(),
+            withdrawer: ours.authorized.withdrawer.into(),
+        },
+        lockup: UpstreamLockup {
+            unix_timestamp: ours.lockup.unix_timestamp,
+            epoch: ours.lockup.epoch,
+            custodian: ours.lockup.custodian.into(),
+        },
+    };
+    (ours, upstream)
+}
+
+fn stake() -> (Stake, UpstreamStake) {
+    let ours = Stake {
+        delegation: Delegation {
+            deactivation_epoch: 650,
+            ..Delegation::new(&[5; 32], 9_000_000_000, 600)
+        },
+        credits_observed: 12_345,
+    };
+    let delegation = &ours.delegation;
+    let upstream = UpstreamStake {
+        delegation: UpstreamDelegation {
+            voter_pubkey: delegation.voter_pubkey.into(),
+            stake: delegation.stake,
+            activation_epoch: delegation.activation_epoch,
+            deactivation_epoch: delegation.deactivation_epoch,
+            ..UpstreamDelegation::default()
+        },
+        credits_observed: ours.credits_observed,
+    };
+    (ours, upstream)
+}
+
+// Upstream deprecates the flag, but accounts still carry its bit.
+#[allow(deprecated)]
+fn states() -> Vec<(StakeStateV2, UpstreamState)> {
+    let (meta, upstream_meta) = meta();
+    let (stake, upstream_stake) = stake();
+    vec![
+        (StakeStateV2::Uninitialized, UpstreamState::Uninitialized),
+        (
+            StakeStateV2::Initialized(meta),
+            UpstreamState::Initialized(upstream_meta),
+        ),
+        (
+            StakeStateV2::Stake(meta, stake, StakeFlags::empty()),
+            UpstreamState::Stake(upstream_meta, upstream_stake, UpstreamFlags::empty()),
+        ),
+        (
+            StakeStateV2::Stake(
+                meta,
+                stake,
+                StakeFlags::MUST_FULLY_ACTIVATE_BEFORE_DEACTIVATION_IS_PERMITTED,
+            ),
+            UpstreamState::Stake(
+                upstream_meta,
+                upstream_stake,
+                UpstreamFlags::MUST_FULLY_ACTIVATE_BEFORE_DEACTIVATION_IS_PERMITTED,
+            ),
+        ),
+        (StakeStateV2::RewardsPool, UpstreamState::RewardsPool),
+    ]
+}
+
+#[test]
+fn writer_matches_upstream_bincode() {
+    for (ours, upstream) in states() {
+        let expected = bincode::serialize(&upstream).unwrap();
+
+        let mut account = [0u8; STAKE_STATE_V2_SIZE];
+        write_stake_state(&ours, &mut account).unwrap();
+        assert_eq!(account[..expected.len()], expected, "{ours:?}");
+        assert!(
+            account[expected.len()..].iter().all(|&byte| byte == 0),
+            "{ours:?} wrote past upstream's encoding"
+        );
+    }
+}
+
+#[test]
+fn parser_reads_upstream_bincode() {
+    for (ours, upstream) in states() {
+        let mut account = [0u8; STAKE_STATE_V2_SIZE];
+        bincode::serialize_into(&mut account[..], &upstream).unwrap();
+        assert_eq!(parse_stake_state(&account), Ok(ours));
+
+        let mut written = [0u8; STAKE_STATE_V2_SIZE];
+        write_stake_state(&ours, &mut written).unwrap();
+        assert_eq!(written, account, "{ours:?}");
+    }
+}
@@ -0,0 +1,354 @@
+//! Differential tests against the stake program's own activation code in
+//! `solana-stake-interface`.
+//!
+//! ```text
+//! cargo test --release --target <host-triple> -p stake-ebpf-check \
+//!     --features host-sim,<backends> --test upstream
+//! ```
+//!
+//! Each case draws a delegation and a synthetic cluster history from a
+//! seeded generator and compares [`Delegation::stake_activating_and_deactivating`]
+//! under every compiled-in backend not in [`EXCLUDED`] with upstream's at
+//! every epoch from activation until the stake settles. Some histories
+//! start with nothing or a few lamports effective, where upstream's
+//! one-lamport minimum step sets the pace. `UPSTREAM_SEED` and
+//! `UPSTREAM_CASES` override the defaults.
+//!
+//! Upstream weighs each step in `f64`, so where the exact quotient sits
+//! within an ulp of an integer it can land one lamport off the integer
+//! result; the default seed hits this in about 1 status in 2,000, nearly
+//! all while cooling down. Those one-lamport disagreements are counted and
+//! printed but pass; anything larger fails. `UPSTREAM_EXACT=1` fails on
+//! every disagreement.
+
+use solana_stake_interface::stake_history::{
+    StakeHistory as UpstreamHistory, StakeHistoryEntry as UpstreamEntry,
+};
+use solana_stake_interface::state::Delegation as UpstreamDelegation;
+use stake_ebpf_check::delegation::StakeActivationStatus;
+use stake_ebpf_check::stake_history::StakeHistoryEntry;
+use stake_ebpf_check::state::Delegation;
+use stake_ebpf_check::stress::Xorshift64Star;
+use stake_ebpf_check::synthetic::{synthetic_history, ChurnParams};
+use stake_ebpf_check::{Backend, BackendVisitor, Epoch, StakeCalculator};
+
+const DEFAULT_SEED: u64 = 1;
+const DEFAULT_CASES: u64 = 2_000;
+
+/// History length per case; long enough for a whale to warm up at 9%.
+const EPOCHS: usize = 96;
+const MAX_REPORTS: usize = 20;
+
+/// Backend ids left out, like the shared vectors' `exclude` lists: `bnum`
+/// (0) overflows on wide operands, and `plain` (4) is placeholder
+/// arithmetic that divides by each operand, so panics on a zero one.
+const EXCLUDED: [u8; 2] = [0, 4];
+
+struct Case {
+    delegation: Delegation,
+    /// Newest first.
+    history: Vec<(Epoch, StakeHistoryEntry)>,
+    new_rate_activation_epoch: Option<Epoch>,
+}
+
+/// Whether `a` and `b` differ by at most a lamport in every field, as
+/// upstream's float rounding can make them.
+fn within_rounding(a: &StakeActivationStatus, b: &StakeActivationStatus) -> bool {
+    a.effective.abs_diff(b.effective) <= 1
+        && a.activating.abs_diff(b.activating) <= 1
+        && a.deactivating.abs_diff(b.deactivating) <= 1
+}
+
+fn env_u64(name: &str, default: u64) -> u64 {
+    std::env::var(name).map_or(default, |value| {
+        value
+            .parse()
+            .unwrap_or_else(|_| panic!("{name}: not a u64"))
+    })
+}
+
+/// Roughly uniform in magnitude between 1 and `2^bits`.
+fn magnitude(rng: &mut Xorshift64Star, bits: u32) -> u64 {
+    let shift = 64 - (rng.next_u64() % u64::from(bits)) as u32;
+    rng.next_u64().checked_shr(shift).unwrap_or(0).max(1)
+}
+
+fn generate(rng: &mut Xorshift64Star) -> Case {
+    const START_EPOCH: Epoch = 100;
+
+    // Cluster stake up to ~1e18 lamports (a billion SOL), churn up to 20%;
+    // one cluster in eight starts with nothing effective and one in eight
+    // with under a thousand lamports, queueing up to ~1M either way.
+    let start = match rng.next_u64() % 8 {
+        kind @ (0 | 1) => {
+            let effective = match kind {
+                0 => 0,
+                _ => rng.next_u64() % 1_000,
+            };
+            StakeHistoryEntry {
+                effective,
+                activating: magnitude(rng, 20),
+                deactivating: rng.next_u64() % (effective + 1),
+            }
+        }
+        _ => {
+            let effective = magnitude(rng, 60);
+            StakeHistoryEntry {
+                effective,
+                activating: effective / 10_000 * (rng.next_u64() % 2_000),
+                deactivating: effective / 10_000 * (rng.next_u64() % 2_000),
+            }
+        }
+    };
+    let churn = ChurnParams {
+        inflow_bps: rng.next_u64() % 2_000,
+        deactivation_bps: rng.next_u64() % 2_000,
+    };
+    let new_rate_activation_epoch = match rng.next_u64() % 3 {
+        0 => None,
+        _ => Some(START_EPOCH + rng.next_u64() % EPOCHS as u64),
+    };
+    let mut history: Vec<_> =
+        synthetic_history(START_EPOCH, start, churn, EPOCHS, new_rate_activation_epoch).collect();
+    history.reverse();
+
+    let activation_epoch = START_EPOCH + rng.next_u64() % (EPOCHS as u64 / 2);
+    let deactivation_epoch = match rng.next_u64() % 2 {
+        0 => u64::MAX,
+        _ => activation_epoch + rng.next_u64() % (EPOCHS as u64 / 2),
+    };
+    // The account's own stake is part of the cluster's activating stake.
+    let stake = magnitude(rng, 60).min(start.activating.max(1));
+    let delegation = Delegation {
+        stake,
+        activation_epoch,
+        deactivation_epoch,
+        ..Delegation::default()
+    };
+
+    Case {
+        delegation,
+        history,
+        new_rate_activation_epoch,
+    }
+}
+
+fn upstream_history(case: &Case) -> UpstreamHistory {
+    let mut history = UpstreamHistory::default();
+    for &(epoch, entry) in case.history.iter().rev() {
+        history.add(
+            epoch,
+            UpstreamEntry {
+                effective: entry.effective,
+                activating: entry.activating,
+                deactivating: entry.deactivating,
+            },
+        );
+    }
+    history
+}
+
+fn upstream(case: &Case, history: &UpstreamHistory, epoch: Epoch) -> StakeActivationStatus {
+    let delegation = UpstreamDelegation {
+        stake: case.delegation.stake,
+        activation_epoch: case.delegation.activation_epoch,
+        deactivation_epoch: case.delegation.deactivation_epoch,
+        ..UpstreamDelegation::default()
+    };
+    let status = delegation.stake_activating_and_deactivating(
+        epoch,
+        history,
+        case.new_rate_activation_epoch,
+    );
+    StakeActivationStatus {
+        effective: status.effective,
+        activating: status.activating,
+        deactivating: status.deactivating,
+    }
+}
+
+fn compared() -> impl Iterator<Item = Backend> {
+    Backend::ALL
+        .iter()
+        .copied()
+        .filter(|backend| !EXCLUDED.contains(&backend.id()))
+}
+
+struct Status<'a> {
+    case: &'a Case,
+    epoch: Epoch,
+}
+
+impl BackendVisitor for Status<'_> {
+    type Output = StakeActivationStatus;
+
+    fn visit<T: StakeCalculator>(self) -> StakeActivationStatus {
+        self.case.delegation.stake_activating_and_deactivating::<T>(
+            self.epoch,
+            self.case.history.as_slice(),
+            self.case.new_rate_activation_epoch,
+        )
+    }
+}
+
+#[test]
+fn delegation_matches_upstream() {
+    let seed = env_u64("UPSTREAM_SEED", DEFAULT_SEED);
+    let cases = env_u64("UPSTREAM_CASES", DEFAULT_CASES);
+    let exact = env_u64("UPSTREAM_EXACT", 0) != 0;
+    let mut rng = Xorshift64Star::new(seed);
+
+    let mut checked = 0u64;
+    let mut rounding = 0u64;
+    let mut mismatches = Vec::new();
+    for case_index in 0..cases {
+        let case = generate(&mut rng);
+        let history = upstream_history(&case);
+
+        let first = case.delegation.activation_epoch.saturating_sub(1);
+        let last = case.history[0].0 + 1;
+        for epoch in first..=last {
+            let expected = upstream(&case, &history, epoch);
+            for backend in compared() {
+                let computed = backend.visit(Status { case: &case, epoch });
+                checked += 1;
+                if computed == expected {
+                    continue;
+                }
+                if !exact && within_rounding(&computed, &expected) {
+                    rounding += 1;
+                    continue;
+                }
+                if mismatches.len() < MAX_REPORTS {
+                    mismatches.push(format!(
+                        "case {case_index} epoch {epoch} {backend:?}: {:?} rate epoch {:?}\n  \
+                         ours     {computed:?}\n  upstream {expected:?}",
+                        case.delegation, case.new_rate_activation_epoch
+                    ));
+                }
+            }
+        }
+    }
+
+    println!(
+        "seed {seed}: {checked} statuses checked, {rounding} one lamport off from float rounding"
+    );
+    assert!(
+        mismatches.is_empty(),
+        "disagrees with upstream (first {}):\n{}",
+        mismatches.len(),
+        mismatches.join("\n")
+    );
+}
+
+/// Fails unless every compared backend agrees exactly with upstream at
+/// each of `epochs`.
+fn assert_matches_upstream(name: &str, case: &Case, epochs: impl IntoIterator<Item = Epoch>) {
+    let history = upstream_history(case);
+    for epoch in epochs {
+        let expected = upstream(case, &history, epoch);
+        for backend in compared() {
+            let computed = backend.visit(Status { case, epoch });
+            assert_eq!(computed, expected, "{name} epoch {epoch} {backend:?}");
+        }
+    }
+}
+
+/// A steady cluster over `epochs`, newest first, with half its stake
+/// queued each way. Powers of two at the original rate keep upstream's
+/// `f64` steps exact, so these cases can demand exact agreement.
+fn steady_history(epochs: std::ops::RangeInclusive<Epoch>) -> Vec<(Epoch, StakeHistoryEntry)> {
+    const EFFECTIVE: u64 = 1 << 58;
+    let entry = StakeHistoryEntry {
+        effective: EFFECTIVE,
+        activating: EFFECTIVE / 2,
+        deactivating: EFFECTIVE / 2,
+    };
+    epochs.rev().map(|epoch| (epoch, entry)).collect()
+}
+
+/// A quarter of [`steady_history`]'s queue each way, so it takes several
+/// epochs to warm up or cool down even at the original rate.
+const WHALE: u64 = 1 << 55;
+
+fn delegation(activation_epoch: Epoch, deactivation_epoch: Epoch) -> Delegation {
+    Delegation {
+        stake: WHALE,
+        activation_epoch,
+        deactivation_epoch,
+        ..Delegation::default()
+    }
+}
+
+/// A cluster with nothing effective yet still warms up, by upstream's
+/// one-lamport minimum step.
+#[test]
+fn zero_effective_cluster_matches_upstream() {
+    let case = Case {
+        delegation: Delegation {
+            stake: 1_000,
+            activation_epoch: 1,
+            ..Delegation::default()
+        },
+        history: (1..=8)
+            .rev()
+            .map(|epoch| {
+                (
+                    epoch,
+                    StakeHistoryEntry::with_effective_and_activating(0, 1_000),
+                )
+            })
+            .collect(),
+        new_rate_activation_epoch: None,
+    };
+    assert_matches_upstream("zero effective", &case, 0..=10);
+    assert_eq!(
+        upstream(&case, &upstream_history(&case), 5),
+        StakeActivationStatus::with_effective_and_activating(4, 996)
+    );
+}
+
+/// The early returns ahead of the warmup loop, and the loop running out of
+/// history, each against upstream.
+#[test]
+fn edge_delegations_match_upstream() {
+    let history = steady_history(0..=20);
+    let cases = [
+        // Genesis stake: effective from the start, then cooling down.
+        ("bootstrap", delegation(u64::MAX, u64::MAX)),
+        ("bootstrap deactivated", delegation(u64::MAX, 8)),
+        ("never deactivated", delegation(3, u64::MAX)),
+        ("deactivated while warming up", delegation(3, 5)),
+        // Activated and deactivated in the same epoch: never any stake.
+        ("activated and deactivated at once", delegation(6, 6)),
+        ("activation past the history", delegation(30, u64::MAX)),
+    ];
+    for (name, delegation) in cases {
+        let case = Case {
+            delegation,
+            history: history.clone(),
+            new_rate_activation_epoch: None,
+        };
+        assert_matches_upstream(name, &case, 0..=35);
+    }
+
+    // No entry for the activation epoch: taken as fully effective.
+    let case = Case {
+        delegation: delegation(3, u64::MAX),
+        history: steady_history(10..=20),
+        new_rate_activation_epoch: None,
+    };
+    assert_matches_upstream("activation epoch missing", &case, 0..=22);
+    let status = upstream(&case, &upstream_history(&case), 4);
+    assert_eq!(status, StakeActivationStatus::with_effective(WHALE));
+
+    // A gap partway through warmup stops it where it got to.
+    let mut gapped = steady_history(0..=20);
+    gapped.retain(|&(epoch, _)| epoch != 5);
+    let case = Case {
+        delegation: delegation(3, 12),
+        history: gapped,
+        new_rate_activation_epoch: None,
+    };
+    assert_matches_upstream("gap in history", &case, 0..=22);
+}
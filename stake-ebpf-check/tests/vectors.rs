@@ -0,0 +1,75 @@
+//! The shared known-answer vectors on every compiled-in backend.
+//!
+//! ```text
+//! cargo test --release --target <host-triple> -p stake-ebpf-check \
+//!     --features host-sim,<backends> --test vectors
+//! ```
+//!
+//! Reads the files under `test-vectors/vectors/` at run time, the same ones
+//! the program's `SelfTest` table is generated from, and fails listing
+//! every answer a backend not excluded from it gets wrong.
+
+use stake_ebpf_check::self_test;
+use stake_ebpf_check::Backend;
+
+#[test]
+fn backends_match_known_answers() {
+    let vectors = test_vectors::load().unwrap_or_else(|e| panic!("{e}"));
+
+    let mut checked = 0;
+    let mut mismatches = Vec::new();
+    for &backend in Backend::ALL {
+        for vector in vectors.iter().filter(|v| !v.excludes(backend.id())) {
+            let computed = backend.rate_limited_stake_change_bps(
+                vector.rate_bps,
+                vector.account_portion,
+                vector.cluster_portion,
+                vector.cluster_effective,
+            );
+            checked += 1;
+            if computed != vector.expected {
+                mismatches.push(format!(
+                    "{} {backend:?}: {computed}, expected {}",
+                    vector.name, vector.expected
+                ));
+            }
+        }
+    }
+
+    println!("{checked} answers checked on {:?}", Backend::ALL);
+    assert!(
+        mismatches.is_empty(),
+        "wrong answers:\n{}",
+        mismatches.join("\n")
+    );
+}
+
+#[test]
+fn self_test_table_is_generated_from_the_files() {
+    let vectors = test_vectors::load().unwrap_or_else(|e| panic!("{e}"));
+    let embedded: Vec<_> = self_test::VECTORS
+        .iter()
+        .map(|v| {
+            (
+                v.rate_bps,
+                v.account_portion,
+                v.cluster_portion,
+                v.cluster_effective,
+                v.expected,
+            )
+        })
+        .collect();
+    let loaded: Vec<_> = vectors
+        .iter()
+        .map(|v| {
+            (
+                v.rate_bps,
+                v.account_portion,
+                v.cluster_portion,
+                v.cluster_effective,
+                v.expected,
+            )
+        })
+        .collect();
+    assert_eq!(embedded, loaded);
+}
@@ -0,0 +1,220 @@
+//! The shared known-answer vector format and its loader.
+//!
+//! Every layer that checks the rate-limited stake change against fixed
+//! answers reads the same files: the program's embedded `SelfTest` table is
+//! generated from them by `stake-ebpf-check`'s build script, the host test
+//! `tests/vectors.rs` runs them on every compiled-in backend,
+//! `cargo xtask fuzz-seeds` turns them into fuzz corpus entries, and the
+//! `conformance-oracle` server takes and answers requests in the format.
+//!
+//! A vector file is JSON:
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "description": "what the set covers",
+//!   "exclude": ["plain"],
+//!   "vectors": [
+//!     {
+//!       "name": "mainnet",
+//!       "rate_bps": 900,
+//!       "account_portion": 10000000000,
+//!       "cluster_portion": 200000000000000000,
+//!       "cluster_effective": 380000000000000000,
+//!       "expected": 1710000000,
+//!       "exclude": []
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! The operands are those of `StakeCalculator::rate_limited_stake_change_bps`
+//! and `expected` is its exact answer. `exclude` lists backends, by feature
+//! name, that are not held to the answer: at the top level for every vector
+//! in the file, per vector for that one only. Both are optional. Names must
+//! be unique across the files in a directory. Readers reject versions other
+//! than [`VERSION`], so a change to the meaning of a field bumps it.
+
+use std::path::{Path, PathBuf};
+
+use serde_json::{json, Value};
+
+pub const VERSION: u64 = 1;
+
+/// Every backend feature, in wire-id order, so an index here is a backend id.
+pub const BACKENDS: [&str; 7] = [
+    "bnum",
+    "crypto",
+    "fixed",
+    "uint",
+    "plain",
+    "manual",
+    "streaming",
+];
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Vector {
+    pub name: String,
+    pub rate_bps: u64,
+    pub account_portion: u64,
+    pub cluster_portion: u64,
+    pub cluster_effective: u64,
+    pub expected: u64,
+    /// Ids of the backends not held to `expected`, file-level exclusions
+    /// included.
+    pub exclude: Vec<u8>,
+}
+
+impl Vector {
+    pub fn excludes(&self, backend_id: u8) -> bool {
+        self.exclude.contains(&backend_id)
+    }
+}
+
+/// Where the checked-in vectors live.
+pub fn vector_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("vectors")
+}
+
+fn u64_field(value: &Value, key: &str) -> Result<u64, String> {
+    value[key]
+        .as_u64()
+        .ok_or_else(|| format!("`{key}` missing or not a u64"))
+}
+
+/// Backend ids named by the optional `exclude` array of `value`.
+fn exclusions(value: &Value) -> Result<Vec<u8>, String> {
+    let names = match &value["exclude"] {
+        Value::Null => return Ok(Vec::new()),
+        names => names
+            .as_array()
+            .ok_or("`exclude` not an array of backend names")?,
+    };
+    names
+        .iter()
+        .map(|name| {
+            let name = name
+                .as_str()
+                .ok_or("`exclude`: backend name not a string")?;
+            BACKENDS
+                .iter()
+                .position(|backend| *backend == name)
+                .map(|id| id as u8)
+                .ok_or_else(|| format!("`exclude`: unknown backend `{name}`"))
+        })
+        .collect()
+}
+
+/// Parses one vector file.
+pub fn parse(json: &str) -> Result<Vec<Vector>, String> {
+    let value: Value = serde_json::from_str(json).map_err(|e| e.to_string())?;
+    if value["version"] != VERSION {
+        return Err(format!("unsupported version {}", value["version"]));
+    }
+
+    let file_exclude = exclusions(&value)?;
+    value["vectors"]
+        .as_array()
+        .ok_or("`vectors` missing or not an array")?
+        .iter()
+        .enumerate()
+        .map(|(index, vector)| {
+            let name = vector["name"]
+                .as_str()
+                .ok_or_else(|| format!("vector {index}: `name` missing or not a string"))?;
+            let field = |key| u64_field(vector, key).map_err(|e| format!("{name}: {e}"));
+            let mut exclude = exclusions(vector).map_err(|e| format!("{name}: {e}"))?;
+            exclude.extend(&file_exclude);
+            exclude.sort_unstable();
+            exclude.dedup();
+            Ok(Vector {
+                name: name.to_owned(),
+                rate_bps: field("rate_bps")?,
+                account_portion: field("account_portion")?,
+                cluster_portion: field("cluster_portion")?,
+                cluster_effective: field("cluster_effective")?,
+                expected: field("expected")?,
+                exclude,
+            })
+        })
+        .collect()
+}
+
+/// A vector file holding `vectors`, one per line as the checked-in files
+/// are laid out. Exclusions are written per vector, so [`parse`] reads the
+/// same vectors back.
+pub fn to_json(description: &str, vectors: &[Vector]) -> String {
+    let lines: Vec<String> = vectors
+        .iter()
+        .map(|vector| {
+            let exclude = match vector.exclude.is_empty() {
+                true => String::new(),
+                false => {
+                    let names: Vec<_> = vector
+                        .exclude
+                        .iter()
+                        .map(|&id| BACKENDS[usize::from(id)])
+                        .collect();
+                    format!(", \"exclude\": {}", json!(names))
+                }
+            };
+            format!(
+                "    {{ \"name\": {}, \"rate_bps\": {}, \"account_portion\": {}, \
+                 \"cluster_portion\": {}, \"cluster_effective\": {}, \"expected\": {}{exclude} }}",
+                json!(vector.name),
+                vector.rate_bps,
+                vector.account_portion,
+                vector.cluster_portion,
+                vector.cluster_effective,
+                vector.expected
+            )
+        })
+        .collect();
+    format!(
+        "{{\n  \"version\": {VERSION},\n  \"description\": {},\n  \"vectors\": [\n{}\n  ]\n}}\n",
+        json!(description),
+        lines.join(",\n")
+    )
+}
+
+/// The `*.json` files in `dir`, sorted by file name.
+pub fn files(dir: &Path) -> Result<Vec<PathBuf>, String> {
+    let entries = std::fs::read_dir(dir).map_err(|e| format!("{}: {e}", dir.display()))?;
+    let mut paths = Vec::new();
+    for entry in entries {
+        let path = entry.map_err(|e| format!("{}: {e}", dir.display()))?.path();
+        if path
+            .extension()
+            .is_some_and(|extension| extension == "json")
+        {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+/// Every vector in `dir`, file by file in name order.
+pub fn load_dir(dir: &Path) -> Result<Vec<Vector>, String> {
+    let mut vectors: Vec<Vector> = Vec::new();
+    for path in files(dir)? {
+        let json =
+            std::fs::read_to_string(&path).map_err(|e| format!("{}: {e}", path.display()))?;
+        for vector in parse(&json).map_err(|e| format!("{}: {e}", path.display()))? {
+            if vectors.iter().any(|other| other.name == vector.name) {
+                return Err(format!(
+                    "{}: duplicate vector `{}`",
+                    path.display(),
+                    vector.name
+                ));
+            }
+            vectors.push(vector);
+        }
+    }
+    Ok(vectors)
+}
+
+/// [`load_dir`] on [`vector_dir`].
+pub fn load() -> Result<Vec<Vector>, String> {
+    load_dir(&vector_dir())
+}
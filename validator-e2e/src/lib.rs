@@ -0,0 +1,214 @@
+//! Every backend build deployed to a local `solana-test-validator` and run
+//! against the shared vectors, end to end.
+//!
+//! ```text
+//! mkdir -p target/validator-builds
+//! for backend in bnum crypto fixed uint plain manual streaming; do
+//!     cargo build-program --features solana-program,$backend
+//!     cp target/bpfel-unknown-none/release/libstake_ebpf_check.so \
+//!         target/validator-builds/stake_ebpf_check-$backend.so
+//! done
+//! cd validator-e2e && cargo test --release --target <host-triple>
+//! ```
+//!
+//! `solana-test-validator` and the `solana` CLI must be on `PATH`. The
+//! harness starts a validator on free ports with a fresh ledger, deploys
+//! each build in [`build_dir`] with `solana program deploy`, so it goes
+//! through the upgradeable loader's verification like any other program,
+//! and sends it real transactions over RPC. The rbpf runner and `cu-bench`
+//! place the ELF directly and skip all of that.
+//!
+//! The program reports through its result account rather than return data,
+//! so that account is what is read back and decoded (see
+//! `stake_ebpf_check::results`). The harness passes without checking
+//! anything if there are no builds or no validator to run them on.
+
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{write_keypair_file, Keypair};
+use solana_sdk::signer::Signer;
+
+/// Overrides [`build_dir`].
+pub const BUILD_DIR_ENV: &str = "STAKE_EBPF_CHECK_BUILD_DIR";
+
+/// Backend names as they appear in build file names, by wire id.
+pub const BACKENDS: [(&str, u8); 7] = [
+    ("bnum", 0),
+    ("crypto", 1),
+    ("fixed", 2),
+    ("uint", 3),
+    ("plain", 4),
+    ("manual", 5),
+    ("streaming", 6),
+];
+
+/// How long the validator gets to answer its health check.
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Where the `solana-program` builds are: [`BUILD_DIR_ENV`] if set,
+/// otherwise `target/validator-builds` in the workspace.
+pub fn build_dir() -> PathBuf {
+    match std::env::var_os(BUILD_DIR_ENV) {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../target/validator-builds"),
+    }
+}
+
+/// A single-backend program build.
+#[derive(Clone, Debug)]
+pub struct Build {
+    pub backend: &'static str,
+    pub backend_id: u8,
+    pub path: PathBuf,
+}
+
+/// The builds present in [`build_dir`] as `stake_ebpf_check-<backend>.so`,
+/// in wire-id order.
+pub fn builds() -> Vec<Build> {
+    let dir = build_dir();
+    BACKENDS
+        .iter()
+        .map(|&(backend, backend_id)| Build {
+            backend,
+            backend_id,
+            path: dir.join(format!("stake_ebpf_check-{backend}.so")),
+        })
+        .filter(|build| build.path.is_file())
+        .collect()
+}
+
+/// A port nothing is listening on at the moment.
+fn free_port() -> Result<u16, String> {
+    TcpListener::bind("127.0.0.1:0")
+        .and_then(|listener| listener.local_addr())
+        .map(|addr| addr.port())
+        .map_err(|e| format!("finding a free port: {e}"))
+}
+
+/// A `solana-test-validator` process, killed and its ledger removed on
+/// drop.
+pub struct LocalValidator {
+    child: Child,
+    ledger: PathBuf,
+    rpc_url: String,
+}
+
+impl LocalValidator {
+    /// Starts a validator and waits until it is healthy, or returns `None`
+    /// (with a note on stderr) if `solana-test-validator` is not installed.
+    pub fn start() -> Result<Option<Self>, String> {
+        if let Err(e) = Command::new("solana-test-validator")
+            .arg("--version")
+            .output()
+        {
+            eprintln!("skipping: solana-test-validator: {e}");
+            return Ok(None);
+        }
+
+        let ledger = std::env::temp_dir().join(format!("validator-e2e-{}", std::process::id()));
+        // The websocket takes the port after the RPC one.
+        let rpc_port = free_port()?;
+        let child = Command::new("solana-test-validator")
+            .arg("--ledger")
+            .arg(&ledger)
+            .args(["--reset", "--quiet"])
+            .args(["--rpc-port", &rpc_port.to_string()])
+            .args(["--faucet-port", &free_port()?.to_string()])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| format!("starting solana-test-validator: {e}"))?;
+        let mut validator = Self {
+            child,
+            ledger,
+            rpc_url: format!("http://127.0.0.1:{rpc_port}"),
+        };
+
+        let rpc = validator.rpc();
+        let started = Instant::now();
+        while rpc.get_health().is_err() {
+            let log = validator.ledger.join("validator.log");
+            if let Ok(Some(status)) = validator.child.try_wait() {
+                return Err(format!(
+                    "validator exited with {status}; see {}",
+                    log.display()
+                ));
+            }
+            if started.elapsed() > STARTUP_TIMEOUT {
+                return Err(format!(
+                    "validator not healthy after {STARTUP_TIMEOUT:?}; see {}",
+                    log.display()
+                ));
+            }
+            std::thread::sleep(Duration::from_millis(500));
+        }
+        Ok(Some(validator))
+    }
+
+    pub fn rpc_url(&self) -> &str {
+        &self.rpc_url
+    }
+
+    /// A client that waits for `confirmed`, so what it reads reflects the
+    /// transactions it has sent.
+    pub fn rpc(&self) -> RpcClient {
+        RpcClient::new_with_commitment(self.rpc_url.clone(), CommitmentConfig::confirmed())
+    }
+
+    /// A new keypair airdropped `lamports`.
+    pub fn fund(&self, lamports: u64) -> Result<Keypair, String> {
+        let rpc = self.rpc();
+        let keypair = Keypair::new();
+        let signature = rpc
+            .request_airdrop(&keypair.pubkey(), lamports)
+            .and_then(|signature| rpc.poll_for_signature(&signature).map(|()| signature))
+            .map_err(|e| format!("airdrop: {e}"))?;
+        eprintln!("funded {} ({signature})", keypair.pubkey());
+        Ok(keypair)
+    }
+
+    /// Deploys `program` with `solana program deploy`, paid for by `payer`,
+    /// at a new address.
+    pub fn deploy(&self, payer: &Keypair, program: &Path) -> Result<Pubkey, String> {
+        let program_keypair = Keypair::new();
+        let program_id = program_keypair.pubkey();
+        let payer_path = self.ledger.join("payer.json");
+        let program_path = self.ledger.join(format!("program-{program_id}.json"));
+        for (keypair, path) in [(payer, &payer_path), (&program_keypair, &program_path)] {
+            write_keypair_file(keypair, path).map_err(|e| format!("{}: {e}", path.display()))?;
+        }
+
+        let output = Command::new("solana")
+            .args(["program", "deploy", "--commitment", "confirmed"])
+            .args(["--url", &self.rpc_url])
+            .arg("--keypair")
+            .arg(&payer_path)
+            .arg("--program-id")
+            .arg(&program_path)
+            .arg(program)
+            .output()
+            .map_err(|e| format!("running solana: {e}"))?;
+        if !output.status.success() {
+            return Err(format!(
+                "deploying {}: {}",
+                program.display(),
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Ok(program_id)
+    }
+}
+
+impl Drop for LocalValidator {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+        let _ = std::fs::remove_dir_all(&self.ledger);
+    }
+}
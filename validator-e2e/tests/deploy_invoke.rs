@@ -0,0 +1,250 @@
+//! Each build deployed and invoked with the shared vectors.
+//!
+//! Every vector the `Allowance` instruction can express runs as one, and
+//! both allowances read back from the result account must match the host
+//! build of the same backend; the activation allowance must also match the
+//! vector unless it excludes the backend. `SelfTest` then runs the whole
+//! embedded table on-chain and must count the same mismatches as the host.
+
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::compute_budget::ComputeBudgetInstruction;
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::native_token::LAMPORTS_PER_SOL;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Keypair;
+use solana_sdk::signer::Signer;
+use solana_sdk::system_instruction;
+use solana_sdk::sysvar;
+use solana_sdk::transaction::Transaction;
+use stake_ebpf_check::instruction::{
+    Instruction as ProgramInstruction, Operands, ALLOWANCE_INSTRUCTION_LEN,
+    SELF_TEST_INSTRUCTION_LEN,
+};
+use stake_ebpf_check::results::{
+    AllowanceResult, SelfTestResult, ALLOWANCE_RESULT_LEN, SELF_TEST_RESULT_LEN,
+};
+use stake_ebpf_check::self_test::{self, SelfTest};
+use stake_ebpf_check::stake_history::StakeHistoryEntry;
+use stake_ebpf_check::{
+    Backend, ORIGINAL_WARMUP_COOLDOWN_RATE_BPS, TOWER_WARMUP_COOLDOWN_RATE_BPS,
+};
+use test_vectors::Vector;
+use validator_e2e::{build_dir, builds, LocalValidator};
+
+/// `SelfTest` on the slower backends runs past the default limit.
+const COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+
+/// `vector` as `Allowance` operands, if they can express it: the program
+/// takes the rate from the epoch and rejects zero cluster stake. The rest
+/// only run under `SelfTest`.
+fn allowance_operands(vector: &Vector) -> Option<Operands> {
+    let new_rate_activation_epoch = match vector.rate_bps {
+        ORIGINAL_WARMUP_COOLDOWN_RATE_BPS => None,
+        // Whatever epoch the validator is in is past the switch.
+        TOWER_WARMUP_COOLDOWN_RATE_BPS => Some(0),
+        _ => return None,
+    };
+    if vector.cluster_portion == 0 {
+        return None;
+    }
+    Some(Operands {
+        // Ignored: the program reads the Clock, and the rate only depends
+        // on which side of the switch it is.
+        epoch: 0,
+        account_portion: vector.account_portion,
+        cluster_state: StakeHistoryEntry {
+            activating: vector.cluster_portion,
+            deactivating: vector.cluster_portion,
+            effective: vector.cluster_effective,
+        },
+        new_rate_activation_epoch,
+    })
+}
+
+fn send(
+    rpc: &RpcClient,
+    payer: &Keypair,
+    signers: &[&Keypair],
+    instruction: Instruction,
+) -> Result<(), String> {
+    let blockhash = rpc.get_latest_blockhash().map_err(|e| e.to_string())?;
+    let mut all_signers = vec![payer];
+    all_signers.extend_from_slice(signers);
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            ComputeBudgetInstruction::set_compute_unit_limit(COMPUTE_UNIT_LIMIT),
+            instruction,
+        ],
+        Some(&payer.pubkey()),
+        &all_signers,
+        blockhash,
+    );
+    rpc.send_and_confirm_transaction(&transaction)
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+/// A zeroed account owned by `program_id`, large enough for either result.
+fn create_result_account(
+    rpc: &RpcClient,
+    payer: &Keypair,
+    program_id: &Pubkey,
+) -> Result<Pubkey, String> {
+    let result = Keypair::new();
+    let len = ALLOWANCE_RESULT_LEN.max(SELF_TEST_RESULT_LEN);
+    let lamports = rpc
+        .get_minimum_balance_for_rent_exemption(len)
+        .map_err(|e| e.to_string())?;
+    send(
+        rpc,
+        payer,
+        &[&result],
+        system_instruction::create_account(
+            &payer.pubkey(),
+            &result.pubkey(),
+            lamports,
+            len as u64,
+            program_id,
+        ),
+    )?;
+    Ok(result.pubkey())
+}
+
+fn program_data(instruction: ProgramInstruction, len: usize) -> Vec<u8> {
+    let mut data = vec![0u8; len];
+    instruction.pack(&mut data);
+    data
+}
+
+#[test]
+fn deployed_backends_match_the_host() {
+    let builds = builds();
+    if builds.is_empty() {
+        eprintln!("skipping: no builds in {}", build_dir().display());
+        return;
+    }
+    let Some(validator) = LocalValidator::start().unwrap_or_else(|e| panic!("{e}")) else {
+        return;
+    };
+    let vectors = test_vectors::load().unwrap_or_else(|e| panic!("{e}"));
+    let rpc = validator.rpc();
+    let payer = validator
+        .fund(100 * LAMPORTS_PER_SOL)
+        .unwrap_or_else(|e| panic!("{e}"));
+
+    let mut checked = 0;
+    let mut mismatches = Vec::new();
+    for build in &builds {
+        let backend = Backend::from_id(build.backend_id).unwrap();
+        let program_id = validator
+            .deploy(&payer, &build.path)
+            .unwrap_or_else(|e| panic!("{e}"));
+        let result_key = create_result_account(&rpc, &payer, &program_id)
+            .unwrap_or_else(|e| panic!("{}: result account: {e}", build.backend));
+        println!("{} deployed at {program_id}", build.backend);
+
+        for vector in &vectors {
+            let Some(operands) = allowance_operands(vector) else {
+                continue;
+            };
+            let instruction = Instruction::new_with_bytes(
+                program_id,
+                &program_data(
+                    ProgramInstruction::Allowance {
+                        backend_id: build.backend_id,
+                        operands,
+                    },
+                    ALLOWANCE_INSTRUCTION_LEN,
+                ),
+                vec![
+                    AccountMeta::new_readonly(sysvar::clock::id(), false),
+                    AccountMeta::new(result_key, false),
+                ],
+            );
+            if let Err(e) = send(&rpc, &payer, &[], instruction) {
+                mismatches.push(format!("{} {}: {e}", vector.name, build.backend));
+                continue;
+            }
+            let result = rpc
+                .get_account_data(&result_key)
+                .ok()
+                .and_then(|data| AllowanceResult::read(&data));
+            let host = (
+                backend.calculate_activation_allowance(
+                    operands.epoch,
+                    operands.account_portion,
+                    &operands.cluster_state,
+                    operands.new_rate_activation_epoch,
+                ),
+                backend.calculate_deactivation_allowance(
+                    operands.epoch,
+                    (operands.account_portion / 2) + 1,
+                    &operands.cluster_state,
+                    operands.new_rate_activation_epoch,
+                ),
+            );
+            checked += 1;
+            match result {
+                Some(result)
+                    if (result.activation, result.deactivation) == host
+                        && result.backend_id == build.backend_id
+                        && (vector.excludes(build.backend_id)
+                            || result.activation == vector.expected) => {}
+                Some(result) => mismatches.push(format!(
+                    "{} {}: on-chain ({}, {}), host {host:?}, expected activation {}",
+                    vector.name,
+                    build.backend,
+                    result.activation,
+                    result.deactivation,
+                    vector.expected
+                )),
+                None => mismatches.push(format!(
+                    "{} {}: unreadable result account",
+                    vector.name, build.backend
+                )),
+            }
+        }
+
+        let instruction = Instruction::new_with_bytes(
+            program_id,
+            &program_data(
+                ProgramInstruction::SelfTest {
+                    backend_id: build.backend_id,
+                },
+                SELF_TEST_INSTRUCTION_LEN,
+            ),
+            vec![AccountMeta::new(result_key, false)],
+        );
+        if let Err(e) = send(&rpc, &payer, &[], instruction) {
+            mismatches.push(format!("self-test {}: {e}", build.backend));
+            continue;
+        }
+        let result = rpc
+            .get_account_data(&result_key)
+            .ok()
+            .and_then(|data| SelfTestResult::read(&data));
+        let host = (self_test::VECTORS.len() as u32, backend.visit(SelfTest));
+        checked += 1;
+        match result {
+            Some(result) if (result.vectors, result.mismatches) == host => {}
+            Some(result) => mismatches.push(format!(
+                "self-test {}: on-chain {} vectors, {} wrong; host {host:?}",
+                build.backend, result.vectors, result.mismatches
+            )),
+            None => mismatches.push(format!(
+                "self-test {}: unreadable result account",
+                build.backend
+            )),
+        }
+    }
+
+    println!(
+        "{checked} results checked on a validator at {}",
+        validator.rpc_url()
+    );
+    assert!(
+        mismatches.is_empty(),
+        "wrong results:\n{}",
+        mismatches.join("\n")
+    );
+}
@@ -0,0 +1,115 @@
+//! `cargo xtask build-all`: the release program once per backend, copied
+//! out with a manifest describing each build.
+//!
+//! Artifacts go to `target/artifacts/` (or `--out <dir>`) as
+//! `stake_ebpf_check-<backend>.so`, next to `manifest.json`:
+//!
+//! ```json
+//! {
+//!   "git_commit": "<HEAD>",
+//!   "git_commit_time": "2024-01-31T12:00:00+00:00",
+//!   "git_dirty": false,
+//!   "target": "bpfel-unknown-none",
+//!   "profile": "release",
+//!   "artifacts": [
+//!     { "backend": "bnum", "features": ["bnum"], "file": "stake_ebpf_check-bnum.so",
+//!       "size": 12345, "text": 9000, "rodata": 1000, "sha256": "<hex>" }
+//!   ]
+//! }
+//! ```
+//!
+//! Artifacts are listed in wire-id order and the manifest records nothing
+//! that varies between runs, so two runs on the same commit and toolchain
+//! produce the same file. `git_dirty` flags builds of uncommitted changes,
+//! whose commit alone does not say what was built.
+//!
+//! Measurements are added to each artifact afterwards: `rbpf-runner
+//! --manifest` adds `instructions`, and `cu-bench` with `CU_MANIFEST` set
+//! adds `compute_units`. `report --manifest` compares manifests from
+//! several commits.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+use elf_tools::Program;
+
+use crate::{build_program, workspace_root, BACKENDS};
+
+const USAGE: &str = "usage: cargo xtask build-all [--out <dir>]";
+
+fn git(args: &[&str]) -> Result<String, String> {
+    let output = Command::new("git")
+        .current_dir(workspace_root())
+        .args(args)
+        .output()
+        .map_err(|e| format!("running git: {e}"))?;
+    if !output.status.success() {
+        return Err(format!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_owned())
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// Builds `backend` alone and copies the artifact into `out`.
+fn build(backend: &str, out: &Path) -> Result<Value, String> {
+    let path = build_program(&[backend])?;
+    let bytes = std::fs::read(&path).map_err(|e| format!("{}: {e}", path.display()))?;
+    let program = Program::parse(&bytes).map_err(|e| format!("{}: {e}", path.display()))?;
+    let file = format!("stake_ebpf_check-{backend}.so");
+    let dest = out.join(&file);
+    std::fs::write(&dest, &bytes).map_err(|e| format!("{}: {e}", dest.display()))?;
+    Ok(json!({
+        "backend": backend,
+        "features": [backend],
+        "file": file,
+        "size": bytes.len(),
+        "text": program.section_size(".text").unwrap_or(0),
+        "rodata": program.section_size(".rodata").unwrap_or(0),
+        "sha256": sha256_hex(&bytes),
+    }))
+}
+
+pub fn run(args: &[String]) -> Result<(), String> {
+    let out = match args {
+        [] => workspace_root().join("target/artifacts"),
+        [flag, dir] if flag == "--out" => PathBuf::from(dir),
+        _ => return Err(USAGE.to_owned()),
+    };
+    std::fs::create_dir_all(&out).map_err(|e| format!("{}: {e}", out.display()))?;
+
+    let git_commit = git(&["rev-parse", "HEAD"])?;
+    let git_commit_time = git(&["show", "-s", "--format=%cI", "HEAD"])?;
+    let git_dirty = !git(&["status", "--porcelain"])?.is_empty();
+
+    let artifacts = BACKENDS
+        .iter()
+        .map(|backend| build(backend, &out))
+        .collect::<Result<Vec<_>, _>>()?;
+    let manifest = json!({
+        "git_commit": git_commit,
+        "git_commit_time": git_commit_time,
+        "git_dirty": git_dirty,
+        "target": "bpfel-unknown-none",
+        "profile": "release",
+        "artifacts": artifacts,
+    });
+
+    let path = out.join("manifest.json");
+    let text = serde_json::to_string_pretty(&manifest).unwrap() + "\n";
+    std::fs::write(&path, text).map_err(|e| format!("{}: {e}", path.display()))?;
+    println!("{} builds, manifest at {}", BACKENDS.len(), path.display());
+    Ok(())
+}
@@ -0,0 +1,38 @@
+//! `cargo xtask ffi-header`: regenerates `include/stake_ebpf_check.h` from
+//! the `ffi` module with cbindgen (`cargo install cbindgen`), configured by
+//! `stake-ebpf-check/cbindgen.toml`. `--check` fails instead of writing if
+//! the checked-in header is stale.
+
+use std::process::Command;
+
+use crate::workspace_root;
+
+pub fn run(args: &[String]) -> Result<(), String> {
+    let check = match args {
+        [] => false,
+        [flag] if flag == "--check" => true,
+        _ => return Err("usage: cargo xtask ffi-header [--check]".to_owned()),
+    };
+
+    let crate_dir = workspace_root().join("stake-ebpf-check");
+    let mut command = Command::new("cbindgen");
+    command.current_dir(&crate_dir).args([
+        "--config",
+        "cbindgen.toml",
+        "--output",
+        "include/stake_ebpf_check.h",
+    ]);
+    if check {
+        command.arg("--verify");
+    }
+    let status = command
+        .status()
+        .map_err(|e| format!("running cbindgen (cargo install cbindgen): {e}"))?;
+    match status.success() {
+        true => Ok(()),
+        false if check => {
+            Err("include/stake_ebpf_check.h is stale; run cargo xtask ffi-header".to_owned())
+        }
+        false => Err("cbindgen failed".to_owned()),
+    }
+}
@@ -0,0 +1,82 @@
+//! `cargo xtask fuzz-seeds`: the shared known-answer vectors as a starting
+//! corpus for the `instruction` fuzz target.
+//!
+//! Each vector whose rate is one the program can select becomes an
+//! `Allowance` for every backend not excluded from it, with the cluster's
+//! activating and deactivating stake both set to the vector's cluster
+//! portion. Files go to `fuzz/corpus/instruction/`, where `cargo fuzz run`
+//! looks first, encoded as the target reads them: the Clock epoch as eight
+//! little-endian bytes, then the instruction data.
+
+use stake_ebpf_check::instruction::{Instruction, Operands, MAX_INSTRUCTION_LEN};
+use stake_ebpf_check::stake_history::StakeHistoryEntry;
+use stake_ebpf_check::{Epoch, ORIGINAL_WARMUP_COOLDOWN_RATE_BPS, TOWER_WARMUP_COOLDOWN_RATE_BPS};
+use test_vectors::Vector;
+
+use crate::{workspace_root, BACKENDS};
+
+/// The `new_rate_activation_epoch` that selects `rate_bps` at epoch zero.
+fn rate_switch(rate_bps: u64) -> Option<Option<Epoch>> {
+    match rate_bps {
+        ORIGINAL_WARMUP_COOLDOWN_RATE_BPS => Some(None),
+        TOWER_WARMUP_COOLDOWN_RATE_BPS => Some(Some(0)),
+        _ => None,
+    }
+}
+
+fn seed(vector: &Vector, new_rate_activation_epoch: Option<Epoch>, backend_id: u8) -> Vec<u8> {
+    let epoch: Epoch = 0;
+    let instruction = Instruction::Allowance {
+        backend_id,
+        operands: Operands {
+            epoch,
+            account_portion: vector.account_portion,
+            cluster_state: StakeHistoryEntry {
+                effective: vector.cluster_effective,
+                activating: vector.cluster_portion,
+                deactivating: vector.cluster_portion,
+            },
+            new_rate_activation_epoch,
+        },
+    };
+    let mut data = [0u8; MAX_INSTRUCTION_LEN];
+    let len = instruction.pack(&mut data);
+
+    let mut seed = epoch.to_le_bytes().to_vec();
+    seed.extend_from_slice(&data[..len]);
+    seed
+}
+
+pub fn run(args: &[String]) -> Result<(), String> {
+    if let Some(arg) = args.first() {
+        return Err(format!("fuzz-seeds takes no arguments, got `{arg}`"));
+    }
+    let vectors = test_vectors::load()?;
+    let dir = workspace_root().join("fuzz/corpus/instruction");
+    std::fs::create_dir_all(&dir).map_err(|e| format!("{}: {e}", dir.display()))?;
+
+    let mut written = 0;
+    let mut skipped = Vec::new();
+    for vector in &vectors {
+        let Some(new_rate_activation_epoch) = rate_switch(vector.rate_bps) else {
+            skipped.push(vector.name.as_str());
+            continue;
+        };
+        for (id, backend) in BACKENDS.iter().enumerate() {
+            let id = id as u8;
+            if vector.excludes(id) {
+                continue;
+            }
+            let path = dir.join(format!("vector-{}-{backend}", vector.name));
+            std::fs::write(&path, seed(vector, new_rate_activation_epoch, id))
+                .map_err(|e| format!("{}: {e}", path.display()))?;
+            written += 1;
+        }
+    }
+
+    println!("{written} seeds in {}", dir.display());
+    if !skipped.is_empty() {
+        println!("no Allowance selects the rate of: {}", skipped.join(", "));
+    }
+    Ok(())
+}
@@ -0,0 +1,94 @@
+//! Project tasks too involved for a cargo alias.
+//!
+//! ```text
+//! cargo xtask <task> [args]
+//! ```
+//!
+//! The alias runs this on the host; tasks that build the program invoke
+//! cargo again, which picks up the workspace's SBF target as usual.
+
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitCode};
+
+mod build_all;
+mod ffi_header;
+mod fuzz_seeds;
+mod sizes;
+mod stack_budget;
+
+/// Every backend feature, in wire-id order.
+pub const BACKENDS: [&str; 7] = [
+    "bnum",
+    "crypto",
+    "fixed",
+    "uint",
+    "plain",
+    "manual",
+    "streaming",
+];
+
+const USAGE: &str = "\
+usage: cargo xtask <task> [args]
+
+tasks:
+  build-all [--out <dir>]      every backend's release build, with a JSON manifest
+  check-stack-budget           check an oversized stack frame fails the program link
+  ffi-header [--check]         regenerate the C header for the ffi feature
+  fuzz-seeds                   seed the instruction fuzz corpus from the test vectors
+  sizes [--all-combinations]   .text/.rodata/.so size of each backend build";
+
+pub fn workspace_root() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .unwrap()
+        .to_path_buf()
+}
+
+/// The release program artifact, as the last `cargo build --release` left
+/// it.
+pub fn program_artifact() -> PathBuf {
+    workspace_root().join("target/bpfel-unknown-none/release/libstake_ebpf_check.so")
+}
+
+/// `cargo` as the one running this task, from the workspace root.
+pub fn cargo() -> Command {
+    let mut command = Command::new(std::env::var_os("CARGO").unwrap_or_else(|| "cargo".into()));
+    command.current_dir(workspace_root());
+    command
+}
+
+/// Builds the release program with exactly `features`.
+pub fn build_program(features: &[&str]) -> Result<PathBuf, String> {
+    let status = cargo()
+        .args(["build-program", "--features"])
+        .arg(features.join(","))
+        .status()
+        .map_err(|e| format!("running cargo: {e}"))?;
+    if !status.success() {
+        return Err(format!("build with {} failed", features.join(",")));
+    }
+    Ok(program_artifact())
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let result = match args.first().map(String::as_str) {
+        Some("build-all") => build_all::run(&args[1..]),
+        Some("check-stack-budget") => stack_budget::run(&args[1..]),
+        Some("ffi-header") => ffi_header::run(&args[1..]),
+        Some("fuzz-seeds") => fuzz_seeds::run(&args[1..]),
+        Some("sizes") => sizes::run(&args[1..]),
+        Some("help") | None => {
+            println!("{USAGE}");
+            Ok(())
+        }
+        Some(task) => Err(format!("unknown task {task}\n{USAGE}")),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
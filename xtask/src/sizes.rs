@@ -0,0 +1,79 @@
+//! `cargo xtask sizes`: artifact sizes per backend build.
+//!
+//! Builds the release program once per backend, plus once with every
+//! backend, and prints `.text`, `.rodata` and file size for each.
+//! `--all-combinations` builds every non-empty subset instead (127 builds).
+//! The file size is the unstripped artifact; deploy cost follows `.text`
+//! and `.rodata` more closely.
+
+use elf_tools::Program;
+
+use crate::{build_program, BACKENDS};
+
+struct Sizes {
+    text: u64,
+    rodata: u64,
+    file: u64,
+}
+
+fn measure(features: &[&str]) -> Result<Sizes, String> {
+    let path = build_program(features)?;
+    let bytes = std::fs::read(&path).map_err(|e| format!("{}: {e}", path.display()))?;
+    let program = Program::parse(&bytes).map_err(|e| format!("{}: {e}", path.display()))?;
+    Ok(Sizes {
+        text: program.section_size(".text").unwrap_or(0),
+        rodata: program.section_size(".rodata").unwrap_or(0),
+        file: bytes.len() as u64,
+    })
+}
+
+fn combinations(all: bool) -> Vec<Vec<&'static str>> {
+    if all {
+        return (1u32..1 << BACKENDS.len())
+            .map(|mask| {
+                BACKENDS
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, _)| mask & (1 << i) != 0)
+                    .map(|(_, backend)| *backend)
+                    .collect()
+            })
+            .collect();
+    }
+    let mut combinations: Vec<Vec<&str>> = BACKENDS.iter().map(|backend| vec![*backend]).collect();
+    combinations.push(BACKENDS.to_vec());
+    combinations
+}
+
+pub fn run(args: &[String]) -> Result<(), String> {
+    let all = match args {
+        [] => false,
+        [flag] if flag == "--all-combinations" => true,
+        _ => return Err("usage: cargo xtask sizes [--all-combinations]".to_string()),
+    };
+
+    // Build everything first so cargo's output stays above the table.
+    let mut rows = Vec::new();
+    for features in combinations(all) {
+        let sizes = measure(&features)?;
+        rows.push((features.join(","), sizes));
+    }
+
+    let width = rows
+        .iter()
+        .map(|(features, _)| features.len())
+        .max()
+        .unwrap_or(0)
+        .max("features".len());
+    println!(
+        "{:<width$}  {:>9}  {:>9}  {:>9}",
+        "features", ".text", ".rodata", ".so"
+    );
+    for (features, sizes) in rows {
+        println!(
+            "{features:<width$}  {:>9}  {:>9}  {:>9}",
+            sizes.text, sizes.rodata, sizes.file
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,42 @@
+//! `cargo xtask check-stack-budget`: proves the stack frame check in
+//! `stake-ebpf-check/build.rs` is live.
+//!
+//! Builds the program once as usual, which must link, and once with
+//! `stack-budget-violation`, which must fail to link with an error naming
+//! `entrypoint_stack_violation`, the function given an oversized frame for
+//! the purpose. Either build doing otherwise fails the task, with cargo's
+//! output.
+
+use crate::{build_program, cargo};
+
+const FEATURES: &str = "manual";
+const VIOLATION: &str = "entrypoint_stack_violation";
+
+pub fn run(args: &[String]) -> Result<(), String> {
+    if !args.is_empty() {
+        return Err("usage: cargo xtask check-stack-budget".to_string());
+    }
+
+    // A build that fails for any other reason, a missing `bpf-linker` say,
+    // would otherwise pass as the expected failure below.
+    build_program(&[FEATURES])?;
+
+    let output = cargo()
+        .args(["build-program", "--features"])
+        .arg(format!("{FEATURES},stack-budget-violation"))
+        .output()
+        .map_err(|e| format!("running cargo: {e}"))?;
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if output.status.success() {
+        return Err(format!(
+            "the stack-budget-violation build linked; the frame limit is not enforced\n{stderr}"
+        ));
+    }
+    if !stderr.contains(VIOLATION) {
+        return Err(format!(
+            "the stack-budget-violation build failed without naming `{VIOLATION}`\n{stderr}"
+        ));
+    }
+    println!("stack-budget-violation build failed on `{VIOLATION}` as expected");
+    Ok(())
+}